@@ -2,18 +2,34 @@ use std::{
     env,
     error::Error,
     fs,
-    io::{BufRead, Write},
+    io::{BufRead, Read, Write},
+    path::PathBuf,
     rc::Rc,
+    time::Duration,
 };
 
-use clap::Parser;
+use base64::{
+    prelude::{BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD},
+    Engine as _,
+};
+use clap::{Parser, ValueEnum};
 use eyre::eyre;
 
 use frost_core::{keys::PublicKeyPackage, Ciphersuite};
 use frost_rerandomized::Randomizer;
+use frostd::Uuid;
+use rand::{thread_rng, RngCore as _};
+use reddsa::frost::redpallas::PallasBlake2b512;
 
 use crate::input::read_from_file_or_stdin;
 
+/// The maximum total size, in bytes, of the messages to sign. In HTTP mode the
+/// signing package (which includes the hex-encoded messages) is split into as
+/// many encrypted chunks as needed, which the server queues for each
+/// participant; this keeps their number well under the server queue limit. It
+/// does not apply to CLI mode.
+pub const MAX_MESSAGES_SIZE: usize = 1024 * 1024;
+
 #[derive(Clone, Parser, Debug, Default)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
@@ -43,13 +59,31 @@ pub struct Args {
     #[arg(short = 'P', long, default_value = "public-key-package.json")]
     pub public_key_package: String,
 
-    /// The messages to sign. Each instance can be a file with the raw message,
-    /// "" or "-". If "" or "-" is specified, then it will be read from standard
-    /// input as a hex string. If none are passed, a single one will be read
-    /// from standard input as a hex string.
+    /// The message to sign. It can be a file with the raw message, "" or "-".
+    /// If "" or "-" is specified, then it will be read from standard input,
+    /// encoded as specified by `message_encoding`. If no message is passed with
+    /// this or `message_file` or `message_stdin`, it will be read from standard
+    /// input. Only one message can be signed per session.
     #[arg(short = 'm', long)]
     pub message: Vec<String>,
 
+    /// The encoding of the messages read from standard input with `message`.
+    #[arg(long, value_enum, default_value_t = MessageEncoding::Hex)]
+    pub message_encoding: MessageEncoding,
+
+    /// A file with the raw message to sign, instead of `message`. Only one
+    /// message can be signed per session.
+    #[arg(long)]
+    pub message_file: Vec<String>,
+
+    /// Read the raw message to sign from standard input, until EOF, instead
+    /// of `message` or `message_file`. Since it
+    /// consumes all of standard input, no other inputs can be read from it;
+    /// thus it can't be used in CLI mode, which reads the commitments and
+    /// signature shares from standard input.
+    #[arg(long, default_value_t = false)]
+    pub message_stdin: bool,
+
     /// The randomizers to use. Each instance can be a file with the raw
     /// randomizer, "" or "-". If "" or "-" is specified, then it will be read
     /// from standard input as a hex string. If none are passed, random ones
@@ -58,11 +92,27 @@ pub struct Args {
     #[arg(short = 'r', long)]
     pub randomizer: Vec<String>,
 
+    /// An auxiliary message to send to participants along with the signing
+    /// package, as a hex string; e.g. the transaction being signed, so that
+    /// they can check what the messages they are signing correspond to. The
+    /// randomizer is derived from it, binding the signature to it, so it
+    /// requires rerandomized signing and can't be used with `randomizer`.
+    /// For HTTP mode.
+    #[arg(long, default_value = "")]
+    pub aux_msg: String,
+
     /// Where to write the generated raw bytes signature. If "-", the
     /// human-readable hex-string is printed to stdout.
     #[arg(short = 's', long, default_value = "")]
     pub signature: String,
 
+    /// Path of a file to append, for each signed message, a JSON record with
+    /// the signing package, the identifiers of the signers, the randomizer (if
+    /// any), the group signature and the SHA-256 hash of every message of the
+    /// session, by index. One record is written per line.
+    #[arg(long)]
+    pub audit_log: Option<String>,
+
     /// IP to bind to, if using socket comms.
     /// IP to connect to, if using HTTP mode.
     #[arg(short, long, default_value = "0.0.0.0")]
@@ -72,6 +122,75 @@ pub struct Args {
     /// Port to connect to, if using HTTP mode.
     #[arg(short, long, default_value_t = 443)]
     pub port: u16,
+
+    /// Path to the PEM-encoded certificate of the CA which issued the server
+    /// certificate, if it is not trusted by the system (e.g. a private CA).
+    /// For HTTP mode.
+    #[arg(long)]
+    pub cacert: Option<String>,
+
+    /// Do not verify the server certificate. This is very insecure and should
+    /// only be used for testing. For HTTP mode.
+    #[arg(long, default_value_t = false)]
+    pub insecure_skip_verify: bool,
+
+    /// The proxy to connect to the server through, e.g.
+    /// "http://proxy.example.com:3128". If not specified, the `HTTPS_PROXY`
+    /// and `ALL_PROXY` environment variables are used, if set. For HTTP mode.
+    #[arg(long)]
+    pub proxy: Option<String>,
+
+    /// On failure, print the error to standard error as a JSON object with
+    /// `error` and `code` fields, for scripts, instead of as plain text.
+    #[arg(long, default_value_t = false)]
+    pub json_errors: bool,
+}
+
+/// The encoding of a message entered in standard input.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum MessageEncoding {
+    /// A hex string.
+    #[default]
+    Hex,
+    /// A base64 string, with the standard alphabet and padding.
+    Base64,
+    /// A base64 string, with the URL-safe alphabet and optional padding.
+    Base64url,
+    /// The message itself, as UTF-8 text on a single line.
+    Utf8,
+}
+
+impl MessageEncoding {
+    /// Decode a message entered in a line of standard input.
+    pub fn decode(&self, line: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(match self {
+            MessageEncoding::Hex => hex::decode(line.trim())?,
+            MessageEncoding::Base64 => BASE64_STANDARD.decode(line.trim())?,
+            MessageEncoding::Base64url => {
+                BASE64_URL_SAFE_NO_PAD.decode(line.trim().trim_end_matches('='))?
+            }
+            // Only strip the line terminator, since other whitespace is part
+            // of the message.
+            MessageEncoding::Utf8 => line
+                .strip_suffix('\n')
+                .map(|l| l.strip_suffix('\r').unwrap_or(l))
+                .unwrap_or(line)
+                .as_bytes()
+                .to_vec(),
+        })
+    }
+}
+
+impl std::fmt::Display for MessageEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MessageEncoding::Hex => "hex",
+            MessageEncoding::Base64 => "base64",
+            MessageEncoding::Base64url => "base64url",
+            MessageEncoding::Utf8 => "UTF-8",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 #[derive(Clone)]
@@ -102,10 +221,22 @@ pub struct ProcessedArgs<C: Ciphersuite> {
     /// The randomizers to use.
     pub randomizers: Vec<Randomizer<C>>,
 
+    /// The auxiliary message to send to participants along with the signing
+    /// package, which the randomizer is derived from. For HTTP mode.
+    pub aux_msg: Vec<u8>,
+
+    /// The random seed the randomizer is derived from along with `aux_msg`,
+    /// sent to the participants with it; empty if `aux_msg` is. See
+    /// [`frostd::SendSigningPackageArgs::seed`].
+    pub aux_msg_seed: Vec<u8>,
+
     /// Where to write the generated raw bytes signature. If "-", the
     /// human-readable hex-string is printed to stdout.
     pub signature: String,
 
+    /// Path of a file to append audit records to, if any.
+    pub audit_log: Option<String>,
+
     /// IP to bind to, if using socket comms.
     /// IP to connect to, if using HTTP mode.
     pub ip: String,
@@ -114,6 +245,29 @@ pub struct ProcessedArgs<C: Ciphersuite> {
     /// Port to connect to, if using HTTP mode.
     pub port: u16,
 
+    /// Path to the PEM-encoded certificate of the CA which issued the server
+    /// certificate, if any. For HTTP mode.
+    pub cacert: Option<String>,
+
+    /// Do not verify the server certificate. For HTTP mode.
+    pub insecure_skip_verify: bool,
+
+    /// The proxy to connect to the server through, if any. For HTTP mode.
+    pub proxy: Option<String>,
+
+    /// The ID of an existing session to resume instead of creating a new one,
+    /// e.g. if a previous coordinator run was interrupted. For HTTP mode.
+    pub session_id: Option<Uuid>,
+
+    /// Reject participants which send commitments or signature shares that
+    /// differ from the ones they have already sent. For HTTP mode.
+    pub strict: bool,
+
+    /// The file to save the session state to as messages are received, and
+    /// to restore it from when resuming the session with `session_id`, so
+    /// that the steps already completed are skipped. For HTTP mode.
+    pub state_file: Option<PathBuf>,
+
     /// The coordinator's communication private key for HTTP mode.
     pub comm_privkey: Option<Vec<u8>>,
 
@@ -128,6 +282,43 @@ pub struct ProcessedArgs<C: Ciphersuite> {
     // require a lot of code change for something simple.
     #[allow(clippy::type_complexity)]
     pub comm_participant_pubkey_getter: Option<Rc<dyn Fn(&Vec<u8>) -> Option<Vec<u8>>>>,
+
+    /// A function called after each message received from a participant,
+    /// with the number of participants which sent the messages expected in
+    /// the current phase (commitments, then signature shares) so far and the
+    /// number expected in total. For HTTP mode.
+    pub progress: Option<Rc<dyn Fn(usize, usize)>>,
+
+    /// A function called with the session ID once the session is created or
+    /// resumed, e.g. to keep a record of it. For HTTP mode.
+    pub on_session: Option<Rc<dyn Fn(Uuid)>>,
+
+    /// How often to refresh the session while waiting for participants, so
+    /// that the server does not time it out. For HTTP mode.
+    pub keep_alive: Option<Duration>,
+
+    /// Compress messages sent to participants before encrypting them.
+    /// Received messages say whether they are compressed. For HTTP mode.
+    pub compress: bool,
+
+    /// How long to wait for participants in each phase (commitments, then
+    /// signature shares) before giving up with a [`ParticipantsTimedOut`]
+    /// error naming those which did not respond. Waits forever if `None`.
+    /// For HTTP mode.
+    ///
+    /// [`ParticipantsTimedOut`]: crate::comms::http::ParticipantsTimedOut
+    pub participant_timeout: Option<Duration>,
+
+    /// If `participant_timeout` expires while waiting for commitments, proceed
+    /// with the participants which sent theirs if there are at least this
+    /// many, instead of giving up. For HTTP mode.
+    pub threshold: Option<u16>,
+
+    /// If set, commit to `messages` with this salt when creating the session,
+    /// and reveal the salt in the signing package, so that participants can
+    /// check that the messages were not picked after seeing their
+    /// commitments. It must be random. For HTTP mode.
+    pub message_commitment_salt: Option<Vec<u8>>,
 }
 
 impl<C: Ciphersuite + 'static> ProcessedArgs<C> {
@@ -166,10 +357,43 @@ impl<C: Ciphersuite + 'static> ProcessedArgs<C> {
 
         let public_key_package: PublicKeyPackage<C> = serde_json::from_str(&out)?;
 
-        let messages = read_messages(&args.message, output, input)?;
+        if args.cli && args.message_stdin {
+            return Err(eyre!(
+                "`message_stdin` can't be used in CLI mode, which also reads the \
+                 commitments and signature shares from standard input"
+            )
+            .into());
+        }
+        let mut messages =
+            if args.message.is_empty() && (!args.message_file.is_empty() || args.message_stdin) {
+                Vec::new()
+            } else {
+                read_messages(&args.message, args.message_encoding, output, input)?
+            };
+        messages.extend(read_message_files(&args.message_file)?);
+        if args.message_stdin {
+            let mut msg = Vec::new();
+            input.read_to_end(&mut msg)?;
+            messages.push(msg);
+        }
+        // The signing package only holds a single message; see `step_2()`.
+        if messages.len() > 1 {
+            return Err(eyre!(
+                "only one message can be signed per session, but {} were given",
+                messages.len()
+            )
+            .into());
+        }
+        // Only the online modes send the messages to the participants.
+        if !args.cli {
+            validate_messages_size(&messages)?;
+        }
 
         println!("Processing randomizer {:?}", args.randomizer);
         let randomizers = read_randomizers(&args.randomizer, output, input)?;
+        let aux_msg = hex::decode(&args.aux_msg)?;
+        validate_aux_msg(&aux_msg, &randomizers)?;
+        let aux_msg_seed = generate_aux_msg_seed(&aux_msg);
 
         Ok(ProcessedArgs {
             cli: args.cli,
@@ -179,12 +403,28 @@ impl<C: Ciphersuite + 'static> ProcessedArgs<C> {
             public_key_package,
             messages,
             randomizers,
+            aux_msg,
+            aux_msg_seed,
             signature: args.signature.clone(),
+            audit_log: args.audit_log.clone(),
             ip: args.ip.clone(),
             port: args.port,
+            cacert: args.cacert.clone(),
+            insecure_skip_verify: args.insecure_skip_verify,
+            proxy: args.proxy.clone(),
+            session_id: None,
+            strict: false,
+            state_file: None,
             comm_privkey: None,
             comm_pubkey: None,
             comm_participant_pubkey_getter: None,
+            progress: None,
+            on_session: None,
+            keep_alive: None,
+            compress: false,
+            participant_timeout: None,
+            threshold: None,
+            message_commitment_salt: None,
         })
     }
 }
@@ -202,23 +442,24 @@ pub fn read_password(password_env_name: &str) -> Result<String, Box<dyn Error>>
 
 pub fn read_messages(
     message_paths: &[String],
+    encoding: MessageEncoding,
     output: &mut dyn Write,
     input: &mut dyn BufRead,
 ) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
     let messages = if message_paths.is_empty() {
-        writeln!(output, "The message to be signed (hex encoded)")?;
+        writeln!(output, "The message to be signed ({} encoded)", encoding)?;
         let mut msg = String::new();
         input.read_line(&mut msg)?;
-        vec![hex::decode(msg.trim())?]
+        vec![encoding.decode(&msg)?]
     } else {
         message_paths
             .iter()
             .map(|filename| {
                 let msg = if *filename == "-" || filename.is_empty() {
-                    writeln!(output, "The message to be signed (hex encoded)")?;
+                    writeln!(output, "The message to be signed ({} encoded)", encoding)?;
                     let mut msg = String::new();
                     input.read_line(&mut msg)?;
-                    hex::decode(msg.trim())?
+                    encoding.decode(&msg)?
                 } else {
                     eprintln!("Reading message from {}...", &filename);
                     fs::read(filename)?
@@ -230,6 +471,65 @@ pub fn read_messages(
     Ok(messages)
 }
 
+/// Read raw messages from the given files, one message per file.
+pub fn read_message_files(paths: &[String]) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+    paths
+        .iter()
+        .map(|filename| {
+            eprintln!("Reading message from {}...", &filename);
+            Ok(fs::read(filename).map_err(|e| eyre!("error reading {}: {}", filename, e))?)
+        })
+        .collect()
+}
+
+/// Check if the total size of the messages does not exceed
+/// [`MAX_MESSAGES_SIZE`].
+pub fn validate_messages_size(messages: &[Vec<u8>]) -> Result<(), Box<dyn Error>> {
+    let size: usize = messages.iter().map(|m| m.len()).sum();
+    if size > MAX_MESSAGES_SIZE {
+        return Err(eyre!(
+            "messages are too large: {} bytes, maximum is {} bytes",
+            size,
+            MAX_MESSAGES_SIZE
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Check if the auxiliary message, if any, can be used. The randomizer is
+/// derived from it, which requires rerandomized signing with randomizers
+/// generated by the coordinator.
+pub fn validate_aux_msg<C: Ciphersuite>(
+    aux_msg: &[u8],
+    randomizers: &[Randomizer<C>],
+) -> Result<(), Box<dyn Error>> {
+    if aux_msg.is_empty() {
+        return Ok(());
+    }
+    if C::ID != PallasBlake2b512::ID {
+        return Err(eyre!("the auxiliary message requires rerandomized signing").into());
+    }
+    if !randomizers.is_empty() {
+        return Err(eyre!(
+            "the auxiliary message can't be used with given randomizers, since the randomizer is derived from it"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Generate a random seed to derive the randomizer from if there is an
+/// auxiliary message, or return an empty one otherwise.
+pub fn generate_aux_msg_seed(aux_msg: &[u8]) -> Vec<u8> {
+    if aux_msg.is_empty() {
+        return Vec::new();
+    }
+    let mut seed = vec![0; 32];
+    thread_rng().fill_bytes(&mut seed);
+    seed
+}
+
 pub fn read_randomizers<C: Ciphersuite + 'static>(
     randomizer_paths: &[String],
     output: &mut dyn Write,
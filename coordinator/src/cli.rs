@@ -1,5 +1,6 @@
 use std::io::{BufRead, Write};
 
+use frost_core::Signature;
 use frost_rerandomized::RandomizedCiphersuite;
 
 use crate::args::Args;
@@ -12,11 +13,13 @@ use crate::step_1::step_1;
 use crate::step_2::step_2;
 use crate::step_3::step_3;
 
+/// Run the coordinator, returning the group signature. It is also printed,
+/// or written to the `signature` file if specified.
 pub async fn cli<C: RandomizedCiphersuite + 'static>(
     args: &Args,
     reader: &mut impl BufRead,
     logger: &mut impl Write,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<Signature<C>, Box<dyn std::error::Error>> {
     let pargs = ProcessedArgs::<C>::new(args, reader, logger)?;
     cli_for_processed_args(pargs, reader, logger).await
 }
@@ -25,7 +28,7 @@ pub async fn cli_for_processed_args<C: RandomizedCiphersuite + 'static>(
     pargs: ProcessedArgs<C>,
     reader: &mut impl BufRead,
     logger: &mut impl Write,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<Signature<C>, Box<dyn std::error::Error>> {
     writeln!(logger, "\n=== STEP 1: CHOOSE PARTICIPANTS ===\n")?;
 
     let mut comms: Box<dyn Comms<C>> = if pargs.cli {
@@ -47,7 +50,7 @@ pub async fn cli_for_processed_args<C: RandomizedCiphersuite + 'static>(
 
     writeln!(logger, "=== STEP 3: BUILD GROUP SIGNATURE ===\n")?;
 
-    step_3(
+    let signature = step_3(
         &pargs,
         &mut *comms,
         reader,
@@ -59,5 +62,5 @@ pub async fn cli_for_processed_args<C: RandomizedCiphersuite + 'static>(
 
     writeln!(logger, "=== END ===")?;
 
-    Ok(())
+    Ok(signature)
 }
@@ -0,0 +1,139 @@
+//! In-memory implementation of the Comms trait, using channels. It does not
+//! require a server nor encryption, which makes it useful for testing the
+//! protocol logic.
+
+use async_trait::async_trait;
+
+use frost_core as frost;
+
+use frost_core::Ciphersuite;
+
+use eyre::eyre;
+use participant::comms::{channel::ChannelComms as ParticipantChannelComms, Message};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use frost::{
+    keys::PublicKeyPackage, round1::SigningCommitments, round2::SignatureShare, Identifier,
+    SigningPackage,
+};
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    io::{BufRead, Write},
+};
+
+use super::Comms;
+
+pub struct ChannelComms<C: Ciphersuite> {
+    /// Receives messages from all participants, tagged with their index.
+    rx: UnboundedReceiver<(usize, Message<C>)>,
+    /// Sends messages to each participant, by index.
+    txs: Vec<UnboundedSender<Message<C>>>,
+    /// The index of each participant, by identifier.
+    indices: BTreeMap<Identifier<C>, usize>,
+}
+
+impl<C: Ciphersuite> ChannelComms<C> {
+    /// Create a ChannelComms for the coordinator, along with the comms for
+    /// `num_participants` participants connected to it.
+    pub fn new(num_participants: usize) -> (Self, Vec<ParticipantChannelComms<C>>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (txs, participants) = (0..num_participants)
+            .map(|index| {
+                let (participant_tx, participant_rx) = mpsc::unbounded_channel();
+                (
+                    participant_tx,
+                    ParticipantChannelComms::new(index, tx.clone(), participant_rx),
+                )
+            })
+            .unzip();
+        (
+            Self {
+                rx,
+                txs,
+                indices: BTreeMap::new(),
+            },
+            participants,
+        )
+    }
+}
+
+#[async_trait(?Send)]
+impl<C: Ciphersuite> Comms<C> for ChannelComms<C> {
+    async fn get_signing_commitments(
+        &mut self,
+        _input: &mut dyn BufRead,
+        _output: &mut dyn Write,
+        _pub_key_package: &PublicKeyPackage<C>,
+        num_of_participants: u16,
+    ) -> Result<BTreeMap<Identifier<C>, SigningCommitments<C>>, Box<dyn Error>> {
+        self.indices = BTreeMap::new();
+        let mut signing_commitments = BTreeMap::new();
+        for _ in 0..num_of_participants {
+            let (index, message) = self
+                .rx
+                .recv()
+                .await
+                .ok_or(eyre!("Did not receive all commitments"))?;
+            if let Message::IdentifiedCommitments {
+                identifier,
+                commitments,
+            } = message
+            {
+                self.indices.insert(identifier, index);
+                signing_commitments.insert(identifier, commitments);
+            } else {
+                Err(eyre!("Expected IdentifiedCommitments message"))?;
+            }
+        }
+        Ok(signing_commitments)
+    }
+
+    async fn get_signature_shares(
+        &mut self,
+        _input: &mut dyn BufRead,
+        _output: &mut dyn Write,
+        signing_package: &SigningPackage<C>,
+        randomizer: Option<frost_rerandomized::Randomizer<C>>,
+    ) -> Result<BTreeMap<Identifier<C>, SignatureShare<C>>, Box<dyn Error>> {
+        for identifier in signing_package.signing_commitments().keys() {
+            let index = self
+                .indices
+                .get(identifier)
+                .ok_or(eyre!("unknown identifier"))?;
+            self.txs[*index]
+                .send(Message::SigningPackageAndRandomizer {
+                    signing_package: signing_package.clone(),
+                    randomizer,
+                })
+                .map_err(|_| eyre!("participant disconnected"))?;
+        }
+
+        let mut signature_shares = BTreeMap::new();
+        for _ in 0..signing_package.signing_commitments().len() {
+            let (index, message) = self
+                .rx
+                .recv()
+                .await
+                .ok_or(eyre!("Did not receive all signature shares"))?;
+            let identifier = self
+                .indices
+                .iter()
+                .find_map(|(i, e)| if *e == index { Some(i) } else { None })
+                .ok_or(eyre!("Unknown participant"))?;
+            match message {
+                Message::SignatureShare(signature_share) => {
+                    signature_shares.insert(*identifier, signature_share);
+                }
+                Message::Declined { reason } => Err(eyre!(
+                    "participant {} declined to sign: {}",
+                    hex::encode(identifier.serialize()),
+                    reason
+                ))?,
+                _ => Err(eyre!("Expected SignatureShare message"))?,
+            }
+        }
+        Ok(signature_shares)
+    }
+}
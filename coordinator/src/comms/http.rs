@@ -5,39 +5,107 @@ use std::{
     error::Error,
     io::{BufRead, Write},
     marker::PhantomData,
-    time::Duration,
+    path::Path,
+    time::{Duration, Instant},
     vec,
 };
 
 use async_trait::async_trait;
 use eyre::{eyre, OptionExt};
 use frost_core::{
-    keys::PublicKeyPackage, round1::SigningCommitments, round2::SignatureShare, Ciphersuite,
-    Identifier, SigningPackage,
+    keys::PublicKeyPackage,
+    round1::SigningCommitments,
+    round2::SignatureShare,
+    serde::{self, Deserialize, Serialize},
+    Ciphersuite, Identifier, SigningPackage,
 };
 
 use frostd::{
-    Msg, PublicKey, SendCommitmentsArgs, SendSignatureSharesArgs, SendSigningPackageArgs, Uuid,
+    Msg, ParticipantDeclinedArgs, ParticipantErrorArgs, PublicKey, SendCommitmentsArgs,
+    SendSignatureSharesArgs, SendSigningPackageArgs, Uuid,
+};
+use participant::comms::http::{
+    ack, build_client, check_response, check_session_ciphersuite, noise_builder, receive,
+    DecryptError, KeepAlive, Noise, RequestBuilderExt as _,
 };
-use participant::comms::http::Noise;
 use rand::thread_rng;
 use xeddsa::{xed25519, Sign as _};
 
 use super::Comms;
 use crate::args::ProcessedArgs;
 
-#[derive(Clone, Debug)]
+/// An error reported by a participant with a [`ParticipantErrorArgs`]
+/// message.
+#[derive(Debug, thiserror::Error)]
+#[error("participant {} reported an error: {reason}", hex::encode(.pubkey))]
+pub struct ParticipantReportedError {
+    /// The communication public key of the participant.
+    pub pubkey: Vec<u8>,
+    /// The reason reported by the participant.
+    pub reason: String,
+}
+
+/// A participant declined to sign with a [`ParticipantDeclinedArgs`] message.
+#[derive(Debug, thiserror::Error)]
+#[error("participant {} declined to sign: {reason}", hex::encode(.pubkey))]
+pub struct ParticipantDeclined {
+    /// The communication public key of the participant.
+    pub pubkey: Vec<u8>,
+    /// The reason given by the participant.
+    pub reason: String,
+}
+
+/// Some participants did not respond within the
+/// [`ProcessedArgs::participant_timeout`].
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "participants did not respond in time: {}",
+    .pubkeys.iter().map(hex::encode).collect::<Vec<_>>().join(", ")
+)]
+pub struct ParticipantsTimedOut {
+    /// The communication public keys of the participants which did not
+    /// respond.
+    pub pubkeys: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "self::serde")]
 pub struct SessionStateArgs {
     pub num_messages: usize,
     pub num_signers: usize,
+    /// Whether to reject participants which send commitments or signature
+    /// shares that differ from the ones they have already sent.
+    pub strict: bool,
+    /// The communication public keys of the participants expected to sign
+    /// each message, if they differ between messages. If `None`, all
+    /// `num_signers` participants sign all messages.
+    #[serde(default)]
+    pub message_signers: Option<Vec<HashSet<Vec<u8>>>>,
+}
+
+impl SessionStateArgs {
+    /// Returns the indices of the messages signed by the participant with the
+    /// given communication public key. Participants send one commitment and
+    /// one signature share for each of them, in that order.
+    fn message_indices(&self, pubkey: &[u8]) -> Vec<usize> {
+        match &self.message_signers {
+            Some(message_signers) => (0..self.num_messages)
+                .filter(|i| message_signers.get(*i).is_some_and(|s| s.contains(pubkey)))
+                .collect(),
+            None => (0..self.num_messages).collect(),
+        }
+    }
 }
 
 /// The current state of a session.
 ///
 /// This can be used by a Coordinator to help maitain state and handle
-/// messages from the Participants.
-#[derive(derivative::Derivative)]
+/// messages from the Participants. It can be saved with [`SessionState::save()`]
+/// and restored with [`SessionState::load()`] to resume a session later.
+#[derive(derivative::Derivative, PartialEq, Serialize, Deserialize)]
 #[derivative(Debug)]
+#[serde(crate = "self::serde")]
+#[serde(bound = "C: Ciphersuite")]
 pub enum SessionState<C: Ciphersuite> {
     /// Waiting for participants to send their commitments.
     WaitingForCommitments {
@@ -46,6 +114,7 @@ pub enum SessionState<C: Ciphersuite> {
         /// Commitments sent by participants so far, for each message being
         /// signed.
         commitments: HashMap<Identifier<C>, Vec<SigningCommitments<C>>>,
+        #[serde(with = "pubkey_map")]
         pubkeys: HashMap<Vec<u8>, Identifier<C>>,
     },
     /// Commitments have been sent by all participants. Coordinator can create
@@ -57,6 +126,7 @@ pub enum SessionState<C: Ciphersuite> {
         /// All commitments sent by participants, for each message being signed.
         commitments: HashMap<Identifier<C>, Vec<SigningCommitments<C>>>,
         /// Pubkey -> Identifier mapping.
+        #[serde(with = "pubkey_map")]
         pubkeys: HashMap<Vec<u8>, Identifier<C>>,
         /// Signature shares sent by participants so far, for each message being
         /// signed.
@@ -69,15 +139,74 @@ pub enum SessionState<C: Ciphersuite> {
         args: SessionStateArgs,
         /// Signature shares sent by participants, for each message being signed.
         signature_shares: HashMap<Identifier<C>, Vec<SignatureShare<C>>>,
+        /// Pubkey -> Identifier mapping.
+        #[serde(default, with = "pubkey_map")]
+        pubkeys: HashMap<Vec<u8>, Identifier<C>>,
     },
 }
 
+/// (De)serializes maps keyed by communication public keys with hex-encoded
+/// keys, since JSON only supports string keys.
+mod pubkey_map {
+    use std::collections::HashMap;
+
+    use frost_core::serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer, V: Serialize>(
+        map: &HashMap<Vec<u8>, V>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(k, v)| (hex::encode(k), v)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, V: Deserialize<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<Vec<u8>, V>, D::Error> {
+        HashMap::<String, V>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(k, v)| Ok((hex::decode(k).map_err(D::Error::custom)?, v)))
+            .collect()
+    }
+}
+
 impl<C: Ciphersuite> SessionState<C> {
     /// Create a new SessionState for the given number of messages and signers.
-    pub fn new(num_messages: usize, num_signers: usize) -> Self {
+    ///
+    /// If `strict` is true, a participant which sends commitments or signature
+    /// shares different from the ones it has already sent is rejected with an
+    /// error. Otherwise the new values silently replace the old ones. In both
+    /// cases, identical retries are accepted.
+    pub fn new(num_messages: usize, num_signers: usize, strict: bool) -> Self {
         let args = SessionStateArgs {
             num_messages,
             num_signers,
+            strict,
+            message_signers: None,
+        };
+        Self::WaitingForCommitments {
+            args,
+            commitments: Default::default(),
+            pubkeys: Default::default(),
+        }
+    }
+
+    /// Create a new SessionState where each message is signed by its own set
+    /// of participants, identified by their communication public keys. The
+    /// sets may overlap; commitments are ready once every participant in any
+    /// of them has sent theirs.
+    ///
+    /// See [`SessionState::new()`] for the meaning of `strict`.
+    pub fn new_with_message_signers(message_signers: Vec<HashSet<Vec<u8>>>, strict: bool) -> Self {
+        let num_signers = message_signers
+            .iter()
+            .flatten()
+            .collect::<HashSet<_>>()
+            .len();
+        let args = SessionStateArgs {
+            num_messages: message_signers.len(),
+            num_signers,
+            strict,
+            message_signers: Some(message_signers),
         };
         Self::WaitingForCommitments {
             args,
@@ -86,13 +215,45 @@ impl<C: Ciphersuite> SessionState<C> {
         }
     }
 
+    /// Save the state to the given file, replacing it atomically so that an
+    /// interruption never leaves a truncated state behind.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, serde_json::to_vec(self)?)?;
+        std::fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+
+    /// Load a state saved with [`SessionState::save()`].
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
     /// Handle a Msg received from a participant.
     ///
     /// This should be called for new Msgs until [`are_commitments_ready()`]
     /// returns true, and after the SigningPackage is sent to the participants,
     /// it should be called for new Msgs until [`are_signature_shares_ready()`]
     /// returns true.
+    ///
+    /// Returns a [`ParticipantReportedError`] if the participant reported an
+    /// error, or a [`ParticipantDeclined`] if it declined to sign, in which
+    /// cases the session should be aborted.
     pub fn recv(&mut self, msg: Msg) -> Result<(), Box<dyn Error>> {
+        if let Ok(error) = serde_json::from_slice::<ParticipantErrorArgs>(&msg.msg) {
+            return Err(ParticipantReportedError {
+                pubkey: msg.sender,
+                reason: error.participant_error,
+            }
+            .into());
+        }
+        if let Ok(declined) = serde_json::from_slice::<ParticipantDeclinedArgs>(&msg.msg) {
+            return Err(ParticipantDeclined {
+                pubkey: msg.sender,
+                reason: declined.participant_declined,
+            }
+            .into());
+        }
         match self {
             SessionState::WaitingForCommitments { .. } => {
                 let send_commitments_args: SendCommitmentsArgs<C> =
@@ -121,14 +282,28 @@ impl<C: Ciphersuite> SessionState<C> {
             pubkeys: usernames,
         } = self
         {
-            if send_commitments_args.commitments.len() != args.num_messages {
+            let message_indices = args.message_indices(&pubkey);
+            if args.message_signers.is_some() && message_indices.is_empty() {
+                return Err(eyre!("participant is not expected to sign any message").into());
+            }
+            if send_commitments_args.commitments.len() != message_indices.len() {
                 return Err(eyre!("wrong number of commitments").into());
             }
 
             // Add commitment to map.
-            // Currently ignores the possibility of overwriting previous values
-            // (it seems better to ignore overwrites, which could be caused by
-            // poor networking connectivity leading to retries)
+            // Unless in strict mode, this ignores the possibility of
+            // overwriting previous values (it seems better to ignore
+            // overwrites, which could be caused by poor networking
+            // connectivity leading to retries). In strict mode, a participant
+            // is not allowed to change its commitments e.g. after seeing the
+            // others'.
+            if args.strict
+                && commitments
+                    .get(&send_commitments_args.identifier)
+                    .is_some_and(|c| *c != send_commitments_args.commitments)
+            {
+                return Err(eyre!("participant sent different commitments twice").into());
+            }
             commitments.insert(
                 send_commitments_args.identifier,
                 send_commitments_args.commitments,
@@ -146,7 +321,80 @@ impl<C: Ciphersuite> SessionState<C> {
             }
             Ok(())
         } else {
-            panic!("wrong state");
+            Err(eyre!("handle_commitments() called in wrong state").into())
+        }
+    }
+
+    /// Returns how many participants sent the messages expected in the
+    /// current phase (commitments, then signature shares) so far, and how
+    /// many are expected in total, in that order.
+    pub fn progress(&self) -> (usize, usize) {
+        match self {
+            SessionState::WaitingForCommitments {
+                args, commitments, ..
+            } => (commitments.len(), args.num_signers),
+            SessionState::WaitingForSignatureShares {
+                commitments,
+                signature_shares,
+                ..
+            } => (signature_shares.len(), commitments.len()),
+            SessionState::SignatureSharesReady {
+                signature_shares, ..
+            } => (signature_shares.len(), signature_shares.len()),
+        }
+    }
+
+    /// Returns the public keys of the participants which did not send the
+    /// messages expected in the current phase yet. While waiting for
+    /// commitments, these are the ones in `signers` which did not send theirs;
+    /// while waiting for signature shares, the ones which sent commitments but
+    /// no signature shares.
+    pub fn missing_pubkeys(&self, signers: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        let mut missing: Vec<Vec<u8>> = match self {
+            SessionState::WaitingForCommitments { pubkeys, .. } => signers
+                .iter()
+                .filter(|pubkey| !pubkeys.contains_key(*pubkey))
+                .cloned()
+                .collect(),
+            SessionState::WaitingForSignatureShares {
+                pubkeys,
+                signature_shares,
+                ..
+            } => pubkeys
+                .iter()
+                .filter(|(_, id)| !signature_shares.contains_key(id))
+                .map(|(pubkey, _)| pubkey.clone())
+                .collect(),
+            SessionState::SignatureSharesReady { .. } => Vec::new(),
+        };
+        missing.sort();
+        missing
+    }
+
+    /// Stop waiting for commitments, proceeding with the participants which
+    /// sent theirs so far. Afterwards, [`has_commitments()`] returns `true`.
+    pub fn finish_commitments(&mut self) -> Result<(), Box<dyn Error>> {
+        if let SessionState::WaitingForCommitments {
+            args,
+            commitments,
+            pubkeys,
+        } = self
+        {
+            if commitments.is_empty() {
+                return Err(eyre!("no participant sent commitments").into());
+            }
+            *self = SessionState::WaitingForSignatureShares {
+                args: SessionStateArgs {
+                    num_signers: commitments.len(),
+                    ..args.clone()
+                },
+                commitments: commitments.clone(),
+                pubkeys: pubkeys.clone(),
+                signature_shares: Default::default(),
+            };
+            Ok(())
+        } else {
+            Err(eyre!("finish_commitments() called in wrong state").into())
         }
     }
 
@@ -180,13 +428,15 @@ impl<C: Ciphersuite> SessionState<C> {
             // Convert the BTreeMap<Identifier, Vec<SigningCommitments>> map
             // into a Vec<BTreeMap<Identifier, SigningCommitments>> map to make
             // it easier for the coordinator to build the SigningPackages.
-            let commitments: Vec<BTreeMap<Identifier<C>, SigningCommitments<C>>> = (0..args
-                .num_messages)
-                .map(|i| commitments.iter().map(|(id, c)| (*id, c[i])).collect())
-                .collect();
-            Ok((commitments, pubkeys.clone()))
+            let mut per_message = vec![BTreeMap::new(); args.num_messages];
+            for (pubkey, id) in pubkeys.iter() {
+                for (c, i) in commitments[id].iter().zip(args.message_indices(pubkey)) {
+                    per_message[i].insert(*id, *c);
+                }
+            }
+            Ok((per_message, pubkeys.clone()))
         } else {
-            panic!("wrong state");
+            Err(eyre!("commitments() called in wrong state").into())
         }
     }
 
@@ -199,26 +449,36 @@ impl<C: Ciphersuite> SessionState<C> {
     /// Handle signature share sent by a participant.
     fn handle_signature_share(
         &mut self,
-        _username: Vec<u8>,
+        pubkey: Vec<u8>,
         send_signature_shares_args: SendSignatureSharesArgs<C>,
     ) -> Result<(), Box<dyn Error>> {
         if let SessionState::WaitingForSignatureShares {
             args,
             commitments,
+            pubkeys,
             signature_shares,
-            ..
         } = self
         {
-            if send_signature_shares_args.signature_share.len() != args.num_messages {
+            if send_signature_shares_args.signature_share.len()
+                != args.message_indices(&pubkey).len()
+            {
                 return Err(eyre!("wrong number of signature shares").into());
             }
             if !commitments.contains_key(&send_signature_shares_args.identifier) {
                 return Err(eyre!("invalid identifier").into());
             }
 
-            // Currently ignoring the possibility of overwriting previous values
-            // (it seems better to ignore overwrites, which could be caused by
-            // poor networking connectivity leading to retries)
+            // Unless in strict mode, ignore the possibility of overwriting
+            // previous values (it seems better to ignore overwrites, which
+            // could be caused by poor networking connectivity leading to
+            // retries)
+            if args.strict
+                && signature_shares
+                    .get(&send_signature_shares_args.identifier)
+                    .is_some_and(|s| *s != send_signature_shares_args.signature_share)
+            {
+                return Err(eyre!("participant sent different signature shares twice").into());
+            }
             signature_shares.insert(
                 send_signature_shares_args.identifier,
                 send_signature_shares_args.signature_share,
@@ -230,11 +490,12 @@ impl<C: Ciphersuite> SessionState<C> {
                 *self = SessionState::SignatureSharesReady {
                     args: args.clone(),
                     signature_shares: signature_shares.clone(),
+                    pubkeys: pubkeys.clone(),
                 }
             }
             Ok(())
         } else {
-            panic!("wrong state");
+            Err(eyre!("handle_signature_share() called in wrong state").into())
         }
     }
 
@@ -247,17 +508,24 @@ impl<C: Ciphersuite> SessionState<C> {
         if let SessionState::SignatureSharesReady {
             args,
             signature_shares,
+            pubkeys,
         } = self
         {
             // Convert the BTreeMap<Identifier, Vec<SigningCommitments>> map
             // into a Vec<BTreeMap<Identifier, SigningCommitments>> map to make
             // it easier for the coordinator to build the SigningPackages.
-            let signature_shares = (0..args.num_messages)
-                .map(|i| signature_shares.iter().map(|(id, s)| (*id, s[i])).collect())
-                .collect();
-            Ok(signature_shares)
+            let mut per_message = vec![BTreeMap::new(); args.num_messages];
+            for (pubkey, id) in pubkeys.iter() {
+                for (s, i) in signature_shares[id]
+                    .iter()
+                    .zip(args.message_indices(pubkey))
+                {
+                    per_message[i].insert(*id, *s);
+                }
+            }
+            Ok(per_message)
         } else {
-            panic!("wrong state");
+            Err(eyre!("signature_shares() called in wrong state").into())
         }
     }
 }
@@ -269,33 +537,179 @@ pub struct HTTPComms<C: Ciphersuite> {
     access_token: Option<String>,
     args: ProcessedArgs<C>,
     state: SessionState<C>,
+    /// Whether `state` was restored from the state file.
+    state_restored: bool,
     pubkeys: HashMap<Vec<u8>, Identifier<C>>,
     // The "send" Noise objects by pubkey of recipients.
     send_noise: Option<HashMap<Vec<u8>, Noise>>,
     // The "receive" Noise objects by pubkey of senders.
     recv_noise: Option<HashMap<Vec<u8>, Noise>>,
+    // The pubkeys of the participants which restarted their Noise session,
+    // which is only allowed once.
+    restarted: HashSet<Vec<u8>>,
+    // The index of the next message sent to the coordinator to process. The
+    // earlier ones were received with `peek` and are removed from the server
+    // with `ack_received()`.
+    received_index: u64,
     _phantom: PhantomData<C>,
 }
 
 impl<C: Ciphersuite> HTTPComms<C> {
     pub fn new(args: &ProcessedArgs<C>) -> Result<Self, Box<dyn Error>> {
-        let client = reqwest::Client::new();
+        let client = build_client(
+            args.cacert.as_deref(),
+            args.insecure_skip_verify,
+            args.proxy.as_deref(),
+        )?;
+        let state = match &args.state_file {
+            Some(path) if path.exists() => {
+                if args.session_id.is_none() {
+                    return Err(eyre!(
+                        "the session ID must be specified to resume the session saved in {}",
+                        path.display()
+                    )
+                    .into());
+                }
+                let state = SessionState::load(path)?;
+                if state.has_signature_shares() {
+                    return Err(eyre!("the saved session was already completed").into());
+                }
+                Some(state)
+            }
+            _ => None,
+        };
         Ok(Self {
             client,
             host_port: format!("https://{}:{}", args.ip, args.port),
             session_id: None,
             access_token: None,
             args: args.clone(),
-            state: SessionState::new(args.messages.len(), args.num_signers as usize),
+            state_restored: state.is_some(),
+            state: state.unwrap_or_else(|| {
+                SessionState::new(args.messages.len(), args.num_signers as usize, args.strict)
+            }),
             pubkeys: Default::default(),
             send_noise: None,
             recv_noise: None,
+            restarted: HashSet::new(),
+            received_index: 0,
             _phantom: Default::default(),
         })
     }
 
-    // Encrypts a message for a given recipient.
-    fn encrypt(&mut self, recipient: &Vec<u8>, msg: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+    // Saves the session state to the state file, if any. Completed states are
+    // not saved since there is nothing left to resume.
+    fn save_state(&self) -> Result<(), Box<dyn Error>> {
+        match &self.args.state_file {
+            Some(path) if !self.state.has_signature_shares() => self.state.save(path),
+            _ => Ok(()),
+        }
+    }
+
+    // Receives and processes the messages sent to the coordinator since the
+    // last call. Returns false if the server no longer knows about the
+    // session.
+    //
+    // Messages are only peeked at, and removed from the server with
+    // `ack_received()` once the current phase is complete. If the coordinator
+    // is interrupted before that, they are received again, along with the
+    // Noise handshakes they start with, when it resumes the session.
+    async fn receive_msgs(&mut self) -> Result<bool, Box<dyn Error>> {
+        let Some(r) = receive(
+            &self.client,
+            &self.host_port,
+            self.access_token
+                .as_ref()
+                .expect("must have been set before"),
+            &frostd::ReceiveArgs {
+                session_id: self.session_id.expect("must have been set before"),
+                as_coordinator: true,
+                peek: true,
+            },
+        )
+        .await?
+        else {
+            return Ok(false);
+        };
+        // Skip the messages already processed by previous calls.
+        let skip = self.received_index.saturating_sub(r.first_index) as usize;
+        let next_index = r.first_index + r.msgs.len() as u64;
+        for msg in r.msgs.into_iter().skip(skip) {
+            if let Some(msg) = self.decrypt(msg)? {
+                self.state.recv(msg)?;
+                self.report_progress();
+            }
+        }
+        self.received_index = next_index;
+        Ok(true)
+    }
+
+    // Removes the messages processed so far from the server, once the current
+    // phase is complete and they are no longer needed to resume it.
+    async fn ack_received(&self) -> Result<(), Box<dyn Error>> {
+        ack(
+            &self.client,
+            &self.host_port,
+            self.access_token
+                .as_ref()
+                .expect("must have been set before"),
+            &frostd::AckArgs {
+                session_id: self.session_id.expect("must have been set before"),
+                as_coordinator: true,
+                index: self.received_index,
+            },
+        )
+        .await
+    }
+
+    // Checks if the participant timeout of the current phase, which started
+    // at `start`, has expired. If so, and if `proceed` is true and enough
+    // participants responded to reach the threshold, moves on without the
+    // others; otherwise returns a ParticipantsTimedOut error.
+    fn check_participant_timeout(
+        &mut self,
+        start: Instant,
+        proceed: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(timeout) = self.args.participant_timeout else {
+            return Ok(());
+        };
+        if start.elapsed() < timeout {
+            return Ok(());
+        }
+        let missing = self.state.missing_pubkeys(&self.args.signers);
+        let (received, _) = self.state.progress();
+        match self.args.threshold {
+            Some(threshold) if proceed && received >= threshold as usize => {
+                eprintln!(
+                    "\nProceeding without the participants which did not respond in time: {}",
+                    missing
+                        .iter()
+                        .map(hex::encode)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                self.state.finish_commitments()
+            }
+            _ => Err(ParticipantsTimedOut { pubkeys: missing }.into()),
+        }
+    }
+
+    // Reports the progress of the current phase to the progress hook, if any.
+    fn report_progress(&self) {
+        if let Some(progress) = &self.args.progress {
+            let (received, expected) = self.state.progress();
+            progress(received, expected);
+        }
+    }
+
+    // Encrypts a message for a given recipient, returning the chunks that
+    // must be sent in order.
+    fn encrypt(
+        &mut self,
+        recipient: &Vec<u8>,
+        msg: Vec<u8>,
+    ) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
         let noise_map = self
             .send_noise
             .as_mut()
@@ -303,16 +717,14 @@ impl<C: Ciphersuite> HTTPComms<C> {
         let noise = noise_map
             .get_mut(recipient)
             .ok_or_eyre("unknown recipient")?;
-        let mut encrypted = vec![0; 65535];
-        let len = noise.write_message(&msg, &mut encrypted)?;
-        encrypted.truncate(len);
-        Ok(encrypted)
+        noise.encrypt(&msg)
     }
 
-    // Decrypts a message.
+    // Decrypts a message chunk. Returns None if more chunks from the same
+    // sender are needed to complete the message.
     // Note that this authenticates the `sender` in the `Msg` struct; if the
     // sender is tampered with, the message would fail to decrypt.
-    fn decrypt(&mut self, msg: Msg) -> Result<Msg, Box<dyn Error>> {
+    fn decrypt(&mut self, msg: Msg) -> Result<Option<Msg>, Box<dyn Error>> {
         let noise_map = self
             .recv_noise
             .as_mut()
@@ -320,14 +732,60 @@ impl<C: Ciphersuite> HTTPComms<C> {
         let noise = noise_map
             .get_mut(&msg.sender)
             .ok_or_eyre("unknown sender")?;
-        let mut decrypted = vec![0; 65535];
-        decrypted.resize(65535, 0);
-        let len = noise.read_message(&msg.msg, &mut decrypted)?;
-        decrypted.truncate(len);
-        Ok(Msg {
+        let decrypted = match noise.decrypt(&msg.msg) {
+            Ok(decrypted) => decrypted,
+            Err(e)
+                if matches!(
+                    e.downcast_ref::<DecryptError>(),
+                    Some(DecryptError::Invalid { .. })
+                ) && !self.restarted.contains(&msg.sender) =>
+            {
+                // The participant may have been restarted since its last
+                // message (to sign offline), in which case it starts a new
+                // Noise session, marked as such. The mark is authenticated
+                // by the handshake, which is bound to this FROST session, and
+                // a restart is only accepted once, so that the session can't
+                // be reset by replaying the participant's messages.
+                let mut restarted = self.new_recv_noise(&msg.sender)?;
+                let decrypted = match restarted.decrypt(&msg.msg) {
+                    Ok(decrypted) if restarted.restarted() => decrypted,
+                    _ => return Err(e),
+                };
+                self.restarted.insert(msg.sender.clone());
+                self.recv_noise
+                    .as_mut()
+                    .expect("was checked above")
+                    .insert(msg.sender.clone(), restarted);
+                decrypted
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(decrypted.map(|decrypted| Msg {
             sender: msg.sender,
             msg: decrypted,
-        })
+        }))
+    }
+
+    // Creates the "receive" Noise object for the participant with the given
+    // pubkey.
+    fn new_recv_noise(&self, pubkey: &[u8]) -> Result<Noise, Box<dyn Error>> {
+        let (Some(comm_privkey), Some(comm_participant_pubkey_getter)) = (
+            &self.args.comm_privkey,
+            &self.args.comm_participant_pubkey_getter,
+        ) else {
+            return Err(
+                eyre!("comm_privkey and comm_participant_pubkey_getter must be specified").into(),
+            );
+        };
+        let comm_participant_pubkey = comm_participant_pubkey_getter(&pubkey.to_vec()).ok_or_eyre("A participant in specified FROST session is not registered in the coordinator's address book")?;
+        let session_id = self.session_id.ok_or_eyre("session ID not set")?;
+        Ok(Noise::new(
+            noise_builder(&session_id)
+                .local_private_key(comm_privkey)
+                .remote_public_key(&comm_participant_pubkey)
+                .build_responder()?,
+        )
+        .with_compression(self.args.compress))
     }
 }
 
@@ -345,7 +803,7 @@ impl<C: Ciphersuite + 'static> Comms<C> for HTTPComms<C> {
             .client
             .post(format!("{}/challenge", self.host_port))
             .json(&frostd::ChallengeArgs {})
-            .send()
+            .send_with_retries()
             .await?
             .json::<frostd::ChallengeOutput>()
             .await?
@@ -374,7 +832,7 @@ impl<C: Ciphersuite + 'static> Comms<C> for HTTPComms<C> {
                         .ok_or_eyre("comm_pubkey must be specified")?,
                     signature: signature.to_vec(),
                 })
-                .send()
+                .send_with_retries()
                 .await?
                 .json::<frostd::LoginOutput>()
                 .await?
@@ -382,26 +840,69 @@ impl<C: Ciphersuite + 'static> Comms<C> for HTTPComms<C> {
                 .to_string(),
         );
 
-        let r = self
-            .client
-            .post(format!("{}/create_new_session", self.host_port))
-            .bearer_auth(self.access_token.as_ref().expect("was just set"))
-            .json(&frostd::CreateNewSessionArgs {
-                pubkeys: self.args.signers.iter().cloned().map(PublicKey).collect(),
-                message_count: 1,
-            })
-            .send()
-            .await?
-            .json::<frostd::CreateNewSessionOutput>()
-            .await?;
+        let session_id = if let Some(session_id) = self.args.session_id {
+            // Resume an existing session. Messages sent by participants while
+            // waiting for their commitments are kept queued in the server
+            // until all of them were received, so they are handled as usual,
+            // even if the previous run had already received some of them.
+            let session_info = self
+                .client
+                .post(format!("{}/get_session_info", self.host_port))
+                .bearer_auth(self.access_token.as_ref().expect("was just set"))
+                .json(&frostd::GetSessionInfoArgs { session_id })
+                .send_with_retries()
+                .await?
+                .json::<frostd::GetSessionInfoOutput>()
+                .await?;
+            if Some(&session_info.coordinator_pubkey) != self.args.comm_pubkey.as_ref() {
+                return Err(eyre!("the session to resume was not created by this user").into());
+            }
+            check_session_ciphersuite::<C>(&session_info)?;
+            if self.args.signers.is_empty() {
+                self.args.signers = session_info.pubkeys.into_iter().map(|p| p.0).collect();
+                if !self.state_restored {
+                    self.state = SessionState::new(
+                        self.args.messages.len(),
+                        self.args.signers.len(),
+                        self.args.strict,
+                    );
+                }
+            }
+            session_id
+        } else {
+            let r = self
+                .client
+                .post(format!("{}/create_new_session", self.host_port))
+                .bearer_auth(self.access_token.as_ref().expect("was just set"))
+                .json(&frostd::CreateNewSessionArgs {
+                    pubkeys: self.args.signers.iter().cloned().map(PublicKey).collect(),
+                    message_count: 1,
+                    coordinators: vec![],
+                    close_quorum: None,
+                    ciphersuite: Some(C::ID.to_string()),
+                    message_commitment: self.args.message_commitment_salt.as_ref().map(|salt| {
+                        let messages: Vec<&[u8]> =
+                            self.args.messages.iter().map(|m| &m[..]).collect();
+                        frostd::commit_to_messages(salt, &messages)
+                    }),
+                })
+                .send_with_retries()
+                .await?
+                .json::<frostd::CreateNewSessionOutput>()
+                .await?;
 
-        if self.args.signers.is_empty() {
-            eprintln!(
-                "Send the following session ID to participants: {}",
-                r.session_id
-            );
+            if self.args.signers.is_empty() {
+                eprintln!(
+                    "Send the following session ID to participants: {}",
+                    r.session_id
+                );
+            }
+            r.session_id
+        };
+        self.session_id = Some(session_id);
+        if let Some(on_session) = &self.args.on_session {
+            on_session(session_id);
         }
-        self.session_id = Some(r.session_id);
 
         let (Some(comm_privkey), Some(comm_participant_pubkey_getter)) = (
             &self.args.comm_privkey,
@@ -418,28 +919,14 @@ impl<C: Ciphersuite + 'static> Comms<C> for HTTPComms<C> {
         let mut recv_noise_map = HashMap::new();
         for pubkey in &self.args.signers {
             let comm_participant_pubkey = comm_participant_pubkey_getter(pubkey).ok_or_eyre("A participant in specified FROST session is not registered in the coordinator's address book")?;
-            let builder = snow::Builder::new(
-                "Noise_K_25519_ChaChaPoly_BLAKE2s"
-                    .parse()
-                    .expect("should be a valid cipher"),
-            );
             let send_noise = Noise::new(
-                builder
+                noise_builder(&session_id)
                     .local_private_key(comm_privkey)
                     .remote_public_key(&comm_participant_pubkey)
                     .build_initiator()?,
-            );
-            let builder = snow::Builder::new(
-                "Noise_K_25519_ChaChaPoly_BLAKE2s"
-                    .parse()
-                    .expect("should be a valid cipher"),
-            );
-            let recv_noise = Noise::new(
-                builder
-                    .local_private_key(comm_privkey)
-                    .remote_public_key(&comm_participant_pubkey)
-                    .build_responder()?,
-            );
+            )
+            .with_compression(self.args.compress);
+            let recv_noise = self.new_recv_noise(pubkey)?;
             send_noise_map.insert(pubkey.clone(), send_noise);
             recv_noise_map.insert(pubkey.clone(), recv_noise);
         }
@@ -448,30 +935,36 @@ impl<C: Ciphersuite + 'static> Comms<C> for HTTPComms<C> {
 
         eprint!("Waiting for participants to send their commitments...");
 
-        loop {
-            let r = self
-                .client
-                .post(format!("{}/receive", self.host_port))
-                .bearer_auth(self.access_token.as_ref().expect("was just set"))
-                .json(&frostd::ReceiveArgs {
-                    session_id: r.session_id,
-                    as_coordinator: true,
-                })
-                .send()
-                .await?
-                .json::<frostd::ReceiveOutput>()
-                .await?;
-            for msg in r.msgs {
-                let msg = self.decrypt(msg)?;
-                self.state.recv(msg)?;
+        // If the state was restored, some or all of the commitments may have
+        // been received already.
+        let mut keep_alive = KeepAlive::new(self.args.keep_alive);
+        let start = Instant::now();
+        while !self.state.has_commitments() {
+            if !self.receive_msgs().await? {
+                return Err(eyre!(
+                    "the session was closed before all participants sent their commitments"
+                )
+                .into());
             }
+            if !self.state.has_commitments() {
+                self.check_participant_timeout(start, true)?;
+            }
+            self.save_state()?;
             tokio::time::sleep(Duration::from_secs(2)).await;
             eprint!(".");
-            if self.state.has_commitments() {
-                break;
-            }
+            keep_alive
+                .tick(
+                    &self.client,
+                    &self.host_port,
+                    self.access_token.as_ref().expect("was just set"),
+                    session_id,
+                )
+                .await?;
         }
         eprintln!();
+        // All the commitments were received, and saved to the state file if
+        // any, so they no longer need to be kept in the server.
+        self.ack_received().await?;
 
         let (commitments, pubkeys) = self.state.commitments()?;
         self.pubkeys = pubkeys;
@@ -490,66 +983,79 @@ impl<C: Ciphersuite + 'static> Comms<C> for HTTPComms<C> {
         eprintln!("Sending SigningPackage to participants...");
         let send_signing_package_args = SendSigningPackageArgs {
             signing_package: vec![signing_package.clone()],
-            aux_msg: Default::default(),
+            aux_msg: self.args.aux_msg.clone(),
             randomizer: randomizer.map(|r| vec![r]).unwrap_or_default(),
+            seed: self.args.aux_msg_seed.clone(),
+            message_commitment_salt: self
+                .args
+                .message_commitment_salt
+                .clone()
+                .unwrap_or_default(),
         };
         // We need to send a message separately for each recipient even if the
         // message is the same, because they are (possibly) encrypted
         // individually for each recipient.
         let pubkeys: Vec<_> = self.pubkeys.keys().cloned().collect();
         for recipient in pubkeys {
-            let msg = self.encrypt(&recipient, serde_json::to_vec(&send_signing_package_args)?)?;
-            let _r = self
-                .client
-                .post(format!("{}/send", self.host_port))
-                .bearer_auth(
-                    self.access_token
-                        .as_ref()
-                        .expect("must have been set before"),
-                )
-                .json(&frostd::SendArgs {
-                    session_id: self.session_id.unwrap(),
-                    recipients: vec![frostd::PublicKey(recipient.clone())],
-                    msg,
-                })
-                .send()
-                .await?
-                .bytes()
-                .await?;
+            let msgs = self.encrypt(&recipient, serde_json::to_vec(&send_signing_package_args)?)?;
+            for msg in msgs {
+                let response = self
+                    .client
+                    .post(format!("{}/send", self.host_port))
+                    .bearer_auth(
+                        self.access_token
+                            .as_ref()
+                            .expect("must have been set before"),
+                    )
+                    .json(&frostd::SendArgs {
+                        session_id: self.session_id.unwrap(),
+                        recipients: vec![frostd::PublicKey(recipient.clone())],
+                        msg,
+                    })
+                    .send_with_retries()
+                    .await?;
+                check_response(response).await?;
+            }
         }
 
         eprintln!("Waiting for participants to send their SignatureShares...");
 
-        loop {
-            let r = self
-                .client
-                .post(format!("{}/receive", self.host_port))
-                .bearer_auth(
+        let mut keep_alive = KeepAlive::new(self.args.keep_alive);
+        let start = Instant::now();
+        while !self.state.has_signature_shares() {
+            if !self.receive_msgs().await? {
+                return Err(eyre!(
+                    "the session was closed before all participants sent their signature shares"
+                )
+                .into());
+            }
+            self.save_state()?;
+            if !self.state.has_signature_shares() {
+                self.check_participant_timeout(start, false)?;
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            eprint!(".");
+            keep_alive
+                .tick(
+                    &self.client,
+                    &self.host_port,
                     self.access_token
                         .as_ref()
                         .expect("must have been set before"),
+                    self.session_id.unwrap(),
                 )
-                .json(&frostd::ReceiveArgs {
-                    session_id: self.session_id.unwrap(),
-                    as_coordinator: true,
-                })
-                .send()
-                .await?
-                .json::<frostd::ReceiveOutput>()
                 .await?;
-            for msg in r.msgs {
-                let msg = self.decrypt(msg)?;
-                self.state.recv(msg)?;
-            }
-            tokio::time::sleep(Duration::from_secs(2)).await;
-            eprint!(".");
-            if self.state.has_signature_shares() {
-                break;
-            }
         }
         eprintln!();
 
-        let _r = self
+        // The session is complete, so there is nothing left to resume.
+        if let Some(path) = &self.args.state_file {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        let response = self
             .client
             .post(format!("{}/close_session", self.host_port))
             .bearer_auth(
@@ -560,10 +1066,11 @@ impl<C: Ciphersuite + 'static> Comms<C> for HTTPComms<C> {
             .json(&frostd::CloseSessionArgs {
                 session_id: self.session_id.unwrap(),
             })
-            .send()
+            .send_with_retries()
             .await?;
+        check_response(response).await?;
 
-        let _r = self
+        let response = self
             .client
             .post(format!("{}/logout", self.host_port))
             .bearer_auth(
@@ -571,8 +1078,9 @@ impl<C: Ciphersuite + 'static> Comms<C> for HTTPComms<C> {
                     .as_ref()
                     .expect("must have been set before"),
             )
-            .send()
+            .send_with_retries()
             .await?;
+        check_response(response).await?;
 
         let signature_shares = self.state.signature_shares()?;
 
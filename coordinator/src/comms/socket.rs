@@ -139,15 +139,21 @@ impl<C: Ciphersuite> Comms<C> for SocketComms<C> {
                 .await
                 .ok_or(eyre!("Did not receive all commitments"))?;
             let message: Message<C> = serde_json::from_slice(&data)?;
-            if let Message::SignatureShare(signature_share) = message {
-                let identifier = self
-                    .endpoints
-                    .iter()
-                    .find_map(|(i, e)| if *e == endpoint { Some(i) } else { None })
-                    .ok_or(eyre!("Unknown participant"))?;
-                signature_shares.insert(*identifier, signature_share);
-            } else {
-                Err(eyre!("Expected IdentifiedCommitments message"))?;
+            let identifier = self
+                .endpoints
+                .iter()
+                .find_map(|(i, e)| if *e == endpoint { Some(i) } else { None })
+                .ok_or(eyre!("Unknown participant"))?;
+            match message {
+                Message::SignatureShare(signature_share) => {
+                    signature_shares.insert(*identifier, signature_share);
+                }
+                Message::Declined { reason } => Err(eyre!(
+                    "participant {} declined to sign: {}",
+                    hex::encode(identifier.serialize()),
+                    reason
+                ))?,
+                _ => Err(eyre!("Expected IdentifiedCommitments message"))?,
             }
         }
         Ok(signature_shares)
@@ -25,10 +25,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // now.
     match r {
         Ok(_) => std::process::exit(0),
-        Err(e) => {
-            eprintln!("{}", e);
-            std::process::exit(1);
-        }
+        Err(e) => participant::errors::exit_with_error(&*e, args.json_errors),
     }
 }
 
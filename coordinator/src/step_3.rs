@@ -1,17 +1,73 @@
 use frost_core::{self as frost, Ciphersuite};
 
-use frost::{Signature, SigningPackage};
-use frost_rerandomized::{RandomizedCiphersuite, Randomizer};
+use eyre::{eyre, OptionExt};
+use frost::{
+    keys::PublicKeyPackage,
+    round2::SignatureShare,
+    serde::{self, Deserialize, Serialize},
+    Identifier, Signature, SigningPackage,
+};
+use frost_rerandomized::{RandomizedCiphersuite, RandomizedParams, Randomizer};
+use participant::round2::aux_msg_randomizer;
 use rand::thread_rng;
 use reddsa::frost::redpallas::PallasBlake2b512;
+use sha2::{Digest, Sha256};
 
 use std::{
-    fs,
+    collections::BTreeMap,
+    error::Error,
+    fs::{self, OpenOptions},
     io::{BufRead, Write},
 };
 
 use crate::{args::ProcessedArgs, comms::Comms, step_1::ParticipantsConfig};
 
+/// A record of a signed message, written to the audit log. Contains only
+/// public data; the secret nonces never leave the participants.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "self::serde")]
+#[serde(bound = "C: Ciphersuite")]
+pub struct AuditRecord<C: Ciphersuite> {
+    /// The signing package sent to the participants.
+    pub signing_package: SigningPackage<C>,
+    /// The index, in `messages`, of the message signed in `signing_package`.
+    pub message_index: usize,
+    /// Every message of the session, including those not signed in this
+    /// record.
+    pub messages: Vec<AuditMessage>,
+    /// The identifiers of the participants who signed.
+    pub identifiers: Vec<Identifier<C>>,
+    /// The randomizer used, if rerandomized. If an auxiliary message was
+    /// sent, this is the randomizer derived from it.
+    pub randomizer: Option<Randomizer<C>>,
+    /// The group signature.
+    pub signature: Signature<C>,
+}
+
+/// A message of the session, identified in the audit log by its hash.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(crate = "self::serde")]
+pub struct AuditMessage {
+    /// The index of the message, in the order it was given.
+    pub index: usize,
+    /// The hex-encoded SHA-256 hash of the message.
+    pub hash: String,
+}
+
+impl AuditMessage {
+    /// Build the audit entries for the given messages, in order.
+    pub fn for_messages(messages: &[Vec<u8>]) -> Vec<Self> {
+        messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| AuditMessage {
+                index,
+                hash: hex::encode(Sha256::digest(message)),
+            })
+            .collect()
+    }
+}
+
 pub async fn step_3<C: RandomizedCiphersuite + 'static>(
     args: &ProcessedArgs<C>,
     comms: &mut dyn Comms<C>,
@@ -39,7 +95,11 @@ async fn request_inputs_signature_shares<C: RandomizedCiphersuite + 'static>(
     signing_package: &SigningPackage<C>,
 ) -> Result<Signature<C>, Box<dyn std::error::Error>> {
     // TODO: support multiple
-    let randomizer = if args.randomizers.is_empty() && C::ID == PallasBlake2b512::ID {
+    // With an auxiliary message, no randomizer is sent: the participants
+    // derive it from the message and `aux_msg_seed`.
+    let randomizer = if !args.aux_msg.is_empty() {
+        None
+    } else if args.randomizers.is_empty() && C::ID == PallasBlake2b512::ID {
         let rng = thread_rng();
         Some(Randomizer::new(rng, signing_package)?)
     } else if args.randomizers.is_empty() {
@@ -52,29 +112,105 @@ async fn request_inputs_signature_shares<C: RandomizedCiphersuite + 'static>(
         .get_signature_shares(input, logger, signing_package, randomizer)
         .await?;
 
-    let group_signature = if let Some(randomizer) = randomizer {
-        let randomizer_params = frost_rerandomized::RandomizedParams::<C>::from_randomizer(
+    // The shares only aggregate if the participants derived the randomizer
+    // from the same auxiliary message.
+    let randomizer = if args.aux_msg.is_empty() {
+        randomizer
+    } else {
+        Some(aux_msg_randomizer(
+            &args.aux_msg_seed,
+            signing_package,
+            &args.aux_msg,
+        )?)
+    };
+
+    let identifiers = signatures_list.keys().cloned().collect();
+
+    let randomized_params = randomizer.map(|randomizer| {
+        RandomizedParams::<C>::from_randomizer(
             participants.pub_key_package.verifying_key(),
             randomizer,
-        );
-
-        frost_rerandomized::aggregate(
-            signing_package,
-            &signatures_list,
-            &participants.pub_key_package,
-            &randomizer_params,
         )
-        .unwrap()
+    });
+
+    let group_signature = aggregate_shares(
+        std::slice::from_ref(signing_package),
+        std::slice::from_ref(&signatures_list),
+        &participants.pub_key_package,
+        randomized_params.as_ref().map(std::slice::from_ref),
+    )?[0];
+
+    if let Some(audit_log) = &args.audit_log {
+        let message_index = args
+            .messages
+            .iter()
+            .position(|m| m == signing_package.message())
+            .ok_or_eyre("the signed message is not one of the session's messages")?;
+        append_audit_record(
+            audit_log,
+            &AuditRecord {
+                signing_package: signing_package.clone(),
+                message_index,
+                messages: AuditMessage::for_messages(&args.messages),
+                identifiers,
+                randomizer,
+                signature: group_signature,
+            },
+        )?;
+    }
+
+    Ok(group_signature)
+}
+
+/// Aggregate the signature shares of each signing package into a group
+/// signature, one for each signing package.
+///
+/// If `randomized_params` is given, the signatures are rerandomized using the
+/// params matching each signing package; otherwise plain FROST signatures are
+/// generated.
+pub fn aggregate_shares<C: RandomizedCiphersuite>(
+    signing_packages: &[SigningPackage<C>],
+    signature_shares: &[BTreeMap<Identifier<C>, SignatureShare<C>>],
+    pubkeys: &PublicKeyPackage<C>,
+    randomized_params: Option<&[RandomizedParams<C>]>,
+) -> Result<Vec<Signature<C>>, Box<dyn Error>> {
+    if signature_shares.len() != signing_packages.len() {
+        return Err(eyre!("number of signature shares and signing packages do not match").into());
+    }
+    let signatures = if let Some(randomized_params) = randomized_params {
+        if randomized_params.len() != signing_packages.len() {
+            return Err(
+                eyre!("number of randomized params and signing packages do not match").into(),
+            );
+        }
+        signing_packages
+            .iter()
+            .zip(signature_shares)
+            .zip(randomized_params)
+            .map(|((p, s), r)| frost_rerandomized::aggregate(p, s, pubkeys, r))
+            .collect::<Result<Vec<_>, _>>()?
     } else {
-        frost::aggregate::<C>(
-            signing_package,
-            &signatures_list,
-            &participants.pub_key_package,
-        )
-        .unwrap()
+        signing_packages
+            .iter()
+            .zip(signature_shares)
+            .map(|(p, s)| frost::aggregate(p, s, pubkeys))
+            .collect::<Result<Vec<_>, _>>()?
     };
+    Ok(signatures)
+}
 
-    Ok(group_signature)
+/// Append a record to the audit log as a single JSON line, flushing it to
+/// disk before returning.
+fn append_audit_record<C: Ciphersuite>(
+    path: &str,
+    record: &AuditRecord<C>,
+) -> Result<(), Box<dyn Error>> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(&line)?;
+    file.sync_data()?;
+    Ok(())
 }
 
 fn print_signature<C: Ciphersuite + 'static>(
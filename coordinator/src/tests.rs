@@ -1,2 +1,5 @@
+mod args;
+mod channel;
 mod common;
+mod session_state;
 mod steps;
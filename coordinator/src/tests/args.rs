@@ -0,0 +1,299 @@
+#![cfg(test)]
+
+use std::{collections::BTreeMap, io::BufWriter};
+
+use coordinator::{
+    args::{validate_aux_msg, Args, MessageEncoding, ProcessedArgs, MAX_MESSAGES_SIZE},
+    cli::cli_for_processed_args,
+};
+use frost::{keys::IdentifierList, round1, round2, SigningPackage};
+use frost_ed25519 as frost;
+use rand::thread_rng;
+use reddsa::frost::redpallas::PallasBlake2b512;
+
+#[tokio::test]
+async fn check_message_file() {
+    let mut rng = thread_rng();
+    let (shares, public_key_package) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let public_key_package_path = temp_dir.path().join("public-key-package.json");
+    std::fs::write(
+        &public_key_package_path,
+        serde_json::to_string(&public_key_package).unwrap(),
+    )
+    .unwrap();
+    // Not valid UTF-8 nor hex, to check if the raw bytes are used
+    let message_path = temp_dir.path().join("message.bin");
+    std::fs::write(&message_path, [0xff, 0x00, 0xfe, b'\n', 0x01]).unwrap();
+
+    let args = Args {
+        cli: true,
+        num_signers: 2,
+        public_key_package: public_key_package_path.to_str().unwrap().to_owned(),
+        message_file: vec![message_path.to_str().unwrap().to_owned()],
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let pargs =
+        ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut "".as_bytes(), &mut buf).unwrap();
+
+    let message = std::fs::read(&message_path).unwrap();
+    assert_eq!(pargs.messages, vec![message.clone()]);
+
+    // Sign the message the coordinator is expected to put in the signing
+    // package, feeding it the commitments and signature shares.
+    let key_packages: BTreeMap<_, _> = shares
+        .into_iter()
+        .take(2)
+        .map(|(id, share)| (id, frost::keys::KeyPackage::try_from(share).unwrap()))
+        .collect();
+    let mut nonces = BTreeMap::new();
+    let mut commitments = BTreeMap::new();
+    for (id, key_package) in &key_packages {
+        let (n, c) = round1::commit(key_package.signing_share(), &mut rng);
+        nonces.insert(*id, n);
+        commitments.insert(*id, c);
+    }
+    let signing_package = SigningPackage::new(commitments.clone(), &message);
+    let mut input = String::new();
+    for (id, c) in &commitments {
+        input += &format!(
+            "{}\n{}\n",
+            hex::encode(id.serialize()),
+            serde_json::to_string(c).unwrap()
+        );
+    }
+    for (id, key_package) in &key_packages {
+        let share = round2::sign(&signing_package, &nonces[id], key_package).unwrap();
+        input += &format!("{}\n", serde_json::to_string(&share).unwrap());
+    }
+
+    let mut buf = BufWriter::new(Vec::new());
+    let signature = cli_for_processed_args(pargs, &mut input.as_bytes(), &mut buf)
+        .await
+        .unwrap();
+
+    public_key_package
+        .verifying_key()
+        .verify(&message, &signature)
+        .unwrap();
+}
+
+#[test]
+fn check_message_file_too_large() {
+    let mut rng = thread_rng();
+    let (_, public_key_package) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let public_key_package_path = temp_dir.path().join("public-key-package.json");
+    std::fs::write(
+        &public_key_package_path,
+        serde_json::to_string(&public_key_package).unwrap(),
+    )
+    .unwrap();
+    let message_path = temp_dir.path().join("message.bin");
+    std::fs::write(&message_path, vec![0u8; MAX_MESSAGES_SIZE + 1]).unwrap();
+
+    let args = Args {
+        num_signers: 2,
+        public_key_package: public_key_package_path.to_str().unwrap().to_owned(),
+        message_file: vec![message_path.to_str().unwrap().to_owned()],
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let r = ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut "".as_bytes(), &mut buf);
+    assert!(r.is_err());
+
+    // The limit does not apply to CLI mode, which does not send the messages
+    let args = Args { cli: true, ..args };
+    let mut buf = BufWriter::new(Vec::new());
+    let pargs =
+        ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut "".as_bytes(), &mut buf).unwrap();
+    assert_eq!(pargs.messages.len(), 1);
+}
+
+#[test]
+fn check_multiple_messages() {
+    let mut rng = thread_rng();
+    let (_, public_key_package) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let public_key_package_path = temp_dir.path().join("public-key-package.json");
+    std::fs::write(
+        &public_key_package_path,
+        serde_json::to_string(&public_key_package).unwrap(),
+    )
+    .unwrap();
+    let message_path = temp_dir.path().join("message.bin");
+    std::fs::write(&message_path, b"message").unwrap();
+
+    // Only one message can be signed, so the others are not silently ignored.
+    let args = Args {
+        cli: true,
+        num_signers: 2,
+        public_key_package: public_key_package_path.to_str().unwrap().to_owned(),
+        message: vec![message_path.to_str().unwrap().to_owned()],
+        message_file: vec![message_path.to_str().unwrap().to_owned()],
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let err = ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut "".as_bytes(), &mut buf)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "only one message can be signed per session, but 2 were given"
+    );
+}
+
+#[test]
+fn check_message_stdin() {
+    let mut rng = thread_rng();
+    let (_, public_key_package) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let public_key_package_path = temp_dir.path().join("public-key-package.json");
+    std::fs::write(
+        &public_key_package_path,
+        serde_json::to_string(&public_key_package).unwrap(),
+    )
+    .unwrap();
+
+    let args = Args {
+        num_signers: 2,
+        public_key_package: public_key_package_path.to_str().unwrap().to_owned(),
+        message_stdin: true,
+        ..Default::default()
+    };
+    let input = [0xffu8, b'\n', 0x00, b'\n'];
+    let mut buf = BufWriter::new(Vec::new());
+    let pargs =
+        ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut input.as_slice(), &mut buf).unwrap();
+
+    assert_eq!(pargs.messages, vec![input.to_vec()]);
+
+    // CLI mode reads other inputs from standard input after the message
+    let args = Args { cli: true, ..args };
+    let mut buf = BufWriter::new(Vec::new());
+    let r = ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut input.as_slice(), &mut buf);
+    assert!(r.is_err());
+}
+
+#[test]
+fn check_message_encoding() {
+    let mut rng = thread_rng();
+    let (_, public_key_package) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let public_key_package_path = temp_dir.path().join("public-key-package.json");
+    std::fs::write(
+        &public_key_package_path,
+        serde_json::to_string(&public_key_package).unwrap(),
+    )
+    .unwrap();
+
+    // The message has a trailing space, which must be kept, and bytes which
+    // are encoded differently in base64 and base64url.
+    let message = b"pay 1 ZEC?>> ".to_vec();
+    for (encoding, input) in [
+        (MessageEncoding::Hex, "7061792031205a45433f3e3e20\n"),
+        (MessageEncoding::Base64, "cGF5IDEgWkVDPz4+IA==\n"),
+        (MessageEncoding::Base64url, "cGF5IDEgWkVDPz4-IA==\n"),
+        (MessageEncoding::Base64url, "cGF5IDEgWkVDPz4-IA\n"),
+        (MessageEncoding::Utf8, "pay 1 ZEC?>> \n"),
+        (MessageEncoding::Utf8, "pay 1 ZEC?>> \r\n"),
+    ] {
+        let args = Args {
+            num_signers: 2,
+            public_key_package: public_key_package_path.to_str().unwrap().to_owned(),
+            message: vec!["-".to_string()],
+            message_encoding: encoding,
+            ..Default::default()
+        };
+        let mut buf = BufWriter::new(Vec::new());
+        let pargs =
+            ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut input.as_bytes(), &mut buf)
+                .unwrap();
+        assert_eq!(pargs.messages, vec![message.clone()], "{}", encoding);
+    }
+
+    // base64url is not accepted as standard base64, and vice versa.
+    for (encoding, input) in [
+        (MessageEncoding::Base64, "cGF5IDEgWkVDPz4-IA==\n"),
+        (MessageEncoding::Base64url, "cGF5IDEgWkVDPz4+IA==\n"),
+        (MessageEncoding::Hex, "pay 1 ZEC?>> \n"),
+    ] {
+        let args = Args {
+            num_signers: 2,
+            public_key_package: public_key_package_path.to_str().unwrap().to_owned(),
+            message: vec!["-".to_string()],
+            message_encoding: encoding,
+            ..Default::default()
+        };
+        let mut buf = BufWriter::new(Vec::new());
+        let r = ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut input.as_bytes(), &mut buf);
+        assert!(r.is_err(), "{}", encoding);
+    }
+}
+
+#[test]
+fn check_aux_msg() {
+    let mut rng = thread_rng();
+    let (_, public_key_package) = frost_core::keys::generate_with_dealer::<PallasBlake2b512, _>(
+        3,
+        2,
+        frost_core::keys::IdentifierList::Default,
+        &mut rng,
+    )
+    .unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let public_key_package_path = temp_dir.path().join("public-key-package.json");
+    std::fs::write(
+        &public_key_package_path,
+        serde_json::to_string(&public_key_package).unwrap(),
+    )
+    .unwrap();
+
+    let mut args = Args {
+        num_signers: 2,
+        public_key_package: public_key_package_path.to_str().unwrap().to_owned(),
+        message_stdin: true,
+        aux_msg: hex::encode(b"aux msg"),
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let pargs =
+        ProcessedArgs::<PallasBlake2b512>::new(&args, &mut "message".as_bytes(), &mut buf).unwrap();
+    assert_eq!(pargs.aux_msg, b"aux msg".to_vec());
+    assert_eq!(pargs.aux_msg_seed.len(), 32);
+
+    args.aux_msg = "not hex".to_string();
+    let mut buf = BufWriter::new(Vec::new());
+    let r = ProcessedArgs::<PallasBlake2b512>::new(&args, &mut "message".as_bytes(), &mut buf);
+    assert!(r.is_err());
+}
+
+#[test]
+fn check_validate_aux_msg() {
+    let mut rng = thread_rng();
+    let signing_package = frost_core::SigningPackage::new(BTreeMap::new(), b"message");
+    let randomizer =
+        frost_rerandomized::Randomizer::<PallasBlake2b512>::new(&mut rng, &signing_package)
+            .unwrap();
+
+    assert!(validate_aux_msg::<PallasBlake2b512>(b"aux msg", &[]).is_ok());
+    // The randomizer is derived from the auxiliary message, so it can't be
+    // given too.
+    assert!(validate_aux_msg(b"aux msg", &[randomizer]).is_err());
+    assert!(validate_aux_msg(b"", &[randomizer]).is_ok());
+    // Without rerandomization there is no randomizer to bind it to.
+    assert!(validate_aux_msg::<frost::Ed25519Sha512>(b"aux msg", &[]).is_err());
+    assert!(validate_aux_msg::<frost::Ed25519Sha512>(b"", &[]).is_ok());
+}
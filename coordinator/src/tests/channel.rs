@@ -0,0 +1,351 @@
+#![cfg(test)]
+
+use std::io::BufWriter;
+
+use coordinator::{
+    args::{Args, ProcessedArgs},
+    comms::channel::ChannelComms,
+    step_1::step_1,
+    step_2::step_2,
+    step_3::step_3,
+};
+use frost::keys::{IdentifierList, KeyPackage};
+use frost_ed25519 as frost;
+use participant::offline::OfflinePhase;
+use rand::thread_rng;
+
+/// Run a full signing flow with 3 participants, entirely in memory.
+#[tokio::test]
+async fn check_channel_comms_signing() {
+    let mut rng = thread_rng();
+    let (shares, public_key_package) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let public_key_package_path = temp_dir.path().join("public-key-package.json");
+    std::fs::write(
+        &public_key_package_path,
+        serde_json::to_string(&public_key_package).unwrap(),
+    )
+    .unwrap();
+
+    let message = b"message to sign";
+    let args = Args {
+        num_signers: 3,
+        public_key_package: public_key_package_path.to_str().unwrap().to_owned(),
+        message_stdin: true,
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let pargs =
+        ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut message.as_slice(), &mut buf)
+            .unwrap();
+
+    let (mut coordinator_comms, participant_comms) =
+        ChannelComms::<frost::Ed25519Sha512>::new(shares.len());
+
+    let coordinator = async {
+        let mut input = "".as_bytes();
+        let mut logger = Vec::new();
+        let participants_config = step_1(&pargs, &mut coordinator_comms, &mut input, &mut logger)
+            .await
+            .unwrap();
+        let signing_package =
+            step_2(&pargs, &mut logger, participants_config.commitments.clone()).unwrap();
+        step_3(
+            &pargs,
+            &mut coordinator_comms,
+            &mut input,
+            &mut logger,
+            participants_config,
+            &signing_package,
+        )
+        .await
+        .unwrap()
+    };
+
+    let mut participants = shares
+        .values()
+        .zip(participant_comms)
+        .map(|(share, mut comms)| {
+            let key_package = KeyPackage::try_from(share.clone()).unwrap();
+            let participant_args = participant::args::Args {
+                key_package: "-".to_string(),
+                ..Default::default()
+            };
+            let input = format!("{}\n", serde_json::to_string(&key_package).unwrap());
+            let mut buf = Vec::new();
+            let pargs = participant::args::ProcessedArgs::<frost::Ed25519Sha512>::new(
+                &participant_args,
+                &mut input.as_bytes(),
+                &mut buf,
+            )
+            .unwrap();
+            async move {
+                participant::cli::run_with_comms(pargs, &mut comms, &mut "".as_bytes(), &mut buf)
+                    .await
+            }
+        });
+    let (p1, p2, p3) = (
+        participants.next().unwrap(),
+        participants.next().unwrap(),
+        participants.next().unwrap(),
+    );
+
+    let (signature, r1, r2, r3) = tokio::join!(coordinator, p1, p2, p3);
+    r1.unwrap();
+    r2.unwrap();
+    r3.unwrap();
+
+    public_key_package
+        .verifying_key()
+        .verify(message, &signature)
+        .unwrap();
+}
+
+/// Check that the coordinator aborts, reporting which participant declined,
+/// if a participant declines to sign.
+#[tokio::test]
+async fn check_channel_comms_participant_declines() {
+    let mut rng = thread_rng();
+    let (shares, public_key_package) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let public_key_package_path = temp_dir.path().join("public-key-package.json");
+    std::fs::write(
+        &public_key_package_path,
+        serde_json::to_string(&public_key_package).unwrap(),
+    )
+    .unwrap();
+
+    let args = Args {
+        num_signers: 3,
+        public_key_package: public_key_package_path.to_str().unwrap().to_owned(),
+        message_stdin: true,
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let pargs = ProcessedArgs::<frost::Ed25519Sha512>::new(
+        &args,
+        &mut b"unexpected message".as_slice(),
+        &mut buf,
+    )
+    .unwrap();
+
+    let (mut coordinator_comms, participant_comms) =
+        ChannelComms::<frost::Ed25519Sha512>::new(shares.len());
+
+    let coordinator = async {
+        let mut input = "".as_bytes();
+        let mut logger = Vec::new();
+        let participants_config = step_1(&pargs, &mut coordinator_comms, &mut input, &mut logger)
+            .await
+            .unwrap();
+        let signing_package =
+            step_2(&pargs, &mut logger, participants_config.commitments.clone()).unwrap();
+        step_3(
+            &pargs,
+            &mut coordinator_comms,
+            &mut input,
+            &mut logger,
+            participants_config,
+            &signing_package,
+        )
+        .await
+    };
+
+    // The first participant is asked to confirm the message, and declines.
+    let declining_identifier = *shares.keys().next().unwrap();
+    let mut participants =
+        shares
+            .values()
+            .zip(participant_comms)
+            .enumerate()
+            .map(|(i, (share, mut comms))| {
+                let key_package = KeyPackage::try_from(share.clone()).unwrap();
+                let participant_args = participant::args::Args {
+                    key_package: "-".to_string(),
+                    confirm: i == 0,
+                    ..Default::default()
+                };
+                let input = format!("{}\n", serde_json::to_string(&key_package).unwrap());
+                let mut buf = Vec::new();
+                let pargs = participant::args::ProcessedArgs::<frost::Ed25519Sha512>::new(
+                    &participant_args,
+                    &mut input.as_bytes(),
+                    &mut buf,
+                )
+                .unwrap();
+                async move {
+                    participant::cli::run_with_comms(
+                        pargs,
+                        &mut comms,
+                        &mut "n\n".as_bytes(),
+                        &mut buf,
+                    )
+                    .await
+                }
+            });
+    let (p1, p2, p3) = (
+        participants.next().unwrap(),
+        participants.next().unwrap(),
+        participants.next().unwrap(),
+    );
+
+    let (r, r1, r2, r3) = tokio::join!(coordinator, p1, p2, p3);
+    r1.unwrap();
+    r2.unwrap();
+    r3.unwrap();
+
+    assert_eq!(
+        r.unwrap_err().to_string(),
+        format!(
+            "participant {} declined to sign: declined by the user",
+            hex::encode(declining_identifier.serialize())
+        )
+    );
+}
+
+/// Run a full signing flow with 3 participants, one of which signs offline:
+/// it saves the signing package to a file, signs it in a separate run without
+/// comms, and sends the signature share in a third run.
+#[tokio::test]
+async fn check_channel_comms_offline_signing() {
+    let mut rng = thread_rng();
+    let (shares, public_key_package) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let public_key_package_path = temp_dir.path().join("public-key-package.json");
+    std::fs::write(
+        &public_key_package_path,
+        serde_json::to_string(&public_key_package).unwrap(),
+    )
+    .unwrap();
+    let signing_package_path = temp_dir.path().join("signing-package.json");
+    let signature_share_path = temp_dir.path().join("signature-share.json");
+
+    let message = b"message to sign";
+    let args = Args {
+        num_signers: 3,
+        public_key_package: public_key_package_path.to_str().unwrap().to_owned(),
+        message_stdin: true,
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let pargs =
+        ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut message.as_slice(), &mut buf)
+            .unwrap();
+
+    let (mut coordinator_comms, participant_comms) =
+        ChannelComms::<frost::Ed25519Sha512>::new(shares.len());
+
+    let coordinator = async {
+        let mut input = "".as_bytes();
+        let mut logger = Vec::new();
+        let participants_config = step_1(&pargs, &mut coordinator_comms, &mut input, &mut logger)
+            .await
+            .unwrap();
+        let signing_package =
+            step_2(&pargs, &mut logger, participants_config.commitments.clone()).unwrap();
+        step_3(
+            &pargs,
+            &mut coordinator_comms,
+            &mut input,
+            &mut logger,
+            participants_config,
+            &signing_package,
+        )
+        .await
+        .unwrap()
+    };
+
+    let mut participants =
+        shares
+            .values()
+            .zip(participant_comms)
+            .enumerate()
+            .map(|(i, (share, mut comms))| {
+                let key_package = KeyPackage::try_from(share.clone()).unwrap();
+                let participant_args = participant::args::Args {
+                    key_package: "-".to_string(),
+                    ..Default::default()
+                };
+                let input = format!("{}\n", serde_json::to_string(&key_package).unwrap());
+                let mut buf = Vec::new();
+                let pargs = participant::args::ProcessedArgs::<frost::Ed25519Sha512>::new(
+                    &participant_args,
+                    &mut input.as_bytes(),
+                    &mut buf,
+                )
+                .unwrap();
+                let signing_package_path = signing_package_path.clone();
+                let signature_share_path = signature_share_path.clone();
+                async move {
+                    if i != 0 {
+                        return participant::cli::run_with_comms(
+                            pargs,
+                            &mut comms,
+                            &mut "".as_bytes(),
+                            &mut buf,
+                        )
+                        .await;
+                    }
+
+                    let mut save_pargs = pargs.clone();
+                    save_pargs.offline = Some(OfflinePhase::SaveSigningPackage(
+                        signing_package_path.clone(),
+                    ));
+                    participant::cli::run_with_comms(
+                        save_pargs,
+                        &mut comms,
+                        &mut "".as_bytes(),
+                        &mut buf,
+                    )
+                    .await?;
+                    assert!(signing_package_path.exists());
+
+                    // No comms are created when signing offline.
+                    let mut sign_pargs = pargs.clone();
+                    sign_pargs.offline = Some(OfflinePhase::Sign {
+                        signing_package: signing_package_path.clone(),
+                        signature_share: signature_share_path.clone(),
+                    });
+                    participant::cli::cli_for_processed_args(
+                        sign_pargs,
+                        &mut "".as_bytes(),
+                        &mut buf,
+                    )
+                    .await?;
+                    // The nonces can't be used again.
+                    assert!(!signing_package_path.exists());
+
+                    let mut send_pargs = pargs;
+                    send_pargs.offline =
+                        Some(OfflinePhase::SendSignatureShare(signature_share_path));
+                    participant::cli::run_with_comms(
+                        send_pargs,
+                        &mut comms,
+                        &mut "".as_bytes(),
+                        &mut buf,
+                    )
+                    .await
+                }
+            });
+    let (p1, p2, p3) = (
+        participants.next().unwrap(),
+        participants.next().unwrap(),
+        participants.next().unwrap(),
+    );
+
+    let (signature, r1, r2, r3) = tokio::join!(coordinator, p1, p2, p3);
+    r1.unwrap();
+    r2.unwrap();
+    r3.unwrap();
+
+    public_key_package
+        .verifying_key()
+        .verify(message, &signature)
+        .unwrap();
+}
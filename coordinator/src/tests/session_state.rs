@@ -0,0 +1,343 @@
+#![cfg(test)]
+
+use coordinator::comms::http::{ParticipantDeclined, ParticipantReportedError, SessionState};
+use frost::keys::IdentifierList;
+use frost_ed25519 as frost;
+use frostd::{
+    Msg, ParticipantDeclinedArgs, ParticipantErrorArgs, SendCommitmentsArgs,
+    SendSignatureSharesArgs,
+};
+use rand::thread_rng;
+use std::collections::HashSet;
+
+#[test]
+fn check_participant_reported_error() {
+    let mut rng = thread_rng();
+    let (shares, _) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let mut state = SessionState::<frost::Ed25519Sha512>::new(1, 2, false);
+
+    // Both participants send their commitments
+    for (i, (identifier, share)) in shares.iter().take(2).enumerate() {
+        let (_, commitments) = frost::round1::commit(share.signing_share(), &mut rng);
+        state
+            .recv(Msg {
+                sender: vec![i as u8; 32],
+                msg: serde_json::to_vec(&SendCommitmentsArgs {
+                    identifier: *identifier,
+                    commitments: vec![commitments],
+                })
+                .unwrap(),
+            })
+            .unwrap();
+    }
+    assert!(state.has_commitments());
+
+    // The first participant fails to sign and reports it
+    let err = state
+        .recv(Msg {
+            sender: vec![0; 32],
+            msg: serde_json::to_vec(&ParticipantErrorArgs {
+                participant_error: "invalid signing package".to_string(),
+            })
+            .unwrap(),
+        })
+        .unwrap_err();
+    let err = err.downcast::<ParticipantReportedError>().unwrap();
+    assert_eq!(err.pubkey, vec![0; 32]);
+    assert_eq!(err.reason, "invalid signing package");
+    assert!(!state.has_signature_shares());
+}
+
+#[test]
+fn check_participant_declined() {
+    let mut rng = thread_rng();
+    let (shares, _) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let mut state = SessionState::<frost::Ed25519Sha512>::new(1, 2, false);
+
+    for (i, (identifier, share)) in shares.iter().take(2).enumerate() {
+        let (_, commitments) = frost::round1::commit(share.signing_share(), &mut rng);
+        state
+            .recv(Msg {
+                sender: vec![i as u8; 32],
+                msg: serde_json::to_vec(&SendCommitmentsArgs {
+                    identifier: *identifier,
+                    commitments: vec![commitments],
+                })
+                .unwrap(),
+            })
+            .unwrap();
+    }
+    assert!(state.has_commitments());
+
+    // The second participant declines to sign
+    let err = state
+        .recv(Msg {
+            sender: vec![1; 32],
+            msg: serde_json::to_vec(&ParticipantDeclinedArgs {
+                participant_declined: "declined by the user".to_string(),
+            })
+            .unwrap(),
+        })
+        .unwrap_err();
+    let err = err.downcast::<ParticipantDeclined>().unwrap();
+    assert_eq!(err.pubkey, vec![1; 32]);
+    assert_eq!(err.reason, "declined by the user");
+    assert!(!state.has_signature_shares());
+}
+
+#[test]
+fn check_strict_rejects_different_commitments() {
+    let mut rng = thread_rng();
+    let (shares, _) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+    let (identifier, share) = shares.iter().next().unwrap();
+    let (_, commitments) = frost::round1::commit(share.signing_share(), &mut rng);
+    let (_, other_commitments) = frost::round1::commit(share.signing_share(), &mut rng);
+    let msg = |commitments| Msg {
+        sender: vec![0; 32],
+        msg: serde_json::to_vec(&SendCommitmentsArgs {
+            identifier: *identifier,
+            commitments: vec![commitments],
+        })
+        .unwrap(),
+    };
+
+    // Identical retries are accepted, but different commitments are not.
+    let mut state = SessionState::<frost::Ed25519Sha512>::new(1, 2, true);
+    state.recv(msg(commitments)).unwrap();
+    state.recv(msg(commitments)).unwrap();
+    let err = state.recv(msg(other_commitments)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "participant sent different commitments twice"
+    );
+    assert!(!state.has_commitments());
+
+    // Without strict mode, the new commitments replace the old ones.
+    let mut state = SessionState::<frost::Ed25519Sha512>::new(1, 2, false);
+    state.recv(msg(commitments)).unwrap();
+    state.recv(msg(other_commitments)).unwrap();
+}
+
+#[test]
+fn check_session_state_save_load() {
+    let mut rng = thread_rng();
+    let (shares, _) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let mut state = SessionState::<frost::Ed25519Sha512>::new(1, 2, false);
+    for (i, (identifier, share)) in shares.iter().take(2).enumerate() {
+        let (_, commitments) = frost::round1::commit(share.signing_share(), &mut rng);
+        state
+            .recv(Msg {
+                sender: vec![i as u8; 32],
+                msg: serde_json::to_vec(&SendCommitmentsArgs {
+                    identifier: *identifier,
+                    commitments: vec![commitments],
+                })
+                .unwrap(),
+            })
+            .unwrap();
+    }
+    assert!(matches!(
+        state,
+        SessionState::WaitingForSignatureShares { .. }
+    ));
+
+    let serialized = serde_json::to_string(&state).unwrap();
+    let deserialized: SessionState<frost::Ed25519Sha512> =
+        serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, state);
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("state.json");
+    state.save(&path).unwrap();
+    let mut loaded = SessionState::<frost::Ed25519Sha512>::load(&path).unwrap();
+    assert_eq!(loaded, state);
+    assert_eq!(loaded.commitments().unwrap(), state.commitments().unwrap());
+}
+
+#[test]
+fn check_wrong_state_errors() {
+    let mut rng = thread_rng();
+    let (shares, _) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+
+    let mut state = SessionState::<frost::Ed25519Sha512>::new(1, 2, false);
+    assert_eq!(
+        state.commitments().unwrap_err().to_string(),
+        "commitments() called in wrong state"
+    );
+    assert_eq!(
+        state.signature_shares().unwrap_err().to_string(),
+        "signature_shares() called in wrong state"
+    );
+
+    for (i, (identifier, share)) in shares.iter().take(2).enumerate() {
+        let (_, commitments) = frost::round1::commit(share.signing_share(), &mut rng);
+        state
+            .recv(Msg {
+                sender: vec![i as u8; 32],
+                msg: serde_json::to_vec(&SendCommitmentsArgs {
+                    identifier: *identifier,
+                    commitments: vec![commitments],
+                })
+                .unwrap(),
+            })
+            .unwrap();
+    }
+    assert!(state.has_commitments());
+    assert_eq!(
+        state.signature_shares().unwrap_err().to_string(),
+        "signature_shares() called in wrong state"
+    );
+    // The state is left untouched and can still be used.
+    state.commitments().unwrap();
+}
+
+#[test]
+fn check_message_signers() {
+    let mut rng = thread_rng();
+    let (shares, public_key_package) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+    let key_packages = shares
+        .values()
+        .map(|s| frost::keys::KeyPackage::try_from(s.clone()).unwrap())
+        .collect::<Vec<_>>();
+    let pubkey = |i: usize| vec![i as u8; 32];
+
+    // The first message is signed by participants 0 and 1, the second one by
+    // participants 1 and 2.
+    let message_signers = [[0, 1], [1, 2]]
+        .iter()
+        .map(|s| s.iter().map(|i| pubkey(*i)).collect::<HashSet<_>>())
+        .collect::<Vec<_>>();
+    let messages = [b"message 1".as_slice(), b"message 2".as_slice()];
+    let mut state =
+        SessionState::<frost::Ed25519Sha512>::new_with_message_signers(message_signers, false);
+
+    // Each participant commits once for each of its messages.
+    let mut nonces = Vec::new();
+    for (i, key_package) in key_packages.iter().enumerate() {
+        let num_messages = if i == 1 { 2 } else { 1 };
+        let (participant_nonces, commitments): (Vec<_>, Vec<_>) = (0..num_messages)
+            .map(|_| frost::round1::commit(key_package.signing_share(), &mut rng))
+            .unzip();
+        assert!(!state.has_commitments());
+        // Commitments for the wrong number of messages are rejected.
+        assert!(state
+            .recv(Msg {
+                sender: pubkey(i),
+                msg: serde_json::to_vec(&SendCommitmentsArgs {
+                    identifier: *key_package.identifier(),
+                    commitments: vec![commitments[0]; 3],
+                })
+                .unwrap(),
+            })
+            .is_err());
+        state
+            .recv(Msg {
+                sender: pubkey(i),
+                msg: serde_json::to_vec(&SendCommitmentsArgs {
+                    identifier: *key_package.identifier(),
+                    commitments,
+                })
+                .unwrap(),
+            })
+            .unwrap();
+        nonces.push(participant_nonces);
+    }
+    assert!(state.has_commitments());
+
+    let (commitments, _) = state.commitments().unwrap();
+    assert_eq!(
+        commitments[0].keys().collect::<Vec<_>>(),
+        vec![key_packages[0].identifier(), key_packages[1].identifier()]
+    );
+    assert_eq!(
+        commitments[1].keys().collect::<Vec<_>>(),
+        vec![key_packages[1].identifier(), key_packages[2].identifier()]
+    );
+
+    let signing_packages = commitments
+        .iter()
+        .zip(messages)
+        .map(|(c, m)| frost::SigningPackage::new(c.clone(), m))
+        .collect::<Vec<_>>();
+    for (i, key_package) in key_packages.iter().enumerate() {
+        let packages = if i == 1 {
+            &signing_packages[..]
+        } else {
+            &signing_packages[i / 2..i / 2 + 1]
+        };
+        let signature_share = packages
+            .iter()
+            .zip(&nonces[i])
+            .map(|(p, n)| frost::round2::sign(p, n, key_package).unwrap())
+            .collect();
+        state
+            .recv(Msg {
+                sender: pubkey(i),
+                msg: serde_json::to_vec(&SendSignatureSharesArgs {
+                    identifier: *key_package.identifier(),
+                    signature_share,
+                })
+                .unwrap(),
+            })
+            .unwrap();
+    }
+    assert!(state.has_signature_shares());
+
+    let signature_shares = state.signature_shares().unwrap();
+    for ((signing_package, signature_shares), message) in
+        signing_packages.iter().zip(signature_shares).zip(messages)
+    {
+        let signature =
+            frost::aggregate(signing_package, &signature_shares, &public_key_package).unwrap();
+        public_key_package
+            .verifying_key()
+            .verify(message, &signature)
+            .unwrap();
+    }
+}
+
+#[test]
+fn check_missing_pubkeys_and_finish_commitments() {
+    let mut rng = thread_rng();
+    let (shares, _) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+    let signers = (0..3u8).map(|i| vec![i; 32]).collect::<Vec<_>>();
+
+    let mut state = SessionState::<frost::Ed25519Sha512>::new(1, 3, false);
+    assert!(state.finish_commitments().is_err());
+
+    // Only the first two participants send their commitments.
+    for (i, (identifier, share)) in shares.iter().take(2).enumerate() {
+        let (_, commitments) = frost::round1::commit(share.signing_share(), &mut rng);
+        state
+            .recv(Msg {
+                sender: signers[i].clone(),
+                msg: serde_json::to_vec(&SendCommitmentsArgs {
+                    identifier: *identifier,
+                    commitments: vec![commitments],
+                })
+                .unwrap(),
+            })
+            .unwrap();
+    }
+    assert!(!state.has_commitments());
+    assert_eq!(state.missing_pubkeys(&signers), vec![signers[2].clone()]);
+
+    state.finish_commitments().unwrap();
+    assert!(state.has_commitments());
+    assert_eq!(state.progress(), (0, 2));
+    let (commitments, _) = state.commitments().unwrap();
+    assert_eq!(commitments[0].len(), 2);
+    // Now waiting for the signature shares of the participants which sent
+    // commitments.
+    assert_eq!(state.missing_pubkeys(&signers), signers[..2].to_vec());
+}
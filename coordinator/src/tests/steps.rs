@@ -2,17 +2,19 @@
 
 use coordinator::{
     args::{Args, ProcessedArgs},
+    cli::cli,
     comms::cli::CLIComms,
     step_1::{step_1, ParticipantsConfig},
     step_2::step_2,
-    step_3::step_3,
+    step_3::{aggregate_shares, step_3, AuditMessage, AuditRecord},
 };
 use frost::{
     keys::{PublicKeyPackage, VerifyingShare},
     round1::{NonceCommitment, SigningCommitments},
-    Identifier, SigningPackage, VerifyingKey,
+    Identifier, Signature, SigningPackage, VerifyingKey,
 };
 use frost_ed25519 as frost;
+use sha2::{Digest, Sha256};
 use std::{collections::BTreeMap, io::BufWriter};
 
 use super::common::get_helpers;
@@ -235,3 +237,217 @@ async fn check_step_3() {
 
     assert_eq!(expected, actual)
 }
+
+#[tokio::test]
+async fn check_cli() {
+    let Helpers {
+        participant_id_1,
+        participant_id_3,
+        commitments_input_1,
+        commitments_input_3,
+        signature_1,
+        signature_3,
+        group_signature,
+        message,
+        pub_key_package,
+        ..
+    } = get_helpers();
+
+    let input = format!(
+        "2\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}\n",
+        pub_key_package,
+        message,
+        participant_id_1,
+        commitments_input_1,
+        participant_id_3,
+        commitments_input_3,
+        signature_1,
+        signature_3
+    );
+    let args = Args {
+        cli: true,
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let signature = cli::<frost::Ed25519Sha512>(&args, &mut input.as_bytes(), &mut buf)
+        .await
+        .unwrap();
+
+    let expected_signature =
+        Signature::deserialize(&hex::decode(group_signature).unwrap()).unwrap();
+    assert_eq!(signature, expected_signature);
+    let (_, group_public) = build_pub_key_package();
+    group_public
+        .verify(&hex::decode(message).unwrap(), &signature)
+        .unwrap();
+}
+
+#[tokio::test]
+async fn check_step_3_audit_log() {
+    let Helpers {
+        signature_1,
+        signature_3,
+        group_signature,
+        message,
+        pub_key_package,
+        ..
+    } = get_helpers();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let audit_log_path = temp_dir.path().join("audit.log");
+
+    let mut buf = BufWriter::new(Vec::new());
+    let args = Args {
+        audit_log: Some(audit_log_path.to_str().unwrap().to_string()),
+        ..Default::default()
+    };
+
+    let input = format!("2\n{}\n{}\n", pub_key_package, message);
+    let mut pargs = ProcessedArgs::new(&args, &mut input.as_bytes(), &mut buf).unwrap();
+    // Another message, which is not signed but must still be recorded, and
+    // which comes before the signed one.
+    pargs.messages.insert(0, b"other message".to_vec());
+
+    let (signer_pubkeys, group_public) = build_pub_key_package();
+    let commitments = build_signing_commitments();
+    let message = hex::decode(message).unwrap();
+    let signing_package = SigningPackage::new(commitments.clone(), &message);
+
+    // Sign the same message twice; each signing must append its own record.
+    for _ in 0..2 {
+        let participants_config = ParticipantsConfig {
+            commitments: commitments.clone(),
+            pub_key_package: PublicKeyPackage::new(signer_pubkeys.clone(), group_public),
+        };
+        let input = format!("{}\n{}\n", signature_1, signature_3);
+        let mut buf = BufWriter::new(Vec::new());
+        step_3(
+            &pargs,
+            &mut CLIComms::new(),
+            &mut input.as_bytes(),
+            &mut buf,
+            participants_config,
+            &signing_package,
+        )
+        .await
+        .unwrap();
+    }
+
+    let expected_signature =
+        Signature::deserialize(&hex::decode(group_signature).unwrap()).unwrap();
+
+    let audit_log = std::fs::read_to_string(&audit_log_path).unwrap();
+    let records = audit_log.lines().collect::<Vec<_>>();
+    assert_eq!(records.len(), 2);
+    for record in records {
+        let record: AuditRecord<frost::Ed25519Sha512> = serde_json::from_str(record).unwrap();
+        assert!(record.signing_package == signing_package);
+        assert_eq!(
+            record.identifiers,
+            commitments.keys().cloned().collect::<Vec<_>>()
+        );
+        assert!(record.randomizer.is_none());
+        assert!(record.signature == expected_signature);
+        assert_eq!(record.message_index, 1);
+        assert_eq!(
+            record.messages,
+            vec![
+                AuditMessage {
+                    index: 0,
+                    hash: hex::encode(Sha256::digest(b"other message")),
+                },
+                AuditMessage {
+                    index: 1,
+                    hash: hex::encode(Sha256::digest(&message)),
+                },
+            ]
+        );
+    }
+}
+
+#[test]
+fn check_aggregate_shares() {
+    check_aggregate_shares_for_ciphersuite::<frost::Ed25519Sha512>(false);
+    check_aggregate_shares_for_ciphersuite::<reddsa::frost::redpallas::PallasBlake2b512>(true);
+}
+
+fn check_aggregate_shares_for_ciphersuite<C: frost_rerandomized::RandomizedCiphersuite>(
+    rerandomized: bool,
+) {
+    let mut rng = rand::thread_rng();
+    let (shares, pubkeys) = frost_core::keys::generate_with_dealer::<C, _>(
+        3,
+        2,
+        frost_core::keys::IdentifierList::Default,
+        &mut rng,
+    )
+    .unwrap();
+    let key_packages: BTreeMap<_, _> = shares
+        .into_iter()
+        .map(|(id, share)| (id, frost_core::keys::KeyPackage::try_from(share).unwrap()))
+        .collect();
+
+    let messages = [b"message 1".as_slice(), b"message 2".as_slice()];
+    let mut signing_packages = Vec::new();
+    let mut randomized_params = Vec::new();
+    let mut signature_shares = Vec::new();
+    for message in messages {
+        let (nonces, commitments): (BTreeMap<_, _>, BTreeMap<_, _>) = key_packages
+            .iter()
+            .take(2)
+            .map(|(id, key_package)| {
+                let (nonces, commitments) =
+                    frost_core::round1::commit(key_package.signing_share(), &mut rng);
+                ((*id, nonces), (*id, commitments))
+            })
+            .unzip();
+        let signing_package = frost_core::SigningPackage::new(commitments, message);
+        let params = frost_rerandomized::RandomizedParams::new(
+            pubkeys.verifying_key(),
+            &signing_package,
+            &mut rng,
+        )
+        .unwrap();
+        let shares = nonces
+            .iter()
+            .map(|(id, nonces)| {
+                let key_package = &key_packages[id];
+                let share = if rerandomized {
+                    frost_rerandomized::sign(
+                        &signing_package,
+                        nonces,
+                        key_package,
+                        *params.randomizer(),
+                    )
+                } else {
+                    frost_core::round2::sign(&signing_package, nonces, key_package)
+                };
+                (*id, share.unwrap())
+            })
+            .collect();
+        signing_packages.push(signing_package);
+        randomized_params.push(params);
+        signature_shares.push(shares);
+    }
+
+    let signatures = aggregate_shares(
+        &signing_packages,
+        &signature_shares,
+        &pubkeys,
+        rerandomized.then_some(randomized_params.as_slice()),
+    )
+    .unwrap();
+
+    assert_eq!(signatures.len(), messages.len());
+    for (i, message) in messages.iter().enumerate() {
+        let verifying_key = if rerandomized {
+            *randomized_params[i].randomized_verifying_key()
+        } else {
+            *pubkeys.verifying_key()
+        };
+        verifying_key.verify(message, &signatures[i]).unwrap();
+    }
+
+    // Mismatched inputs must be rejected.
+    assert!(aggregate_shares(&signing_packages, &signature_shares[..1], &pubkeys, None).is_err());
+}
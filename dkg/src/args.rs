@@ -5,4 +5,20 @@ use clap::Parser;
 pub struct Args {
     #[arg(short = 'C', long, default_value = "ed25519")]
     pub ciphersuite: String,
+
+    /// Write the resulting key package to the given file, instead of printing
+    /// it.
+    #[arg(long)]
+    pub out_key_package: Option<String>,
+
+    /// Write the resulting public key package to the given file, instead of
+    /// printing it.
+    #[arg(long)]
+    pub out_public_key_package: Option<String>,
+
+    /// Indent the resulting key package and public key package, to make them
+    /// easier to read and diff. The round packages are always printed on a
+    /// single line, since they are read back one line at a time.
+    #[arg(long, default_value_t = false)]
+    pub pretty: bool,
 }
@@ -1,12 +1,15 @@
 use frost_core::keys::{KeyPackage, PublicKeyPackage};
+use frost_core::serde::Serialize;
 use frost_core::{self as frost, Ciphersuite};
 
 use rand::thread_rng;
 use reddsa::frost::redpallas::keys::EvenY;
 use std::collections::BTreeMap;
 use std::io::{BufRead, Write};
+use std::path::Path;
 
-use crate::inputs::{read_round1_package, read_round2_package, request_inputs};
+use crate::args::Args;
+use crate::inputs::{add_round1_package, read_round1_package, read_round2_package, request_inputs};
 
 // The redpallas ciphersuite, when used for generating Orchard spending key
 // signatures, requires ensuring public key have an even Y coordinate. Since the
@@ -40,6 +43,7 @@ impl MaybeIntoEvenY for reddsa::frost::redpallas::PallasBlake2b512 {
 }
 
 pub fn cli<C: Ciphersuite + 'static + MaybeIntoEvenY>(
+    args: &Args,
     reader: &mut impl BufRead,
     logger: &mut impl Write,
 ) -> Result<(), Box<dyn std::error::Error>> {
@@ -73,7 +77,12 @@ pub fn cli<C: Ciphersuite + 'static + MaybeIntoEvenY>(
     let mut received_round1_packages = BTreeMap::new();
     for _ in 0..config.max_signers - 1 {
         let (identifier, round1_package) = read_round1_package(reader, logger)?;
-        received_round1_packages.insert(identifier, round1_package);
+        add_round1_package(
+            &mut received_round1_packages,
+            config.identifier,
+            identifier,
+            round1_package,
+        )?;
         writeln!(logger)?;
     }
 
@@ -114,16 +123,47 @@ pub fn cli<C: Ciphersuite + 'static + MaybeIntoEvenY>(
         &received_round2_packages,
     )?);
 
-    writeln!(
-        logger,
-        "Participant key package:\n\n{}\n",
-        serde_json::to_string(&key_package)?,
-    )?;
-    writeln!(
-        logger,
-        "Participant public key package:\n\n{}\n",
-        serde_json::to_string(&public_key_package)?,
-    )?;
+    let key_package = to_json(&key_package, args.pretty)?;
+    if let Some(path) = &args.out_key_package {
+        write_atomically(path, &key_package)?;
+        writeln!(logger, "Participant key package written to {}\n", path)?;
+    } else {
+        writeln!(logger, "Participant key package:\n\n{}\n", key_package)?;
+    }
+    let public_key_package = to_json(&public_key_package, args.pretty)?;
+    if let Some(path) = &args.out_public_key_package {
+        write_atomically(path, &public_key_package)?;
+        writeln!(
+            logger,
+            "Participant public key package written to {}\n",
+            path
+        )?;
+    } else {
+        writeln!(
+            logger,
+            "Participant public key package:\n\n{}\n",
+            public_key_package
+        )?;
+    }
+
+    Ok(())
+}
 
+/// Write `contents` to the given file, replacing it atomically so that an
+/// interruption never leaves a truncated file behind.
+fn write_atomically(path: &str, contents: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Path::new(path);
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, contents)?;
+    std::fs::rename(tmp_path, path)?;
     Ok(())
 }
+
+/// JSON-encode `value`, indented if `pretty` is set.
+fn to_json<T: Serialize>(value: &T, pretty: bool) -> serde_json::Result<String> {
+    if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
@@ -1,3 +1,4 @@
+use eyre::eyre;
 use frost_core::{self as frost, Ciphersuite};
 
 use frost::{
@@ -5,6 +6,7 @@ use frost::{
     Error, Identifier,
 };
 
+use std::collections::BTreeMap;
 use std::io::{BufRead, Write};
 
 #[derive(Debug, PartialEq, Clone)]
@@ -106,6 +108,22 @@ pub fn read_round1_package<C: Ciphersuite + 'static>(
     Ok((identifier, round1_package))
 }
 
+/// Add a Round 1 Package received from the participant with the given
+/// identifier to `received`. Fails if the identifier is our own or if a
+/// package was already received from it, instead of overwriting it.
+pub fn add_round1_package<C: Ciphersuite>(
+    received: &mut BTreeMap<Identifier<C>, round1::Package<C>>,
+    own_identifier: Identifier<C>,
+    identifier: Identifier<C>,
+    round1_package: round1::Package<C>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if identifier == own_identifier || received.contains_key(&identifier) {
+        return Err(eyre!("identifier collision between participants").into());
+    }
+    received.insert(identifier, round1_package);
+    Ok(())
+}
+
 pub fn read_round2_package<C: Ciphersuite + 'static>(
     input: &mut impl BufRead,
     logger: &mut dyn Write,
@@ -11,9 +11,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut logger = io::stdout();
 
     if args.ciphersuite == "ed25519" {
-        cli::<frost_ed25519::Ed25519Sha512>(&mut reader, &mut logger)?;
+        cli::<frost_ed25519::Ed25519Sha512>(&args, &mut reader, &mut logger)?;
     } else if args.ciphersuite == "redpallas" {
-        cli::<reddsa::frost::redpallas::PallasBlake2b512>(&mut reader, &mut logger)?;
+        cli::<reddsa::frost::redpallas::PallasBlake2b512>(&args, &mut reader, &mut logger)?;
     }
 
     Ok(())
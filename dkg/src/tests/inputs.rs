@@ -1,8 +1,10 @@
+use std::collections::BTreeMap;
 use std::io::BufWriter;
 
-use crate::inputs::{request_inputs, Config};
+use crate::inputs::{add_round1_package, request_inputs, Config};
 use frost::Error;
 use frost_ed25519 as frost;
+use rand::thread_rng;
 
 #[test]
 fn check_valid_input_for_signers() {
@@ -96,3 +98,27 @@ fn return_malformed_identifier_error_if_identifier_invalid() {
         Error::MalformedIdentifier
     );
 }
+
+#[test]
+fn return_error_if_round1_identifiers_collide() {
+    let mut rng = thread_rng();
+    let own_identifier: frost::Identifier = 1u16.try_into().unwrap();
+    let identifier: frost::Identifier = 2u16.try_into().unwrap();
+    let (_, package_1) = frost::keys::dkg::part1(identifier, 3, 2, &mut rng).unwrap();
+    let (_, package_2) = frost::keys::dkg::part1(3u16.try_into().unwrap(), 3, 2, &mut rng).unwrap();
+
+    let mut received = BTreeMap::new();
+    add_round1_package(&mut received, own_identifier, identifier, package_1).unwrap();
+
+    // A second package with the same identifier is rejected, and does not
+    // overwrite the first one.
+    let err = add_round1_package(&mut received, own_identifier, identifier, package_2.clone())
+        .unwrap_err();
+    assert_eq!(err.to_string(), "identifier collision between participants");
+    assert_eq!(received.len(), 1);
+
+    // So is a package with our own identifier.
+    let err =
+        add_round1_package(&mut received, own_identifier, own_identifier, package_2).unwrap_err();
+    assert_eq!(err.to_string(), "identifier collision between participants");
+}
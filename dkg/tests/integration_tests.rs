@@ -1,9 +1,13 @@
 use frost_core::{self as frost, Ciphersuite};
 
-use dkg::cli::{cli, MaybeIntoEvenY};
+use dkg::{
+    args::Args,
+    cli::{cli, MaybeIntoEvenY},
+};
 
 use std::collections::HashMap;
 use std::io::{BufRead, Write};
+use std::path::Path;
 use std::thread;
 
 use frost::keys::{KeyPackage, PublicKeyPackage};
@@ -30,12 +34,32 @@ fn read_line(mut reader: impl BufRead) -> Result<String, std::io::Error> {
 // is correct.
 #[test]
 fn check_dkg() {
-    check_dkg_for_ciphersuite::<frost_ed25519::Ed25519Sha512>();
-    check_dkg_for_ciphersuite::<reddsa::frost::redpallas::PallasBlake2b512>();
+    check_dkg_for_ciphersuite::<frost_ed25519::Ed25519Sha512>(None);
+    check_dkg_for_ciphersuite::<reddsa::frost::redpallas::PallasBlake2b512>(None);
 }
 
+// Test if the DKG CLI writes the resulting packages to the files passed with
+// `--out-key-package` and `--out-public-key-package`.
+#[test]
+fn check_dkg_out_files() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    check_dkg_for_ciphersuite::<frost_ed25519::Ed25519Sha512>(Some(temp_dir.path()));
+    check_dkg_for_ciphersuite::<reddsa::frost::redpallas::PallasBlake2b512>(Some(temp_dir.path()));
+}
+
+// If `out_dir` is specified, each participant writes its packages to files in
+// it instead of printing them.
 #[allow(clippy::needless_range_loop)]
-fn check_dkg_for_ciphersuite<C: Ciphersuite + 'static + MaybeIntoEvenY>() {
+fn check_dkg_for_ciphersuite<C: Ciphersuite + 'static + MaybeIntoEvenY>(out_dir: Option<&Path>) {
+    let out_paths = |i: usize| {
+        out_dir.map(|dir| {
+            (
+                dir.join(format!("key-package-{}.json", i)),
+                dir.join(format!("public-key-package-{}.json", i)),
+            )
+        })
+    };
+
     let mut input_writers = Vec::new();
     let mut output_readers = Vec::new();
     let mut join_handles = Vec::new();
@@ -45,8 +69,13 @@ fn check_dkg_for_ciphersuite<C: Ciphersuite + 'static + MaybeIntoEvenY>() {
 
         let (mut input_reader, input_writer) = pipe::pipe();
         let (output_reader, mut output_writer) = pipe::pipe();
+        let args = Args {
+            out_key_package: out_paths(i).map(|p| p.0.to_str().unwrap().to_owned()),
+            out_public_key_package: out_paths(i).map(|p| p.1.to_str().unwrap().to_owned()),
+            ..Default::default()
+        };
         join_handles.push(thread::spawn(move || {
-            cli::<C>(&mut input_reader, &mut output_writer).unwrap()
+            cli::<C>(&args, &mut input_reader, &mut output_writer).unwrap()
         }));
         input_writers.push(input_writer);
         output_readers.push(output_reader);
@@ -209,6 +238,34 @@ fn check_dkg_for_ciphersuite<C: Ciphersuite + 'static + MaybeIntoEvenY>() {
             read_line(&mut output_readers[i]).unwrap(),
             "=== DKG FINISHED ===\n"
         );
+
+        if let Some((key_package_path, public_key_package_path)) = out_paths(i) {
+            assert_eq!(
+                read_line(&mut output_readers[i]).unwrap(),
+                format!(
+                    "Participant key package written to {}\n",
+                    key_package_path.display()
+                )
+            );
+            assert_eq!(read_line(&mut output_readers[i]).unwrap(), "\n");
+            assert_eq!(
+                read_line(&mut output_readers[i]).unwrap(),
+                format!(
+                    "Participant public key package written to {}\n",
+                    public_key_package_path.display()
+                )
+            );
+            assert_eq!(read_line(&mut output_readers[i]).unwrap(), "\n");
+
+            let key_package_json = std::fs::read_to_string(key_package_path).unwrap();
+            let _key_package: KeyPackage<C> = serde_json::from_str(&key_package_json).unwrap();
+            let public_key_package_json = std::fs::read_to_string(public_key_package_path).unwrap();
+            let public_key_package: PublicKeyPackage<C> =
+                serde_json::from_str(&public_key_package_json).unwrap();
+            public_key_packages.insert(i, public_key_package);
+            continue;
+        }
+
         assert_eq!(
             read_line(&mut output_readers[i]).unwrap(),
             "Participant key package:\n"
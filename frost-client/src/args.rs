@@ -1,10 +1,25 @@
+use std::error::Error;
+
 use clap::{Parser, Subcommand};
+use coordinator::args::MessageEncoding;
+use participant::comms::http::build_client;
 
 #[derive(Parser, Clone)]
 #[command(version, about, long_about = None)]
 pub(crate) struct Args {
     #[command(subcommand)]
     pub(crate) command: Command,
+
+    /// The directory of the config file (`credentials.toml`) to use, for
+    /// commands where `--config` is not specified. If not specified, it uses
+    /// the `frost` directory in the user's config directory
+    /// ($XDG_CONFIG_HOME, or $HOME/.config, in Linux).
+    #[arg(long, global = true)]
+    pub(crate) config_dir: Option<String>,
+    /// On failure, print the error to standard error as a JSON object with
+    /// `error` and `code` fields, for scripts, instead of as plain text.
+    #[arg(long, global = true, default_value_t = false)]
+    pub(crate) json_errors: bool,
 }
 
 #[derive(Subcommand, Clone)]
@@ -16,6 +31,15 @@ pub(crate) enum Command {
         /// $HOME/.local/frost/credentials.toml
         #[arg(short, long)]
         config: Option<String>,
+        /// Only print the signed contact string of the user to standard
+        /// output, with the given `name`, for use in scripts. Nothing else
+        /// is printed unless it fails.
+        #[arg(long, default_value_t = false, requires = "name")]
+        non_interactive: bool,
+        /// The name to put in the contact string printed with
+        /// `--non-interactive`.
+        #[arg(long, requires = "non_interactive")]
+        name: Option<String>,
     },
     /// Exports the user's contact, printing a string with the contact
     /// information encoded.
@@ -106,6 +130,78 @@ pub(crate) enum Command {
         #[arg(short, long)]
         group: String,
     },
+    /// Exports the public key package of a group to a file, so that third
+    /// parties can verify signatures. Contains no secret data.
+    ExportPublicKeyPackage {
+        /// The path to the config file to manage. If not specified, it uses
+        /// $HOME/.local/frost/credentials.toml
+        #[arg(short, long)]
+        config: Option<String>,
+        /// The group to export, identified by the group public key (use
+        /// `groups` to list)
+        #[arg(short, long)]
+        group: String,
+        /// The path of the file to write the public key package to.
+        #[arg(short, long)]
+        out: String,
+    },
+    /// Converts a JSON-encoded SecretShare, as written by a trusted dealer, to
+    /// the corresponding KeyPackage, after verifying it against its
+    /// commitment. A KeyPackage can't be converted back, since it does not
+    /// contain the commitment.
+    ConvertShare {
+        /// The file containing the JSON-encoded SecretShare.
+        #[arg(short, long)]
+        input: String,
+        /// The path of the file to write the JSON-encoded KeyPackage to.
+        #[arg(short, long)]
+        out: String,
+    },
+    /// Lists the supported ciphersuites: the name to pass to `-C`, their ID
+    /// (context string), and whether keys are converted to have an even Y
+    /// coordinate.
+    Ciphersuites {},
+    /// Benchmarks the local FROST operations, without any network: trusted
+    /// dealer key generation, then commitment, signing and aggregation with
+    /// the minimum number of signers. Prints the time spent in each phase.
+    Bench {
+        #[arg(short = 'C', long, default_value = "ed25519")]
+        ciphersuite: String,
+        /// The threshold (minimum number of signers).
+        #[arg(short = 't', long, default_value_t = 2)]
+        min: u16,
+        /// The total number of participants (maximum number of signers).
+        #[arg(short = 'n', long, default_value_t = 3)]
+        max: u16,
+        /// The number of key generations and signing ceremonies to run.
+        #[arg(short, long, default_value_t = 10)]
+        iterations: u32,
+    },
+    /// Prints the identifier of the user in a group, and those of the other
+    /// participants, as used when signing.
+    Whoami {
+        /// The path to the config file to manage. If not specified, it uses
+        /// $HOME/.local/frost/credentials.toml
+        #[arg(short, long)]
+        config: Option<String>,
+        /// The group to use, identified by the group public key (use `groups`
+        /// to list)
+        #[arg(short, long)]
+        group: String,
+    },
+    /// Checks that the user's KeyPackage in a group matches the group's
+    /// PublicKeyPackage: that its verifying share is listed under its
+    /// identifier, and that both have the same group verifying key.
+    CheckShare {
+        /// The path to the config file to manage. If not specified, it uses
+        /// $HOME/.local/frost/credentials.toml
+        #[arg(short, long)]
+        config: Option<String>,
+        /// The group to check, identified by the group public key (use
+        /// `groups` to list)
+        #[arg(short, long)]
+        group: String,
+    },
     /// Lists the active FROST signing sessions the user is in.
     Sessions {
         /// The path to the config file to manage. If not specified, it uses
@@ -120,6 +216,51 @@ pub(crate) enum Command {
         /// by the group public key (use `groups` to list).
         #[arg(short, long)]
         group: Option<String>,
+        #[command(flatten)]
+        tls: TlsOptions,
+    },
+    /// Shows the coordinator, participants and message count of a FROST
+    /// signing session, resolving public keys to contact names.
+    SessionInfo {
+        /// The path to the config file to manage. If not specified, it uses
+        /// $HOME/.local/frost/credentials.toml
+        #[arg(short, long)]
+        config: Option<String>,
+        /// The ID of the session (use `sessions` to list).
+        #[arg(short = 'S', long)]
+        session: String,
+        /// The server URL to use. If `group` is specified and `server_url`
+        /// is not, it will use the server URL associated with `group` if any.
+        #[arg(short, long)]
+        server_url: Option<String>,
+        /// Optional group whose associated server URL will be used, identified
+        /// by the group public key (use `groups` to list).
+        #[arg(short, long)]
+        group: Option<String>,
+        #[command(flatten)]
+        tls: TlsOptions,
+    },
+    /// Removes the locally stored sessions which are no longer active in the
+    /// server. Sessions are stored when created with `coordinator` or joined
+    /// with `participant`. Only lists them unless `--yes` is passed.
+    PruneSessions {
+        /// The path to the config file to manage. If not specified, it uses
+        /// $HOME/.local/frost/credentials.toml
+        #[arg(short, long)]
+        config: Option<String>,
+        /// The server URL to use. If `group` is specified and `server_url`
+        /// is not, it will use the server URL associated with `group` if any.
+        #[arg(short, long)]
+        server_url: Option<String>,
+        /// Optional group whose associated server URL will be used, identified
+        /// by the group public key (use `groups` to list).
+        #[arg(short, long)]
+        group: Option<String>,
+        /// Actually remove the sessions, instead of only listing them.
+        #[arg(short, long, default_value_t = false)]
+        yes: bool,
+        #[command(flatten)]
+        tls: TlsOptions,
     },
     Coordinator {
         /// The path to the config file to manage. If not specified, it uses
@@ -138,12 +279,63 @@ pub(crate) enum Command {
         /// The comma-separated hex-encoded public keys of the signers to use.
         #[arg(short = 'S', long, value_delimiter = ',')]
         signers: Vec<String>,
+        /// The ID of an existing session to resume instead of creating a new
+        /// one, e.g. if a previous run was interrupted. If `signers` is not
+        /// specified, the participants of the session are used.
+        #[arg(long)]
+        session_id: Option<String>,
+        /// Reject participants which send commitments or signature shares
+        /// that differ from the ones they have already sent, e.g. to change
+        /// their commitments after seeing the others'. Identical retries are
+        /// still accepted.
+        #[arg(long, default_value_t = false)]
+        strict: bool,
+        /// The file to save the session progress to. If it exists, the
+        /// progress saved in it is restored, which requires `session-id`,
+        /// and the steps already completed are skipped. It is removed once
+        /// the session completes. When resuming a rerandomized (e.g.
+        /// redpallas) session after the signing package was sent, pass the
+        /// same randomizer as before.
+        #[arg(long)]
+        state_file: Option<String>,
+        /// Refresh the session every given number of seconds while waiting
+        /// for participants, so that the server does not time it out during
+        /// a slow signing ceremony. It should be well under the session
+        /// timeout of the server.
+        #[arg(long)]
+        keep_alive: Option<u64>,
+        /// Compress messages to participants before encrypting them, which
+        /// helps with large groups. Participants don't need to pass
+        /// `--compress` too.
+        #[arg(long, default_value_t = false)]
+        compress: bool,
+        /// Give up waiting for participants after the given number of
+        /// seconds in each phase (commitments, then signature shares),
+        /// reporting which of them did not respond. By default, it waits
+        /// until all of them respond.
+        #[arg(long)]
+        participant_timeout: Option<u64>,
+        /// If `participant-timeout` expires while waiting for commitments,
+        /// proceed with the participants which sent theirs if there are at
+        /// least this many, instead of aborting.
+        #[arg(long)]
+        threshold: Option<u16>,
+        /// Commit to the messages when creating the session, revealing them
+        /// only in the signing package, so that participants can check that
+        /// they were not picked after seeing their commitments. Not supported
+        /// when resuming a session.
+        #[arg(long, default_value_t = false, conflicts_with = "session_id")]
+        commit_messages: bool,
         /// The messages to sign. Each instance can be a file with the raw message,
         /// "" or "-". If "" or "-" is specified, then it will be read from standard
-        /// input as a hex string. If none are passed, a single one will be read
-        /// from standard input as a hex string.
+        /// input, encoded as specified by `message-encoding`. If none are passed,
+        /// a single one will be read from standard input.
         #[arg(short = 'm', long)]
         message: Vec<String>,
+        /// The encoding of the messages read from standard input with
+        /// `message`.
+        #[arg(long, value_enum, default_value_t = MessageEncoding::Hex)]
+        message_encoding: MessageEncoding,
         /// The randomizers to use. Each instance can be a file with the raw
         /// randomizer, "" or "-". If "" or "-" is specified, then it will be read
         /// from standard input as a hex string. If none are passed, random ones
@@ -151,10 +343,24 @@ pub(crate) enum Command {
         /// the `message` parameter.
         #[arg(short = 'r', long)]
         randomizer: Vec<String>,
+        /// An auxiliary message to send to participants along with the
+        /// signing package, as a hex string; e.g. the transaction being
+        /// signed, so that they can check what the messages they are signing
+        /// correspond to. The randomizer is derived from it, binding the
+        /// signature to it, so it can't be used with `randomizer`.
+        #[arg(long, default_value = "")]
+        aux_msg: String,
         /// Where to write the generated raw bytes signature. If "-", the
         /// human-readable hex-string is printed to stdout.
         #[arg(short = 'o', long, default_value = "")]
         signature: String,
+        /// Path of a file to append, for each signed message, a JSON record
+        /// with the signing package, the identifiers of the signers, the
+        /// randomizer (if any) and the group signature.
+        #[arg(long)]
+        audit_log: Option<String>,
+        #[command(flatten)]
+        tls: TlsOptions,
     },
     Participant {
         /// The path to the config file to manage. If not specified, it uses
@@ -171,8 +377,125 @@ pub(crate) enum Command {
         #[arg(short, long)]
         group: String,
         /// The session ID to use (use `sessions` to list). Can be omitted in
-        /// case there is a single active session.
+        /// case there is a single active session. Can be repeated to join
+        /// several sessions at once, signing each of them as its signing
+        /// package arrives.
         #[arg(short = 'S', long)]
-        session: Option<String>,
+        session: Vec<String>,
+        /// List the active sessions and ask which one to join, instead of
+        /// requiring `--session` when there are several of them.
+        #[arg(long, default_value_t = false, conflicts_with_all = ["session", "sign_offline"])]
+        list: bool,
+        /// Join all the active sessions at once, signing each of them as its
+        /// signing package arrives.
+        #[arg(
+            long,
+            default_value_t = false,
+            conflicts_with_all = [
+                "session",
+                "list",
+                "confirm",
+                "save_signing_package",
+                "sign_offline",
+                "send_signature_share",
+            ]
+        )]
+        all: bool,
+        /// Only join the session if it was created by this coordinator,
+        /// given as a contact name or a hex-encoded public key.
+        #[arg(long)]
+        expect_coordinator: Option<String>,
+        #[command(flatten)]
+        tls: TlsOptions,
+        /// Show the message to be signed and ask for confirmation before
+        /// signing it. If declined, the coordinator is told so and nothing is
+        /// signed.
+        #[arg(long, default_value_t = false)]
+        confirm: bool,
+        /// Refresh the session every given number of seconds while waiting
+        /// for the coordinator, so that the server does not time it out
+        /// during a slow signing ceremony. It should be well under the
+        /// session timeout of the server.
+        #[arg(long)]
+        keep_alive: Option<u64>,
+        /// Compress messages to the coordinator before encrypting them. The
+        /// coordinator doesn't need to pass `--compress` too.
+        #[arg(long, default_value_t = false)]
+        compress: bool,
+        /// Offline signing, step 1: save the signing package received from
+        /// the coordinator to the given file instead of signing it, to be
+        /// signed with `--sign-offline`. The file holds the secret nonces too.
+        #[arg(long)]
+        save_signing_package: Option<String>,
+        /// Offline signing, step 2: sign the signing package saved with
+        /// `--save-signing-package`, writing the signature share to
+        /// `--signature-share-out`. This does not contact the server. The
+        /// signing package file is deleted afterwards, since its nonces must
+        /// never be used again.
+        #[arg(long)]
+        sign_offline: Option<String>,
+        /// The file to write the signature share to, with `--sign-offline`.
+        #[arg(long)]
+        signature_share_out: Option<String>,
+        /// Offline signing, step 3: send the signature share written with
+        /// `--sign-offline` to the coordinator.
+        #[arg(long)]
+        send_signature_share: Option<String>,
     },
 }
+
+impl Command {
+    /// The `config` argument of the command, if it takes a single config.
+    pub(crate) fn config_mut(&mut self) -> Option<&mut Option<String>> {
+        match self {
+            Command::Init { config, .. }
+            | Command::Export { config, .. }
+            | Command::Import { config, .. }
+            | Command::Contacts { config }
+            | Command::RemoveContact { config, .. }
+            | Command::Groups { config }
+            | Command::RemoveGroup { config, .. }
+            | Command::ExportPublicKeyPackage { config, .. }
+            | Command::Whoami { config, .. }
+            | Command::CheckShare { config, .. }
+            | Command::Sessions { config, .. }
+            | Command::SessionInfo { config, .. }
+            | Command::PruneSessions { config, .. }
+            | Command::Coordinator { config, .. }
+            | Command::Participant { config, .. } => Some(config),
+            Command::TrustedDealer { .. }
+            | Command::ConvertShare { .. }
+            | Command::Ciphersuites { .. }
+            | Command::Bench { .. } => None,
+        }
+    }
+}
+
+/// The options to connect to the server over TLS, shared by the commands
+/// which connect to it.
+#[derive(clap::Args, Clone)]
+pub(crate) struct TlsOptions {
+    /// Path to the PEM-encoded certificate of the CA which issued the server
+    /// certificate, if it is not trusted by the system.
+    #[arg(long)]
+    pub(crate) cacert: Option<String>,
+    /// Do not verify the server certificate. This is very insecure and should
+    /// only be used for testing.
+    #[arg(long, default_value_t = false)]
+    pub(crate) insecure_skip_verify: bool,
+    /// The proxy to connect to the server through. If not specified, the
+    /// `HTTPS_PROXY` and `ALL_PROXY` environment variables are used, if set.
+    #[arg(long)]
+    pub(crate) proxy: Option<String>,
+}
+
+impl TlsOptions {
+    /// Create a HTTP client to connect to the server with these options.
+    pub(crate) fn build_client(&self) -> Result<reqwest::Client, Box<dyn Error>> {
+        build_client(
+            self.cacert.as_deref(),
+            self.insecure_skip_verify,
+            self.proxy.as_deref(),
+        )
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use eyre::eyre;
+use frost_core::{keys::KeyPackage, Ciphersuite, SigningPackage};
+use frost_ed25519::Ed25519Sha512;
+use frost_rerandomized::{RandomizedCiphersuite, RandomizedParams};
+use participant::round1::generate_nonces_and_commitments_with_rng;
+use participant::round2::{generate_signature, Round2Config};
+use rand::thread_rng;
+use reddsa::frost::redpallas::PallasBlake2b512;
+use trusted_dealer::MaybeIntoEvenY;
+
+use crate::args::Command;
+
+/// The message signed in each iteration.
+const BENCH_MESSAGE: &[u8] = b"frost-client bench";
+
+/// The time spent in each phase of the benchmark, summed over all iterations.
+/// Commitment and signing times are summed over all signers.
+#[derive(Debug, Default)]
+pub(crate) struct BenchReport {
+    pub(crate) iterations: u32,
+    pub(crate) keygen: Duration,
+    pub(crate) commit: Duration,
+    pub(crate) sign: Duration,
+    pub(crate) aggregate: Duration,
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} iterations", self.iterations)?;
+        writeln!(f, "{:<10} {:>12} {:>12}", "phase", "total", "average")?;
+        for (name, total) in [
+            ("keygen", self.keygen),
+            ("commit", self.commit),
+            ("sign", self.sign),
+            ("aggregate", self.aggregate),
+        ] {
+            writeln!(
+                f,
+                "{:<10} {:>12.2?} {:>12.2?}",
+                name,
+                total,
+                total / self.iterations.max(1)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn bench(args: &Command) -> Result<(), Box<dyn Error>> {
+    let Command::Bench {
+        ciphersuite,
+        min,
+        max,
+        iterations,
+    } = (*args).clone()
+    else {
+        panic!("invalid Command");
+    };
+
+    let report = if ciphersuite == "ed25519" {
+        run_bench::<Ed25519Sha512>(min, max, iterations)?
+    } else if ciphersuite == "redpallas" {
+        run_bench::<PallasBlake2b512>(min, max, iterations)?
+    } else {
+        return Err(eyre!("unsupported ciphersuite").into());
+    };
+    print!("{}", report);
+
+    Ok(())
+}
+
+/// Run `iterations` times a trusted dealer key generation for a `min`-of-`max`
+/// group, followed by a signing ceremony with `min` signers, all locally.
+/// Fails if any of the generated signatures is invalid.
+pub(crate) fn run_bench<C: RandomizedCiphersuite + MaybeIntoEvenY + 'static>(
+    min: u16,
+    max: u16,
+    iterations: u32,
+) -> Result<BenchReport, Box<dyn Error>> {
+    let config = trusted_dealer::Config::new(min, max, vec![])?;
+    let rerandomized = C::ID == PallasBlake2b512::ID;
+    let mut rng = thread_rng();
+    let mut report = BenchReport {
+        iterations,
+        ..Default::default()
+    };
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let (shares, public_key_package) =
+            trusted_dealer::trusted_dealer::<C, _>(&config, &mut rng)?;
+        report.keygen += start.elapsed();
+
+        let mut key_packages = BTreeMap::new();
+        for (identifier, share) in shares.into_iter().take(min as usize) {
+            key_packages.insert(identifier, KeyPackage::try_from(share)?);
+        }
+
+        let mut nonces = BTreeMap::new();
+        let mut commitments = BTreeMap::new();
+        for (identifier, key_package) in &key_packages {
+            let start = Instant::now();
+            let (n, c) = generate_nonces_and_commitments_with_rng(key_package, &mut rng);
+            report.commit += start.elapsed();
+            nonces.insert(*identifier, n);
+            commitments.insert(*identifier, c);
+        }
+
+        let signing_package = SigningPackage::new(commitments, BENCH_MESSAGE);
+        let randomized_params = if rerandomized {
+            Some(RandomizedParams::new(
+                public_key_package.verifying_key(),
+                &signing_package,
+                &mut rng,
+            )?)
+        } else {
+            None
+        };
+        let round_2_config = Round2Config {
+            signing_package: signing_package.clone(),
+            randomizer: randomized_params.as_ref().map(|p| *p.randomizer()),
+            aux_msg: Vec::new(),
+            seed: Vec::new(),
+        };
+
+        let mut signature_shares = BTreeMap::new();
+        for (identifier, key_package) in &key_packages {
+            let start = Instant::now();
+            let signature_share =
+                generate_signature(round_2_config.clone(), key_package, &nonces[identifier])?;
+            report.sign += start.elapsed();
+            signature_shares.insert(*identifier, signature_share);
+        }
+
+        let start = Instant::now();
+        let signature = coordinator::step_3::aggregate_shares(
+            &[signing_package],
+            &[signature_shares],
+            &public_key_package,
+            randomized_params.as_ref().map(std::slice::from_ref),
+        )?
+        .remove(0);
+        report.aggregate += start.elapsed();
+
+        let verifying_key = match &randomized_params {
+            Some(params) => *params.randomized_verifying_key(),
+            None => *public_key_package.verifying_key(),
+        };
+        verifying_key
+            .verify(BENCH_MESSAGE, &signature)
+            .map_err(|_| eyre!("the generated signature is invalid"))?;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_bench() {
+        let report = run_bench::<Ed25519Sha512>(2, 3, 2).unwrap();
+        assert_eq!(report.iterations, 2);
+        assert!(report.sign > Duration::ZERO);
+        assert!(report.to_string().contains("aggregate"));
+
+        run_bench::<PallasBlake2b512>(3, 5, 2).unwrap();
+
+        assert!(run_bench::<Ed25519Sha512>(4, 3, 1).is_err());
+    }
+}
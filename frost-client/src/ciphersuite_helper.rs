@@ -1,17 +1,21 @@
-use std::{error::Error, marker::PhantomData};
+use std::{error::Error, io::Write, marker::PhantomData};
 
 use eyre::eyre;
 use frost_core::{
-    keys::{KeyPackage, PublicKeyPackage},
+    keys::{KeyPackage, PublicKeyPackage, SecretShare},
     Ciphersuite,
 };
 use frost_ed25519::Ed25519Sha512;
 use reddsa::frost::redpallas::PallasBlake2b512;
 
+use crate::args::Command;
+
 /// Additional information about a group, derived from the key packages.
 #[derive(Debug, Clone)]
 pub struct GroupInfo {
     pub hex_verifying_key: String,
+    /// The hex-encoded identifier of the user in the group.
+    pub hex_identifier: String,
     pub threshold: usize,
     pub num_participants: usize,
 }
@@ -23,6 +27,29 @@ pub trait CiphersuiteHelper {
         encoded_key_package: &[u8],
         encoded_public_key_package: &[u8],
     ) -> Result<GroupInfo, Box<dyn Error>>;
+
+    /// Check that an encoded key package belongs to the group described by an
+    /// encoded public key package, returning a description of each mismatch
+    /// found (none if they match).
+    fn check_key_package(
+        &self,
+        encoded_key_package: &[u8],
+        encoded_public_key_package: &[u8],
+    ) -> Result<Vec<String>, Box<dyn Error>>;
+
+    /// Convert an encoded public key package to its JSON representation.
+    fn public_key_package_to_json(
+        &self,
+        encoded_public_key_package: &[u8],
+    ) -> Result<serde_json::Value, Box<dyn Error>>;
+
+    /// Convert a JSON-encoded SecretShare to the JSON representation of the
+    /// KeyPackage derived from it. This verifies the share against its
+    /// commitment.
+    fn secret_share_to_key_package(
+        &self,
+        secret_share: &str,
+    ) -> Result<serde_json::Value, Box<dyn Error>>;
 }
 
 /// An implementation of CiphersuiteHelper that works for any Ciphersuite.
@@ -41,18 +68,66 @@ where
     }
 }
 
+/// A ciphersuite supported by the CLIs.
+pub struct CiphersuiteInfo {
+    /// The name used to select the ciphersuite in the command line (`-C`).
+    pub name: &'static str,
+    /// The ciphersuite ID, i.e. its context string.
+    pub id: &'static str,
+    /// Whether generated keys are converted to have an even Y coordinate, as
+    /// required by Zcash.
+    pub even_y: bool,
+    helper: fn() -> Box<dyn CiphersuiteHelper>,
+}
+
+/// The supported ciphersuites.
+pub const CIPHERSUITES: &[CiphersuiteInfo] = &[
+    CiphersuiteInfo {
+        name: "ed25519",
+        id: Ed25519Sha512::ID,
+        even_y: false,
+        helper: || Box::new(CiphersuiteHelperImpl::<Ed25519Sha512>::default()),
+    },
+    CiphersuiteInfo {
+        name: "redpallas",
+        id: PallasBlake2b512::ID,
+        even_y: true,
+        helper: || Box::new(CiphersuiteHelperImpl::<PallasBlake2b512>::default()),
+    },
+];
+
 /// Get a CiphersuiteHelper for the given ciphersuite.
 pub(crate) fn ciphersuite_helper(
     ciphersuite_id: &str,
 ) -> Result<Box<dyn CiphersuiteHelper>, Box<dyn Error>> {
-    if ciphersuite_id == Ed25519Sha512::ID {
-        return Ok(Box::new(CiphersuiteHelperImpl::<Ed25519Sha512>::default()));
-    } else if ciphersuite_id == PallasBlake2b512::ID {
-        return Ok(Box::new(
-            CiphersuiteHelperImpl::<PallasBlake2b512>::default(),
-        ));
+    CIPHERSUITES
+        .iter()
+        .find(|c| c.id == ciphersuite_id)
+        .map(|c| (c.helper)())
+        .ok_or_else(|| eyre!("invalid ciphersuite ID").into())
+}
+
+/// Print the supported ciphersuites.
+pub(crate) fn list(args: &Command) -> Result<(), Box<dyn Error>> {
+    let Command::Ciphersuites {} = (*args).clone() else {
+        panic!("invalid Command");
+    };
+
+    print_ciphersuites(&mut std::io::stdout())
+}
+
+fn print_ciphersuites(output: &mut dyn Write) -> Result<(), Box<dyn Error>> {
+    writeln!(output, "{:<12} {:<28} even Y", "name", "ID")?;
+    for c in CIPHERSUITES {
+        writeln!(
+            output,
+            "{:<12} {:<28} {}",
+            c.name,
+            c.id,
+            if c.even_y { "yes" } else { "no" }
+        )?;
     }
-    Err(eyre!("invalid ciphersuite ID").into())
+    Ok(())
 }
 
 impl<C> CiphersuiteHelper for CiphersuiteHelperImpl<C>
@@ -70,8 +145,91 @@ where
         let hex_verifying_key = hex::encode(public_key_package.verifying_key().serialize()?);
         Ok(GroupInfo {
             hex_verifying_key,
+            hex_identifier: hex::encode(key_package.identifier().serialize()),
             threshold: *key_package.min_signers() as usize,
             num_participants: public_key_package.verifying_shares().len(),
         })
     }
+
+    fn check_key_package(
+        &self,
+        encoded_key_package: &[u8],
+        encoded_public_key_package: &[u8],
+    ) -> Result<Vec<String>, Box<dyn Error>> {
+        let key_package: KeyPackage<C> = postcard::from_bytes(encoded_key_package)?;
+        let public_key_package: PublicKeyPackage<C> =
+            postcard::from_bytes(encoded_public_key_package)?;
+        let hex_identifier = hex::encode(key_package.identifier().serialize());
+
+        let mut mismatches = Vec::new();
+        if key_package.verifying_key() != public_key_package.verifying_key() {
+            mismatches.push(format!(
+                "the group verifying key of the key package ({}) differs from the one of the \
+                 public key package ({})",
+                hex::encode(key_package.verifying_key().serialize()?),
+                hex::encode(public_key_package.verifying_key().serialize()?)
+            ));
+        }
+        match public_key_package
+            .verifying_shares()
+            .get(key_package.identifier())
+        {
+            None => mismatches.push(format!(
+                "identifier {} is not in the public key package",
+                hex_identifier
+            )),
+            Some(verifying_share) if verifying_share != key_package.verifying_share() => mismatches
+                .push(format!(
+                    "the verifying share of identifier {} differs from the one in the public \
+                     key package",
+                    hex_identifier
+                )),
+            Some(_) => {}
+        }
+        Ok(mismatches)
+    }
+
+    fn public_key_package_to_json(
+        &self,
+        encoded_public_key_package: &[u8],
+    ) -> Result<serde_json::Value, Box<dyn Error>> {
+        let public_key_package: PublicKeyPackage<C> =
+            postcard::from_bytes(encoded_public_key_package)?;
+        Ok(serde_json::to_value(public_key_package)?)
+    }
+
+    fn secret_share_to_key_package(
+        &self,
+        secret_share: &str,
+    ) -> Result<serde_json::Value, Box<dyn Error>> {
+        let secret_share: SecretShare<C> = serde_json::from_str(secret_share)?;
+        let key_package = KeyPackage::try_from(secret_share)?;
+        Ok(serde_json::to_value(key_package)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_print_ciphersuites() {
+        let mut output = Vec::new();
+        print_ciphersuites(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let lines = output.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("ed25519 "));
+        assert!(lines[1].contains("FROST-ED25519-SHA512-v1"));
+        assert!(lines[1].ends_with(" no"));
+        assert!(lines[2].starts_with("redpallas "));
+        assert!(lines[2].contains("FROST(Pallas, BLAKE2b-512)"));
+        assert!(lines[2].ends_with(" yes"));
+
+        for c in CIPHERSUITES {
+            assert!(ciphersuite_helper(c.id).is_ok());
+        }
+        assert!(ciphersuite_helper("FROST-UNKNOWN-v1").is_err());
+    }
 }
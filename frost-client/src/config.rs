@@ -2,12 +2,16 @@ use core::str;
 use std::{
     collections::BTreeMap,
     error::Error,
+    fs::File,
     path::{Path, PathBuf},
     str::FromStr,
 };
 
 use eyre::{eyre, OptionExt};
+use fs4::fs_std::FileExt as _;
 use serde::{Deserialize, Serialize};
+use snow::resolvers::CryptoResolver as _;
+use uuid::Uuid;
 
 use crate::{ciphersuite_helper::ciphersuite_helper, contact::Contact, write_atomic};
 
@@ -17,6 +21,10 @@ pub struct Config {
     /// The path the config was loaded from.
     #[serde(skip)]
     path: Option<PathBuf>,
+    /// The contents of the config file when it was loaded, if it existed;
+    /// used to detect if another process changed it in the meantime.
+    #[serde(skip)]
+    loaded_bytes: Option<Vec<u8>>,
     pub version: u8,
     /// The communication key pair for the user.
     pub communication_key: Option<CommunicationKey>,
@@ -26,6 +34,9 @@ pub struct Config {
     /// The FROST groups the user belongs to, keyed by hex-encoded verifying key
     #[serde(default)]
     pub group: BTreeMap<String, Group>,
+    /// The signing sessions the user has seen, keyed by session ID.
+    #[serde(default)]
+    pub session: BTreeMap<String, Session>,
 }
 
 impl Config {
@@ -37,6 +48,38 @@ impl Config {
             .cloned()
             .ok_or_eyre("contact not found")?)
     }
+
+    /// Keep a record of a session the user created or joined in the given
+    /// server, so that it can be removed with [`Self::prune_sessions()`] once
+    /// the server no longer knows it.
+    pub fn add_session(&mut self, session_id: Uuid, server_url: &str, coordinator_pubkey: &[u8]) {
+        self.session.insert(
+            session_id.to_string(),
+            Session {
+                server_url: server_url.to_string(),
+                coordinator_pubkey: coordinator_pubkey.to_vec(),
+            },
+        );
+    }
+
+    /// Remove the sessions stored for the given server which are not in
+    /// `active_session_ids`, returning the IDs of the removed sessions.
+    /// Sessions stored for other servers are left untouched.
+    pub fn prune_sessions(&mut self, server_url: &str, active_session_ids: &[Uuid]) -> Vec<String> {
+        let stale: Vec<String> = self
+            .session
+            .iter()
+            .filter(|(id, session)| {
+                session.server_url == server_url
+                    && !active_session_ids.iter().any(|a| a.to_string() == **id)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &stale {
+            self.session.remove(id);
+        }
+        stale
+    }
 }
 
 /// The communication key pair for the user.
@@ -48,14 +91,34 @@ pub struct CommunicationKey {
         deserialize_with = "serdect::slice::deserialize_hex_or_bin_vec"
     )]
     pub privkey: Vec<u8>,
-    /// The public key.
+    /// The public key. If it is missing, it can be derived again from the
+    /// private key with [`CommunicationKey::from_privkey()`].
     #[serde(
+        default,
         serialize_with = "serdect::slice::serialize_hex_lower_or_bin",
         deserialize_with = "serdect::slice::deserialize_hex_or_bin_vec"
     )]
     pub pubkey: Vec<u8>,
 }
 
+impl CommunicationKey {
+    /// Create the key pair of the given X25519 private key, deriving its
+    /// public key.
+    pub fn from_privkey(privkey: Vec<u8>) -> Result<Self, Box<dyn Error>> {
+        if privkey.len() != 32 {
+            return Err(eyre!("invalid communication private key length").into());
+        }
+        let mut dh = snow::resolvers::DefaultResolver
+            .resolve_dh(&snow::params::DHChoice::Curve25519)
+            .ok_or_eyre("X25519 is not supported")?;
+        dh.set(&privkey);
+        Ok(Self {
+            pubkey: dh.pubkey().to_vec(),
+            privkey,
+        })
+    }
+}
+
 /// A FROST group the user belongs to.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Group {
@@ -121,6 +184,19 @@ pub struct Participant {
     pub pubkey: Vec<u8>,
 }
 
+/// A signing session the user has seen.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    /// The server the session is in.
+    pub server_url: String,
+    /// The communication public key of the session coordinator.
+    #[serde(
+        serialize_with = "serdect::slice::serialize_hex_lower_or_bin",
+        deserialize_with = "serdect::slice::deserialize_hex_or_bin_vec"
+    )]
+    pub coordinator_pubkey: Vec<u8>,
+}
+
 impl Config {
     /// Returns the default path of the config
     /// ($HOME/.config/frost/credentials.toml in Linux) if `path` is None,
@@ -129,11 +205,34 @@ impl Config {
         if let Some(path) = path {
             Ok(PathBuf::from_str(&path)?)
         } else {
-            Ok(dirs::config_local_dir()
-                .unwrap()
-                .join("frost")
-                .join("credentials.toml"))
+            Ok(Self::path_in_dir(
+                dirs::config_local_dir()
+                    .ok_or_eyre("could not find the config directory")?
+                    .join("frost"),
+            ))
+        }
+    }
+
+    /// Returns the path of the config in the given directory.
+    pub fn path_in_dir(dir: impl AsRef<Path>) -> PathBuf {
+        dir.as_ref().join("credentials.toml")
+    }
+
+    /// Acquire the lock of the config in the given path, which is released
+    /// when the returned file is dropped. Fails if another process holds it.
+    ///
+    /// The lock is taken on a separate `.lock` file, since the config file
+    /// itself is replaced when written.
+    pub fn lock(path: &Path) -> Result<File, Box<dyn Error>> {
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
         }
+        let file = File::create(lock_path)?;
+        file.try_lock_exclusive()
+            .map_err(|_| eyre!("config is locked by another process"))?;
+        Ok(file)
     }
 
     pub fn path(&self) -> Option<&Path> {
@@ -155,18 +254,111 @@ impl Config {
         let s = str::from_utf8(&bytes)?;
         let mut config: Config = toml::from_str(s)?;
         config.path = Some(path);
+        config.loaded_bytes = Some(bytes);
         Ok(config)
     }
 
     /// Write the config to path it was loaded from.
+    ///
+    /// Fails if another process is writing the config, or if it changed it
+    /// since it was loaded, so that its changes are not overwritten.
     pub fn write(&self) -> Result<(), Box<dyn Error>> {
+        let path = self
+            .path
+            .clone()
+            .ok_or_else(|| eyre!("path not specified"))?;
+        let _lock = Self::lock(&path)?;
+        let current_bytes = if path.exists() {
+            Some(std::fs::read(&path)?)
+        } else {
+            None
+        };
+        if current_bytes != self.loaded_bytes {
+            return Err(eyre!("config was changed by another process; please try again").into());
+        }
         let s = toml::to_string_pretty(self)?;
         let bytes = s.as_bytes();
-        Ok(write_atomic::write_file(
-            self.path
-                .clone()
-                .ok_or_else(|| eyre!("path not specified"))?,
-            bytes,
-        )?)
+        Ok(write_atomic::write_file(path, bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(server_url: &str) -> Session {
+        Session {
+            server_url: server_url.to_string(),
+            coordinator_pubkey: vec![0; 32],
+        }
+    }
+
+    #[test]
+    fn check_communication_key_from_privkey() {
+        let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+        let keypair = builder.generate_keypair().unwrap();
+
+        let communication_key = CommunicationKey::from_privkey(keypair.private.clone()).unwrap();
+        assert_eq!(communication_key.privkey, keypair.private);
+        assert_eq!(communication_key.pubkey, keypair.public);
+
+        assert!(CommunicationKey::from_privkey(vec![0; 31]).is_err());
+    }
+
+    #[test]
+    fn check_config_lock() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = Config::path_in_dir(temp_dir.path());
+        let read = || Config::read(Some(path.to_str().unwrap().to_owned())).unwrap();
+
+        // Another process writing the config holds the lock.
+        let config = read();
+        let lock = Config::lock(&path).unwrap();
+        assert_eq!(
+            config.write().unwrap_err().to_string(),
+            "config is locked by another process"
+        );
+        assert_eq!(
+            Config::lock(&path).unwrap_err().to_string(),
+            "config is locked by another process"
+        );
+        drop(lock);
+        config.write().unwrap();
+
+        // Both processes read the config, then the second one writes it; the
+        // first one must not overwrite its changes.
+        let mut first = read();
+        let mut second = read();
+        second
+            .session
+            .insert(Uuid::new_v4().to_string(), session("localhost:2744"));
+        second.write().unwrap();
+        first
+            .session
+            .insert(Uuid::new_v4().to_string(), session("localhost:2744"));
+        assert_eq!(
+            first.write().unwrap_err().to_string(),
+            "config was changed by another process; please try again"
+        );
+        assert_eq!(read().session.len(), 1);
+    }
+
+    #[test]
+    fn check_prune_sessions() {
+        let active = Uuid::new_v4();
+        let stale = Uuid::new_v4();
+        let other_server = Uuid::new_v4();
+
+        let mut config = Config::default();
+        config.add_session(active, "localhost:2744", &[0; 32]);
+        config.add_session(stale, "localhost:2744", &[0; 32]);
+        config.add_session(other_server, "example.com:2744", &[0; 32]);
+
+        let pruned = config.prune_sessions("localhost:2744", &[active]);
+
+        assert_eq!(pruned, vec![stale.to_string()]);
+        assert!(config.session.contains_key(&active.to_string()));
+        assert!(!config.session.contains_key(&stale.to_string()));
+        assert!(config.session.contains_key(&other_server.to_string()));
     }
 }
@@ -1,9 +1,14 @@
 use std::error::Error;
 
 use eyre::{eyre, OptionExt};
+use rand::thread_rng;
 use serde::{Deserialize, Serialize};
+use xeddsa::{xed25519, Sign as _, Verify as _};
 
-use crate::{args::Command, config::Config};
+use crate::{
+    args::Command,
+    config::{CommunicationKey, Config},
+};
 
 /// A FROST contact, which critically has the public key required to
 /// send and receive encrypted and authenticated messages to them.
@@ -20,9 +25,30 @@ pub struct Contact {
         deserialize_with = "serdect::slice::deserialize_hex_or_bin_vec"
     )]
     pub pubkey: Vec<u8>,
+    /// Signature of the contact by the private key matching `pubkey`, which
+    /// shows that the contact was not tampered with after being exported. It
+    /// is not stored in the config file.
+    #[serde(skip)]
+    pub signature: Option<Vec<u8>>,
 }
 
 impl Contact {
+    /// Create the contact of the user with the given communication key pair,
+    /// signed with it, to be exported.
+    pub fn new_signed(
+        name: String,
+        communication_key: &CommunicationKey,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut contact = Contact {
+            version: Some(0),
+            name,
+            pubkey: communication_key.pubkey.clone(),
+            signature: None,
+        };
+        contact.sign(&communication_key.privkey)?;
+        Ok(contact)
+    }
+
     /// Returns a human-readable summary of the contact; used when it is
     /// printed to the terminal.
     pub fn as_human_readable_summary(&self) -> String {
@@ -35,7 +61,12 @@ impl Contact {
 
     /// Returns the contact encoded as a text string, with Bech32.
     pub fn as_text(&self) -> Result<String, Box<dyn Error>> {
-        let bytes = postcard::to_allocvec(self)?;
+        let mut bytes = postcard::to_allocvec(self)?;
+        // The signature is appended after the encoded contact, so that
+        // unsigned contacts keep the same format.
+        if let Some(signature) = &self.signature {
+            bytes.extend_from_slice(signature);
+        }
         let hrp = bech32::Hrp::parse("zffrost").expect("valid hrp");
         Ok(bech32::encode::<bech32::Bech32m>(hrp, &bytes)?)
     }
@@ -46,12 +77,52 @@ impl Contact {
         if hrp.as_str() != "zffrost" {
             return Err(eyre!("invalid contact format").into());
         }
-        let contact: Contact = postcard::from_bytes(&bytes)?;
+        let (mut contact, signature): (Contact, _) = postcard::take_from_bytes(&bytes)?;
         if contact.version != Some(0) {
             return Err(eyre!("invalid contact version").into());
         }
+        contact.signature = match signature.len() {
+            0 => None,
+            64 => Some(signature.to_vec()),
+            _ => return Err(eyre!("invalid contact format").into()),
+        };
         Ok(contact)
     }
+
+    /// Signs the contact with the given communication private key, which must
+    /// match the contact's public key.
+    pub fn sign(&mut self, privkey: &[u8]) -> Result<(), Box<dyn Error>> {
+        let privkey = xed25519::PrivateKey::from(
+            &TryInto::<[u8; 32]>::try_into(privkey).map_err(|_| eyre!("invalid comm_privkey"))?,
+        );
+        // The signature is skipped when encoding, so this is the encoded
+        // contact without it.
+        let message = postcard::to_allocvec(self)?;
+        let signature: [u8; 64] = privkey.sign(&message, &mut thread_rng());
+        self.signature = Some(signature.to_vec());
+        Ok(())
+    }
+
+    /// Verifies the signature of the contact. Returns false if the contact
+    /// is not signed, and an error if the signature is invalid.
+    ///
+    /// Note that this does not prevent someone from replacing the entire
+    /// contact with one signed by their own key.
+    pub fn verify(&self) -> Result<bool, Box<dyn Error>> {
+        let Some(signature) = &self.signature else {
+            return Ok(false);
+        };
+        let pubkey = xed25519::PublicKey(
+            TryInto::<[u8; 32]>::try_into(self.pubkey.as_slice())
+                .map_err(|_| eyre!("invalid contact public key"))?,
+        );
+        let signature = TryInto::<[u8; 64]>::try_into(signature.as_slice())
+            .map_err(|_| eyre!("invalid contact signature"))?;
+        pubkey
+            .verify(&postcard::to_allocvec(self)?, &signature)
+            .map_err(|_| eyre!("invalid contact signature; it may have been tampered with"))?;
+        Ok(true)
+    }
 }
 
 /// Import a contact into the user's address book, in the config file.
@@ -67,6 +138,13 @@ pub(crate) fn import(args: &Command) -> Result<(), Box<dyn Error>> {
     let mut config = Config::read(config)?;
 
     let mut contact = Contact::from_text(&text_contact)?;
+    if !contact.verify()? {
+        eprintln!(
+            "WARNING: this contact is not signed, so it is not possible to check \
+            if it was tampered with. Confirm its public key with the contact \
+            through another channel before using it."
+        );
+    }
     // We don't want the version when writing to the config file.
     contact.version = None;
     config.contact.insert(contact.name.clone(), contact.clone());
@@ -86,16 +164,11 @@ pub(crate) fn export(args: &Command) -> Result<(), Box<dyn Error>> {
     };
 
     let config = Config::read(config)?;
+    let communication_key = config
+        .communication_key
+        .ok_or(eyre!("pubkey not generated yet"))?;
 
-    // Build the contact to export.
-    let contact = Contact {
-        version: Some(0),
-        name,
-        pubkey: config
-            .communication_key
-            .ok_or(eyre!("pubkey not generated yet"))?
-            .pubkey,
-    };
+    let contact = Contact::new_signed(name, &communication_key)?;
 
     eprintln!("Exporting this information:");
     eprint!("{}", contact.as_human_readable_summary());
@@ -152,3 +225,53 @@ pub(crate) fn remove(args: &Command) -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed_contact() -> Contact {
+        let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+        let keypair = builder.generate_keypair().unwrap();
+        let mut contact = Contact {
+            version: Some(0),
+            name: "alice".to_string(),
+            pubkey: keypair.public,
+            signature: None,
+        };
+        contact.sign(&keypair.private).unwrap();
+        contact
+    }
+
+    #[test]
+    fn check_signed_contact() {
+        let contact = signed_contact();
+
+        let imported = Contact::from_text(&contact.as_text().unwrap()).unwrap();
+        assert_eq!(imported.name, contact.name);
+        assert_eq!(imported.pubkey, contact.pubkey);
+        assert!(imported.verify().unwrap());
+
+        // Unsigned contacts can still be imported, but are not verified.
+        let mut unsigned = contact.clone();
+        unsigned.signature = None;
+        let imported = Contact::from_text(&unsigned.as_text().unwrap()).unwrap();
+        assert_eq!(imported.signature, None);
+        assert!(!imported.verify().unwrap());
+    }
+
+    #[test]
+    fn check_tampered_contact() {
+        let mut contact = signed_contact();
+        contact.name = "mallory".to_string();
+
+        let imported = Contact::from_text(&contact.as_text().unwrap()).unwrap();
+        assert!(imported.verify().is_err());
+
+        let mut contact = signed_contact();
+        contact.pubkey = signed_contact().pubkey;
+
+        let imported = Contact::from_text(&contact.as_text().unwrap()).unwrap();
+        assert!(imported.verify().is_err());
+    }
+}
@@ -1,5 +1,7 @@
 use std::error::Error;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::time::Duration;
 
 use coordinator::cli::cli_for_processed_args;
 use eyre::eyre;
@@ -10,10 +12,11 @@ use frost_core::keys::PublicKeyPackage;
 use frost_core::Ciphersuite;
 use frost_ed25519::Ed25519Sha512;
 use frost_rerandomized::RandomizedCiphersuite;
+use rand::{thread_rng, RngCore as _};
 use reddsa::frost::redpallas::PallasBlake2b512;
 use reqwest::Url;
 
-use crate::{args::Command, config::Config};
+use crate::{args::Command, config::Config, session::session_recorder};
 
 pub(crate) async fn run(args: &Command) -> Result<(), Box<dyn Error>> {
     let Command::Coordinator { config, group, .. } = (*args).clone() else {
@@ -41,9 +44,21 @@ pub(crate) async fn run_for_ciphersuite<C: RandomizedCiphersuite + 'static>(
         server_url,
         group,
         signers,
+        session_id,
+        strict,
+        state_file,
+        keep_alive,
+        compress,
+        participant_timeout,
+        threshold,
+        commit_messages,
         message,
+        message_encoding,
         randomizer,
+        aux_msg,
         signature,
+        audit_log,
+        tls,
     } = (*args).clone()
     else {
         panic!("invalid Command");
@@ -72,6 +87,25 @@ pub(crate) async fn run_for_ciphersuite<C: RandomizedCiphersuite + 'static>(
         .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
     let num_signers = signers.len() as u16;
 
+    let messages =
+        coordinator::args::read_messages(&message, message_encoding, &mut output, &mut input)?;
+    coordinator::args::validate_messages_size(&messages)?;
+
+    let comm_pubkey = config
+        .communication_key
+        .clone()
+        .ok_or_eyre("user not initialized")?
+        .pubkey;
+    let record_session = session_recorder(
+        config.path().map(|p| p.to_string_lossy().into_owned()),
+        server_url.clone(),
+    );
+
+    let randomizers = coordinator::args::read_randomizers(&randomizer, &mut output, &mut input)?;
+    let aux_msg = hex::decode(aux_msg)?;
+    coordinator::args::validate_aux_msg::<C>(&aux_msg, &randomizers)?;
+    let aux_msg_seed = coordinator::args::generate_aux_msg_seed(&aux_msg);
+
     let group_participants = group.participant.clone();
     let pargs = coordinator::args::ProcessedArgs {
         cli: false,
@@ -79,14 +113,26 @@ pub(crate) async fn run_for_ciphersuite<C: RandomizedCiphersuite + 'static>(
         signers,
         num_signers,
         public_key_package,
-        messages: coordinator::args::read_messages(&message, &mut output, &mut input)?,
-        randomizers: coordinator::args::read_randomizers(&randomizer, &mut output, &mut input)?,
+        messages,
+        randomizers,
+        aux_msg,
+        aux_msg_seed,
         signature,
+        audit_log,
         ip: server_url_parsed
             .host_str()
             .ok_or_eyre("host missing in URL")?
             .to_owned(),
         port: server_url_parsed.port().unwrap_or(2744),
+        cacert: tls.cacert,
+        insecure_skip_verify: tls.insecure_skip_verify,
+        proxy: tls.proxy,
+        session_id: session_id
+            .map(|s| frostd::Uuid::parse_str(&s))
+            .transpose()
+            .wrap_err("error parsing session-id")?,
+        strict,
+        state_file: state_file.map(PathBuf::from),
         comm_privkey: Some(
             config
                 .communication_key
@@ -108,6 +154,21 @@ pub(crate) async fn run_for_ciphersuite<C: RandomizedCiphersuite + 'static>(
                 .find(|p| p.pubkey == *participant_pubkey)
                 .map(|p| p.pubkey.clone())
         })),
+        progress: Some(Rc::new(|received, expected| {
+            eprintln!("\n{} of {} participants responded", received, expected);
+        })),
+        on_session: Some(Rc::new(move |session_id: frostd::Uuid| {
+            record_session(session_id, &comm_pubkey)
+        })),
+        keep_alive: keep_alive.map(Duration::from_secs),
+        compress,
+        participant_timeout: participant_timeout.map(Duration::from_secs),
+        threshold,
+        message_commitment_salt: commit_messages.then(|| {
+            let mut salt = vec![0; 32];
+            thread_rng().fill_bytes(&mut salt);
+            salt
+        }),
     };
 
     cli_for_processed_args(pargs, &mut input, &mut output).await?;
@@ -1,8 +1,19 @@
 use std::error::Error;
 
-use eyre::OptionExt;
+use eyre::{eyre, OptionExt};
+use serde::{Deserialize, Serialize};
 
-use crate::{args::Command, config::Config};
+use crate::{args::Command, ciphersuite_helper::ciphersuite_helper, config::Config};
+
+/// The public information of a group, exported for third parties who only
+/// need to verify signatures. Contains no secret data.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedPublicKeyPackage {
+    /// The ciphersuite ID of the group.
+    pub ciphersuite: String,
+    /// The JSON-encoded public key package of the group.
+    pub public_key_package: serde_json::Value,
+}
 
 pub(crate) fn list(args: &Command) -> Result<(), Box<dyn Error>> {
     let Command::Groups { config } = (*args).clone() else {
@@ -33,3 +44,267 @@ pub(crate) fn remove(args: &Command) -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+/// Export the public key package of a group to a file.
+pub(crate) fn export_public(args: &Command) -> Result<(), Box<dyn Error>> {
+    let Command::ExportPublicKeyPackage { config, group, out } = (*args).clone() else {
+        panic!("invalid Command");
+    };
+
+    let config = Config::read(config)?;
+
+    let group = config.group.get(&group).ok_or_eyre("group not found")?;
+    let helper = ciphersuite_helper(&group.ciphersuite)?;
+    let exported = ExportedPublicKeyPackage {
+        ciphersuite: group.ciphersuite.clone(),
+        public_key_package: helper.public_key_package_to_json(&group.public_key_package)?,
+    };
+
+    std::fs::write(&out, serde_json::to_vec_pretty(&exported)?)?;
+    eprintln!("Public key package written to {}", out);
+
+    Ok(())
+}
+
+/// Print the identifier of the user in a group and those of the other
+/// participants.
+pub(crate) fn whoami(args: &Command) -> Result<(), Box<dyn Error>> {
+    let Command::Whoami { config, group } = (*args).clone() else {
+        panic!("invalid Command");
+    };
+
+    let config = Config::read(config)?;
+
+    eprint!("{}", whoami_summary(&config, &group)?);
+
+    Ok(())
+}
+
+pub(crate) fn check_share(args: &Command) -> Result<(), Box<dyn Error>> {
+    let Command::CheckShare { config, group } = (*args).clone() else {
+        panic!("invalid Command");
+    };
+
+    let config = Config::read(config)?;
+
+    let mismatches = share_mismatches(&config, &group)?;
+    if mismatches.is_empty() {
+        eprintln!("OK: the key package matches the group's public key package");
+        return Ok(());
+    }
+    for mismatch in &mismatches {
+        eprintln!("Mismatch: {}", mismatch);
+    }
+    Err(eyre!("the key package does not match the group's public key package").into())
+}
+
+/// Return the mismatches between the user's key package in the given group and
+/// the group's public key package, if any.
+fn share_mismatches(config: &Config, group: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let group = config.group.get(group).ok_or_eyre("group not found")?;
+    let helper = ciphersuite_helper(&group.ciphersuite)?;
+    helper.check_key_package(&group.key_package, &group.public_key_package)
+}
+
+/// Return a human-readable summary of the identifiers of the participants of
+/// the given group, including the user's.
+fn whoami_summary(config: &Config, group: &str) -> Result<String, Box<dyn Error>> {
+    let group = config.group.get(group).ok_or_eyre("group not found")?;
+    let helper = ciphersuite_helper(&group.ciphersuite)?;
+    let info = helper.group_info(&group.key_package, &group.public_key_package)?;
+    let own_pubkey = config.communication_key.as_ref().map(|k| &k.pubkey);
+
+    let mut s = format!(
+        "Group \"{}\"\nYour identifier: {}\nParticipants:\n",
+        group.description, info.hex_identifier
+    );
+    for participant in group.participant.values() {
+        let name = if Some(&participant.pubkey) == own_pubkey {
+            "(you)".to_string()
+        } else {
+            config
+                .contact_by_pubkey(&participant.pubkey)
+                .map(|c| c.name)
+                .unwrap_or_else(|_| "(unknown contact)".to_string())
+        };
+        s += &format!(
+            "\t{}\t{}\t({})\n",
+            hex::encode(&participant.identifier),
+            name,
+            hex::encode(&participant.pubkey)
+        );
+    }
+    Ok(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use frost_core::Ciphersuite;
+    use frost_ed25519::{
+        keys::{IdentifierList, KeyPackage, PublicKeyPackage},
+        Ed25519Sha512, Identifier,
+    };
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::{
+        config::{CommunicationKey, Group, Participant},
+        contact::Contact,
+    };
+
+    #[test]
+    fn check_export_public() {
+        let mut rng = thread_rng();
+        let (shares, public_key_package) =
+            frost_ed25519::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng)
+                .unwrap();
+        let key_package = KeyPackage::try_from(shares.into_values().next().unwrap()).unwrap();
+        let group_id = hex::encode(public_key_package.verifying_key().serialize().unwrap());
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join("credentials.toml");
+        let out_path = temp_dir.path().join("public-key-package.json");
+
+        let mut config = Config::read(Some(config_path.to_str().unwrap().to_owned())).unwrap();
+        config.group.insert(
+            group_id.clone(),
+            Group {
+                description: "test".to_string(),
+                ciphersuite: Ed25519Sha512::ID.to_string(),
+                public_key_package: postcard::to_allocvec(&public_key_package).unwrap(),
+                key_package: postcard::to_allocvec(&key_package).unwrap(),
+                server_url: None,
+                participant: BTreeMap::new(),
+            },
+        );
+        config.write().unwrap();
+
+        let command = |group: &str| Command::ExportPublicKeyPackage {
+            config: Some(config_path.to_str().unwrap().to_owned()),
+            group: group.to_owned(),
+            out: out_path.to_str().unwrap().to_owned(),
+        };
+
+        assert!(export_public(&command("not a group")).is_err());
+        assert!(!out_path.exists());
+
+        export_public(&command(&group_id)).unwrap();
+
+        let exported: ExportedPublicKeyPackage =
+            serde_json::from_slice(&std::fs::read(&out_path).unwrap()).unwrap();
+        assert_eq!(exported.ciphersuite, Ed25519Sha512::ID);
+        assert!(exported.public_key_package.get("signing_share").is_none());
+        let parsed: PublicKeyPackage = serde_json::from_value(exported.public_key_package).unwrap();
+        assert_eq!(parsed, public_key_package);
+    }
+
+    #[test]
+    fn check_whoami_summary() {
+        let mut rng = thread_rng();
+        let (shares, public_key_package) =
+            frost_ed25519::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng)
+                .unwrap();
+        let key_package = KeyPackage::try_from(shares.into_values().next().unwrap()).unwrap();
+        let group_id = hex::encode(public_key_package.verifying_key().serialize().unwrap());
+
+        let own_pubkey = vec![1u8; 32];
+        let other_pubkey = vec![2u8; 32];
+        let other_identifier = Identifier::try_from(2).unwrap();
+
+        let mut config = Config::default();
+        config.communication_key = Some(CommunicationKey {
+            privkey: vec![0u8; 32],
+            pubkey: own_pubkey.clone(),
+        });
+        config.contact.insert(
+            "bob".to_string(),
+            Contact {
+                version: None,
+                name: "bob".to_string(),
+                pubkey: other_pubkey.clone(),
+                signature: None,
+            },
+        );
+        let participant = [
+            (*key_package.identifier(), own_pubkey),
+            (other_identifier, other_pubkey),
+        ]
+        .into_iter()
+        .map(|(identifier, pubkey)| {
+            (
+                hex::encode(identifier.serialize()),
+                Participant {
+                    identifier: identifier.serialize(),
+                    pubkey,
+                },
+            )
+        })
+        .collect();
+        config.group.insert(
+            group_id.clone(),
+            Group {
+                description: "test".to_string(),
+                ciphersuite: Ed25519Sha512::ID.to_string(),
+                public_key_package: postcard::to_allocvec(&public_key_package).unwrap(),
+                key_package: postcard::to_allocvec(&key_package).unwrap(),
+                server_url: None,
+                participant,
+            },
+        );
+
+        let summary = whoami_summary(&config, &group_id).unwrap();
+
+        // Identifier 1 is the one of the first share generated by the dealer.
+        let expected_identifier = hex::encode(Identifier::try_from(1).unwrap().serialize());
+        assert!(summary.contains(&format!("Your identifier: {}\n", expected_identifier)));
+        assert!(summary.contains(&format!("\t{}\t(you)\t", expected_identifier)));
+        assert!(summary.contains(&format!(
+            "\t{}\tbob\t",
+            hex::encode(other_identifier.serialize())
+        )));
+        assert!(whoami_summary(&config, "not a group").is_err());
+    }
+
+    #[test]
+    fn check_share_mismatches() {
+        let mut rng = thread_rng();
+        let (shares, public_key_package) =
+            frost_ed25519::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng)
+                .unwrap();
+        let (other_shares, _) =
+            frost_ed25519::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng)
+                .unwrap();
+        let key_package = KeyPackage::try_from(shares.into_values().next().unwrap()).unwrap();
+        let other_key_package =
+            KeyPackage::try_from(other_shares.into_values().next().unwrap()).unwrap();
+
+        let config_with = |key_package: &KeyPackage| {
+            let mut config = Config::default();
+            config.group.insert(
+                "group".to_string(),
+                Group {
+                    description: "test".to_string(),
+                    ciphersuite: Ed25519Sha512::ID.to_string(),
+                    public_key_package: postcard::to_allocvec(&public_key_package).unwrap(),
+                    key_package: postcard::to_allocvec(key_package).unwrap(),
+                    server_url: None,
+                    participant: BTreeMap::new(),
+                },
+            );
+            config
+        };
+
+        let config = config_with(&key_package);
+        assert!(share_mismatches(&config, "group").unwrap().is_empty());
+        assert!(share_mismatches(&config, "not a group").is_err());
+
+        // A key package from another group has the same identifier, but a
+        // different verifying share and group verifying key.
+        let mismatches = share_mismatches(&config_with(&other_key_package), "group").unwrap();
+        assert_eq!(mismatches.len(), 2);
+        assert!(mismatches[0].starts_with("the group verifying key"));
+        assert!(mismatches[1].starts_with("the verifying share"));
+    }
+}
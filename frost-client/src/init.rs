@@ -1,21 +1,64 @@
 use std::error::Error;
+use std::io::Write;
+
+use eyre::OptionExt;
 
 use crate::{
     args::Command,
     config::{CommunicationKey, Config},
+    contact::Contact,
 };
 
 pub(crate) async fn init(args: &Command) -> Result<(), Box<dyn Error>> {
-    let Command::Init { config } = (*args).clone() else {
+    let Command::Init {
+        config,
+        non_interactive,
+        name,
+    } = (*args).clone()
+    else {
         panic!("invalid Command");
     };
 
     let mut config = Config::read(config)?;
 
-    if config.communication_key.is_some() {
-        eprintln!("Skipping keypair generation; keypair already generated and stored");
+    if non_interactive {
+        let name = name.ok_or_eyre("name is required")?;
+        let contact = init_config(&mut config, &mut std::io::sink(), Some(name))?
+            .expect("requested since name is set");
+        println!("{}", contact.as_text()?);
     } else {
-        eprintln!("Generating keypair... ");
+        init_config(&mut config, &mut std::io::stderr(), None)?;
+    }
+
+    Ok(())
+}
+
+/// Generate the communication key pair of the user, unless it was already
+/// generated, and write the config file, logging the progress to `logger`.
+/// If `name` is specified, returns the signed contact of the user with that
+/// name.
+fn init_config(
+    config: &mut Config,
+    logger: &mut dyn Write,
+    name: Option<String>,
+) -> Result<Option<Contact>, Box<dyn Error>> {
+    if let Some(communication_key) = &config.communication_key {
+        if communication_key.pubkey.is_empty() {
+            writeln!(
+                logger,
+                "Public key missing; deriving it from the stored private key"
+            )?;
+            config.communication_key = Some(CommunicationKey::from_privkey(
+                communication_key.privkey.clone(),
+            )?);
+        } else {
+            writeln!(
+                logger,
+                "Skipping keypair generation; keypair already generated and stored"
+            )?;
+        }
+    } else {
+        writeln!(logger, "Generating keypair... ")?;
         let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
         let keypair = builder.generate_keypair().unwrap();
         config.communication_key = Some(CommunicationKey {
@@ -24,12 +67,56 @@ pub(crate) async fn init(args: &Command) -> Result<(), Box<dyn Error>> {
         });
     };
 
-    eprintln!(
+    writeln!(
+        logger,
         "Writing to config file at {}...",
         config.path().expect("should not be None").display()
-    );
+    )?;
     config.write()?;
-    eprintln!("Done.");
+    writeln!(logger, "Done.")?;
 
-    Ok(())
+    name.map(|name| {
+        Contact::new_signed(
+            name,
+            config
+                .communication_key
+                .as_ref()
+                .expect("was just generated"),
+        )
+    })
+    .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_init_non_interactive() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = Config::path_in_dir(temp_dir.path());
+        let mut config = Config::read(Some(path.to_str().unwrap().to_string())).unwrap();
+
+        let mut logger = Vec::new();
+        let contact = init_config(&mut config, &mut logger, Some("alice".to_string()))
+            .unwrap()
+            .unwrap();
+
+        // The stored key pair is valid, i.e. its public key matches the
+        // private key.
+        let config = Config::read(Some(path.to_str().unwrap().to_string())).unwrap();
+        let communication_key = config.communication_key.unwrap();
+        assert_eq!(
+            CommunicationKey::from_privkey(communication_key.privkey.clone())
+                .unwrap()
+                .pubkey,
+            communication_key.pubkey
+        );
+
+        // The contact string can be imported by others.
+        let imported = Contact::from_text(&contact.as_text().unwrap()).unwrap();
+        assert!(imported.verify().unwrap());
+        assert_eq!(imported.name, "alice");
+        assert_eq!(imported.pubkey, communication_key.pubkey);
+    }
 }
@@ -1,4 +1,5 @@
 pub mod args;
+pub mod bench;
 pub mod ciphersuite_helper;
 pub mod config;
 pub mod contact;
@@ -7,6 +8,7 @@ pub mod group;
 pub mod init;
 pub mod participant;
 pub mod session;
+pub mod share;
 pub mod trusted_dealer;
 pub mod write_atomic;
 
@@ -14,13 +16,26 @@ use std::error::Error;
 
 use args::{Args, Command};
 use clap::Parser;
+use config::Config;
+use eyre::OptionExt as _;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     stable_eyre::install()?;
-    let args = Args::parse();
+    let mut args = Args::parse();
 
-    match args.command {
+    if let Some(config_dir) = &args.config_dir {
+        if let Some(config @ None) = args.command.config_mut() {
+            *config = Some(
+                Config::path_in_dir(config_dir)
+                    .to_str()
+                    .ok_or_eyre("invalid config directory")?
+                    .to_owned(),
+            );
+        }
+    }
+
+    let r = match args.command {
         Command::Init { .. } => init::init(&args.command).await,
         Command::Export { .. } => contact::export(&args.command),
         Command::Import { .. } => contact::import(&args.command),
@@ -28,11 +43,22 @@ async fn main() -> Result<(), Box<dyn Error>> {
         Command::RemoveContact { .. } => contact::remove(&args.command),
         Command::Groups { .. } => group::list(&args.command),
         Command::RemoveGroup { .. } => group::remove(&args.command),
+        Command::ExportPublicKeyPackage { .. } => group::export_public(&args.command),
+        Command::Whoami { .. } => group::whoami(&args.command),
+        Command::CheckShare { .. } => group::check_share(&args.command),
+        Command::ConvertShare { .. } => share::convert(&args.command),
+        Command::Ciphersuites { .. } => ciphersuite_helper::list(&args.command),
+        Command::Bench { .. } => bench::bench(&args.command),
         Command::Sessions { .. } => session::list(&args.command).await,
+        Command::SessionInfo { .. } => session::info(&args.command).await,
+        Command::PruneSessions { .. } => session::prune(&args.command).await,
         Command::TrustedDealer { .. } => trusted_dealer::trusted_dealer(&args.command),
         Command::Coordinator { .. } => crate::coordinator::run(&args.command).await,
         Command::Participant { .. } => crate::participant::run(&args.command).await,
-    }?;
+    };
 
-    Ok(())
+    match r {
+        Err(e) if args.json_errors => participant::errors::exit_with_error(&*e, true),
+        r => r,
+    }
 }
@@ -1,5 +1,6 @@
 use std::error::Error;
 use std::rc::Rc;
+use std::time::Duration;
 
 use eyre::eyre;
 use eyre::Context;
@@ -12,8 +13,12 @@ use frost_core::Ciphersuite;
 use frost_ed25519::Ed25519Sha512;
 use frost_rerandomized::RandomizedCiphersuite;
 
-use crate::{args::Command, config::Config};
-use participant::cli::cli_for_processed_args;
+use crate::{args::Command, config::Config, session::session_recorder};
+use participant::{
+    cli::{cli_for_processed_args, run_sessions},
+    offline::OfflinePhase,
+};
+use uuid::Uuid;
 
 pub(crate) async fn run(args: &Command) -> Result<(), Box<dyn Error>> {
     let Command::Participant { config, group, .. } = (*args).clone() else {
@@ -40,7 +45,18 @@ pub(crate) async fn run_for_ciphersuite<C: RandomizedCiphersuite + 'static>(
         config,
         server_url,
         group,
-        session,
+        mut session,
+        list,
+        all,
+        expect_coordinator,
+        tls,
+        confirm,
+        keep_alive,
+        compress,
+        save_signing_package,
+        sign_offline,
+        signature_share_out,
+        send_signature_share,
     } = (*args).clone()
     else {
         panic!("invalid Command");
@@ -55,25 +71,66 @@ pub(crate) async fn run_for_ciphersuite<C: RandomizedCiphersuite + 'static>(
     let mut input = Box::new(std::io::stdin().lock());
     let mut output = std::io::stdout();
 
-    let server_url = if let Some(server_url) = server_url {
-        server_url
+    let offline = OfflinePhase::from_args(
+        save_signing_package.as_deref(),
+        sign_offline.as_deref(),
+        signature_share_out.as_deref(),
+        send_signature_share.as_deref(),
+    )?;
+
+    let config_path = config.path().map(|p| p.to_string_lossy().into_owned());
+
+    // Signing offline does not connect to the server, which may be unknown
+    // to the offline machine.
+    let (ip, port, on_session) = if let Some(OfflinePhase::Sign { .. }) = offline {
+        (String::new(), 0, None)
     } else {
-        group.server_url.clone().ok_or_eyre("server-url required")?
+        let server_url = if let Some(server_url) = server_url {
+            server_url
+        } else {
+            group.server_url.clone().ok_or_eyre("server-url required")?
+        };
+        if list {
+            let session_id = crate::session::select(
+                &config,
+                &server_url,
+                &tls,
+                &mut input,
+                &mut std::io::stderr(),
+            )
+            .await?;
+            session = vec![session_id.to_string()];
+        }
+        let server_url_parsed =
+            Url::parse(&format!("http://{}", server_url)).wrap_err("error parsing server-url")?;
+        (
+            server_url_parsed
+                .host_str()
+                .ok_or_eyre("host missing in URL")?
+                .to_owned(),
+            server_url_parsed.port().unwrap_or(2744),
+            Some(session_recorder(config_path, server_url)),
+        )
     };
-    let server_url_parsed =
-        Url::parse(&format!("http://{}", server_url)).wrap_err("error parsing server-url")?;
+
+    let expected_coordinator_pubkey = expect_coordinator
+        .map(|c| resolve_pubkey(&config, &c))
+        .transpose()?;
+
+    // Keep the record of used nonces next to the config.
+    let used_commitments_path = config.path().map(|p| p.with_file_name("used-commitments"));
 
     let group_participants = group.participant.clone();
     let pargs = participant::args::ProcessedArgs {
         cli: false,
         http: true,
         key_package,
-        ip: server_url_parsed
-            .host_str()
-            .ok_or_eyre("host missing in URL")?
-            .to_owned(),
-        port: server_url_parsed.port().unwrap_or(2744),
-        session_id: session.unwrap_or_default(),
+        ip,
+        port,
+        session_id: session.first().cloned().unwrap_or_default(),
+        cacert: tls.cacert,
+        insecure_skip_verify: tls.insecure_skip_verify,
+        proxy: tls.proxy,
         comm_privkey: Some(
             config
                 .communication_key
@@ -95,9 +152,39 @@ pub(crate) async fn run_for_ciphersuite<C: RandomizedCiphersuite + 'static>(
                 .find(|p| p.pubkey == *coordinator_pubkey)
                 .map(|p| p.pubkey.clone())
         })),
+        expected_coordinator_pubkey,
+        used_commitments_path,
+        confirm,
+        on_session,
+        keep_alive: keep_alive.map(Duration::from_secs),
+        compress,
+        offline,
     };
 
-    cli_for_processed_args(pargs, &mut input, &mut output).await?;
+    if all || session.len() > 1 {
+        let session_ids = session
+            .iter()
+            .map(|s| Uuid::parse_str(s).wrap_err("invalid session ID"))
+            .collect::<Result<Vec<_>, _>>()?;
+        run_sessions(pargs, session_ids, &mut output).await?;
+    } else {
+        cli_for_processed_args(pargs, &mut input, &mut output).await?;
+    }
 
     Ok(())
 }
+
+/// Return the public key of the contact with the given name, or the given
+/// hex-encoded public key if there is no such contact.
+fn resolve_pubkey(config: &Config, name_or_pubkey: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    if let Some(contact) = config.contact.get(name_or_pubkey) {
+        return Ok(contact.pubkey.clone());
+    }
+    hex::decode(name_or_pubkey.trim()).map_err(|_| {
+        eyre!(
+            "{} is neither a contact name nor a public key",
+            name_or_pubkey
+        )
+        .into()
+    })
+}
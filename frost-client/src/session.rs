@@ -1,16 +1,26 @@
-use std::error::Error;
+use std::{
+    error::Error,
+    io::{BufRead, Write},
+    rc::Rc,
+};
 
 use eyre::{eyre, OptionExt as _};
+use participant::comms::http::RequestBuilderExt as _;
 use rand::thread_rng;
+use uuid::Uuid;
 use xeddsa::{xed25519, Sign as _};
 
-use crate::{args::Command, config::Config};
+use crate::{
+    args::{Command, TlsOptions},
+    config::Config,
+};
 
 pub(crate) async fn list(args: &Command) -> Result<(), Box<dyn Error>> {
     let Command::Sessions {
         config,
         group,
         server_url,
+        tls,
     } = (*args).clone()
     else {
         panic!("invalid Command");
@@ -18,18 +28,279 @@ pub(crate) async fn list(args: &Command) -> Result<(), Box<dyn Error>> {
 
     let config = Config::read(config)?;
 
-    let server_url = if let Some(server_url) = server_url {
-        server_url
+    let server_url = resolve_server_url(&config, server_url, group)?;
+
+    let client = tls.build_client()?;
+    let host_port = format!("http://{}", server_url);
+
+    let access_token = login(&client, &host_port, &config).await?;
+
+    let sessions = fetch_sessions(&client, &host_port, &access_token).await?;
+
+    if sessions.is_empty() {
+        eprintln!("No active sessions.");
+    } else {
+        for (session_id, r) in sessions {
+            eprintln!("{}", session_summary(&config, &session_id, &r));
+        }
+    }
+
+    Ok(())
+}
+
+/// Return a function which keeps a record of the sessions the user creates or
+/// joins in the given server, in the config at `config_path`, so that
+/// `prune-sessions` can remove them later. The config is read again since it
+/// may have changed after it was loaded. Failing to record a session is only
+/// reported, since the record is not needed to sign.
+pub(crate) fn session_recorder(
+    config_path: Option<String>,
+    server_url: String,
+) -> Rc<dyn Fn(Uuid, &[u8])> {
+    Rc::new(move |session_id: Uuid, coordinator_pubkey: &[u8]| {
+        let record = || -> Result<(), Box<dyn Error>> {
+            let mut config = Config::read(config_path.clone())?;
+            config.add_session(session_id, &server_url, coordinator_pubkey);
+            config.write()
+        };
+        if let Err(e) = record() {
+            eprintln!(
+                "Could not record session {} in the config: {}",
+                session_id, e
+            );
+        }
+    })
+}
+
+/// List the active sessions the user is in, and ask which one to join.
+/// Used by `participant --list`.
+pub(crate) async fn select(
+    config: &Config,
+    server_url: &str,
+    tls: &TlsOptions,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> Result<Uuid, Box<dyn Error>> {
+    let client = tls.build_client()?;
+    let host_port = format!("https://{}", server_url);
+
+    let access_token = login(&client, &host_port, config).await?;
+
+    let sessions = fetch_sessions(&client, &host_port, &access_token).await?;
+
+    choose_session(config, &sessions, input, output)
+}
+
+/// Get the IDs and information of the active sessions the user is in.
+async fn fetch_sessions(
+    client: &reqwest::Client,
+    host_port: &str,
+    access_token: &str,
+) -> Result<Vec<(Uuid, frostd::GetSessionInfoOutput)>, Box<dyn Error>> {
+    let r = client
+        .post(format!("{}/list_sessions", host_port))
+        .bearer_auth(access_token)
+        .send_with_retries()
+        .await?
+        .json::<frostd::ListSessionsOutput>()
+        .await?;
+
+    let mut sessions = Vec::with_capacity(r.session_ids.len());
+    for session_id in r.session_ids {
+        let r = client
+            .post(format!("{}/get_session_info", host_port))
+            .bearer_auth(access_token)
+            .json(&frostd::GetSessionInfoArgs { session_id })
+            .send_with_retries()
+            .await?
+            .json::<frostd::GetSessionInfoOutput>()
+            .await?;
+        sessions.push((session_id, r));
+    }
+    Ok(sessions)
+}
+
+/// Print the given sessions, numbered from 1, and read the one to use, given
+/// either as its number or its ID.
+fn choose_session(
+    config: &Config,
+    sessions: &[(Uuid, frostd::GetSessionInfoOutput)],
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+) -> Result<Uuid, Box<dyn Error>> {
+    if sessions.is_empty() {
+        return Err(eyre!("no active sessions").into());
+    }
+    for (i, (session_id, info)) in sessions.iter().enumerate() {
+        writeln!(
+            output,
+            "[{}] {}",
+            i + 1,
+            session_summary(config, session_id, info)
+        )?;
+    }
+    writeln!(output, "Select a session by number or ID:")?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    let answer = answer.trim();
+    let session_id = if let Ok(i) = answer.parse::<usize>() {
+        i.checked_sub(1)
+            .and_then(|i| sessions.get(i))
+            .map(|(session_id, _)| *session_id)
+    } else {
+        Uuid::parse_str(answer)
+            .ok()
+            .filter(|id| sessions.iter().any(|(session_id, _)| session_id == id))
+    };
+    Ok(session_id.ok_or_else(|| eyre!("{} is not one of the listed sessions", answer))?)
+}
+
+/// Show the coordinator, participants and message count of a session.
+pub(crate) async fn info(args: &Command) -> Result<(), Box<dyn Error>> {
+    let Command::SessionInfo {
+        config,
+        session,
+        server_url,
+        group,
+        tls,
+    } = (*args).clone()
+    else {
+        panic!("invalid Command");
+    };
+
+    let config = Config::read(config)?;
+
+    let session_id = Uuid::parse_str(session.trim()).map_err(|_| eyre!("invalid session ID"))?;
+    let server_url = resolve_server_url(&config, server_url, group)?;
+
+    let client = tls.build_client()?;
+    let host_port = format!("https://{}", server_url);
+
+    let access_token = login(&client, &host_port, &config).await?;
+
+    let r = client
+        .post(format!("{}/get_session_info", host_port))
+        .bearer_auth(&access_token)
+        .json(&frostd::GetSessionInfoArgs { session_id })
+        .send_with_retries()
+        .await?;
+    if !r.status().is_success() {
+        let error = r.json::<frostd::Error>().await?;
+        return Err(eyre!("could not get session info: {}", error).into());
+    }
+    let r = r.json::<frostd::GetSessionInfoOutput>().await?;
+
+    eprint!("{}", session_summary(&config, &session_id, &r));
+
+    Ok(())
+}
+
+/// Return a human-readable summary of the given session, showing the names of
+/// the coordinator and participants if they are contacts, and their public
+/// keys otherwise.
+fn session_summary(
+    config: &Config,
+    session_id: &Uuid,
+    info: &frostd::GetSessionInfoOutput,
+) -> String {
+    let display_name = |pubkey: &[u8]| match config.contact_by_pubkey(pubkey) {
+        Ok(contact) => format!("{}\t({})", contact.name, hex::encode(pubkey)),
+        Err(_) => hex::encode(pubkey),
+    };
+
+    let mut s = format!(
+        "Session with ID {}\nCoordinator: {}\nMessages: {}\nSigners: {}\n",
+        session_id,
+        display_name(&info.coordinator_pubkey),
+        info.message_count,
+        info.pubkeys.len()
+    );
+    for pubkey in &info.pubkeys {
+        s += &format!("\t{}\n", display_name(&pubkey.0));
+    }
+    s
+}
+
+/// Remove the locally stored sessions which the server no longer knows about.
+pub(crate) async fn prune(args: &Command) -> Result<(), Box<dyn Error>> {
+    let Command::PruneSessions {
+        config,
+        group,
+        server_url,
+        yes,
+        tls,
+    } = (*args).clone()
+    else {
+        panic!("invalid Command");
+    };
+
+    let mut config = Config::read(config)?;
+
+    let server_url = resolve_server_url(&config, server_url, group)?;
+
+    let client = tls.build_client()?;
+    let host_port = format!("http://{}", server_url);
+
+    let access_token = login(&client, &host_port, &config).await?;
+
+    let r = client
+        .post(format!("{}/list_sessions", host_port))
+        .bearer_auth(&access_token)
+        .send_with_retries()
+        .await?
+        .json::<frostd::ListSessionsOutput>()
+        .await?;
+
+    let pruned = config.prune_sessions(&server_url, &r.session_ids);
+
+    if pruned.is_empty() {
+        eprintln!("No sessions to prune.");
+        return Ok(());
+    }
+    for session_id in &pruned {
+        eprintln!("Session with ID {}", session_id);
+    }
+    if yes {
+        config.write()?;
+        eprintln!("Pruned {} session(s).", pruned.len());
+    } else {
+        eprintln!(
+            "{} session(s) would be pruned. Run again with --yes to remove them.",
+            pruned.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Return the server URL to use: `server_url` if specified, otherwise the
+/// server URL associated with `group`.
+fn resolve_server_url(
+    config: &Config,
+    server_url: Option<String>,
+    group: Option<String>,
+) -> Result<String, Box<dyn Error>> {
+    if let Some(server_url) = server_url {
+        Ok(server_url)
     } else if let Some(group) = group {
         let group = config.group.get(&group).ok_or_eyre("Group not found")?;
-        group
+        Ok(group
             .server_url
             .clone()
-            .ok_or_eyre("the group specified does not have an associated server URL")?
+            .ok_or_eyre("the group specified does not have an associated server URL")?)
     } else {
-        return Err(eyre!("must specify either server_url or group").into());
-    };
+        Err(eyre!("must specify either server_url or group").into())
+    }
+}
 
+/// Log in to the server with the user's communication key, returning the
+/// access token.
+async fn login(
+    client: &reqwest::Client,
+    host_port: &str,
+    config: &Config,
+) -> Result<String, Box<dyn Error>> {
     let comm_privkey = config
         .communication_key
         .clone()
@@ -43,15 +314,12 @@ pub(crate) async fn list(args: &Command) -> Result<(), Box<dyn Error>> {
         .pubkey
         .clone();
 
-    let client = reqwest::Client::new();
-    let host_port = format!("http://{}", server_url);
-
     let mut rng = thread_rng();
 
     let challenge = client
         .post(format!("{}/challenge", host_port))
         .json(&frostd::ChallengeArgs {})
-        .send()
+        .send_with_retries()
         .await?
         .json::<frostd::ChallengeOutput>()
         .await?
@@ -63,69 +331,111 @@ pub(crate) async fn list(args: &Command) -> Result<(), Box<dyn Error>> {
     );
     let signature: [u8; 64] = privkey.sign(challenge.as_bytes(), &mut rng);
 
-    let access_token = client
+    Ok(client
         .post(format!("{}/login", host_port))
         .json(&frostd::KeyLoginArgs {
             challenge,
             pubkey: comm_pubkey.clone(),
             signature: signature.to_vec(),
         })
-        .send()
+        .send_with_retries()
         .await?
         .json::<frostd::LoginOutput>()
         .await?
         .access_token
-        .to_string();
+        .to_string())
+}
 
-    // Get session ID from server
-    let r = client
-        .post(format!("{}/list_sessions", host_port))
-        .bearer_auth(&access_token)
-        .send()
-        .await?
-        .json::<frostd::ListSessionsOutput>()
-        .await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contact::Contact;
 
-    if r.session_ids.is_empty() {
-        eprintln!("No active sessions.");
-    } else {
-        for session_id in r.session_ids {
-            let r = client
-                .post(format!("{}/get_session_info", host_port))
-                .bearer_auth(&access_token)
-                .json(&frostd::GetSessionInfoArgs { session_id })
-                .send()
-                .await?
-                .json::<frostd::GetSessionInfoOutput>()
-                .await?;
-            let coordinator = config.contact_by_pubkey(&r.coordinator_pubkey);
-            let participants: Vec<_> = r
-                .pubkeys
-                .iter()
-                .map(|pubkey| config.contact_by_pubkey(&pubkey.0))
-                .collect();
-            eprintln!("Session with ID {}", session_id);
-            eprintln!(
-                "Coordinator: {}",
-                coordinator
-                    .map(|c| c.name)
-                    .unwrap_or("(Unknown contact)".to_string())
+    #[test]
+    fn check_session_summary() {
+        let session_id = Uuid::new_v4();
+        let coordinator_pubkey = vec![1u8; 32];
+        let bob_pubkey = vec![2u8; 32];
+        let unknown_pubkey = vec![3u8; 32];
+
+        let mut config = Config::default();
+        for (name, pubkey) in [("alice", &coordinator_pubkey), ("bob", &bob_pubkey)] {
+            config.contact.insert(
+                name.to_string(),
+                Contact {
+                    version: None,
+                    name: name.to_string(),
+                    pubkey: pubkey.clone(),
+                    signature: None,
+                },
             );
-            eprintln!("Signers: {}", participants.len());
-            for participant in participants {
-                if let Ok(participant) = participant {
-                    eprintln!(
-                        "\t{}\t({})",
-                        participant.name,
-                        hex::encode(participant.pubkey)
-                    );
-                } else {
-                    eprintln!("\t(Unknown contact)");
-                }
-            }
-            eprintln!();
         }
+        let info = frostd::GetSessionInfoOutput {
+            message_count: 1,
+            pubkeys: vec![
+                frostd::PublicKey(bob_pubkey.clone()),
+                frostd::PublicKey(unknown_pubkey.clone()),
+            ],
+            coordinator_pubkey: coordinator_pubkey.clone(),
+            coordinator_pubkeys: vec![frostd::PublicKey(coordinator_pubkey.clone())],
+            ciphersuite: None,
+            message_commitment: None,
+        };
+
+        let summary = session_summary(&config, &session_id, &info);
+
+        assert_eq!(
+            summary,
+            format!(
+                "Session with ID {}\nCoordinator: alice\t({})\nMessages: 1\nSigners: 2\n\
+                 \tbob\t({})\n\t{}\n",
+                session_id,
+                hex::encode(&coordinator_pubkey),
+                hex::encode(&bob_pubkey),
+                hex::encode(&unknown_pubkey),
+            )
+        );
     }
 
-    Ok(())
+    #[test]
+    fn check_choose_session() {
+        let config = Config::default();
+        let sessions = [1u8, 2]
+            .map(|i| {
+                (
+                    Uuid::new_v4(),
+                    frostd::GetSessionInfoOutput {
+                        message_count: i,
+                        pubkeys: vec![frostd::PublicKey(vec![i; 32])],
+                        coordinator_pubkey: vec![i + 10; 32],
+                        coordinator_pubkeys: vec![frostd::PublicKey(vec![i + 10; 32])],
+                        ciphersuite: None,
+                        message_commitment: None,
+                    },
+                )
+            })
+            .to_vec();
+
+        let mut output = Vec::new();
+        let session_id =
+            choose_session(&config, &sessions, &mut "2\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(session_id, sessions[1].0);
+        let output = String::from_utf8(output).unwrap();
+        for (i, (session_id, _)) in sessions.iter().enumerate() {
+            assert!(output.contains(&format!("[{}] Session with ID {}", i + 1, session_id)));
+        }
+
+        let answer = format!("{}\n", sessions[0].0);
+        let session_id =
+            choose_session(&config, &sessions, &mut answer.as_bytes(), &mut Vec::new()).unwrap();
+        assert_eq!(session_id, sessions[0].0);
+
+        for answer in ["0\n", "3\n", "foo\n"] {
+            assert!(
+                choose_session(&config, &sessions, &mut answer.as_bytes(), &mut Vec::new())
+                    .is_err()
+            );
+        }
+        assert!(choose_session(&config, &[], &mut "1\n".as_bytes(), &mut Vec::new()).is_err());
+    }
 }
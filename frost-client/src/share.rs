@@ -0,0 +1,87 @@
+use std::error::Error;
+
+use eyre::{eyre, OptionExt};
+
+use crate::{args::Command, ciphersuite_helper::ciphersuite_helper, write_atomic};
+
+/// Convert a SecretShare file to a KeyPackage file.
+pub(crate) fn convert(args: &Command) -> Result<(), Box<dyn Error>> {
+    let Command::ConvertShare { input, out } = (*args).clone() else {
+        panic!("invalid Command");
+    };
+
+    let secret_share = std::fs::read_to_string(&input)?;
+    let key_package = secret_share_to_key_package(&secret_share)?;
+
+    // The key package is secret, so write it readable by the owner only.
+    write_atomic::write_file(&out, &serde_json::to_vec_pretty(&key_package)?)?;
+    eprintln!("Key package written to {}", out);
+
+    Ok(())
+}
+
+/// Convert a JSON-encoded SecretShare of any supported ciphersuite to the
+/// JSON representation of the corresponding KeyPackage.
+fn secret_share_to_key_package(secret_share: &str) -> Result<serde_json::Value, Box<dyn Error>> {
+    let value: serde_json::Value = serde_json::from_str(secret_share)?;
+    if value.get("commitment").is_none() {
+        if value.get("verifying_share").is_some() {
+            return Err(eyre!(
+                "the input is already a KeyPackage, which can't be converted to a \
+                 SecretShare since it does not contain the commitment"
+            )
+            .into());
+        }
+        return Err(eyre!("the input is not a SecretShare").into());
+    }
+    let ciphersuite = value
+        .get("header")
+        .and_then(|h| h.get("ciphersuite"))
+        .and_then(|c| c.as_str())
+        .ok_or_eyre("the SecretShare does not specify its ciphersuite")?;
+
+    ciphersuite_helper(ciphersuite)?.secret_share_to_key_package(secret_share)
+}
+
+#[cfg(test)]
+mod tests {
+    use frost_ed25519::keys::{IdentifierList, KeyPackage};
+    use rand::thread_rng;
+
+    use super::*;
+
+    #[test]
+    fn check_secret_share_to_key_package() {
+        let mut rng = thread_rng();
+        let (shares, _) =
+            frost_ed25519::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng)
+                .unwrap();
+        let secret_share = shares.into_values().next().unwrap();
+        let expected = KeyPackage::try_from(secret_share.clone()).unwrap();
+
+        let converted =
+            secret_share_to_key_package(&serde_json::to_string(&secret_share).unwrap()).unwrap();
+        let key_package: KeyPackage = serde_json::from_value(converted.clone()).unwrap();
+        assert_eq!(key_package, expected);
+
+        // A KeyPackage can't be converted back.
+        assert!(secret_share_to_key_package(&converted.to_string()).is_err());
+    }
+
+    #[test]
+    fn check_secret_share_to_key_package_invalid_share() {
+        let mut rng = thread_rng();
+        let (shares, _) =
+            frost_ed25519::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng)
+                .unwrap();
+        let mut shares = shares.into_values();
+        let (share_1, share_2) = (shares.next().unwrap(), shares.next().unwrap());
+
+        // The signing share of another participant does not match the
+        // commitment for this one's identifier.
+        let mut tampered = serde_json::to_value(&share_1).unwrap();
+        tampered["signing_share"] = serde_json::to_value(share_2.signing_share()).unwrap();
+
+        assert!(secret_share_to_key_package(&tampered.to_string()).is_err());
+    }
+}
@@ -53,11 +53,7 @@ pub(crate) fn trusted_dealer_for_ciphersuite<C: Ciphersuite + MaybeIntoEvenY + '
         return Err(eyre!("The `names` option must specify `num_signers` names").into());
     }
 
-    let trusted_dealer_config = trusted_dealer::Config {
-        max_signers: num_signers,
-        min_signers: threshold,
-        secret: vec![],
-    };
+    let trusted_dealer_config = trusted_dealer::Config::new(threshold, num_signers, vec![])?;
     let mut rng = thread_rng();
 
     // Generate key shares
@@ -82,6 +78,7 @@ pub(crate) fn trusted_dealer_for_ciphersuite<C: Ciphersuite + MaybeIntoEvenY + '
             version: None,
             name: name.clone(),
             pubkey,
+            signature: None,
         };
         contacts.push(contact);
     }
@@ -1,17 +1,43 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
+/// The default maximum number of participants in a session.
+pub const DEFAULT_MAX_PARTICIPANTS: usize = 100;
+
+/// The default maximum number of messages queued for each recipient in a
+/// session.
+pub const DEFAULT_MAX_QUEUE_LEN: usize = 1000;
+
+/// The default number of seconds a session stays open since it was last used.
+pub const DEFAULT_SESSION_TIMEOUT: u64 = 60 * 60 * 24;
+
+/// The default maximum number of seconds a coordinator can keep a session
+/// open with `extend_session`.
+pub const DEFAULT_MAX_SESSION_EXTENSION: u64 = 60 * 60 * 24 * 7;
+
+/// The server arguments. Each of them can also be set with the environment
+/// variable shown in its `env` attribute (e.g. `FROSTD_PORT`), which is
+/// useful in container deployments. Flags passed in the command line take
+/// precedence over environment variables.
 #[derive(Parser, Debug, Default)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
-    /// IP to bind to.
+    /// IP to bind to. Can be repeated (or comma-separated in the environment
+    /// variable) to bind to multiple IPs, e.g. both an IPv4 and an IPv6
+    /// address.
     ///
     /// If `no_tls_very_insecure` is set, it will bind to 127.0.0.1
     /// regardless of the value passed here.
-    #[arg(short, long, default_value = "0.0.0.0")]
-    pub ip: String,
+    #[arg(
+        short,
+        long,
+        env = "FROSTD_IP",
+        default_value = "0.0.0.0",
+        value_delimiter = ','
+    )]
+    pub ip: Vec<String>,
 
     /// Port to bind to.
-    #[arg(short, long, default_value_t = 2744)]
+    #[arg(short, long, env = "FROSTD_PORT", default_value_t = 2744)]
     pub port: u16,
 
     /// The path of the certificate to use for HTTPS (PEM format).
@@ -19,26 +45,108 @@ pub struct Args {
     /// For production deployments, it's recommended to provide HTTPS using
     /// a reverse proxy such as nginx. In that case, set `no_tls_very_insecure`
     /// instead.
-    #[arg(short = 'c', long)]
+    #[arg(short = 'c', long, env = "FROSTD_TLS_CERT")]
     pub tls_cert: Option<String>,
 
     /// The path of the private key to use for HTTPS (PEM format).
-    #[arg(short = 'k', long)]
+    #[arg(short = 'k', long, env = "FROSTD_TLS_KEY")]
     pub tls_key: Option<String>,
 
     /// Flag to disable TLS/HTTPS. DO NOT set this flag unless you're providing
     /// TLS/HTTPS on your own (e.g. with nginx or another reverse proxy).
-    #[arg(short, long, default_value_t = false)]
+    #[arg(
+        short,
+        long,
+        env = "FROSTD_NO_TLS_VERY_INSECURE",
+        default_value_t = false
+    )]
     pub no_tls_very_insecure: bool,
+
+    /// The minimum TLS version accepted from clients. Only secure cipher
+    /// suites are supported in either case, but setting "1.3" restricts
+    /// clients to the TLS 1.3 ones. Ignored if `no_tls_very_insecure` is set.
+    #[arg(
+        long,
+        env = "FROSTD_TLS_MIN_VERSION",
+        value_enum,
+        default_value_t = TlsVersion::Tls12
+    )]
+    pub tls_min_version: TlsVersion,
+
+    /// The maximum number of participants allowed in a session. Sessions
+    /// with more participants are rejected, since the number of messages
+    /// (e.g. in a DKG broadcast) grows quadratically with it.
+    #[arg(long, env = "FROSTD_MAX_PARTICIPANTS", default_value_t = DEFAULT_MAX_PARTICIPANTS)]
+    pub max_participants: usize,
+
+    /// The maximum number of messages queued for each recipient in a
+    /// session. This prevents a session member from exhausting the server
+    /// memory by flooding another one with messages.
+    #[arg(long, env = "FROSTD_MAX_QUEUE_LEN", default_value_t = DEFAULT_MAX_QUEUE_LEN)]
+    pub max_queue_len: usize,
+
+    /// What to do with a message sent to a recipient whose queue is full:
+    /// reject it with an error, or drop the oldest queued message to make
+    /// room for it.
+    #[arg(
+        long,
+        env = "FROSTD_QUEUE_FULL",
+        value_enum,
+        default_value_t = QueueFullPolicy::Reject
+    )]
+    pub queue_full: QueueFullPolicy,
+
+    /// How many seconds a session stays open since it was last used. Clients
+    /// can keep a session open while they wait (e.g. for a human to act) with
+    /// their keep-alive option.
+    #[arg(long, env = "FROSTD_SESSION_TIMEOUT", default_value_t = DEFAULT_SESSION_TIMEOUT)]
+    pub session_timeout: u64,
+
+    /// The maximum number of seconds a coordinator can keep a session open
+    /// with `extend_session`, e.g. for a long manual ceremony.
+    #[arg(
+        long,
+        env = "FROSTD_MAX_SESSION_EXTENSION",
+        default_value_t = DEFAULT_MAX_SESSION_EXTENSION
+    )]
+    pub max_session_extension: u64,
+
+    /// Token that enables the admin API (e.g. `/admin/queues`), which must be
+    /// passed as a bearer token to call it. The admin API exposes
+    /// information about all sessions, for debugging, and is disabled if
+    /// this is not set. Prefer setting it with the environment variable,
+    /// since command line arguments can be seen by other local users.
+    #[arg(long, env = "FROSTD_ADMIN_TOKEN")]
+    pub admin_token: Option<String>,
 }
 
 impl Args {
-    /// Get the effective IP to use, considering the arguments passed.
-    pub fn ip(&self) -> String {
+    /// Get the effective IPs to use, considering the arguments passed.
+    pub fn ip(&self) -> Vec<String> {
         if self.no_tls_very_insecure {
-            "127.0.0.1".to_string()
+            vec!["127.0.0.1".to_string()]
         } else {
             self.ip.clone()
         }
     }
 }
+
+/// A TLS protocol version.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum TlsVersion {
+    #[default]
+    #[value(name = "1.2")]
+    Tls12,
+    #[value(name = "1.3")]
+    Tls13,
+}
+
+/// What to do with a message sent to a recipient whose queue is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum QueueFullPolicy {
+    /// Reject the message with an error.
+    #[default]
+    Reject,
+    /// Drop the oldest message queued for the recipient.
+    DropOldest,
+}
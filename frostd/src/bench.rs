@@ -0,0 +1,440 @@
+//! A load testing tool that runs simulated signing ceremonies against a
+//! running server, measuring the latency of the main API calls.
+//!
+//! The ceremonies only mimic the message flow of a FROST signing session
+//! (commitments, signing package, signature shares); the messages are random
+//! bytes of similar sizes, since the server does not look into them anyway.
+
+use std::{
+    error::Error,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use clap::Parser;
+use eyre::eyre;
+use rand::{thread_rng, RngCore as _};
+use tokio::task::JoinSet;
+use uuid::Uuid;
+use xeddsa::{xed25519, Sign as _};
+
+use crate::{
+    ChallengeArgs, ChallengeOutput, CloseSessionArgs, CloseSessionOutput, CreateNewSessionArgs,
+    CreateNewSessionOutput, KeyLoginArgs, KeyLoginOutput, PublicKey, ReceiveArgs, ReceiveOutput,
+    SendArgs,
+};
+
+/// The size of the simulated commitments and signature shares.
+const SHARE_SIZE: usize = 256;
+/// The size of the simulated signing package.
+const SIGNING_PACKAGE_SIZE: usize = 1024;
+/// How long to wait between `receive` calls that returned no messages.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about, long_about = None)]
+pub struct BenchArgs {
+    /// The URL of the server to benchmark.
+    #[arg(short, long, default_value = "https://127.0.0.1:2744")]
+    pub url: String,
+
+    /// The number of signing ceremonies to run concurrently.
+    #[arg(short = 'n', long, default_value_t = 10)]
+    pub sessions: usize,
+
+    /// The number of ceremonies each concurrent worker runs, one after the
+    /// other.
+    #[arg(short, long, default_value_t = 1)]
+    pub count: usize,
+
+    /// The number of participants in each ceremony, besides the coordinator.
+    #[arg(short, long, default_value_t = 3)]
+    pub participants: usize,
+
+    /// Path to the PEM-encoded certificate of the CA which issued the server
+    /// certificate, if it is not trusted by the system.
+    #[arg(long)]
+    pub cacert: Option<String>,
+
+    /// Do not verify the server certificate. This is very insecure and should
+    /// only be used for testing.
+    #[arg(long, default_value_t = false)]
+    pub insecure_skip_verify: bool,
+}
+
+/// The latencies measured for an API call.
+#[derive(Debug, Default)]
+pub struct Latencies(Vec<Duration>);
+
+impl Latencies {
+    /// The number of calls measured.
+    pub fn count(&self) -> usize {
+        self.0.len()
+    }
+
+    /// The latency below which `p` percent of the calls completed, or zero if
+    /// no calls were measured.
+    pub fn percentile(&self, p: f64) -> Duration {
+        let mut sorted = self.0.clone();
+        sorted.sort();
+        if sorted.is_empty() {
+            return Duration::ZERO;
+        }
+        let index = ((p / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+        sorted[index]
+    }
+
+    fn extend(&mut self, other: Latencies) {
+        self.0.extend(other.0);
+    }
+
+    /// Run `f` and record how long it took.
+    async fn measure<T>(
+        &mut self,
+        f: impl std::future::Future<Output = Result<T, Box<dyn Error + Send + Sync>>>,
+    ) -> Result<T, Box<dyn Error + Send + Sync>> {
+        let start = Instant::now();
+        let r = f.await;
+        self.0.push(start.elapsed());
+        r
+    }
+}
+
+/// The results of a benchmark run.
+#[derive(Debug, Default)]
+pub struct BenchReport {
+    /// The number of ceremonies that completed.
+    pub ceremonies: usize,
+    /// How long the whole run took.
+    pub elapsed: Duration,
+    pub create_new_session: Latencies,
+    pub send: Latencies,
+    pub receive: Latencies,
+}
+
+impl BenchReport {
+    fn extend(&mut self, other: BenchReport) {
+        self.ceremonies += other.ceremonies;
+        self.create_new_session.extend(other.create_new_session);
+        self.send.extend(other.send);
+        self.receive.extend(other.receive);
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.elapsed.as_secs_f64();
+        writeln!(
+            f,
+            "{} ceremonies in {:.2}s ({:.2} ceremonies/s)",
+            self.ceremonies,
+            secs,
+            self.ceremonies as f64 / secs
+        )?;
+        writeln!(
+            f,
+            "{:<20} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "call", "count", "calls/s", "p50", "p90", "p99", "max"
+        )?;
+        for (name, latencies) in [
+            ("create_new_session", &self.create_new_session),
+            ("send", &self.send),
+            ("receive", &self.receive),
+        ] {
+            writeln!(
+                f,
+                "{:<20} {:>8} {:>10.1} {:>10.2?} {:>10.2?} {:>10.2?} {:>10.2?}",
+                name,
+                latencies.count(),
+                latencies.count() as f64 / secs,
+                latencies.percentile(50.0),
+                latencies.percentile(90.0),
+                latencies.percentile(99.0),
+                latencies.percentile(100.0),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A simulated user, logged in to the server.
+struct BenchUser {
+    pubkey: Vec<u8>,
+    access_token: Uuid,
+}
+
+/// Run the benchmark described by `args` against a running server.
+pub async fn run_bench(args: &BenchArgs) -> Result<BenchReport, Box<dyn Error>> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(cacert) = &args.cacert {
+        let pem = std::fs::read(cacert).map_err(|e| eyre!("error reading {}: {}", cacert, e))?;
+        builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+    }
+    if args.insecure_skip_verify {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    let client = builder.build()?;
+
+    let start = Instant::now();
+    let mut workers = JoinSet::new();
+    for _ in 0..args.sessions {
+        let client = client.clone();
+        let args = args.clone();
+        workers.spawn(async move {
+            let mut report = BenchReport::default();
+            for _ in 0..args.count {
+                run_ceremony(&client, &args.url, args.participants, &mut report).await?;
+                report.ceremonies += 1;
+            }
+            Ok::<_, Box<dyn Error + Send + Sync>>(report)
+        });
+    }
+    let mut report = BenchReport::default();
+    while let Some(r) = workers.join_next().await {
+        report.extend(r?.map_err(|e| -> Box<dyn Error> { e })?);
+    }
+    report.elapsed = start.elapsed();
+    Ok(report)
+}
+
+/// Run a single simulated signing ceremony with the given number of
+/// participants, recording the latencies in `report`.
+async fn run_ceremony(
+    client: &reqwest::Client,
+    url: &str,
+    num_participants: usize,
+    report: &mut BenchReport,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let coordinator = login(client, url).await?;
+    let mut participants = Vec::with_capacity(num_participants);
+    for _ in 0..num_participants {
+        participants.push(login(client, url).await?);
+    }
+
+    let session_id = report
+        .create_new_session
+        .measure(async {
+            Ok(post::<_, CreateNewSessionOutput>(
+                client,
+                url,
+                "create_new_session",
+                &coordinator,
+                &CreateNewSessionArgs {
+                    pubkeys: participants
+                        .iter()
+                        .map(|p| PublicKey(p.pubkey.clone()))
+                        .collect(),
+                    message_count: 1,
+                    coordinators: vec![],
+                    close_quorum: None,
+                    ciphersuite: None,
+                    message_commitment: None,
+                },
+            )
+            .await?
+            .session_id)
+        })
+        .await?;
+
+    // Round 1: participants send their commitments to the coordinator.
+    for participant in &participants {
+        send(
+            client,
+            url,
+            participant,
+            session_id,
+            vec![],
+            SHARE_SIZE,
+            report,
+        )
+        .await?;
+    }
+    receive_all(
+        client,
+        url,
+        &coordinator,
+        session_id,
+        true,
+        participants.len(),
+        report,
+    )
+    .await?;
+
+    // Round 2: the coordinator sends the signing package, and participants
+    // reply with their signature shares.
+    for participant in &participants {
+        let recipients = vec![PublicKey(participant.pubkey.clone())];
+        send(
+            client,
+            url,
+            &coordinator,
+            session_id,
+            recipients,
+            SIGNING_PACKAGE_SIZE,
+            report,
+        )
+        .await?;
+    }
+    for participant in &participants {
+        receive_all(client, url, participant, session_id, false, 1, report).await?;
+        send(
+            client,
+            url,
+            participant,
+            session_id,
+            vec![],
+            SHARE_SIZE,
+            report,
+        )
+        .await?;
+    }
+    receive_all(
+        client,
+        url,
+        &coordinator,
+        session_id,
+        true,
+        participants.len(),
+        report,
+    )
+    .await?;
+
+    post::<_, CloseSessionOutput>(
+        client,
+        url,
+        "close_session",
+        &coordinator,
+        &CloseSessionArgs { session_id },
+    )
+    .await?;
+    Ok(())
+}
+
+/// Log in with a new random keypair.
+async fn login(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<BenchUser, Box<dyn Error + Send + Sync>> {
+    let keypair =
+        snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse()?).generate_keypair()?;
+
+    let challenge = client
+        .post(format!("{}/challenge", url))
+        .json(&ChallengeArgs {})
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<ChallengeOutput>()
+        .await?
+        .challenge;
+
+    let private = xed25519::PrivateKey::from(
+        &TryInto::<[u8; 32]>::try_into(keypair.private.clone())
+            .map_err(|_| eyre!("invalid private key"))?,
+    );
+    let signature: [u8; 64] = private.sign(challenge.as_bytes(), &mut thread_rng());
+    let access_token = client
+        .post(format!("{}/login", url))
+        .json(&KeyLoginArgs {
+            challenge,
+            pubkey: keypair.public.clone(),
+            signature: signature.to_vec(),
+        })
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<KeyLoginOutput>()
+        .await?
+        .access_token;
+
+    Ok(BenchUser {
+        pubkey: keypair.public,
+        access_token,
+    })
+}
+
+/// Call the given API method as `user`, returning its output.
+async fn post<A: serde::Serialize, O: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+    method: &str,
+    user: &BenchUser,
+    args: &A,
+) -> Result<O, Box<dyn Error + Send + Sync>> {
+    let response = client
+        .post(format!("{}/{}", url, method))
+        .bearer_auth(user.access_token)
+        .json(args)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let error = response.json::<crate::Error>().await?;
+        return Err(eyre!("{} failed: {}", method, error).into());
+    }
+    // Methods without output return an empty body.
+    let body = response.bytes().await?;
+    let body: &[u8] = if body.is_empty() { b"null" } else { &body };
+    Ok(serde_json::from_slice(body)?)
+}
+
+/// Send a random message of the given size.
+async fn send(
+    client: &reqwest::Client,
+    url: &str,
+    user: &BenchUser,
+    session_id: Uuid,
+    recipients: Vec<PublicKey>,
+    size: usize,
+    report: &mut BenchReport,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut msg = vec![0; size];
+    thread_rng().fill_bytes(&mut msg);
+    report
+        .send
+        .measure(post::<_, ()>(
+            client,
+            url,
+            "send",
+            user,
+            &SendArgs {
+                session_id,
+                recipients,
+                msg,
+            },
+        ))
+        .await
+}
+
+/// Poll the server until `count` messages were received.
+async fn receive_all(
+    client: &reqwest::Client,
+    url: &str,
+    user: &BenchUser,
+    session_id: Uuid,
+    as_coordinator: bool,
+    count: usize,
+    report: &mut BenchReport,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut received = 0;
+    while received < count {
+        let r = report
+            .receive
+            .measure(post::<_, ReceiveOutput>(
+                client,
+                url,
+                "receive",
+                user,
+                &ReceiveArgs {
+                    session_id,
+                    as_coordinator,
+                    peek: false,
+                },
+            ))
+            .await?;
+        received += r.msgs.len();
+        if r.msgs.is_empty() {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,10 @@
+use clap::Parser;
+use frostd::bench::{run_bench, BenchArgs};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = BenchArgs::parse();
+    let report = run_bench(&args).await?;
+    print!("{}", report);
+    Ok(())
+}
@@ -1,11 +1,17 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
 use axum::{extract::State, Json};
 use uuid::Uuid;
 use xeddsa::{xed25519, Verify as _};
 
 use crate::{
-    state::{Session, SharedState},
+    args::QueueFullPolicy,
+    state::{MsgQueue, MutexExt as _, RwLockExt as _, Session, SharedState},
     types::*,
-    user::User,
+    user::{Admin, User},
     AppError,
 };
 
@@ -18,7 +24,7 @@ pub(crate) async fn challenge(
     // Create new challenge.
     let challenge = Uuid::new_v4();
 
-    state.challenges.write().unwrap().insert(challenge);
+    state.challenges.write_unpoisoned().insert(challenge);
 
     let output = ChallengeOutput { challenge };
     Ok(Json(output))
@@ -44,7 +50,7 @@ pub(crate) async fn login(
         .verify(args.challenge.as_bytes(), &signature)
         .map_err(|_| AppError::Unauthorized)?;
 
-    let mut challenges = state.challenges.write().unwrap();
+    let mut challenges = state.challenges.write_unpoisoned();
     if !challenges.remove(&args.challenge) {
         return Err(AppError::Unauthorized);
     }
@@ -52,7 +58,7 @@ pub(crate) async fn login(
 
     let access_token = Uuid::new_v4();
 
-    let mut access_tokens = state.access_tokens.write().unwrap();
+    let mut access_tokens = state.access_tokens.write_unpoisoned();
     access_tokens.insert(access_token, args.pubkey);
 
     let token = KeyLoginOutput { access_token };
@@ -61,21 +67,32 @@ pub(crate) async fn login(
 }
 
 /// Implement the logout API.
-#[tracing::instrument(level = "debug", ret, err(Debug), skip(state, user))]
+#[tracing::instrument(
+    level = "debug",
+    ret,
+    err(Debug),
+    skip(state, user),
+    fields(pubkey = %hex::encode(&user.pubkey))
+)]
 pub(crate) async fn logout(
     State(state): State<SharedState>,
     user: User,
 ) -> Result<Json<()>, AppError> {
     state
         .access_tokens
-        .write()
-        .unwrap()
+        .write_unpoisoned()
         .remove(&user.current_token);
     Ok(Json(()))
 }
 
 /// Implement the create_new_session API.
-#[tracing::instrument(level = "debug", ret, err(Debug), skip(state, user))]
+#[tracing::instrument(
+    level = "debug",
+    ret,
+    err(Debug),
+    skip(state, user),
+    fields(pubkey = %hex::encode(&user.pubkey), session_id = tracing::field::Empty)
+)]
 pub(crate) async fn create_new_session(
     State(state): State<SharedState>,
     user: User,
@@ -84,12 +101,30 @@ pub(crate) async fn create_new_session(
     if args.message_count == 0 {
         return Err(AppError::InvalidArgument("message_count".into()));
     }
+    // FROST requires at least 2 participants, which is also what the DKG
+    // client requires.
+    if args.pubkeys.len() < 2 || args.pubkeys.len() > state.max_participants {
+        return Err(AppError::InvalidArgument("pubkeys".into()));
+    }
+    let mut coordinator_pubkeys = vec![user.pubkey];
+    for pubkey in args.coordinators.into_iter().map(|p| p.0) {
+        if coordinator_pubkeys.contains(&pubkey) {
+            return Err(AppError::InvalidArgument("coordinators".into()));
+        }
+        coordinator_pubkeys.push(pubkey);
+    }
+    let close_quorum = args.close_quorum.unwrap_or(coordinator_pubkeys.len());
+    if close_quorum == 0 || close_quorum > coordinator_pubkeys.len() {
+        return Err(AppError::InvalidArgument("close_quorum".into()));
+    }
 
     // Create new session object.
     let id = Uuid::new_v4();
+    tracing::Span::current().record("session_id", tracing::field::display(id));
 
-    let mut sessions = state.sessions.sessions.write().unwrap();
-    let mut sessions_by_pubkey = state.sessions.sessions_by_pubkey.write().unwrap();
+    // Lock order: see `SessionState`.
+    let mut sessions = state.sessions.sessions.write_unpoisoned();
+    let mut sessions_by_pubkey = state.sessions.sessions_by_pubkey.write_unpoisoned();
 
     // Save session ID in global state
     for pubkey in &args.pubkeys {
@@ -99,175 +134,539 @@ pub(crate) async fn create_new_session(
             .insert(id);
     }
     // Create Session object
+    let pubkeys: Vec<Vec<u8>> = args.pubkeys.into_iter().map(|p| p.0).collect();
     let session = Session {
-        pubkeys: args.pubkeys.into_iter().map(|p| p.0).collect(),
-        coordinator_pubkey: user.pubkey,
+        queue: pubkeys
+            .iter()
+            .map(|pubkey| (pubkey.clone(), Default::default()))
+            .collect(),
+        pubkeys,
+        coordinator_queues: coordinator_pubkeys
+            .iter()
+            .map(|_| Default::default())
+            .collect(),
+        coordinator_pubkeys,
+        close_quorum,
+        close_approvals: Default::default(),
         message_count: args.message_count,
-        queue: Default::default(),
+        ciphersuite: args.ciphersuite,
+        message_commitment: args.message_commitment,
+        extended_until: None,
+        closed: false,
     };
     // Save session into global state.
-    sessions.insert(id, session);
+    sessions.insert(id, Arc::new(Mutex::new(session)));
 
     let user = CreateNewSessionOutput { session_id: id };
     Ok(Json(user))
 }
 
-/// Implement the create_new_session API.
-#[tracing::instrument(level = "debug", ret, err(Debug), skip(state, user))]
+/// Implement the list_sessions API.
+#[tracing::instrument(
+    level = "debug",
+    ret,
+    err(Debug),
+    skip(state, user, args),
+    fields(pubkey = %hex::encode(&user.pubkey))
+)]
 pub(crate) async fn list_sessions(
     State(state): State<SharedState>,
     user: User,
+    args: Option<Json<ListSessionsArgs>>,
 ) -> Result<Json<ListSessionsOutput>, AppError> {
-    let sessions_by_pubkey = state.sessions.sessions_by_pubkey.read().unwrap();
+    // The arguments are optional, for compatibility with clients which do not
+    // send them.
+    let args = args.map(|Json(args)| args).unwrap_or_default();
 
-    let session_ids = sessions_by_pubkey
+    let sessions_by_pubkey = state.sessions.sessions_by_pubkey.read_unpoisoned();
+
+    let mut session_ids: Vec<Uuid> = sessions_by_pubkey
         .get(&user.pubkey)
         .map(|s| s.iter().cloned().collect())
         .unwrap_or_default();
+    // Sort so that pages are consistent across calls.
+    session_ids.sort();
+
+    let total = session_ids.len();
+    let session_ids = session_ids
+        .into_iter()
+        .skip(args.offset.unwrap_or(0))
+        .take(args.limit.unwrap_or(usize::MAX))
+        .collect();
 
-    Ok(Json(ListSessionsOutput { session_ids }))
+    Ok(Json(ListSessionsOutput { session_ids, total }))
 }
 
 /// Implement the get_session_info API
-#[tracing::instrument(level = "debug", ret, err(Debug), skip(state, user))]
+#[tracing::instrument(
+    level = "debug",
+    ret,
+    err(Debug),
+    skip(state, user, args),
+    fields(pubkey = %hex::encode(&user.pubkey), session_id = %args.session_id)
+)]
 pub(crate) async fn get_session_info(
     State(state): State<SharedState>,
     user: User,
     Json(args): Json<GetSessionInfoArgs>,
 ) -> Result<Json<GetSessionInfoOutput>, AppError> {
-    let sessions = state.sessions.sessions.read().unwrap();
-    let sessions_by_pubkey = state.sessions.sessions_by_pubkey.read().unwrap();
+    // Lock order: see `SessionState`.
+    let sessions = state.sessions.sessions.read_unpoisoned();
+    let sessions_by_pubkey = state.sessions.sessions_by_pubkey.read_unpoisoned();
 
-    let user_sessions = sessions_by_pubkey
+    let is_participant = sessions_by_pubkey
         .get(&user.pubkey)
-        .ok_or(AppError::SessionNotFound)?;
-
-    if !user_sessions.contains(&args.session_id) {
-        return Err(AppError::SessionNotFound);
-    }
+        .is_some_and(|s| s.contains(&args.session_id));
 
     let session = sessions
         .get(&args.session_id)
-        .ok_or(AppError::SessionNotFound)?;
+        .ok_or(AppError::SessionNotFound)?
+        .lock_unpoisoned();
 
-    Ok(Json(GetSessionInfoOutput {
+    // The coordinators can also get the session info, e.g. to resume it.
+    if !is_participant && !session.is_coordinator(&user.pubkey) {
+        return Err(AppError::SessionNotFound);
+    }
+
+    let output = GetSessionInfoOutput {
         message_count: session.message_count,
         pubkeys: session.pubkeys.iter().cloned().map(PublicKey).collect(),
-        coordinator_pubkey: session.coordinator_pubkey.clone(),
-    }))
+        coordinator_pubkey: session.coordinator_pubkeys[0].clone(),
+        coordinator_pubkeys: session
+            .coordinator_pubkeys
+            .iter()
+            .cloned()
+            .map(PublicKey)
+            .collect(),
+        ciphersuite: session.ciphersuite.clone(),
+        message_commitment: session.message_commitment.clone(),
+    };
+    drop(session);
+    drop(sessions_by_pubkey);
+    drop(sessions);
+    // Clients call this periodically to keep the session open while they
+    // wait, so it renews the timeout.
+    state.sessions.renew(&args.session_id);
+
+    Ok(Json(output))
 }
 
 /// Implement the send API
+///
+/// The allowed flows are:
+///
+/// - A participant sends to the coordinator which created the session, with
+///   no recipients. Other coordinators do not get the message, since
+///   participants encrypt it to the key of the creator. If the participant is
+///   the creator, it gets the message in its coordinator queue too, since it
+///   receives its own commitments and shares like any other.
+/// - A coordinator which is not a participant sends to the other
+///   coordinators, with no recipients. It does not get the message itself,
+///   and the message is rejected if there are no other coordinators.
+/// - A coordinator sends to the given participants, which can include itself
+///   only if it is also a participant.
 // TODO: get identifier from channel rather from arguments
-#[tracing::instrument(level = "debug", ret, err(Debug), skip(state, user))]
+// Do not log `args` since it contains the message contents.
+#[tracing::instrument(
+    level = "debug",
+    ret,
+    err(Debug),
+    skip(state, user, args),
+    fields(
+        pubkey = %hex::encode(&user.pubkey),
+        session_id = %args.session_id,
+        recipients = args.recipients.len(),
+    )
+)]
 pub(crate) async fn send(
     State(state): State<SharedState>,
     user: User,
     Json(args): Json<SendArgs>,
 ) -> Result<(), AppError> {
-    // Get the mutex lock to read and write from the state
-    let mut sessions = state.sessions.sessions.write().unwrap();
-
-    // TODO: change to get_mut and modify in-place, if HashMapDelay ever
-    // adds support to it
-    let mut session = sessions
-        .remove(&args.session_id)
+    let session = state
+        .sessions
+        .get(&args.session_id)
         .ok_or(AppError::SessionNotFound)?;
 
-    let recipients = if args.recipients.is_empty() {
-        vec![Vec::new()]
+    let mut session = session.lock_unpoisoned();
+    // The session may have been closed after it was looked up; the message
+    // would then be silently lost.
+    if session.closed {
+        return Err(AppError::SessionNotFound);
+    }
+    // Anyone in the session can send messages to the coordinators, but only
+    // coordinators can send messages to participants; otherwise a participant
+    // could impersonate a coordinator, e.g. sending a forged SigningPackage.
+    if !args.recipients.is_empty() && !session.is_coordinator(&user.pubkey) {
+        return Err(AppError::NotCoordinator);
+    }
+    // Messages without recipients are sent to the coordinators: to the
+    // creator of the session if sent by a participant, and to every other
+    // coordinator if sent by a coordinator which is not a participant, since
+    // it would otherwise be sending a message to itself.
+    let to_coordinators = args.recipients.is_empty();
+    let coordinator_indices: Vec<usize> = if !to_coordinators {
+        Vec::new()
+    } else if session.pubkeys.contains(&user.pubkey) {
+        vec![0]
     } else {
-        args.recipients.into_iter().map(|p| p.0).collect()
+        (0..session.coordinator_pubkeys.len())
+            .filter(|&i| session.coordinator_pubkeys[i] != user.pubkey)
+            .collect()
     };
-    for pubkey in &recipients {
+    if to_coordinators && coordinator_indices.is_empty() {
+        return Err(AppError::InvalidArgument("recipients".into()));
+    }
+    let recipients = if to_coordinators {
+        Vec::new()
+    } else {
+        // Deduplicate recipients, otherwise they would get the same message
+        // more than once. Only participants of the session can be sent
+        // messages, so the coordinator can only send a message to itself if
+        // it is also a participant.
+        let mut recipients: Vec<Vec<u8>> = Vec::with_capacity(args.recipients.len());
+        for pubkey in args.recipients.into_iter().map(|p| p.0) {
+            if !session.queue.contains_key(&pubkey) {
+                return Err(AppError::InvalidArgument("recipients".into()));
+            }
+            if !recipients.contains(&pubkey) {
+                recipients.push(pubkey);
+            }
+        }
+        recipients
+    };
+    // Check every recipient before queueing anything, so that the message is
+    // either queued for all of them or for none.
+    let limit = state.queue_limit;
+    let full = if to_coordinators {
+        coordinator_indices
+            .iter()
+            .any(|&i| session.coordinator_queues[i].len() >= limit.max_len)
+    } else {
+        recipients
+            .iter()
+            .any(|pubkey| session.queue.get(pubkey).map_or(0, |q| q.len()) >= limit.max_len)
+    };
+    if limit.policy == QueueFullPolicy::Reject && full {
+        return Err(AppError::QueueFull);
+    }
+    let push = |queue: &mut MsgQueue| {
+        if queue.len() >= limit.max_len {
+            queue.pop();
+        }
+        queue.push(Msg {
+            sender: user.pubkey.clone(),
+            msg: args.msg.clone(),
+        });
+    };
+    if to_coordinators {
+        for i in coordinator_indices {
+            push(&mut session.coordinator_queues[i]);
+        }
+    } else {
         session
             .queue
-            .entry(pubkey.clone())
-            .or_default()
-            .push_back(Msg {
-                sender: user.pubkey.clone(),
-                msg: args.msg.clone(),
-            });
+            .iter_mut()
+            .filter(|(pubkey, _)| recipients.contains(pubkey))
+            .for_each(|(_, q)| push(q));
     }
-    sessions.insert(args.session_id, session);
+    drop(session);
+    state.sessions.renew(&args.session_id);
 
     Ok(())
 }
 
 /// Implement the recv API
-#[tracing::instrument(level = "debug", ret, err(Debug), skip(state, user))]
+// Do not log the return value since it contains the message contents.
+#[tracing::instrument(
+    level = "debug",
+    err(Debug),
+    skip(state, user, args),
+    fields(
+        pubkey = %hex::encode(&user.pubkey),
+        session_id = %args.session_id,
+        as_coordinator = args.as_coordinator,
+    )
+)]
 pub(crate) async fn receive(
     State(state): State<SharedState>,
     user: User,
     Json(args): Json<ReceiveArgs>,
 ) -> Result<Json<ReceiveOutput>, AppError> {
-    // Get the mutex lock to read and write from the state
-    let sessions = state.sessions.sessions.read().unwrap();
-
-    // TODO: change to get_mut and modify in-place, if HashMapDelay ever
-    // adds support to it. This will also simplify the code since
-    // we have to do a workaround in order to not renew the timeout if there
-    // are no messages. See https://github.com/AgeManning/delay_map/issues/26
-    let session = sessions
+    let session = state
+        .sessions
         .get(&args.session_id)
         .ok_or(AppError::SessionNotFound)?;
+    let mut session = session.lock_unpoisoned();
 
-    let pubkey = if user.pubkey == session.coordinator_pubkey && args.as_coordinator {
-        Vec::new()
+    if args.as_coordinator && !session.is_coordinator(&user.pubkey) {
+        return Err(AppError::NotCoordinator);
+    }
+
+    // The queue is kept even when drained so that message indices keep
+    // increasing.
+    let queue = session
+        .queue_mut(&user.pubkey, args.as_coordinator)
+        .ok_or(AppError::SessionNotFound)?;
+    let first_index = queue.first_index;
+    let msgs: Vec<_> = if args.peek {
+        queue.msgs.iter().cloned().collect()
     } else {
-        user.pubkey
+        queue.drain()
     };
+    drop(session);
 
     // If there are no new messages, we don't want to renew the timeout.
-    // Thus only if there are new messages we drop the read-only lock
-    // to get the write lock and re-insert the updated session.
-    let msgs = if session.queue.contains_key(&pubkey) {
-        drop(sessions);
-        let mut sessions = state.sessions.sessions.write().unwrap();
-        let mut session = sessions
-            .remove(&args.session_id)
-            .ok_or(AppError::SessionNotFound)?;
-        let msgs = session.queue.entry(pubkey).or_default().drain(..).collect();
-        sessions.insert(args.session_id, session);
-        msgs
-    } else {
-        vec![]
-    };
+    if !msgs.is_empty() {
+        state.sessions.renew(&args.session_id);
+    }
+
+    Ok(Json(ReceiveOutput { msgs, first_index }))
+}
 
-    Ok(Json(ReceiveOutput { msgs }))
+/// Implement the ack API, which removes messages that were received with
+/// `peek` from the queue once they have been processed.
+#[tracing::instrument(
+    level = "debug",
+    ret,
+    err(Debug),
+    skip(state, user, args),
+    fields(
+        pubkey = %hex::encode(&user.pubkey),
+        session_id = %args.session_id,
+        as_coordinator = args.as_coordinator,
+        index = args.index,
+    )
+)]
+pub(crate) async fn ack(
+    State(state): State<SharedState>,
+    user: User,
+    Json(args): Json<AckArgs>,
+) -> Result<Json<()>, AppError> {
+    let session = state
+        .sessions
+        .get(&args.session_id)
+        .ok_or(AppError::SessionNotFound)?;
+    let mut session = session.lock_unpoisoned();
+
+    if args.as_coordinator && !session.is_coordinator(&user.pubkey) {
+        return Err(AppError::NotCoordinator);
+    }
+    if !session
+        .queue_mut(&user.pubkey, args.as_coordinator)
+        .ok_or(AppError::SessionNotFound)?
+        .ack(args.index)
+    {
+        return Err(AppError::InvalidArgument("index".into()));
+    }
+    drop(session);
+    state.sessions.renew(&args.session_id);
+
+    Ok(Json(()))
 }
 
 /// Implement the close_session API.
-#[tracing::instrument(level = "debug", ret, err(Debug), skip(state, user))]
+#[tracing::instrument(
+    level = "debug",
+    ret,
+    err(Debug),
+    skip(state, user, args),
+    fields(pubkey = %hex::encode(&user.pubkey), session_id = %args.session_id)
+)]
 pub(crate) async fn close_session(
     State(state): State<SharedState>,
     user: User,
     Json(args): Json<CloseSessionArgs>,
-) -> Result<Json<()>, AppError> {
-    let mut sessions = state.sessions.sessions.write().unwrap();
-    let mut sessions_by_pubkey = state.sessions.sessions_by_pubkey.write().unwrap();
+) -> Result<Json<CloseSessionOutput>, AppError> {
+    // Lock order: see `SessionState`.
+    let mut sessions = state.sessions.sessions.write_unpoisoned();
+    let mut sessions_by_pubkey = state.sessions.sessions_by_pubkey.write_unpoisoned();
 
-    let user_sessions = sessions_by_pubkey
+    let is_participant = sessions_by_pubkey
         .get(&user.pubkey)
-        .ok_or(AppError::SessionNotFound)?;
+        .is_some_and(|s| s.contains(&args.session_id));
 
-    if !user_sessions.contains(&args.session_id) {
-        return Err(AppError::SessionNotFound);
-    }
-
-    let session = sessions
+    let mut session = sessions
         .get(&args.session_id)
-        .ok_or(AppError::SessionNotFound)?;
+        .ok_or(AppError::SessionNotFound)?
+        .lock_unpoisoned();
 
-    if session.coordinator_pubkey != user.pubkey {
+    let is_coordinator = session.is_coordinator(&user.pubkey);
+    if !is_participant && !is_coordinator {
+        return Err(AppError::SessionNotFound);
+    }
+    if !is_coordinator {
         return Err(AppError::NotCoordinator);
     }
 
+    // With multiple coordinators, the session is only closed once enough of
+    // them asked for it.
+    session.close_approvals.insert(user.pubkey);
+    if session.close_approvals.len() < session.close_quorum {
+        return Ok(Json(CloseSessionOutput { closed: false }));
+    }
+
+    session.closed = true;
     for username in session.pubkeys.clone() {
         if let Some(v) = sessions_by_pubkey.get_mut(&username) {
             v.remove(&args.session_id);
         }
     }
+    drop(session);
     sessions.remove(&args.session_id);
-    Ok(Json(()))
+    Ok(Json(CloseSessionOutput { closed: true }))
+}
+
+/// Implement the extend_session API.
+///
+/// This keeps the session open for the given duration even if it is not
+/// used, e.g. while a manual ceremony is underway. Later activity can only
+/// keep it open for longer.
+#[tracing::instrument(level = "debug", err(Debug), skip(state, user))]
+pub(crate) async fn extend_session(
+    State(state): State<SharedState>,
+    user: User,
+    Json(args): Json<ExtendSessionArgs>,
+) -> Result<Json<ExtendSessionOutput>, AppError> {
+    let extension = Duration::from_secs(args.seconds);
+    if extension > state.sessions.max_extension {
+        return Err(AppError::InvalidArgument(format!(
+            "seconds (at most {})",
+            state.sessions.max_extension.as_secs()
+        )));
+    }
+    // Never shorten the session below the usual timeout.
+    let timeout = extension.max(state.sessions.timeout);
+
+    // Lock order: see `SessionState`.
+    let mut sessions = state.sessions.sessions.write_unpoisoned();
+    let sessions_by_pubkey = state.sessions.sessions_by_pubkey.read_unpoisoned();
+
+    let is_participant = sessions_by_pubkey
+        .get(&user.pubkey)
+        .is_some_and(|s| s.contains(&args.session_id));
+
+    let session = sessions
+        .get(&args.session_id)
+        .ok_or(AppError::SessionNotFound)?
+        .clone();
+    let mut session = session.lock_unpoisoned();
+
+    let is_coordinator = session.is_coordinator(&user.pubkey);
+    if !is_participant && !is_coordinator {
+        return Err(AppError::SessionNotFound);
+    }
+    if !is_coordinator {
+        return Err(AppError::NotCoordinator);
+    }
+
+    session.extended_until = Some(Instant::now() + timeout);
+    drop(session);
+    drop(sessions_by_pubkey);
+    sessions.update_timeout(&args.session_id, timeout);
+
+    let expires_at = (SystemTime::now() + timeout)
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| AppError::InvalidArgument("seconds".into()))?
+        .as_secs();
+    Ok(Json(ExtendSessionOutput { expires_at }))
+}
+
+/// Implement the batch API, which calls multiple API methods with a single
+/// request. Each request is executed in order with the same authentication,
+/// and its result is returned regardless of whether the others fail.
+#[tracing::instrument(
+    level = "debug",
+    err(Debug),
+    skip(state, user, args),
+    fields(pubkey = %hex::encode(&user.pubkey), requests = args.requests.len())
+)]
+pub(crate) async fn batch(
+    State(state): State<SharedState>,
+    user: User,
+    Json(args): Json<BatchArgs>,
+) -> Result<Json<BatchOutput>, AppError> {
+    let mut results = Vec::with_capacity(args.requests.len());
+    for request in args.requests {
+        let state = State(state.clone());
+        let user = user.clone();
+        let result = match request {
+            BatchRequest::CreateNewSession(args) => {
+                batch_result(create_new_session(state, user, Json(args)).await)
+            }
+            BatchRequest::ListSessions(args) => {
+                batch_result(list_sessions(state, user, Some(Json(args))).await)
+            }
+            BatchRequest::GetSessionInfo(args) => {
+                batch_result(get_session_info(state, user, Json(args)).await)
+            }
+            BatchRequest::Send(args) => batch_result(send(state, user, Json(args)).await.map(Json)),
+            BatchRequest::Receive(args) => batch_result(receive(state, user, Json(args)).await),
+            BatchRequest::Ack(args) => batch_result(ack(state, user, Json(args)).await),
+            BatchRequest::CloseSession(args) => {
+                batch_result(close_session(state, user, Json(args)).await)
+            }
+            BatchRequest::ExtendSession(args) => {
+                batch_result(extend_session(state, user, Json(args)).await)
+            }
+        };
+        results.push(result);
+    }
+    Ok(Json(BatchOutput { results }))
+}
+
+/// Implement the admin_queues API.
+///
+/// This is meant for debugging stuck sessions, and is only available if the
+/// server was configured with an admin token.
+#[tracing::instrument(level = "debug", err(Debug), skip(state, _admin, _args))]
+pub(crate) async fn admin_queues(
+    State(state): State<SharedState>,
+    _admin: Admin,
+    Json(_args): Json<AdminQueuesArgs>,
+) -> Result<Json<AdminQueuesOutput>, AppError> {
+    let sessions = state.sessions.sessions.read_unpoisoned();
+
+    let mut sessions: Vec<SessionQueues> = sessions
+        .iter()
+        .map(|(session_id, session)| {
+            let session = session.lock_unpoisoned();
+            let count = |pubkey: &Vec<u8>| session.queue.get(pubkey).map_or(0, |q| q.len());
+            SessionQueues {
+                session_id: *session_id,
+                coordinator: session.coordinator_queues[0].len(),
+                coordinators: session
+                    .coordinator_pubkeys
+                    .iter()
+                    .zip(&session.coordinator_queues)
+                    .skip(1)
+                    .map(|(pubkey, queue)| ParticipantQueue {
+                        pubkey: PublicKey(pubkey.clone()),
+                        count: queue.len(),
+                    })
+                    .collect(),
+                participants: session
+                    .pubkeys
+                    .iter()
+                    .map(|pubkey| ParticipantQueue {
+                        pubkey: PublicKey(pubkey.clone()),
+                        count: count(pubkey),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    sessions.sort_by_key(|s| s.session_id);
+
+    Ok(Json(AdminQueuesOutput { sessions }))
+}
+
+/// Convert the result of an API method into a BatchResult.
+fn batch_result<T: serde::Serialize>(result: Result<Json<T>, AppError>) -> BatchResult {
+    match result {
+        Ok(Json(output)) => BatchResult::Ok(
+            serde_json::to_value(output).expect("API outputs are always serializable"),
+        ),
+        Err(err) => BatchResult::Err(err.into()),
+    }
 }
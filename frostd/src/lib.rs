@@ -1,20 +1,31 @@
 pub mod args;
+pub mod bench;
 mod functions;
 mod state;
 mod types;
 mod user;
 
-use std::net::SocketAddr;
+use std::{
+    fs::File,
+    io::BufReader,
+    net::{IpAddr, SocketAddr, TcpListener},
+    sync::Arc,
+    time::Duration,
+};
 
 use axum_server::tls_rustls::RustlsConfig;
-use eyre::OptionExt;
-pub use state::{AppState, SharedState};
+use eyre::{eyre, OptionExt};
+pub use state::{AppState, QueueLimit, SharedState};
 use thiserror::Error;
-use tower_http::trace::TraceLayer;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer,
+};
 pub use types::*;
 
-use args::Args;
+use args::{Args, TlsVersion};
 use axum::{
+    extract::Request,
     http::StatusCode,
     response::{IntoResponse, Response},
     routing::post,
@@ -26,7 +37,7 @@ use axum::{
 // TODO: use methods of a single object instead of separate functions?
 pub fn router(shared_state: SharedState) -> Router {
     // Shared state that is passed to each handler by axum
-    Router::new()
+    let mut router = Router::new()
         .route("/challenge", post(functions::challenge))
         .route("/login", post(functions::login))
         .route("/logout", post(functions::logout))
@@ -35,43 +46,145 @@ pub fn router(shared_state: SharedState) -> Router {
         .route("/get_session_info", post(functions::get_session_info))
         .route("/send", post(functions::send))
         .route("/receive", post(functions::receive))
+        .route("/ack", post(functions::ack))
         .route("/close_session", post(functions::close_session))
-        .layer(TraceLayer::new_for_http())
+        .route("/extend_session", post(functions::extend_session))
+        .route("/batch", post(functions::batch));
+    // Don't even expose the admin API if no admin token was configured.
+    if shared_state.admin_token.is_some() {
+        router = router.route("/admin/queues", post(functions::admin_queues));
+    }
+    router
+        // Layers wrap the ones added before them, so the request ID is set
+        // before the request is traced, and propagated to the response after.
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(
+            TraceLayer::new_for_http().make_span_with(|request: &Request| {
+                // Tag the span with the request ID so that all events logged
+                // while handling the request can be correlated.
+                let request_id = request
+                    .headers()
+                    .get("x-request-id")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default();
+                tracing::debug_span!(
+                    "request",
+                    method = %request.method(),
+                    uri = %request.uri(),
+                    request_id,
+                )
+            }),
+        )
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
         .with_state(shared_state)
 }
 
 /// Run the server with the specified arguments.
 pub async fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
-    let shared_state = AppState::new().await?;
+    let shared_state = AppState::new(
+        args.max_participants,
+        QueueLimit {
+            max_len: args.max_queue_len,
+            policy: args.queue_full,
+        },
+        Duration::from_secs(args.session_timeout),
+        Duration::from_secs(args.max_session_extension),
+        args.admin_token.clone(),
+    )
+    .await?;
     let app = router(shared_state.clone());
 
-    let addr: SocketAddr = format!("{}:{}", args.ip(), args.port).parse()?;
-
-    if args.no_tls_very_insecure {
-        tracing::warn!(
-            "starting an INSECURE HTTP server at {}. This should be done only \
-            for testing or if you are providing TLS/HTTPS with a separate \
-            mechanism (e.g. reverse proxy such as nginx)",
-            addr,
-        );
-        let listener = tokio::net::TcpListener::bind(addr).await?;
-        Ok(axum::serve(listener, app).await?)
+    let config = if args.no_tls_very_insecure {
+        None
     } else {
-        let config = RustlsConfig::from_pem_file(
+        Some(RustlsConfig::from_config(Arc::new(tls_config(
             args.tls_cert
-                .clone()
+                .as_deref()
                 .ok_or_eyre("tls-cert argument is required")?,
             args.tls_key
-                .clone()
+                .as_deref()
                 .ok_or_eyre("tls-key argument is required")?,
-        )
-        .await?;
+            args.tls_min_version,
+        )?)))
+    };
+
+    // Serve on each address concurrently, until one of the servers fails.
+    let mut servers = tokio::task::JoinSet::new();
+    for listener in bind(&args.ip(), args.port)? {
+        let addr = listener.local_addr()?;
+        let app = app.clone();
+        match &config {
+            None => {
+                tracing::warn!(
+                    "starting an INSECURE HTTP server at {}. This should be done only \
+                    for testing or if you are providing TLS/HTTPS with a separate \
+                    mechanism (e.g. reverse proxy such as nginx)",
+                    addr,
+                );
+                let listener = tokio::net::TcpListener::from_std(listener)?;
+                servers.spawn(async move { axum::serve(listener, app).await });
+            }
+            Some(config) => {
+                tracing::info!("starting HTTPS server at {}", addr);
+                let server = axum_server::tls_rustls::from_tcp_rustls(listener, config.clone());
+                servers.spawn(async move { server.serve(app.into_make_service()).await });
+            }
+        }
+    }
+    while let Some(result) = servers.join_next().await {
+        result??;
+    }
+    Ok(())
+}
 
-        tracing::info!("starting HTTPS server at {}", addr);
-        Ok(axum_server::bind_rustls(addr, config)
-            .serve(app.into_make_service())
-            .await?)
+/// Bind a listener to each of the given IPs, on the given port. Failing to
+/// bind to one of them is logged but does not prevent binding to the others;
+/// an error is only returned if no listener could be bound at all.
+fn bind(ips: &[String], port: u16) -> Result<Vec<TcpListener>, Box<dyn std::error::Error>> {
+    let mut listeners = Vec::new();
+    for ip in ips {
+        // Also accept IPv6 addresses in brackets, e.g. `[::1]`.
+        let ip: IpAddr = ip.trim_start_matches('[').trim_end_matches(']').parse()?;
+        let addr = SocketAddr::new(ip, port);
+        match TcpListener::bind(addr) {
+            Ok(listener) => {
+                // Required to hand the listener over to tokio.
+                listener.set_nonblocking(true)?;
+                listeners.push(listener);
+            }
+            Err(e) => tracing::error!("could not bind to {}: {}", addr, e),
+        }
+    }
+    if listeners.is_empty() {
+        return Err(eyre!("could not bind to any of the given IPs").into());
     }
+    Ok(listeners)
+}
+
+/// Build the TLS config of the server from the PEM-encoded certificate chain
+/// and private key in the given files, accepting `min_version` and later
+/// versions of TLS.
+pub fn tls_config(
+    cert_path: &str,
+    key_path: &str,
+    min_version: TlsVersion,
+) -> Result<rustls::ServerConfig, Box<dyn std::error::Error>> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(cert_path)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    let key = rustls_pemfile::private_key(&mut BufReader::new(File::open(key_path)?))?
+        .ok_or_eyre("tls-key does not contain a private key")?;
+
+    let versions: &[&'static rustls::SupportedProtocolVersion] = match min_version {
+        TlsVersion::Tls12 => &[&rustls::version::TLS13, &rustls::version::TLS12],
+        TlsVersion::Tls13 => &[&rustls::version::TLS13],
+    };
+    let mut config = rustls::ServerConfig::builder_with_protocol_versions(versions)
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    // Same as `RustlsConfig::from_pem_file()`.
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    Ok(config)
 }
 
 /// An error. Wraps a StatusCode which is returned by the server when the
@@ -86,6 +199,8 @@ pub(crate) enum AppError {
     SessionNotFound,
     #[error("user is not the coordinator")]
     NotCoordinator,
+    #[error("the message queue of a recipient is full")]
+    QueueFull,
 }
 
 // These make it easier to clients to tell which error happened.
@@ -93,6 +208,7 @@ pub const INVALID_ARGUMENT: usize = 1;
 pub const UNAUTHORIZED: usize = 2;
 pub const SESSION_NOT_FOUND: usize = 3;
 pub const NOT_COORDINATOR: usize = 4;
+pub const QUEUE_FULL: usize = 5;
 
 impl AppError {
     pub fn error_code(&self) -> usize {
@@ -101,6 +217,7 @@ impl AppError {
             AppError::Unauthorized => UNAUTHORIZED,
             AppError::SessionNotFound => SESSION_NOT_FOUND,
             AppError::NotCoordinator => NOT_COORDINATOR,
+            AppError::QueueFull => QUEUE_FULL,
         }
     }
 }
@@ -1,24 +1,58 @@
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     pin::Pin,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use delay_map::{HashMapDelay, HashSetDelay};
 use futures::{Stream, StreamExt as _};
 use uuid::Uuid;
 
-use crate::Msg;
+use crate::{
+    args::{QueueFullPolicy, DEFAULT_MAX_QUEUE_LEN},
+    MessageCommitment, Msg,
+};
 
-/// How long a session stays open.
-const SESSION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60 * 60 * 24);
 /// How long a challenge can be replied to.
 const CHALLENGE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
 /// How long an acesss token lasts.
 const ACCESS_TOKEN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60 * 60);
 
+/// Extension trait to acquire a `RwLock` even if it is poisoned, i.e. if a
+/// thread panicked while holding it.
+///
+/// The server state is made of plain collections which remain usable after
+/// an interrupted update, while failing on a poisoned lock would make every
+/// later request panic, effectively taking down the server.
+pub(crate) trait RwLockExt<T> {
+    fn read_unpoisoned(&self) -> RwLockReadGuard<'_, T>;
+    fn write_unpoisoned(&self) -> RwLockWriteGuard<'_, T>;
+}
+
+impl<T> RwLockExt<T> for RwLock<T> {
+    fn read_unpoisoned(&self) -> RwLockReadGuard<'_, T> {
+        self.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_unpoisoned(&self) -> RwLockWriteGuard<'_, T> {
+        self.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Extension trait to acquire a `Mutex` even if it is poisoned. See
+/// [`RwLockExt`].
+pub(crate) trait MutexExt<T> {
+    fn lock_unpoisoned(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> MutexExt<T> for Mutex<T> {
+    fn lock_unpoisoned(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
 /// Helper struct that allows calling `next()` on a `Stream` behind a `RwLock`
 /// (namely a `HashMapDelay` or `HashSetDelay` in our case) without locking
 /// the `RwLock` while waiting.
@@ -31,7 +65,7 @@ impl<T: Stream + Unpin> Stream for RwLockStream<'_, T> {
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<<Self as Stream>::Item>> {
-        self.0.write().unwrap().poll_next_unpin(cx)
+        self.0.write_unpoisoned().poll_next_unpin(cx)
     }
 }
 
@@ -40,45 +74,223 @@ impl<T: Stream + Unpin> Stream for RwLockStream<'_, T> {
 pub struct Session {
     /// The public keys of the participants
     pub(crate) pubkeys: Vec<Vec<u8>>,
-    /// The public key of the coordinator
-    pub(crate) coordinator_pubkey: Vec<u8>,
+    /// The public keys of the coordinators. The first one is the coordinator
+    /// which created the session.
+    pub(crate) coordinator_pubkeys: Vec<Vec<u8>>,
+    /// How many coordinators must call `close_session` for the session to be
+    /// closed.
+    pub(crate) close_quorum: usize,
+    /// The coordinators which called `close_session` so far.
+    pub(crate) close_approvals: HashSet<Vec<u8>>,
     /// The number of messages being simultaneously signed.
     pub(crate) message_count: u8,
-    /// The message queue.
-    pub(crate) queue: HashMap<Vec<u8>, VecDeque<Msg>>,
+    /// The ID of the ciphersuite used in the session, if specified by the
+    /// coordinator.
+    pub(crate) ciphersuite: Option<String>,
+    /// The message commitment, if specified by the coordinator.
+    pub(crate) message_commitment: Option<MessageCommitment>,
+    /// The message queue of each participant. Queues are created with the
+    /// session and only for its participants.
+    pub(crate) queue: HashMap<Vec<u8>, MsgQueue>,
+    /// The message queue of each coordinator, in the same order as
+    /// `coordinator_pubkeys`.
+    pub(crate) coordinator_queues: Vec<MsgQueue>,
+    /// Until when the session is kept open regardless of activity, if a
+    /// coordinator extended it.
+    pub(crate) extended_until: Option<Instant>,
+    /// Whether the session was removed from the server, i.e. closed or timed
+    /// out. Handlers which looked the session up before it was removed must
+    /// check this after locking it, since they no longer hold the map lock.
+    pub(crate) closed: bool,
+}
+
+impl Session {
+    /// Whether `pubkey` is one of the coordinators of the session.
+    pub(crate) fn is_coordinator(&self, pubkey: &[u8]) -> bool {
+        self.coordinator_pubkeys.iter().any(|p| p == pubkey)
+    }
+
+    /// The queue of the messages sent to `pubkey`: its coordinator queue if
+    /// `as_coordinator` is set, or its participant queue otherwise. Returns
+    /// `None` if it is not a coordinator or participant, respectively.
+    pub(crate) fn queue_mut(
+        &mut self,
+        pubkey: &[u8],
+        as_coordinator: bool,
+    ) -> Option<&mut MsgQueue> {
+        if as_coordinator {
+            let index = self.coordinator_pubkeys.iter().position(|p| p == pubkey)?;
+            Some(&mut self.coordinator_queues[index])
+        } else {
+            self.queue.get_mut(pubkey)
+        }
+    }
+}
+
+/// The messages queued for a recipient.
+///
+/// Each message sent to the recipient is given the next index, starting from
+/// zero, so that messages can be acknowledged with [`MsgQueue::ack()`] after
+/// being peeked at.
+#[derive(Debug, Default)]
+pub(crate) struct MsgQueue {
+    /// The index of the first message in `msgs`.
+    pub(crate) first_index: u64,
+    pub(crate) msgs: VecDeque<Msg>,
+}
+
+impl MsgQueue {
+    /// The number of queued messages.
+    pub(crate) fn len(&self) -> usize {
+        self.msgs.len()
+    }
+
+    /// The index that will be given to the next message queued.
+    pub(crate) fn next_index(&self) -> u64 {
+        self.first_index + self.msgs.len() as u64
+    }
+
+    /// Queue a message.
+    pub(crate) fn push(&mut self, msg: Msg) {
+        self.msgs.push_back(msg);
+    }
+
+    /// Drop the oldest queued message, if any.
+    pub(crate) fn pop(&mut self) {
+        if self.msgs.pop_front().is_some() {
+            self.first_index += 1;
+        }
+    }
+
+    /// Remove and return all queued messages.
+    pub(crate) fn drain(&mut self) -> Vec<Msg> {
+        self.first_index = self.next_index();
+        self.msgs.drain(..).collect()
+    }
+
+    /// Remove the messages with an index lower than `index`. Returns false
+    /// if `index` is past the messages queued so far.
+    pub(crate) fn ack(&mut self, index: u64) -> bool {
+        if index > self.next_index() {
+            return false;
+        }
+        while self.first_index < index {
+            self.pop();
+        }
+        true
+    }
+}
+
+/// The limit on the number of messages queued for each recipient in a
+/// session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueueLimit {
+    /// The maximum number of messages queued for each recipient.
+    pub max_len: usize,
+    /// What to do with messages sent to a recipient whose queue is full.
+    pub policy: QueueFullPolicy,
+}
+
+impl Default for QueueLimit {
+    fn default() -> Self {
+        Self {
+            max_len: DEFAULT_MAX_QUEUE_LEN,
+            policy: QueueFullPolicy::default(),
+        }
+    }
 }
 
 /// The global state of the server.
+///
+/// `challenges` and `access_tokens` must never be locked while holding any
+/// other lock. See [`SessionState`] for the lock order of sessions.
 #[derive(Debug)]
 pub struct AppState {
     pub(crate) sessions: SessionState,
     pub(crate) challenges: Arc<RwLock<HashSetDelay<Uuid>>>,
     pub(crate) access_tokens: Arc<RwLock<HashMapDelay<Uuid, Vec<u8>>>>,
+    /// The maximum number of participants allowed in a session.
+    pub(crate) max_participants: usize,
+    /// The limit on the number of messages queued for each recipient.
+    pub(crate) queue_limit: QueueLimit,
+    /// The token required to call the admin API; it is disabled if `None`.
+    pub(crate) admin_token: Option<String>,
 }
 
+/// The signing sessions of the server.
+///
+/// To avoid deadlocks, locks must always be acquired in this order:
+/// `sessions`, then `sessions_by_pubkey`, then the lock of an individual
+/// `Session`. Any of them can be skipped, but a lock must never be acquired
+/// while holding one that comes later in the order.
 #[derive(Debug, Default)]
 pub struct SessionState {
-    /// Mapping of signing sessions by UUID.
-    pub(crate) sessions: Arc<RwLock<HashMapDelay<Uuid, Session>>>,
+    /// Mapping of signing sessions by UUID. Each session is behind its own
+    /// lock so that handlers can modify it in place while only holding
+    /// the map lock for reading.
+    pub(crate) sessions: Arc<RwLock<HashMapDelay<Uuid, Arc<Mutex<Session>>>>>,
     pub(crate) sessions_by_pubkey: Arc<RwLock<HashMap<Vec<u8>, HashSet<Uuid>>>>,
+    /// How long a session stays open since it was last used.
+    pub(crate) timeout: Duration,
+    /// The longest a coordinator can keep a session open with
+    /// `extend_session`.
+    pub(crate) max_extension: Duration,
 }
 
 impl SessionState {
     /// Create a new SessionState
-    pub fn new(timeout: Duration) -> Self {
+    pub fn new(timeout: Duration, max_extension: Duration) -> Self {
         Self {
             sessions: RwLock::new(HashMapDelay::new(timeout)).into(),
             sessions_by_pubkey: Default::default(),
+            timeout,
+            max_extension,
         }
     }
+
+    /// Get the session with the given ID, if it exists.
+    pub(crate) fn get(&self, session_id: &Uuid) -> Option<Arc<Mutex<Session>>> {
+        self.sessions.read_unpoisoned().get(session_id).cloned()
+    }
+
+    /// Renew the timeout of the session with the given ID.
+    ///
+    /// This locks `sessions`, thus it must not be called while holding any
+    /// other session lock.
+    pub(crate) fn renew(&self, session_id: &Uuid) {
+        let mut sessions = self.sessions.write_unpoisoned();
+        // Activity must not cut short an explicit extension.
+        let Some(timeout) = sessions.get(session_id).map(|session| {
+            session
+                .lock_unpoisoned()
+                .extended_until
+                .map_or(self.timeout, |until| {
+                    until
+                        .saturating_duration_since(Instant::now())
+                        .max(self.timeout)
+                })
+        }) else {
+            return;
+        };
+        sessions.update_timeout(session_id, timeout);
+    }
 }
 
 impl AppState {
-    pub async fn new() -> Result<SharedState, Box<dyn std::error::Error>> {
+    pub async fn new(
+        max_participants: usize,
+        queue_limit: QueueLimit,
+        session_timeout: Duration,
+        max_session_extension: Duration,
+        admin_token: Option<String>,
+    ) -> Result<SharedState, Box<dyn std::error::Error>> {
         let state = Arc::new(Self {
-            sessions: SessionState::new(SESSION_TIMEOUT),
+            sessions: SessionState::new(session_timeout, max_session_extension),
             challenges: RwLock::new(HashSetDelay::new(CHALLENGE_TIMEOUT)).into(),
             access_tokens: RwLock::new(HashMapDelay::new(ACCESS_TOKEN_TIMEOUT)).into(),
+            max_participants,
+            queue_limit,
+            admin_token,
         });
 
         // In order to effectively removed timed out entries, we need to
@@ -92,9 +304,11 @@ impl AppState {
                     Some(Ok((uuid, session))) => {
                         tracing::debug!("session {} timed out", uuid);
                         let mut sessions_by_pubkey =
-                            state_clone.sessions.sessions_by_pubkey.write().unwrap();
-                        for pubkey in session.pubkeys {
-                            if let Some(sessions) = sessions_by_pubkey.get_mut(&pubkey) {
+                            state_clone.sessions.sessions_by_pubkey.write_unpoisoned();
+                        let mut session = session.lock_unpoisoned();
+                        session.closed = true;
+                        for pubkey in &session.pubkeys {
+                            if let Some(sessions) = sessions_by_pubkey.get_mut(pubkey) {
                                 sessions.remove(&uuid);
                             }
                         }
@@ -143,3 +357,107 @@ impl AppState {
 
 /// Type alias for the global state under a reference-counted pointer.
 pub type SharedState = Arc<AppState>;
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use axum_test::TestServer;
+    use rand::thread_rng;
+    use xeddsa::{xed25519, Sign as _};
+
+    use super::*;
+    use crate::{
+        args::{DEFAULT_MAX_PARTICIPANTS, DEFAULT_MAX_SESSION_EXTENSION, DEFAULT_SESSION_TIMEOUT},
+        router,
+        types::*,
+    };
+
+    /// Panic while holding the given lock, poisoning it, as a handler could.
+    fn poison<T>(lock: impl FnOnce() -> T) {
+        let r = catch_unwind(AssertUnwindSafe(|| {
+            let _guard = lock();
+            panic!("injected panic");
+        }));
+        assert!(r.is_err());
+    }
+
+    /// Test if the server keeps serving requests after a panic while one of
+    /// its locks was held.
+    #[tokio::test]
+    async fn test_poisoned_locks() {
+        let state = AppState::new(
+            DEFAULT_MAX_PARTICIPANTS,
+            QueueLimit::default(),
+            Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+            Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+            None,
+        )
+        .await
+        .unwrap();
+        let server = TestServer::new(router(state.clone())).unwrap();
+
+        poison(|| state.challenges.write().unwrap());
+        poison(|| state.access_tokens.write().unwrap());
+        poison(|| state.sessions.sessions.write().unwrap());
+        poison(|| state.sessions.sessions_by_pubkey.write().unwrap());
+        assert!(state.challenges.is_poisoned());
+
+        let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+        let keypair = builder.generate_keypair().unwrap();
+        let other_keypair = builder.generate_keypair().unwrap();
+
+        let res = server.post("/challenge").json(&ChallengeArgs {}).await;
+        res.assert_status_ok();
+        let challenge = res.json::<ChallengeOutput>().challenge;
+
+        let private =
+            xed25519::PrivateKey::from(&TryInto::<[u8; 32]>::try_into(keypair.private).unwrap());
+        let signature: [u8; 64] = private.sign(challenge.as_bytes(), &mut thread_rng());
+        let res = server
+            .post("/login")
+            .json(&KeyLoginArgs {
+                challenge,
+                pubkey: keypair.public.clone(),
+                signature: signature.to_vec(),
+            })
+            .await;
+        res.assert_status_ok();
+        let token = res.json::<KeyLoginOutput>().access_token;
+
+        let res = server
+            .post("/create_new_session")
+            .authorization_bearer(token)
+            .json(&CreateNewSessionArgs {
+                pubkeys: vec![
+                    PublicKey(keypair.public.clone()),
+                    PublicKey(other_keypair.public.clone()),
+                ],
+                message_count: 1,
+                coordinators: vec![],
+                close_quorum: None,
+                ciphersuite: None,
+                message_commitment: None,
+            })
+            .await;
+        res.assert_status_ok();
+        let session_id = res.json::<CreateNewSessionOutput>().session_id;
+
+        let session = state.sessions.get(&session_id).unwrap();
+        poison(|| session.lock().unwrap());
+
+        let res = server
+            .post("/get_session_info")
+            .authorization_bearer(token)
+            .json(&GetSessionInfoArgs { session_id })
+            .await;
+        res.assert_status_ok();
+
+        let res = server
+            .post("/list_sessions")
+            .authorization_bearer(token)
+            .await;
+        res.assert_status_ok();
+        assert_eq!(res.json::<ListSessionsOutput>().session_ids, [session_id]);
+    }
+}
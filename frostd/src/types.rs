@@ -3,6 +3,7 @@ use frost_core::{
 };
 use frost_rerandomized::Randomizer;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 pub use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -11,6 +12,40 @@ pub struct Error {
     pub msg: String,
 }
 
+impl Error {
+    /// A short hint on how to address the error, to show users along with the
+    /// message. Returns `None` for unknown codes, e.g. ones returned by a newer
+    /// server.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self.code {
+            crate::INVALID_ARGUMENT => Some(
+                "check the arguments; the client may also be incompatible with the server version",
+            ),
+            crate::UNAUTHORIZED => Some("log in again; your access token may have expired"),
+            crate::SESSION_NOT_FOUND => Some(
+                "check the session ID with `frost-client sessions`; the session may have been closed or timed out",
+            ),
+            crate::NOT_COORDINATOR => {
+                Some("only the coordinator can do this; check that you are using its key")
+            }
+            crate::QUEUE_FULL => {
+                Some("wait for the recipient to receive its pending messages and try again")
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)?;
+        if let Some(hint) = self.hint() {
+            write!(f, " ({})", hint)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RegisterArgs {
     pub username: String,
@@ -65,6 +100,30 @@ pub struct LoginArgs {
 pub struct CreateNewSessionArgs {
     pub pubkeys: Vec<PublicKey>,
     pub message_count: u8,
+    /// Other coordinators of the session, besides the one creating it. Any
+    /// coordinator can send messages to participants and to the other
+    /// coordinators, and approve closing the session (see `close_quorum`).
+    /// The messages sent by participants to the coordinator are only received
+    /// by the one creating the session, since participants encrypt them to
+    /// its key. The server does not check that the coordinators agree on the
+    /// signing package either, since it can't read the messages.
+    #[serde(default)]
+    pub coordinators: Vec<PublicKey>,
+    /// How many coordinators must call `close_session` for the session to be
+    /// closed. If `None`, all of them must.
+    #[serde(default)]
+    pub close_quorum: Option<usize>,
+    /// The ID of the ciphersuite used in the session (e.g.
+    /// `FROST-ED25519-SHA512-v1`), so that participants can check they use
+    /// the same one. It is not interpreted by the server.
+    #[serde(default)]
+    pub ciphersuite: Option<String>,
+    /// A commitment to the messages to be signed, created with
+    /// [`commit_to_messages()`], so that participants can check that the
+    /// coordinator did not pick them after seeing their commitments. It is
+    /// not interpreted by the server.
+    #[serde(default)]
+    pub message_commitment: Option<MessageCommitment>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -72,9 +131,21 @@ pub struct CreateNewSessionOutput {
     pub session_id: Uuid,
 }
 
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ListSessionsArgs {
+    /// The maximum number of session IDs to return; all of them if `None`.
+    pub limit: Option<usize>,
+    /// The number of session IDs to skip before the returned ones.
+    pub offset: Option<usize>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ListSessionsOutput {
+    /// The requested page of session IDs, sorted.
     pub session_ids: Vec<Uuid>,
+    /// The total number of sessions of the user, regardless of pagination.
+    #[serde(default)]
+    pub total: usize,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -86,7 +157,18 @@ pub struct GetSessionInfoArgs {
 pub struct GetSessionInfoOutput {
     pub message_count: u8,
     pub pubkeys: Vec<PublicKey>,
+    /// The public key of the coordinator which created the session.
     pub coordinator_pubkey: Vec<u8>,
+    /// The public keys of all coordinators, starting with
+    /// `coordinator_pubkey`.
+    #[serde(default)]
+    pub coordinator_pubkeys: Vec<PublicKey>,
+    /// The ciphersuite ID passed to `create_new_session`, if any.
+    #[serde(default)]
+    pub ciphersuite: Option<String>,
+    /// The message commitment passed to `create_new_session`, if any.
+    #[serde(default)]
+    pub message_commitment: Option<MessageCommitment>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -99,6 +181,43 @@ pub struct PublicKey(
     pub Vec<u8>,
 );
 
+/// A commitment to the messages signed in a session. See
+/// [`commit_to_messages()`].
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MessageCommitment(
+    #[serde(
+        serialize_with = "serdect::slice::serialize_hex_lower_or_bin",
+        deserialize_with = "serdect::slice::deserialize_hex_or_bin_vec"
+    )]
+    pub Vec<u8>,
+);
+
+impl std::fmt::Debug for MessageCommitment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("MessageCommitment")
+            .field(&hex::encode(&self.0))
+            .finish()
+    }
+}
+
+/// Commit to the messages to be signed in a session, which are revealed
+/// later in the signing package along with `salt`. The salt must be random
+/// and secret until then, so that the server can't guess low-entropy
+/// messages from the commitment.
+pub fn commit_to_messages(salt: &[u8], messages: &[&[u8]]) -> MessageCommitment {
+    let mut hasher = Sha256::new();
+    hasher.update(b"frostd message commitment");
+    hasher.update((salt.len() as u64).to_be_bytes());
+    hasher.update(salt);
+    hasher.update((messages.len() as u64).to_be_bytes());
+    for message in messages {
+        hasher.update((message.len() as u64).to_be_bytes());
+        hasher.update(message);
+    }
+    MessageCommitment(hasher.finalize().to_vec())
+}
+
 impl std::fmt::Debug for PublicKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_tuple("PublicKey")
@@ -118,25 +237,88 @@ pub struct SendArgs {
     pub msg: Vec<u8>,
 }
 
+/// The version of the [`Msg`] wire format. Messages with any other version
+/// are rejected when deserializing.
+pub const MSG_VERSION: u8 = 0;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "VersionedMsg", into = "VersionedMsg")]
 pub struct Msg {
     pub sender: Vec<u8>,
+    pub msg: Vec<u8>,
+}
+
+/// The wire format of [`Msg`], which adds its version. It defaults to the
+/// current one, since messages were sent without it before it was added.
+#[derive(Serialize, Deserialize)]
+struct VersionedMsg {
+    #[serde(default)]
+    version: u8,
+    sender: Vec<u8>,
     #[serde(
         serialize_with = "serdect::slice::serialize_hex_lower_or_bin",
         deserialize_with = "serdect::slice::deserialize_hex_or_bin_vec"
     )]
-    pub msg: Vec<u8>,
+    msg: Vec<u8>,
+}
+
+impl TryFrom<VersionedMsg> for Msg {
+    type Error = String;
+
+    fn try_from(msg: VersionedMsg) -> Result<Self, Self::Error> {
+        if msg.version != MSG_VERSION {
+            return Err(format!(
+                "unsupported message version {} (expected {}); the server may be newer \
+                 than this client",
+                msg.version, MSG_VERSION
+            ));
+        }
+        Ok(Msg {
+            sender: msg.sender,
+            msg: msg.msg,
+        })
+    }
+}
+
+impl From<Msg> for VersionedMsg {
+    fn from(msg: Msg) -> Self {
+        VersionedMsg {
+            version: MSG_VERSION,
+            sender: msg.sender,
+            msg: msg.msg,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReceiveArgs {
     pub session_id: Uuid,
     pub as_coordinator: bool,
+    /// Return the queued messages without removing them from the queue. They
+    /// are then returned again by later calls, until they are removed with
+    /// the `ack` API.
+    #[serde(default)]
+    pub peek: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ReceiveOutput {
     pub msgs: Vec<Msg>,
+    /// The index of the first message in `msgs`. Messages sent to each
+    /// recipient of a session are indexed in order, starting from zero.
+    #[serde(default)]
+    pub first_index: u64,
+}
+
+/// Removes the messages received with `peek` from the queue.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AckArgs {
+    pub session_id: Uuid,
+    pub as_coordinator: bool,
+    /// Messages with an index lower than this are removed, i.e. this is the
+    /// `first_index` of a `receive` output plus the number of its messages
+    /// that were processed.
+    pub index: u64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -144,6 +326,99 @@ pub struct CloseSessionArgs {
     pub session_id: Uuid,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CloseSessionOutput {
+    /// Whether the session was closed. If `false`, the call was only recorded
+    /// as an approval, and more coordinators must call `close_session` before
+    /// the session is closed.
+    pub closed: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExtendSessionArgs {
+    pub session_id: Uuid,
+    /// How many seconds to keep the session open from now, regardless of
+    /// activity. It is limited by the server configuration.
+    pub seconds: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtendSessionOutput {
+    /// When the session will time out if it is not used, in seconds since
+    /// the Unix epoch.
+    pub expires_at: u64,
+}
+
+/// A single request in a batch: the name of the API method to call, and its
+/// arguments.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "method", content = "args", rename_all = "snake_case")]
+pub enum BatchRequest {
+    CreateNewSession(CreateNewSessionArgs),
+    ListSessions(ListSessionsArgs),
+    GetSessionInfo(GetSessionInfoArgs),
+    Send(SendArgs),
+    Receive(ReceiveArgs),
+    Ack(AckArgs),
+    CloseSession(CloseSessionArgs),
+    ExtendSession(ExtendSessionArgs),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchArgs {
+    pub requests: Vec<BatchRequest>,
+}
+
+/// The result of a single request in a batch. Each request succeeds or fails
+/// independently of the others.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchResult {
+    /// The output of the method, as it would be returned by its own endpoint.
+    Ok(serde_json::Value),
+    Err(Error),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchOutput {
+    /// The results, in the same order as the requests.
+    pub results: Vec<BatchResult>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AdminQueuesArgs {}
+
+/// The number of messages queued for a participant (or a coordinator) of a
+/// session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ParticipantQueue {
+    pub pubkey: PublicKey,
+    pub count: usize,
+}
+
+/// The number of messages queued for each recipient of a session. The
+/// contents of the messages are not exposed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionQueues {
+    pub session_id: Uuid,
+    /// The number of messages queued for the coordinator which created the
+    /// session.
+    pub coordinator: usize,
+    /// The number of messages queued for each of the other coordinators, if
+    /// any.
+    #[serde(default)]
+    pub coordinators: Vec<ParticipantQueue>,
+    /// The number of messages queued for each participant, in the same order
+    /// as the session `pubkeys`.
+    pub participants: Vec<ParticipantQueue>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AdminQueuesOutput {
+    /// The message queues of every session in the server, sorted by ID.
+    pub sessions: Vec<SessionQueues>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(bound = "C: Ciphersuite")]
 pub struct SendCommitmentsArgs<C: Ciphersuite> {
@@ -156,13 +431,41 @@ pub struct SendCommitmentsArgs<C: Ciphersuite> {
 #[serde(bound = "C: Ciphersuite")]
 pub struct SendSigningPackageArgs<C: Ciphersuite> {
     pub signing_package: Vec<SigningPackage<C>>,
+    /// Auxiliary data for the participants, e.g. the transaction being
+    /// signed, allowing them to check what the signed messages correspond to.
+    /// It is not part of the messages being signed, but if set, the
+    /// randomizers are derived from it and `seed`, so the signatures commit
+    /// to it.
     #[serde(
         serialize_with = "serdect::slice::serialize_hex_lower_or_bin",
         deserialize_with = "serdect::slice::deserialize_hex_or_bin_vec"
     )]
     pub aux_msg: Vec<u8>,
+    /// The randomizers to sign each signing package with, if rerandomized.
+    /// Empty if `aux_msg` is set, since they are then derived from `seed`.
     #[derivative(Debug = "ignore")]
     pub randomizer: Vec<Randomizer<C>>,
+    /// The random seed to derive the randomizers from if `aux_msg` is set;
+    /// empty otherwise. The randomizer of each signing package is the scalar
+    /// returned by `C::hash_randomizer(seed || signing_package || aux_msg)`,
+    /// where `signing_package` is its serialization. The coordinator and the
+    /// participants each derive it, so the signatures only verify if all of
+    /// them used the same auxiliary message.
+    #[serde(
+        default,
+        serialize_with = "serdect::slice::serialize_hex_lower_or_bin",
+        deserialize_with = "serdect::slice::deserialize_hex_or_bin_vec"
+    )]
+    #[derivative(Debug = "ignore")]
+    pub seed: Vec<u8>,
+    /// The salt of the message commitment passed to `create_new_session`, if
+    /// any, revealed so that participants can check the commitment.
+    #[serde(
+        default,
+        serialize_with = "serdect::slice::serialize_hex_lower_or_bin",
+        deserialize_with = "serdect::slice::deserialize_hex_or_bin_vec"
+    )]
+    pub message_commitment_salt: Vec<u8>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -171,3 +474,83 @@ pub struct SendSignatureSharesArgs<C: Ciphersuite> {
     pub identifier: Identifier<C>,
     pub signature_share: Vec<SignatureShare<C>>,
 }
+
+/// Sent by a participant to the coordinator, instead of its commitments or
+/// signature shares, to report that it could not proceed (e.g. because the
+/// signing package was invalid). Its `participant_error` field makes it
+/// distinguishable from the other messages.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParticipantErrorArgs {
+    /// The reason reported by the participant.
+    pub participant_error: String,
+}
+
+/// Sent by a participant to the coordinator, instead of its signature shares,
+/// to report that it declined to sign (e.g. because the user did not confirm
+/// the message). Its `participant_declined` field makes it distinguishable
+/// from the other messages.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParticipantDeclinedArgs {
+    /// The reason given by the participant.
+    pub participant_declined: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppError;
+
+    #[test]
+    fn check_error_hints() {
+        for error in [
+            AppError::InvalidArgument("session_id".to_string()),
+            AppError::Unauthorized,
+            AppError::SessionNotFound,
+            AppError::NotCoordinator,
+            AppError::QueueFull,
+        ] {
+            let error = Error::from(error);
+            let hint = error.hint().expect("all server errors have hints");
+            assert!(!hint.is_empty());
+            assert_eq!(error.to_string(), format!("{} ({})", error.msg, hint));
+        }
+
+        // Unknown codes are displayed as is.
+        let error = Error {
+            code: 0,
+            msg: "unknown error".to_string(),
+        };
+        assert_eq!(error.hint(), None);
+        assert_eq!(error.to_string(), "unknown error");
+    }
+
+    #[test]
+    fn check_msg_version() {
+        let msg = Msg {
+            sender: vec![1, 2],
+            msg: vec![3, 4],
+        };
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"version": 0, "sender": [1, 2], "msg": "0304"})
+        );
+        let decoded: Msg = serde_json::from_value(json).unwrap();
+        assert_eq!((decoded.sender, decoded.msg), (msg.sender, msg.msg));
+
+        // Messages sent before the version was added are still accepted.
+        let decoded: Msg =
+            serde_json::from_value(serde_json::json!({"sender": [1, 2], "msg": "0304"})).unwrap();
+        assert_eq!(decoded.msg, vec![3, 4]);
+
+        let err = serde_json::from_value::<Msg>(
+            serde_json::json!({"version": 1, "sender": [1, 2], "msg": "0304"}),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("unsupported message version 1 (expected 0)"));
+    }
+}
@@ -7,10 +7,13 @@ use axum_extra::{
 };
 use uuid::Uuid;
 
-use crate::{state::SharedState, AppError};
+use crate::{
+    state::{RwLockExt as _, SharedState},
+    AppError,
+};
 
 /// An User
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[allow(dead_code)]
 pub(crate) struct User {
     pub(crate) pubkey: Vec<u8>,
@@ -42,8 +45,7 @@ impl FromRequestParts<SharedState> for User {
 
         let pubkey = state
             .access_tokens
-            .read()
-            .unwrap()
+            .read_unpoisoned()
             .get(&access_token)
             .cloned();
 
@@ -57,3 +59,40 @@ impl FromRequestParts<SharedState> for User {
         }
     }
 }
+
+/// An administrator of the server, i.e. a client which provided the admin
+/// token configured in the server. This is distinct from user
+/// authentication; an Admin argument in an axum handler makes it only
+/// callable with the admin token.
+#[derive(Clone, Debug)]
+pub(crate) struct Admin;
+
+#[async_trait]
+impl FromRequestParts<SharedState> for Admin {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &SharedState,
+    ) -> Result<Self, Self::Rejection> {
+        let TypedHeader(Authorization(bearer)) = parts
+            .extract::<TypedHeader<Authorization<Bearer>>>()
+            .await
+            .map_err(|_| AppError::Unauthorized)?;
+
+        match &state.admin_token {
+            Some(admin_token) if constant_time_eq(bearer.token(), admin_token) => Ok(Admin),
+            _ => Err(AppError::Unauthorized),
+        }
+    }
+}
+
+/// Compare two strings without leaking, through timing, how much of them
+/// matches.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
@@ -1,10 +1,21 @@
 use core::str;
-use std::{collections::BTreeMap, error::Error, time::Duration};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use axum_test::TestServer;
 use coordinator::comms::http::SessionState;
 use frostd::{
-    args::Args, router, AppState, SendCommitmentsArgs, SendSignatureSharesArgs,
+    args::{
+        Args, QueueFullPolicy, TlsVersion, DEFAULT_MAX_PARTICIPANTS, DEFAULT_MAX_QUEUE_LEN,
+        DEFAULT_MAX_SESSION_EXTENSION, DEFAULT_SESSION_TIMEOUT,
+    },
+    router, AppState, QueueLimit, SendCommitmentsArgs, SendSignatureSharesArgs,
     SendSigningPackageArgs,
 };
 use rand::thread_rng;
@@ -55,7 +66,14 @@ async fn test_main_router<
         .collect();
 
     // Instantiate test server using axum_test
-    let shared_state = AppState::new().await?;
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
     let router = router(shared_state);
     let server = TestServer::new(router)?;
 
@@ -125,6 +143,10 @@ async fn test_main_router<
                 frostd::PublicKey(bob_keypair.public.clone()),
             ],
             message_count: 2,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
         })
         .await;
     res.assert_status_ok();
@@ -183,7 +205,7 @@ async fn test_main_router<
     }
 
     // As the coordinator, get the commitments
-    let mut coordinator_state = SessionState::<C>::new(2, 2);
+    let mut coordinator_state = SessionState::<C>::new(2, 2, false);
     loop {
         let res = server
             .post("/receive")
@@ -191,6 +213,7 @@ async fn test_main_router<
             .json(&frostd::ReceiveArgs {
                 session_id,
                 as_coordinator: true,
+                peek: false,
             })
             .await;
         res.assert_status_ok();
@@ -235,6 +258,8 @@ async fn test_main_router<
         } else {
             Vec::new()
         },
+        seed: Vec::new(),
+        message_commitment_salt: Vec::new(),
     };
     let res = server
         .post("/send")
@@ -260,6 +285,7 @@ async fn test_main_router<
                 .json(&frostd::ReceiveArgs {
                     session_id,
                     as_coordinator: false,
+                    peek: false,
                 })
                 .await
                 .json::<frostd::ReceiveOutput>();
@@ -269,8 +295,12 @@ async fn test_main_router<
                 break serde_json::from_slice(&r.msgs[0].msg)?;
             }
         };
+        assert_eq!(r.aux_msg, aux_msg);
 
         // Generate SignatureShares for each SigningPackage
+        if rerandomized {
+            assert_eq!(r.signing_package.len(), r.randomizer.len());
+        }
         let signature_shares = if rerandomized {
             r.signing_package
                 .iter()
@@ -321,6 +351,7 @@ async fn test_main_router<
             .json(&frostd::ReceiveArgs {
                 session_id,
                 as_coordinator: true,
+                peek: false,
             })
             .await
             .json::<frostd::ReceiveOutput>();
@@ -336,26 +367,12 @@ async fn test_main_router<
     let signature_shares = coordinator_state.signature_shares()?;
 
     // Generate the final Signature for each message
-    let signatures = if rerandomized {
-        signing_packages
-            .iter()
-            .enumerate()
-            .map(|(i, p)| {
-                frost_rerandomized::aggregate(
-                    p,
-                    &signature_shares[i],
-                    &pubkeys,
-                    &randomized_params[i],
-                )
-            })
-            .collect::<Result<Vec<_>, _>>()?
-    } else {
-        signing_packages
-            .iter()
-            .enumerate()
-            .map(|(i, p)| frost::aggregate(p, &signature_shares[i], &pubkeys))
-            .collect::<Result<Vec<_>, _>>()?
-    };
+    let signatures = coordinator::step_3::aggregate_shares(
+        &signing_packages,
+        &signature_shares,
+        &pubkeys,
+        rerandomized.then_some(randomized_params.as_slice()),
+    )?;
 
     // Close the session
     let res = server
@@ -380,6 +397,3197 @@ async fn test_main_router<
     Ok(())
 }
 
+/// Log in to the test server with the given keypair, returning the access
+/// token.
+async fn login(server: &TestServer, keypair: &snow::Keypair) -> Uuid {
+    let mut rng = thread_rng();
+    let res = server
+        .post("/challenge")
+        .json(&frostd::ChallengeArgs {})
+        .await;
+    res.assert_status_ok();
+    let challenge = res.json::<frostd::ChallengeOutput>().challenge;
+
+    let private = xed25519::PrivateKey::from(
+        &TryInto::<[u8; 32]>::try_into(keypair.private.clone()).unwrap(),
+    );
+    let signature: [u8; 64] = private.sign(challenge.as_bytes(), &mut rng);
+    let res = server
+        .post("/login")
+        .json(&frostd::KeyLoginArgs {
+            challenge,
+            pubkey: keypair.public.clone(),
+            signature: signature.to_vec(),
+        })
+        .await;
+    res.assert_status_ok();
+    res.json::<frostd::KeyLoginOutput>().access_token
+}
+
+/// Test if sessions with too few or too many participants are rejected.
+#[tokio::test]
+async fn test_max_participants() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        3,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let keypair = builder.generate_keypair().unwrap();
+    let token = login(&server, &keypair).await;
+
+    let pubkeys = (0..4)
+        .map(|_| frostd::PublicKey(builder.generate_keypair().unwrap().public))
+        .collect::<Vec<_>>();
+
+    for (n, ok) in [(1, false), (2, true), (3, true), (4, false)] {
+        let res = server
+            .post("/create_new_session")
+            .authorization_bearer(token)
+            .json(&frostd::CreateNewSessionArgs {
+                pubkeys: pubkeys[..n].to_vec(),
+                message_count: 1,
+                coordinators: vec![],
+                close_quorum: None,
+                ciphersuite: None,
+                message_commitment: None,
+            })
+            .await;
+        if ok {
+            res.assert_status_ok();
+        } else {
+            res.assert_status_failure();
+            let r: frostd::Error = res.json();
+            assert_eq!(r.code, frostd::INVALID_ARGUMENT);
+        }
+    }
+
+    Ok(())
+}
+
+/// Test if no messages are lost when sends and receives to the same session
+/// are interleaved.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_send_receive() -> Result<(), Box<dyn std::error::Error>> {
+    const MSGS_PER_SENDER: usize = 50;
+
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = Arc::new(TestServer::new(router)?);
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let keypairs = (0..3)
+        .map(|_| builder.generate_keypair().unwrap())
+        .collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    for keypair in &keypairs {
+        tokens.push(login(&server, keypair).await);
+    }
+
+    // The first user is the coordinator.
+    let res = server
+        .post("/create_new_session")
+        .authorization_bearer(tokens[0])
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: keypairs
+                .iter()
+                .map(|k| frostd::PublicKey(k.public.clone()))
+                .collect(),
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .await;
+    res.assert_status_ok();
+    let session_id = res.json::<frostd::CreateNewSessionOutput>().session_id;
+
+    // Each participant sends its messages to the coordinator, while the
+    // coordinator receives them concurrently.
+    let mut senders = Vec::new();
+    for (sender, token) in tokens.iter().enumerate().skip(1) {
+        let server = server.clone();
+        let token = *token;
+        senders.push(tokio::spawn(async move {
+            for i in 0..MSGS_PER_SENDER {
+                let res = server
+                    .post("/send")
+                    .authorization_bearer(token)
+                    .json(&frostd::SendArgs {
+                        session_id,
+                        recipients: vec![],
+                        msg: vec![sender as u8, i as u8],
+                    })
+                    .await;
+                res.assert_status_ok();
+            }
+        }));
+    }
+
+    let expected = (tokens.len() - 1) * MSGS_PER_SENDER;
+    let mut received = Vec::new();
+    for _ in 0..1000 {
+        let res = server
+            .post("/receive")
+            .authorization_bearer(tokens[0])
+            .json(&frostd::ReceiveArgs {
+                session_id,
+                as_coordinator: true,
+                peek: false,
+            })
+            .await;
+        res.assert_status_ok();
+        received.extend(res.json::<frostd::ReceiveOutput>().msgs);
+        if received.len() >= expected {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    for sender in senders {
+        sender.await?;
+    }
+
+    // Messages from each sender must arrive complete and in order.
+    assert_eq!(received.len(), expected);
+    for (sender, keypair) in keypairs.iter().enumerate().skip(1) {
+        let msgs = received
+            .iter()
+            .filter(|m| m.sender == keypair.public)
+            .map(|m| m.msg.clone())
+            .collect::<Vec<_>>();
+        let expected_msgs = (0..MSGS_PER_SENDER)
+            .map(|i| vec![sender as u8, i as u8])
+            .collect::<Vec<_>>();
+        assert_eq!(msgs, expected_msgs);
+    }
+
+    Ok(())
+}
+
+/// Stress test creating and closing sessions concurrently, to catch deadlocks
+/// and lost updates.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_create_close_sessions() -> Result<(), Box<dyn std::error::Error>> {
+    const TASKS: usize = 32;
+
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = Arc::new(TestServer::new(router)?);
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let keypairs = (0..4)
+        .map(|_| builder.generate_keypair().unwrap())
+        .collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    for keypair in &keypairs {
+        tokens.push(login(&server, keypair).await);
+    }
+    let pubkeys = keypairs
+        .iter()
+        .map(|k| frostd::PublicKey(k.public.clone()))
+        .collect::<Vec<_>>();
+
+    // Each task creates a session as one of the users; tasks with an even
+    // index close their session right away, and all of them also query
+    // their session, in order to interleave every kind of lock acquisition.
+    let mut tasks = Vec::new();
+    for i in 0..TASKS {
+        let server = server.clone();
+        let token = tokens[i % tokens.len()];
+        let pubkeys = pubkeys.clone();
+        tasks.push(tokio::spawn(async move {
+            let res = server
+                .post("/create_new_session")
+                .authorization_bearer(token)
+                .json(&frostd::CreateNewSessionArgs {
+                    pubkeys,
+                    message_count: 1,
+                    coordinators: vec![],
+                    close_quorum: None,
+                    ciphersuite: None,
+                    message_commitment: None,
+                })
+                .await;
+            res.assert_status_ok();
+            let session_id = res.json::<frostd::CreateNewSessionOutput>().session_id;
+
+            let res = server
+                .post("/get_session_info")
+                .authorization_bearer(token)
+                .json(&frostd::GetSessionInfoArgs { session_id })
+                .await;
+            res.assert_status_ok();
+
+            let closed = i % 2 == 0;
+            if closed {
+                let res = server
+                    .post("/close_session")
+                    .authorization_bearer(token)
+                    .json(&frostd::CloseSessionArgs { session_id })
+                    .await;
+                res.assert_status_ok();
+            }
+            (session_id, closed)
+        }));
+    }
+
+    let mut open_sessions = HashSet::new();
+    for task in tasks {
+        let (session_id, closed) = tokio::time::timeout(Duration::from_secs(30), task)
+            .await
+            .expect("possible deadlock: task did not finish in time")?;
+        if !closed {
+            open_sessions.insert(session_id);
+        }
+    }
+
+    // Every user must see exactly the sessions which were not closed.
+    for token in &tokens {
+        let res = server
+            .post("/list_sessions")
+            .authorization_bearer(*token)
+            .await;
+        res.assert_status_ok();
+        let session_ids = res.json::<frostd::ListSessionsOutput>().session_ids;
+        assert_eq!(
+            session_ids.into_iter().collect::<HashSet<_>>(),
+            open_sessions
+        );
+    }
+
+    Ok(())
+}
+
+/// Test if list_sessions pages through the sessions of a user.
+#[tokio::test]
+async fn test_list_sessions_pagination() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let keypair = builder.generate_keypair().unwrap();
+    let token = login(&server, &keypair).await;
+    let pubkeys = vec![
+        frostd::PublicKey(keypair.public.clone()),
+        frostd::PublicKey(builder.generate_keypair().unwrap().public),
+    ];
+
+    let mut session_ids = Vec::new();
+    for _ in 0..5 {
+        let res = server
+            .post("/create_new_session")
+            .authorization_bearer(token)
+            .json(&frostd::CreateNewSessionArgs {
+                pubkeys: pubkeys.clone(),
+                message_count: 1,
+                coordinators: vec![],
+                close_quorum: None,
+                ciphersuite: None,
+                message_commitment: None,
+            })
+            .await;
+        res.assert_status_ok();
+        session_ids.push(res.json::<frostd::CreateNewSessionOutput>().session_id);
+    }
+    session_ids.sort();
+
+    // Without arguments, all sessions are returned.
+    let res = server
+        .post("/list_sessions")
+        .authorization_bearer(token)
+        .await;
+    res.assert_status_ok();
+    let r = res.json::<frostd::ListSessionsOutput>();
+    assert_eq!(r.session_ids, session_ids);
+    assert_eq!(r.total, 5);
+
+    for (limit, offset, expected) in [
+        (Some(2), None, &session_ids[..2]),
+        (Some(2), Some(2), &session_ids[2..4]),
+        (Some(2), Some(4), &session_ids[4..]),
+        (None, Some(3), &session_ids[3..]),
+        (Some(2), Some(10), &session_ids[5..]),
+    ] {
+        let res = server
+            .post("/list_sessions")
+            .authorization_bearer(token)
+            .json(&frostd::ListSessionsArgs { limit, offset })
+            .await;
+        res.assert_status_ok();
+        let r = res.json::<frostd::ListSessionsOutput>();
+        assert_eq!(r.session_ids, expected);
+        assert_eq!(r.total, 5);
+    }
+
+    Ok(())
+}
+
+/// Test if only the coordinator can send messages to participants.
+#[tokio::test]
+async fn test_send_to_participant_as_participant() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let alice_keypair = builder.generate_keypair().unwrap();
+    let bob_keypair = builder.generate_keypair().unwrap();
+    let carol_keypair = builder.generate_keypair().unwrap();
+    let alice_token = login(&server, &alice_keypair).await;
+    let bob_token = login(&server, &bob_keypair).await;
+
+    // Alice is the coordinator
+    let res = server
+        .post("/create_new_session")
+        .authorization_bearer(alice_token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: vec![
+                frostd::PublicKey(bob_keypair.public.clone()),
+                frostd::PublicKey(carol_keypair.public.clone()),
+            ],
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .await;
+    res.assert_status_ok();
+    let session_id = res.json::<frostd::CreateNewSessionOutput>().session_id;
+
+    // Bob can't send messages to Carol
+    let res = server
+        .post("/send")
+        .authorization_bearer(bob_token)
+        .json(&frostd::SendArgs {
+            session_id,
+            recipients: vec![frostd::PublicKey(carol_keypair.public.clone())],
+            msg: b"forged signing package".to_vec(),
+        })
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::NOT_COORDINATOR);
+
+    // ... but can send messages to the coordinator
+    let res = server
+        .post("/send")
+        .authorization_bearer(bob_token)
+        .json(&frostd::SendArgs {
+            session_id,
+            recipients: vec![],
+            msg: b"commitments".to_vec(),
+        })
+        .await;
+    res.assert_status_ok();
+
+    // ... and the coordinator can send messages to Carol
+    let res = server
+        .post("/send")
+        .authorization_bearer(alice_token)
+        .json(&frostd::SendArgs {
+            session_id,
+            recipients: vec![frostd::PublicKey(carol_keypair.public.clone())],
+            msg: b"signing package".to_vec(),
+        })
+        .await;
+    res.assert_status_ok();
+
+    Ok(())
+}
+
+/// Test if messages sent to a recipient whose queue is full are rejected, or
+/// replace the oldest queued message, depending on the configured policy.
+#[tokio::test]
+async fn test_queue_limit() -> Result<(), Box<dyn std::error::Error>> {
+    for policy in [QueueFullPolicy::Reject, QueueFullPolicy::DropOldest] {
+        let shared_state = AppState::new(
+            DEFAULT_MAX_PARTICIPANTS,
+            QueueLimit { max_len: 2, policy },
+            Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+            Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+            None,
+        )
+        .await?;
+        let router = router(shared_state);
+        let server = TestServer::new(router)?;
+
+        let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+        let alice_keypair = builder.generate_keypair().unwrap();
+        let bob_keypair = builder.generate_keypair().unwrap();
+        let carol_keypair = builder.generate_keypair().unwrap();
+        let alice_token = login(&server, &alice_keypair).await;
+        let bob_token = login(&server, &bob_keypair).await;
+
+        let res = server
+            .post("/create_new_session")
+            .authorization_bearer(alice_token)
+            .json(&frostd::CreateNewSessionArgs {
+                pubkeys: vec![
+                    frostd::PublicKey(bob_keypair.public.clone()),
+                    frostd::PublicKey(carol_keypair.public.clone()),
+                ],
+                message_count: 1,
+                coordinators: vec![],
+                close_quorum: None,
+                ciphersuite: None,
+                message_commitment: None,
+            })
+            .await;
+        res.assert_status_ok();
+        let session_id = res.json::<frostd::CreateNewSessionOutput>().session_id;
+
+        // Bob floods the coordinator with one message more than the limit
+        for i in 0..3u8 {
+            let res = server
+                .post("/send")
+                .authorization_bearer(bob_token)
+                .json(&frostd::SendArgs {
+                    session_id,
+                    recipients: vec![],
+                    msg: vec![i],
+                })
+                .await;
+            if i == 2 && policy == QueueFullPolicy::Reject {
+                res.assert_status_failure();
+                assert_eq!(res.json::<frostd::Error>().code, frostd::QUEUE_FULL);
+            } else {
+                res.assert_status_ok();
+            }
+        }
+
+        let res = server
+            .post("/receive")
+            .authorization_bearer(alice_token)
+            .json(&frostd::ReceiveArgs {
+                session_id,
+                as_coordinator: true,
+                peek: false,
+            })
+            .await;
+        res.assert_status_ok();
+        let msgs = res
+            .json::<frostd::ReceiveOutput>()
+            .msgs
+            .into_iter()
+            .map(|m| m.msg)
+            .collect::<Vec<_>>();
+        match policy {
+            QueueFullPolicy::Reject => assert_eq!(msgs, vec![vec![0], vec![1]]),
+            QueueFullPolicy::DropOldest => assert_eq!(msgs, vec![vec![1], vec![2]]),
+        }
+    }
+
+    Ok(())
+}
+
+/// Test if messages received with `peek` stay queued until acknowledged, so
+/// that a client which crashes before processing them can receive them again.
+#[tokio::test]
+async fn test_peek_and_ack() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let alice_keypair = builder.generate_keypair().unwrap();
+    let bob_keypair = builder.generate_keypair().unwrap();
+    let carol_keypair = builder.generate_keypair().unwrap();
+    let alice_token = login(&server, &alice_keypair).await;
+    let bob_token = login(&server, &bob_keypair).await;
+
+    let res = server
+        .post("/create_new_session")
+        .authorization_bearer(alice_token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: vec![
+                frostd::PublicKey(bob_keypair.public.clone()),
+                frostd::PublicKey(carol_keypair.public.clone()),
+            ],
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .await;
+    res.assert_status_ok();
+    let session_id = res.json::<frostd::CreateNewSessionOutput>().session_id;
+
+    let send = |i: u8| {
+        server
+            .post("/send")
+            .authorization_bearer(bob_token)
+            .json(&frostd::SendArgs {
+                session_id,
+                recipients: vec![],
+                msg: vec![i],
+            })
+    };
+    let receive = |peek: bool| {
+        server
+            .post("/receive")
+            .authorization_bearer(alice_token)
+            .json(&frostd::ReceiveArgs {
+                session_id,
+                as_coordinator: true,
+                peek,
+            })
+    };
+    let ack = |index: u64| {
+        server
+            .post("/ack")
+            .authorization_bearer(alice_token)
+            .json(&frostd::AckArgs {
+                session_id,
+                as_coordinator: true,
+                index,
+            })
+    };
+    let msgs = |r: frostd::ReceiveOutput| {
+        (
+            r.first_index,
+            r.msgs.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        )
+    };
+
+    send(0).await.assert_status_ok();
+    send(1).await.assert_status_ok();
+
+    // The coordinator peeks at the messages, then "crashes" before
+    // processing them, so it peeks again and gets the same messages.
+    for _ in 0..2 {
+        let res = receive(true).await;
+        res.assert_status_ok();
+        assert_eq!(msgs(res.json()), (0, vec![vec![0], vec![1]]));
+    }
+
+    // It processes the first message only, and acknowledges it.
+    ack(1).await.assert_status_ok();
+    send(2).await.assert_status_ok();
+    let res = receive(true).await;
+    res.assert_status_ok();
+    assert_eq!(msgs(res.json()), (1, vec![vec![1], vec![2]]));
+
+    // Messages that were not queued yet can't be acknowledged.
+    let res = ack(4).await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::INVALID_ARGUMENT);
+
+    ack(3).await.assert_status_ok();
+    let res = receive(true).await;
+    res.assert_status_ok();
+    assert_eq!(msgs(res.json()), (3, vec![]));
+
+    // Receiving without peeking keeps indexing messages in order.
+    send(3).await.assert_status_ok();
+    let res = receive(false).await;
+    res.assert_status_ok();
+    assert_eq!(msgs(res.json()), (3, vec![vec![3]]));
+    let res = receive(true).await;
+    res.assert_status_ok();
+    assert_eq!(msgs(res.json()), (4, vec![]));
+
+    Ok(())
+}
+
+/// Test if duplicated recipients get a message only once, and that the
+/// coordinator can't send a message to itself unless it is a participant.
+#[tokio::test]
+async fn test_send_duplicated_recipients() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let alice_keypair = builder.generate_keypair().unwrap();
+    let bob_keypair = builder.generate_keypair().unwrap();
+    let carol_keypair = builder.generate_keypair().unwrap();
+    let alice_token = login(&server, &alice_keypair).await;
+    let bob_token = login(&server, &bob_keypair).await;
+
+    let res = server
+        .post("/create_new_session")
+        .authorization_bearer(alice_token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: vec![
+                frostd::PublicKey(bob_keypair.public.clone()),
+                frostd::PublicKey(carol_keypair.public.clone()),
+            ],
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .await;
+    res.assert_status_ok();
+    let session_id = res.json::<frostd::CreateNewSessionOutput>().session_id;
+
+    let bob = frostd::PublicKey(bob_keypair.public.clone());
+    let res = server
+        .post("/send")
+        .authorization_bearer(alice_token)
+        .json(&frostd::SendArgs {
+            session_id,
+            recipients: vec![bob.clone(), bob.clone()],
+            msg: vec![0],
+        })
+        .await;
+    res.assert_status_ok();
+
+    let res = server
+        .post("/receive")
+        .authorization_bearer(bob_token)
+        .json(&frostd::ReceiveArgs {
+            session_id,
+            as_coordinator: false,
+            peek: false,
+        })
+        .await;
+    res.assert_status_ok();
+    let r = res.json::<frostd::ReceiveOutput>();
+    assert_eq!(r.msgs.len(), 1);
+    assert_eq!(r.msgs[0].msg, vec![0]);
+
+    // Alice is not a participant, so she can't send a message to herself.
+    let res = server
+        .post("/send")
+        .authorization_bearer(alice_token)
+        .json(&frostd::SendArgs {
+            session_id,
+            recipients: vec![bob, frostd::PublicKey(alice_keypair.public.clone())],
+            msg: vec![1],
+        })
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::INVALID_ARGUMENT);
+
+    // Nothing was queued for Bob either.
+    let res = server
+        .post("/receive")
+        .authorization_bearer(bob_token)
+        .json(&frostd::ReceiveArgs {
+            session_id,
+            as_coordinator: false,
+            peek: false,
+        })
+        .await;
+    res.assert_status_ok();
+    assert!(res.json::<frostd::ReceiveOutput>().msgs.is_empty());
+
+    Ok(())
+}
+
+/// Test a session with two coordinators: only the creator receives the
+/// messages participants send to the coordinator, either can send messages to
+/// participants, and closing the session requires both.
+#[tokio::test]
+async fn test_multiple_coordinators() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let alice_keypair = builder.generate_keypair().unwrap();
+    let bob_keypair = builder.generate_keypair().unwrap();
+    let carol_keypair = builder.generate_keypair().unwrap();
+    let dave_keypair = builder.generate_keypair().unwrap();
+    let alice_token = login(&server, &alice_keypair).await;
+    let bob_token = login(&server, &bob_keypair).await;
+    let dave_token = login(&server, &dave_keypair).await;
+
+    let create_args = |close_quorum: Option<usize>| frostd::CreateNewSessionArgs {
+        pubkeys: vec![
+            frostd::PublicKey(bob_keypair.public.clone()),
+            frostd::PublicKey(carol_keypair.public.clone()),
+        ],
+        message_count: 1,
+        coordinators: vec![frostd::PublicKey(dave_keypair.public.clone())],
+        close_quorum,
+        ciphersuite: None,
+        message_commitment: None,
+    };
+
+    // The quorum can't be larger than the number of coordinators.
+    let res = server
+        .post("/create_new_session")
+        .authorization_bearer(alice_token)
+        .json(&create_args(Some(3)))
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::INVALID_ARGUMENT);
+
+    let res = server
+        .post("/create_new_session")
+        .authorization_bearer(alice_token)
+        .json(&create_args(None))
+        .await;
+    res.assert_status_ok();
+    let session_id = res.json::<frostd::CreateNewSessionOutput>().session_id;
+
+    let res = server
+        .post("/get_session_info")
+        .authorization_bearer(dave_token)
+        .json(&frostd::GetSessionInfoArgs { session_id })
+        .await;
+    res.assert_status_ok();
+    let info = res.json::<frostd::GetSessionInfoOutput>();
+    assert_eq!(info.coordinator_pubkey, alice_keypair.public);
+    assert_eq!(
+        info.coordinator_pubkeys
+            .into_iter()
+            .map(|p| p.0)
+            .collect::<Vec<_>>(),
+        vec![alice_keypair.public.clone(), dave_keypair.public.clone()]
+    );
+
+    // Bob's message to the coordinator only reaches Alice, to whom it is
+    // encrypted.
+    let res = server
+        .post("/send")
+        .authorization_bearer(bob_token)
+        .json(&frostd::SendArgs {
+            session_id,
+            recipients: vec![],
+            msg: vec![0],
+        })
+        .await;
+    res.assert_status_ok();
+    for (token, count) in [(alice_token, 1), (dave_token, 0)] {
+        let res = server
+            .post("/receive")
+            .authorization_bearer(token)
+            .json(&frostd::ReceiveArgs {
+                session_id,
+                as_coordinator: true,
+                peek: false,
+            })
+            .await;
+        res.assert_status_ok();
+        let r = res.json::<frostd::ReceiveOutput>();
+        assert_eq!(r.msgs.len(), count);
+        assert!(r.msgs.iter().all(|m| m.sender == bob_keypair.public));
+    }
+
+    // Dave can send messages to participants.
+    let res = server
+        .post("/send")
+        .authorization_bearer(dave_token)
+        .json(&frostd::SendArgs {
+            session_id,
+            recipients: vec![frostd::PublicKey(bob_keypair.public.clone())],
+            msg: vec![1],
+        })
+        .await;
+    res.assert_status_ok();
+    let res = server
+        .post("/receive")
+        .authorization_bearer(bob_token)
+        .json(&frostd::ReceiveArgs {
+            session_id,
+            as_coordinator: false,
+            peek: false,
+        })
+        .await;
+    res.assert_status_ok();
+    let r = res.json::<frostd::ReceiveOutput>();
+    assert_eq!(r.msgs.len(), 1);
+    assert_eq!(r.msgs[0].sender, dave_keypair.public);
+
+    // Participants can't close the session.
+    let close = |token: Uuid| {
+        server
+            .post("/close_session")
+            .authorization_bearer(token)
+            .json(&frostd::CloseSessionArgs { session_id })
+    };
+    let res = close(bob_token).await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::NOT_COORDINATOR);
+
+    // The session is kept open until both coordinators closed it.
+    let res = close(alice_token).await;
+    res.assert_status_ok();
+    assert!(!res.json::<frostd::CloseSessionOutput>().closed);
+    let res = server
+        .post("/get_session_info")
+        .authorization_bearer(bob_token)
+        .json(&frostd::GetSessionInfoArgs { session_id })
+        .await;
+    res.assert_status_ok();
+
+    let res = close(dave_token).await;
+    res.assert_status_ok();
+    assert!(res.json::<frostd::CloseSessionOutput>().closed);
+    let res = server
+        .post("/get_session_info")
+        .authorization_bearer(bob_token)
+        .json(&frostd::GetSessionInfoArgs { session_id })
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::SESSION_NOT_FOUND);
+
+    Ok(())
+}
+
+/// Test if a participant running another ciphersuite than the one the session
+/// was created with detects it from the session info, before any signing
+/// message is exchanged.
+#[tokio::test]
+async fn test_session_ciphersuite_mismatch() -> Result<(), Box<dyn std::error::Error>> {
+    type Redpallas = reddsa::frost::redpallas::PallasBlake2b512;
+    type Ed25519 = frost_ed25519::Ed25519Sha512;
+
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let alice_keypair = builder.generate_keypair().unwrap();
+    let bob_keypair = builder.generate_keypair().unwrap();
+    let carol_keypair = builder.generate_keypair().unwrap();
+    let alice_token = login(&server, &alice_keypair).await;
+    let bob_token = login(&server, &bob_keypair).await;
+
+    let mut session_ids = Vec::new();
+    for ciphersuite in [
+        Some(<Redpallas as frost::Ciphersuite>::ID.to_string()),
+        None,
+    ] {
+        let res = server
+            .post("/create_new_session")
+            .authorization_bearer(alice_token)
+            .json(&frostd::CreateNewSessionArgs {
+                pubkeys: vec![
+                    frostd::PublicKey(bob_keypair.public.clone()),
+                    frostd::PublicKey(carol_keypair.public.clone()),
+                ],
+                message_count: 1,
+                coordinators: vec![],
+                close_quorum: None,
+                ciphersuite,
+                message_commitment: None,
+            })
+            .await;
+        res.assert_status_ok();
+        session_ids.push(res.json::<frostd::CreateNewSessionOutput>().session_id);
+    }
+
+    let server = &server;
+    let get_session_info = |session_id: Uuid| async move {
+        let res = server
+            .post("/get_session_info")
+            .authorization_bearer(bob_token)
+            .json(&frostd::GetSessionInfoArgs { session_id })
+            .await;
+        res.assert_status_ok();
+        res.json::<frostd::GetSessionInfoOutput>()
+    };
+
+    let info = get_session_info(session_ids[0]).await;
+    assert_eq!(
+        participant::comms::http::check_session_ciphersuite::<Ed25519>(&info)
+            .unwrap_err()
+            .to_string(),
+        format!(
+            "session uses ciphersuite {} but you are running {}",
+            <Redpallas as frost::Ciphersuite>::ID,
+            <Ed25519 as frost::Ciphersuite>::ID
+        )
+    );
+    participant::comms::http::check_session_ciphersuite::<Redpallas>(&info)?;
+
+    // Nothing was sent in the session yet.
+    let res = server
+        .post("/receive")
+        .authorization_bearer(alice_token)
+        .json(&frostd::ReceiveArgs {
+            session_id: session_ids[0],
+            as_coordinator: true,
+            peek: false,
+        })
+        .await;
+    res.assert_status_ok();
+    assert!(res.json::<frostd::ReceiveOutput>().msgs.is_empty());
+
+    // Sessions created without a ciphersuite can't be checked.
+    let info = get_session_info(session_ids[1]).await;
+    assert_eq!(info.ciphersuite, None);
+    participant::comms::http::check_session_ciphersuite::<Ed25519>(&info)?;
+
+    Ok(())
+}
+
+/// Test which messages a coordinator can send to itself: only those it sends
+/// as a participant of the session.
+#[tokio::test]
+async fn test_send_to_self() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let alice_keypair = builder.generate_keypair().unwrap();
+    let bob_keypair = builder.generate_keypair().unwrap();
+    let dave_keypair = builder.generate_keypair().unwrap();
+    let alice_token = login(&server, &alice_keypair).await;
+    let dave_token = login(&server, &dave_keypair).await;
+
+    let create_session = |pubkeys: Vec<Vec<u8>>, coordinators: Vec<Vec<u8>>| {
+        let server = &server;
+        async move {
+            let res = server
+                .post("/create_new_session")
+                .authorization_bearer(alice_token)
+                .json(&frostd::CreateNewSessionArgs {
+                    pubkeys: pubkeys.into_iter().map(frostd::PublicKey).collect(),
+                    message_count: 1,
+                    coordinators: coordinators.into_iter().map(frostd::PublicKey).collect(),
+                    close_quorum: None,
+                    ciphersuite: None,
+                    message_commitment: None,
+                })
+                .await;
+            res.assert_status_ok();
+            res.json::<frostd::CreateNewSessionOutput>().session_id
+        }
+    };
+    let send = |session_id: Uuid, recipients: Vec<Vec<u8>>| {
+        let server = &server;
+        async move {
+            server
+                .post("/send")
+                .authorization_bearer(alice_token)
+                .json(&frostd::SendArgs {
+                    session_id,
+                    recipients: recipients.into_iter().map(frostd::PublicKey).collect(),
+                    msg: b"to self".to_vec(),
+                })
+                .await
+        }
+    };
+    let receive = |session_id: Uuid, token: Uuid, as_coordinator: bool| {
+        let server = &server;
+        async move {
+            let res = server
+                .post("/receive")
+                .authorization_bearer(token)
+                .json(&frostd::ReceiveArgs {
+                    session_id,
+                    as_coordinator,
+                    peek: false,
+                })
+                .await;
+            res.assert_status_ok();
+            res.json::<frostd::ReceiveOutput>().msgs
+        }
+    };
+
+    // As a participant too, the coordinator gets both the messages it sends
+    // to the coordinators and to itself.
+    let session_id = create_session(
+        vec![alice_keypair.public.clone(), bob_keypair.public.clone()],
+        vec![],
+    )
+    .await;
+    send(session_id, vec![]).await.assert_status_ok();
+    let msgs = receive(session_id, alice_token, true).await;
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].sender, alice_keypair.public);
+    send(session_id, vec![alice_keypair.public.clone()])
+        .await
+        .assert_status_ok();
+    assert_eq!(receive(session_id, alice_token, false).await.len(), 1);
+
+    // Otherwise, it can send messages to neither.
+    let session_id = create_session(vec![bob_keypair.public.clone()], vec![]).await;
+    for recipients in [vec![], vec![alice_keypair.public.clone()]] {
+        let res = send(session_id, recipients).await;
+        res.assert_status_failure();
+        assert_eq!(res.json::<frostd::Error>().code, frostd::INVALID_ARGUMENT);
+    }
+
+    // With other coordinators, only they get its messages.
+    let session_id = create_session(
+        vec![bob_keypair.public.clone()],
+        vec![dave_keypair.public.clone()],
+    )
+    .await;
+    send(session_id, vec![]).await.assert_status_ok();
+    assert!(receive(session_id, alice_token, true).await.is_empty());
+    assert_eq!(receive(session_id, dave_token, true).await.len(), 1);
+
+    Ok(())
+}
+
+/// Test if a participant can't read nor acknowledge messages as a
+/// coordinator.
+#[tokio::test]
+async fn test_receive_as_coordinator_not_coordinator() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let alice_keypair = builder.generate_keypair().unwrap();
+    let bob_keypair = builder.generate_keypair().unwrap();
+    let carol_keypair = builder.generate_keypair().unwrap();
+    let alice_token = login(&server, &alice_keypair).await;
+    let bob_token = login(&server, &bob_keypair).await;
+
+    let res = server
+        .post("/create_new_session")
+        .authorization_bearer(alice_token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: vec![
+                frostd::PublicKey(bob_keypair.public.clone()),
+                frostd::PublicKey(carol_keypair.public.clone()),
+            ],
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .await;
+    res.assert_status_ok();
+    let session_id = res.json::<frostd::CreateNewSessionOutput>().session_id;
+
+    let res = server
+        .post("/receive")
+        .authorization_bearer(bob_token)
+        .json(&frostd::ReceiveArgs {
+            session_id,
+            as_coordinator: true,
+            peek: false,
+        })
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::NOT_COORDINATOR);
+
+    let res = server
+        .post("/ack")
+        .authorization_bearer(bob_token)
+        .json(&frostd::AckArgs {
+            session_id,
+            as_coordinator: true,
+            index: 0,
+        })
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::NOT_COORDINATOR);
+
+    // Receiving as a participant still works.
+    let res = server
+        .post("/receive")
+        .authorization_bearer(bob_token)
+        .json(&frostd::ReceiveArgs {
+            session_id,
+            as_coordinator: false,
+            peek: false,
+        })
+        .await;
+    res.assert_status_ok();
+
+    Ok(())
+}
+
+/// Test if users which are not in a session can neither read from nor
+/// acknowledge its queues, nor be sent messages in it.
+#[tokio::test]
+async fn test_queues_non_member() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let alice_keypair = builder.generate_keypair().unwrap();
+    let bob_keypair = builder.generate_keypair().unwrap();
+    let carol_keypair = builder.generate_keypair().unwrap();
+    let alice_token = login(&server, &alice_keypair).await;
+    let carol_token = login(&server, &carol_keypair).await;
+
+    let res = server
+        .post("/create_new_session")
+        .authorization_bearer(alice_token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: vec![frostd::PublicKey(bob_keypair.public.clone())],
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .await;
+    res.assert_status_ok();
+    let session_id = res.json::<frostd::CreateNewSessionOutput>().session_id;
+
+    let res = server
+        .post("/send")
+        .authorization_bearer(alice_token)
+        .json(&frostd::SendArgs {
+            session_id,
+            recipients: vec![frostd::PublicKey(carol_keypair.public.clone())],
+            msg: vec![0],
+        })
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::INVALID_ARGUMENT);
+
+    let res = server
+        .post("/receive")
+        .authorization_bearer(carol_token)
+        .json(&frostd::ReceiveArgs {
+            session_id,
+            as_coordinator: false,
+            peek: false,
+        })
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::SESSION_NOT_FOUND);
+
+    let res = server
+        .post("/ack")
+        .authorization_bearer(carol_token)
+        .json(&frostd::AckArgs {
+            session_id,
+            as_coordinator: false,
+            index: 0,
+        })
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::SESSION_NOT_FOUND);
+
+    Ok(())
+}
+
+/// Test if a coordinator can keep a session open past its timeout, up to the
+/// configured maximum.
+#[tokio::test]
+async fn test_extend_session() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(1),
+        Duration::from_secs(10),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let alice_keypair = builder.generate_keypair().unwrap();
+    let bob_keypair = builder.generate_keypair().unwrap();
+    let carol_keypair = builder.generate_keypair().unwrap();
+    let alice_token = login(&server, &alice_keypair).await;
+    let bob_token = login(&server, &bob_keypair).await;
+
+    let mut session_ids = Vec::new();
+    for _ in 0..2 {
+        let res = server
+            .post("/create_new_session")
+            .authorization_bearer(alice_token)
+            .json(&frostd::CreateNewSessionArgs {
+                pubkeys: vec![
+                    frostd::PublicKey(bob_keypair.public.clone()),
+                    frostd::PublicKey(carol_keypair.public.clone()),
+                ],
+                message_count: 1,
+                coordinators: vec![],
+                close_quorum: None,
+                ciphersuite: None,
+                message_commitment: None,
+            })
+            .await;
+        res.assert_status_ok();
+        session_ids.push(res.json::<frostd::CreateNewSessionOutput>().session_id);
+    }
+    let (extended_id, expired_id) = (session_ids[0], session_ids[1]);
+
+    // Only coordinators can extend a session.
+    let res = server
+        .post("/extend_session")
+        .authorization_bearer(bob_token)
+        .json(&frostd::ExtendSessionArgs {
+            session_id: extended_id,
+            seconds: 5,
+        })
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::NOT_COORDINATOR);
+
+    // The extension is limited by the server configuration.
+    let res = server
+        .post("/extend_session")
+        .authorization_bearer(alice_token)
+        .json(&frostd::ExtendSessionArgs {
+            session_id: extended_id,
+            seconds: 11,
+        })
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::INVALID_ARGUMENT);
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let res = server
+        .post("/extend_session")
+        .authorization_bearer(alice_token)
+        .json(&frostd::ExtendSessionArgs {
+            session_id: extended_id,
+            seconds: 5,
+        })
+        .await;
+    res.assert_status_ok();
+    let expires_at = res.json::<frostd::ExtendSessionOutput>().expires_at;
+    assert!((now + 5..=now + 6).contains(&expires_at));
+
+    // Wait for the sessions to time out without the extension.
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let res = server
+        .post("/get_session_info")
+        .authorization_bearer(alice_token)
+        .json(&frostd::GetSessionInfoArgs {
+            session_id: expired_id,
+        })
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::SESSION_NOT_FOUND);
+
+    let res = server
+        .post("/get_session_info")
+        .authorization_bearer(alice_token)
+        .json(&frostd::GetSessionInfoArgs {
+            session_id: extended_id,
+        })
+        .await;
+    res.assert_status_ok();
+
+    Ok(())
+}
+
+/// Test if the admin API reports how many messages are queued for each
+/// recipient, and that it can't be called without the admin token.
+#[tokio::test]
+async fn test_admin_queues() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        Some("admin-token".to_string()),
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let alice_keypair = builder.generate_keypair().unwrap();
+    let bob_keypair = builder.generate_keypair().unwrap();
+    let carol_keypair = builder.generate_keypair().unwrap();
+    let alice_token = login(&server, &alice_keypair).await;
+    let bob_token = login(&server, &bob_keypair).await;
+
+    let res = server
+        .post("/create_new_session")
+        .authorization_bearer(alice_token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: vec![
+                frostd::PublicKey(bob_keypair.public.clone()),
+                frostd::PublicKey(carol_keypair.public.clone()),
+            ],
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .await;
+    res.assert_status_ok();
+    let session_id = res.json::<frostd::CreateNewSessionOutput>().session_id;
+
+    // Queue two messages for Bob, one for Carol and one for the coordinator
+    for (token, recipients) in [
+        (alice_token, vec![bob_keypair.public.clone()]),
+        (
+            alice_token,
+            vec![bob_keypair.public.clone(), carol_keypair.public.clone()],
+        ),
+        (bob_token, vec![]),
+    ] {
+        let res = server
+            .post("/send")
+            .authorization_bearer(token)
+            .json(&frostd::SendArgs {
+                session_id,
+                recipients: recipients.into_iter().map(frostd::PublicKey).collect(),
+                msg: b"secret".to_vec(),
+            })
+            .await;
+        res.assert_status_ok();
+    }
+
+    let res = server
+        .post("/admin/queues")
+        .authorization_bearer("admin-token")
+        .json(&frostd::AdminQueuesArgs {})
+        .await;
+    res.assert_status_ok();
+    let output = res.json::<frostd::AdminQueuesOutput>();
+    assert_eq!(output.sessions.len(), 1);
+    let queues = &output.sessions[0];
+    assert_eq!(queues.session_id, session_id);
+    assert_eq!(queues.coordinator, 1);
+    let counts = queues
+        .participants
+        .iter()
+        .map(|p| (p.pubkey.0.clone(), p.count))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        counts,
+        vec![
+            (bob_keypair.public.clone(), 2),
+            (carol_keypair.public.clone(), 1)
+        ]
+    );
+
+    // Regular users can't call the admin API
+    let res = server
+        .post("/admin/queues")
+        .authorization_bearer(alice_token)
+        .json(&frostd::AdminQueuesArgs {})
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::UNAUTHORIZED);
+
+    let res = server
+        .post("/admin/queues")
+        .authorization_bearer("wrong-token")
+        .json(&frostd::AdminQueuesArgs {})
+        .await;
+    res.assert_status_failure();
+    assert_eq!(res.json::<frostd::Error>().code, frostd::UNAUTHORIZED);
+
+    Ok(())
+}
+
+/// Test calling multiple API methods with the batch API, including one that
+/// fails.
+#[tokio::test]
+async fn test_batch() -> Result<(), Box<dyn std::error::Error>> {
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let keypair = builder.generate_keypair().unwrap();
+    let token = login(&server, &keypair).await;
+    let pubkeys = vec![
+        frostd::PublicKey(keypair.public.clone()),
+        frostd::PublicKey(builder.generate_keypair().unwrap().public),
+    ];
+
+    // The first request succeeds and the second fails, since the session
+    // does not exist.
+    let res = server
+        .post("/batch")
+        .authorization_bearer(token)
+        .json(&frostd::BatchArgs {
+            requests: vec![
+                frostd::BatchRequest::CreateNewSession(frostd::CreateNewSessionArgs {
+                    pubkeys: pubkeys.clone(),
+                    message_count: 1,
+                    coordinators: vec![],
+                    close_quorum: None,
+                    ciphersuite: None,
+                    message_commitment: None,
+                }),
+                frostd::BatchRequest::GetSessionInfo(frostd::GetSessionInfoArgs {
+                    session_id: Uuid::new_v4(),
+                }),
+            ],
+        })
+        .await;
+    res.assert_status_ok();
+    let r = res.json::<frostd::BatchOutput>();
+    assert_eq!(r.results.len(), 2);
+    let frostd::BatchResult::Ok(output) = &r.results[0] else {
+        panic!("create_new_session should succeed");
+    };
+    let session_id =
+        serde_json::from_value::<frostd::CreateNewSessionOutput>(output.clone())?.session_id;
+    let frostd::BatchResult::Err(err) = &r.results[1] else {
+        panic!("get_session_info should fail");
+    };
+    assert_eq!(err.code, frostd::SESSION_NOT_FOUND);
+
+    // The session created by the batch can be used by other requests.
+    let res = server
+        .post("/batch")
+        .authorization_bearer(token)
+        .json(&frostd::BatchArgs {
+            requests: vec![
+                frostd::BatchRequest::GetSessionInfo(frostd::GetSessionInfoArgs { session_id }),
+                frostd::BatchRequest::ListSessions(Default::default()),
+            ],
+        })
+        .await;
+    res.assert_status_ok();
+    let r = res.json::<frostd::BatchOutput>();
+    let frostd::BatchResult::Ok(output) = &r.results[0] else {
+        panic!("get_session_info should succeed");
+    };
+    let session_info = serde_json::from_value::<frostd::GetSessionInfoOutput>(output.clone())?;
+    assert_eq!(session_info.message_count, 1);
+    assert_eq!(session_info.coordinator_pubkey, keypair.public);
+    let frostd::BatchResult::Ok(output) = &r.results[1] else {
+        panic!("list_sessions should succeed");
+    };
+    let sessions = serde_json::from_value::<frostd::ListSessionsOutput>(output.clone())?;
+    assert_eq!(sessions.session_ids, vec![session_id]);
+
+    // The batch API requires authentication.
+    let res = server
+        .post("/batch")
+        .json(&frostd::BatchArgs { requests: vec![] })
+        .await;
+    res.assert_status_failure();
+
+    Ok(())
+}
+
+/// Test if the arguments can be set with environment variables, and if
+/// command line flags take precedence over them.
+#[test]
+fn test_args_from_env() {
+    use clap::Parser as _;
+
+    std::env::set_var("FROSTD_IP", "10.0.0.1");
+    std::env::set_var("FROSTD_PORT", "8080");
+    std::env::set_var("FROSTD_TLS_CERT", "cert.pem");
+    std::env::set_var("FROSTD_TLS_KEY", "key.pem");
+    std::env::set_var("FROSTD_NO_TLS_VERY_INSECURE", "true");
+
+    let args = Args::try_parse_from(["frostd"]).unwrap();
+    assert_eq!(args.ip, vec!["10.0.0.1"]);
+    assert_eq!(args.port, 8080);
+    assert_eq!(args.tls_cert.as_deref(), Some("cert.pem"));
+    assert_eq!(args.tls_key.as_deref(), Some("key.pem"));
+    assert!(args.no_tls_very_insecure);
+    assert_eq!(args.max_participants, DEFAULT_MAX_PARTICIPANTS);
+    assert_eq!(args.max_queue_len, DEFAULT_MAX_QUEUE_LEN);
+    assert_eq!(args.queue_full, QueueFullPolicy::Reject);
+    assert_eq!(args.session_timeout, DEFAULT_SESSION_TIMEOUT);
+    assert_eq!(args.max_session_extension, DEFAULT_MAX_SESSION_EXTENSION);
+
+    let args = Args::try_parse_from(["frostd", "--port", "9090"]).unwrap();
+    assert_eq!(args.port, 9090);
+    assert_eq!(args.ip, vec!["10.0.0.1"]);
+
+    for var in [
+        "FROSTD_IP",
+        "FROSTD_PORT",
+        "FROSTD_TLS_CERT",
+        "FROSTD_TLS_KEY",
+        "FROSTD_NO_TLS_VERY_INSECURE",
+    ] {
+        std::env::remove_var(var);
+    }
+}
+
+/// A writer that captures logs in memory, to allow checking what was logged.
+#[derive(Clone, Default)]
+struct CaptureWriter(Arc<Mutex<Vec<u8>>>);
+
+impl std::io::Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Test if handler logs carry the session ID and request ID, and do not
+/// include message contents.
+#[tokio::test]
+async fn test_session_logging() -> Result<(), Box<dyn std::error::Error>> {
+    let writer = CaptureWriter::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer({
+            let writer = writer.clone();
+            move || writer.clone()
+        })
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let shared_state = AppState::new(
+        DEFAULT_MAX_PARTICIPANTS,
+        QueueLimit::default(),
+        Duration::from_secs(DEFAULT_SESSION_TIMEOUT),
+        Duration::from_secs(DEFAULT_MAX_SESSION_EXTENSION),
+        None,
+    )
+    .await?;
+    let router = router(shared_state);
+    let server = TestServer::new(router)?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let keypair = builder.generate_keypair().unwrap();
+    let other_keypair = builder.generate_keypair().unwrap();
+    let token = login(&server, &keypair).await;
+
+    let res = server
+        .post("/create_new_session")
+        .authorization_bearer(token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: vec![
+                frostd::PublicKey(keypair.public.clone()),
+                frostd::PublicKey(other_keypair.public.clone()),
+            ],
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .await;
+    res.assert_status_ok();
+    let session_id = res.json::<frostd::CreateNewSessionOutput>().session_id;
+
+    let msg = b"very secret message".to_vec();
+    let res = server
+        .post("/send")
+        .authorization_bearer(token)
+        .json(&frostd::SendArgs {
+            session_id,
+            recipients: vec![],
+            msg: msg.clone(),
+        })
+        .await;
+    res.assert_status_ok();
+
+    let logs = String::from_utf8(writer.0.lock().unwrap().clone())?;
+    assert!(logs.contains(&format!(
+        "create_new_session{{pubkey={}",
+        hex::encode(&keypair.public)
+    )));
+    assert!(logs.contains(&format!("session_id={}", session_id)));
+    assert!(logs.contains("request_id="));
+    assert!(!logs.contains(&hex::encode(&msg)));
+
+    Ok(())
+}
+
+/// Spawn a server listening on 127.0.0.1 at `port` with the default args and
+/// a self-signed TLS certificate, and wait for it to start. Returns the path
+/// of the certificate, to pass as `cacert` to clients, and the temporary
+/// directory containing it, which must be kept until the end of the test.
+async fn spawn_tls_server(port: u16) -> Result<(String, tempfile::TempDir), Box<dyn Error>> {
+    spawn_tls_server_with(port, |_| {}).await
+}
+
+/// Same as [`spawn_tls_server()`], but changing the server args with `f`
+/// first.
+async fn spawn_tls_server_with(
+    port: u16,
+    f: impl FnOnce(&mut Args),
+) -> Result<(String, tempfile::TempDir), Box<dyn Error>> {
+    use rcgen::{generate_simple_self_signed, CertifiedKey};
+
+    let subject_alt_names = vec!["127.0.0.1".to_string(), "localhost".to_string()];
+    let CertifiedKey { cert, key_pair } = generate_simple_self_signed(subject_alt_names).unwrap();
+    let temp_dir = tempfile::tempdir()?;
+    let cert_path = temp_dir.path().join("cert.pem");
+    let key_path = temp_dir.path().join("cert.key.pem");
+    std::fs::write(&cert_path, cert.pem())?;
+    std::fs::write(&key_path, key_pair.serialize_pem())?;
+    let cacert = cert_path.to_str().unwrap().to_string();
+
+    let mut args = Args {
+        ip: vec!["127.0.0.1".to_string()],
+        port,
+        tls_cert: Some(cacert.clone()),
+        tls_key: Some(key_path.to_str().unwrap().to_string()),
+        no_tls_very_insecure: false,
+        tls_min_version: TlsVersion::Tls12,
+        max_participants: DEFAULT_MAX_PARTICIPANTS,
+        max_queue_len: DEFAULT_MAX_QUEUE_LEN,
+        queue_full: QueueFullPolicy::Reject,
+        session_timeout: DEFAULT_SESSION_TIMEOUT,
+        max_session_extension: DEFAULT_MAX_SESSION_EXTENSION,
+        admin_token: None,
+    };
+    f(&mut args);
+    tokio::spawn(async move {
+        frostd::run(&args).await.unwrap();
+    });
+
+    // Wait for server to start listening
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    Ok((cacert, temp_dir))
+}
+
+/// The args of a coordinator in HTTP mode, connecting to a server spawned
+/// with [`spawn_tls_server()`], which signs `message` with the participants
+/// with the given public keys. Tests change the fields they need.
+fn coordinator_pargs<C: frost::Ciphersuite>(
+    port: u16,
+    cacert: &str,
+    public_key_package: &frost::keys::PublicKeyPackage<C>,
+    keypair: &snow::Keypair,
+    participant_pubkeys: &[Vec<u8>],
+    message: &[u8],
+) -> coordinator::args::ProcessedArgs<C> {
+    let signers = participant_pubkeys.to_vec();
+    let participant_pubkeys = signers.clone();
+    coordinator::args::ProcessedArgs {
+        cli: false,
+        http: true,
+        num_signers: signers.len() as u16,
+        signers,
+        public_key_package: public_key_package.clone(),
+        messages: vec![message.to_vec()],
+        randomizers: Vec::new(),
+        aux_msg: Vec::new(),
+        aux_msg_seed: Vec::new(),
+        signature: String::new(),
+        audit_log: None,
+        ip: "127.0.0.1".to_string(),
+        port,
+        cacert: Some(cacert.to_string()),
+        insecure_skip_verify: false,
+        proxy: None,
+        session_id: None,
+        strict: false,
+        state_file: None,
+        comm_privkey: Some(keypair.private.clone()),
+        comm_pubkey: Some(keypair.public.clone()),
+        comm_participant_pubkey_getter: Some(Rc::new(move |pubkey: &Vec<u8>| {
+            participant_pubkeys.contains(pubkey).then(|| pubkey.clone())
+        })),
+        progress: None,
+        on_session: None,
+        keep_alive: None,
+        compress: false,
+        participant_timeout: None,
+        threshold: None,
+        message_commitment_salt: None,
+    }
+}
+
+/// The args of a participant in HTTP mode, connecting to a server spawned
+/// with [`spawn_tls_server()`], which signs with `key_package` in the sessions
+/// of the coordinator with the given public key. Tests change the fields they
+/// need.
+fn participant_pargs<C: frost::Ciphersuite>(
+    port: u16,
+    cacert: &str,
+    key_package: frost::keys::KeyPackage<C>,
+    keypair: &snow::Keypair,
+    coordinator_pubkey: &[u8],
+) -> participant::args::ProcessedArgs<C> {
+    let coordinator_pubkey = coordinator_pubkey.to_vec();
+    participant::args::ProcessedArgs {
+        cli: false,
+        http: true,
+        key_package,
+        ip: "127.0.0.1".to_string(),
+        port,
+        session_id: String::new(),
+        cacert: Some(cacert.to_string()),
+        insecure_skip_verify: false,
+        proxy: None,
+        comm_privkey: Some(keypair.private.clone()),
+        comm_pubkey: Some(keypair.public.clone()),
+        comm_coordinator_pubkey_getter: Some(Rc::new(move |pubkey: &Vec<u8>| {
+            (*pubkey == coordinator_pubkey).then(|| pubkey.clone())
+        })),
+        expected_coordinator_pubkey: None,
+        used_commitments_path: None,
+        confirm: false,
+        on_session: None,
+        keep_alive: None,
+        compress: false,
+        offline: None,
+    }
+}
+
+/// Spawn the HTTPS server with a certificate issued by a "private CA" (a
+/// self-signed certificate) and check if clients can connect to it only when
+/// the CA certificate is provided or verification is disabled.
+#[tokio::test]
+async fn test_http_cacert() -> Result<(), Box<dyn std::error::Error>> {
+    use rcgen::{generate_simple_self_signed, CertifiedKey};
+    let subject_alt_names = vec!["127.0.0.1".to_string(), "localhost".to_string()];
+    let CertifiedKey { cert, key_pair } = generate_simple_self_signed(subject_alt_names).unwrap();
+    let temp_dir = tempfile::tempdir()?;
+    let cert_path = temp_dir.path().join("cert.pem");
+    let key_path = temp_dir.path().join("cert.key.pem");
+    std::fs::write(&cert_path, cert.pem())?;
+    std::fs::write(&key_path, key_pair.serialize_pem())?;
+
+    let args = Args {
+        ip: vec!["127.0.0.1".to_string()],
+        port: 2745,
+        tls_cert: Some(cert_path.to_str().unwrap().to_string()),
+        tls_key: Some(key_path.to_str().unwrap().to_string()),
+        no_tls_very_insecure: false,
+        tls_min_version: TlsVersion::Tls12,
+        max_participants: DEFAULT_MAX_PARTICIPANTS,
+        max_queue_len: DEFAULT_MAX_QUEUE_LEN,
+        queue_full: QueueFullPolicy::Reject,
+        session_timeout: DEFAULT_SESSION_TIMEOUT,
+        max_session_extension: DEFAULT_MAX_SESSION_EXTENSION,
+        admin_token: None,
+    };
+    tokio::spawn(async move {
+        frostd::run(&args).await.unwrap();
+    });
+
+    // Wait for server to start listening
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let challenge = |client: reqwest::Client| async move {
+        client
+            .post("https://127.0.0.1:2745/challenge")
+            .json(&frostd::ChallengeArgs {})
+            .send()
+            .await
+    };
+
+    let client = participant::comms::http::build_client(cert_path.to_str(), false, None)?;
+    let r = challenge(client).await?;
+    assert_eq!(r.status(), reqwest::StatusCode::OK);
+
+    let client = participant::comms::http::build_client(None, false, None)?;
+    assert!(challenge(client).await.is_err());
+
+    let client = participant::comms::http::build_client(None, true, None)?;
+    let r = challenge(client).await?;
+    assert_eq!(r.status(), reqwest::StatusCode::OK);
+
+    Ok(())
+}
+
+/// Check if clients send requests through the specified proxy, using a stub
+/// proxy which records the request it gets.
+#[tokio::test]
+async fn test_http_proxy() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let proxy = format!("http://{}", listener.local_addr()?);
+    let proxy_thread = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            let n = stream.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..n]);
+        }
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        String::from_utf8(request).unwrap()
+    });
+
+    let client = participant::comms::http::build_client(None, false, Some(&proxy))?;
+    let r = client
+        .post("http://frostd.invalid/challenge")
+        .send()
+        .await?;
+    assert_eq!(r.status(), reqwest::StatusCode::OK);
+
+    let request = proxy_thread.join().unwrap();
+    assert!(request.starts_with("POST http://frostd.invalid/challenge HTTP/1.1\r\n"));
+
+    Ok(())
+}
+
+/// Test if a coordinator can resume a session after a previous run was
+/// interrupted while waiting for commitments, and complete the signing. The
+/// commitments received by the previous run must be received again.
+#[tokio::test]
+async fn test_http_resume_session() -> Result<(), Box<dyn std::error::Error>> {
+    let (cacert, _temp_dir) = spawn_tls_server(2746).await?;
+
+    let mut rng = thread_rng();
+    let (shares, public_key_package) = frost_ed25519::keys::generate_with_dealer(
+        2,
+        2,
+        frost_ed25519::keys::IdentifierList::Default,
+        &mut rng,
+    )?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let coordinator_keypair = builder.generate_keypair().unwrap();
+    let participant_keypairs = [
+        builder.generate_keypair().unwrap(),
+        builder.generate_keypair().unwrap(),
+    ];
+    let participant_pubkeys = participant_keypairs
+        .iter()
+        .map(|k| k.public.clone())
+        .collect::<Vec<_>>();
+
+    // Create the session as the coordinator would.
+    let client = participant::comms::http::build_client(Some(&cacert), false, None)?;
+    let host_port = "https://127.0.0.1:2746";
+    let access_token = participant::comms::http::login(
+        &client,
+        host_port,
+        &coordinator_keypair.private,
+        &coordinator_keypair.public,
+    )
+    .await?;
+    let session_id = client
+        .post(format!("{}/create_new_session", host_port))
+        .bearer_auth(access_token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: participant_pubkeys
+                .iter()
+                .cloned()
+                .map(frostd::PublicKey)
+                .collect(),
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .send()
+        .await?
+        .json::<frostd::CreateNewSessionOutput>()
+        .await?
+        .session_id;
+
+    // The sessions joined by participants and resumed by the coordinator.
+    let joined = Rc::new(RefCell::new(Vec::new()));
+
+    let mut participants =
+        shares
+            .values()
+            .zip(participant_keypairs.iter())
+            .map(|(share, keypair)| {
+                let pargs = participant::args::ProcessedArgs {
+                    session_id: session_id.to_string(),
+                    on_session: Some(Rc::new({
+                        let joined = joined.clone();
+                        move |session_id: Uuid, coordinator_pubkey: &[u8]| {
+                            joined
+                                .borrow_mut()
+                                .push((session_id, Some(coordinator_pubkey.to_vec())))
+                        }
+                    })),
+                    ..participant_pargs(
+                        2746,
+                        &cacert,
+                        frost_ed25519::keys::KeyPackage::try_from(share.clone()).unwrap(),
+                        keypair,
+                        &coordinator_keypair.public,
+                    )
+                };
+                async move {
+                    participant::cli::cli_for_processed_args(
+                        pargs,
+                        &mut "".as_bytes(),
+                        &mut Vec::new(),
+                    )
+                    .await
+                }
+            });
+    let (p1, p2) = (participants.next().unwrap(), participants.next().unwrap());
+
+    // Resume the session in each coordinator run. No signers are passed, so
+    // the participants of the session are used.
+    let message = b"message to sign";
+    let progress = Rc::new(RefCell::new(Vec::new()));
+    let pargs = coordinator::args::ProcessedArgs {
+        signers: Vec::new(),
+        session_id: Some(session_id),
+        progress: Some(Rc::new({
+            let progress = progress.clone();
+            move |received, expected| progress.borrow_mut().push((received, expected))
+        })),
+        on_session: Some(Rc::new({
+            let joined = joined.clone();
+            move |session_id: Uuid| joined.borrow_mut().push((session_id, None))
+        })),
+        ..coordinator_pargs(
+            2746,
+            &cacert,
+            &public_key_package,
+            &coordinator_keypair,
+            &participant_pubkeys,
+            message,
+        )
+    };
+    let coordinator = async {
+        // The first run is interrupted after receiving the commitments of the
+        // first participant, since the second one has not started yet.
+        let mut comms = coordinator::comms::http::HTTPComms::new(&pargs)?;
+        let interrupted = tokio::time::timeout(
+            Duration::from_secs(6),
+            coordinator::step_1::step_1(&pargs, &mut comms, &mut "".as_bytes(), &mut Vec::new()),
+        )
+        .await;
+        assert!(interrupted.is_err());
+
+        // "Restart" the coordinator, which must still get the commitments of
+        // the first participant.
+        let second_run = async {
+            let mut comms = coordinator::comms::http::HTTPComms::new(&pargs)?;
+            let mut input = "".as_bytes();
+            let mut logger = Vec::new();
+            let participants_config =
+                coordinator::step_1::step_1(&pargs, &mut comms, &mut input, &mut logger).await?;
+            let signing_package = coordinator::step_2::step_2(
+                &pargs,
+                &mut logger,
+                participants_config.commitments.clone(),
+            )?;
+            let signature = coordinator::step_3::step_3(
+                &pargs,
+                &mut comms,
+                &mut input,
+                &mut logger,
+                participants_config,
+                &signing_package,
+            )
+            .await?;
+            Ok::<_, Box<dyn Error>>(signature)
+        };
+        let (signature, r2) = tokio::join!(second_run, p2);
+        r2?;
+        signature
+    };
+
+    let (signature, r1) = tokio::join!(coordinator, p1);
+    r1?;
+    public_key_package
+        .verifying_key()
+        .verify(message, &signature?)?;
+
+    // The progress is reported as each participant sends its commitments,
+    // and then its signature shares; the second run reports the commitments
+    // of the first participant again.
+    assert_eq!(
+        *progress.borrow(),
+        vec![(1, 2), (1, 2), (2, 2), (1, 2), (2, 2)]
+    );
+
+    let coordinator_pubkey = Some(coordinator_keypair.public.clone());
+    assert_eq!(
+        joined.borrow().iter().filter(|j| j.0 == session_id).count(),
+        4
+    );
+    assert_eq!(
+        joined
+            .borrow()
+            .iter()
+            .filter(|j| j.1 == coordinator_pubkey)
+            .count(),
+        2
+    );
+
+    Ok(())
+}
+
+/// Test if a participant can sign in two sessions at once from a single run,
+/// logging in only once.
+#[tokio::test]
+async fn test_http_multiple_sessions() -> Result<(), Box<dyn std::error::Error>> {
+    let (cacert, _temp_dir) = spawn_tls_server(2754).await?;
+
+    let mut rng = thread_rng();
+    let (shares, public_key_package) = frost_ed25519::keys::generate_with_dealer(
+        2,
+        2,
+        frost_ed25519::keys::IdentifierList::Default,
+        &mut rng,
+    )?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let coordinator_keypair = builder.generate_keypair().unwrap();
+    let participant_keypairs = [
+        builder.generate_keypair().unwrap(),
+        builder.generate_keypair().unwrap(),
+    ];
+    let participant_pubkeys = participant_keypairs
+        .iter()
+        .map(|k| k.public.clone())
+        .collect::<Vec<_>>();
+
+    // Create both sessions upfront, so that the participants can find them.
+    let client = participant::comms::http::build_client(Some(&cacert), false, None)?;
+    let host_port = "https://127.0.0.1:2754";
+    let access_token = participant::comms::http::login(
+        &client,
+        host_port,
+        &coordinator_keypair.private,
+        &coordinator_keypair.public,
+    )
+    .await?;
+    let mut session_ids = Vec::new();
+    for _ in 0..2 {
+        session_ids.push(
+            client
+                .post(format!("{}/create_new_session", host_port))
+                .bearer_auth(&access_token)
+                .json(&frostd::CreateNewSessionArgs {
+                    pubkeys: participant_pubkeys
+                        .iter()
+                        .cloned()
+                        .map(frostd::PublicKey)
+                        .collect(),
+                    message_count: 1,
+                    coordinators: vec![],
+                    close_quorum: None,
+                    ciphersuite: None,
+                    message_commitment: None,
+                })
+                .send()
+                .await?
+                .json::<frostd::CreateNewSessionOutput>()
+                .await?
+                .session_id,
+        );
+    }
+
+    // The first participant joins all of its sessions, and the second one
+    // names them.
+    let mut participants = shares
+        .values()
+        .zip(participant_keypairs.iter())
+        .zip([vec![], session_ids.clone()]);
+    let mut participant = || {
+        let ((share, keypair), session_ids) = participants.next().unwrap();
+        let pargs = participant_pargs(
+            2754,
+            &cacert,
+            frost_ed25519::keys::KeyPackage::try_from(share.clone()).unwrap(),
+            keypair,
+            &coordinator_keypair.public,
+        );
+        async move {
+            let mut logger = Vec::new();
+            participant::cli::run_sessions(pargs, session_ids, &mut logger).await?;
+            Ok::<_, Box<dyn Error>>(String::from_utf8(logger)?)
+        }
+    };
+    let (p1, p2) = (participant(), participant());
+
+    let coordinator = |session_id: Uuid, message: &'static [u8]| {
+        let pargs = coordinator::args::ProcessedArgs {
+            signers: Vec::new(),
+            session_id: Some(session_id),
+            ..coordinator_pargs(
+                2754,
+                &cacert,
+                &public_key_package,
+                &coordinator_keypair,
+                &participant_pubkeys,
+                message,
+            )
+        };
+        async move {
+            let mut comms = coordinator::comms::http::HTTPComms::new(&pargs)?;
+            let mut input = "".as_bytes();
+            let mut logger = Vec::new();
+            let participants_config =
+                coordinator::step_1::step_1(&pargs, &mut comms, &mut input, &mut logger).await?;
+            let signing_package = coordinator::step_2::step_2(
+                &pargs,
+                &mut logger,
+                participants_config.commitments.clone(),
+            )?;
+            let signature = coordinator::step_3::step_3(
+                &pargs,
+                &mut comms,
+                &mut input,
+                &mut logger,
+                participants_config,
+                &signing_package,
+            )
+            .await?;
+            Ok::<_, Box<dyn Error>>(signature)
+        }
+    };
+    let messages: [&[u8]; 2] = [b"first message", b"second message"];
+    let c1 = coordinator(session_ids[0], messages[0]);
+    let c2 = coordinator(session_ids[1], messages[1]);
+
+    let (s1, s2, r1, r2) = tokio::join!(c1, c2, p1, p2);
+    public_key_package
+        .verifying_key()
+        .verify(messages[0], &s1?)?;
+    public_key_package
+        .verifying_key()
+        .verify(messages[1], &s2?)?;
+    for log in [r1?, r2?] {
+        for session_id in &session_ids {
+            assert!(log.contains(&format!("Session {}:", session_id)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Test if participants reject a signing package whose messages do not match
+/// the ones the coordinator committed to when creating the session.
+#[tokio::test]
+async fn test_http_message_commitment_mismatch() -> Result<(), Box<dyn std::error::Error>> {
+    use coordinator::comms::http::ParticipantReportedError;
+
+    let (cacert, _temp_dir) = spawn_tls_server(2755).await?;
+
+    let mut rng = thread_rng();
+    let (shares, public_key_package) = frost_ed25519::keys::generate_with_dealer(
+        2,
+        2,
+        frost_ed25519::keys::IdentifierList::Default,
+        &mut rng,
+    )?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let coordinator_keypair = builder.generate_keypair().unwrap();
+    let participant_keypairs = [
+        builder.generate_keypair().unwrap(),
+        builder.generate_keypair().unwrap(),
+    ];
+    let participant_pubkeys = participant_keypairs
+        .iter()
+        .map(|k| k.public.clone())
+        .collect::<Vec<_>>();
+
+    // Create the session committing to a message, and then run the
+    // coordinator with another one, as if it picked it after seeing the
+    // commitments of the participants.
+    let salt = vec![7; 32];
+    let client = participant::comms::http::build_client(Some(&cacert), false, None)?;
+    let access_token = participant::comms::http::login(
+        &client,
+        "https://127.0.0.1:2755",
+        &coordinator_keypair.private,
+        &coordinator_keypair.public,
+    )
+    .await?;
+    let session_id = client
+        .post("https://127.0.0.1:2755/create_new_session")
+        .bearer_auth(&access_token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: participant_pubkeys
+                .iter()
+                .cloned()
+                .map(frostd::PublicKey)
+                .collect(),
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: Some(frostd::commit_to_messages(
+                &salt,
+                &[&b"committed message"[..]],
+            )),
+        })
+        .send()
+        .await?
+        .json::<frostd::CreateNewSessionOutput>()
+        .await?
+        .session_id;
+
+    let mut participants =
+        shares
+            .values()
+            .zip(participant_keypairs.iter())
+            .map(|(share, keypair)| {
+                let pargs = participant::args::ProcessedArgs {
+                    session_id: session_id.to_string(),
+                    ..participant_pargs(
+                        2755,
+                        &cacert,
+                        frost_ed25519::keys::KeyPackage::try_from(share.clone()).unwrap(),
+                        keypair,
+                        &coordinator_keypair.public,
+                    )
+                };
+                async move {
+                    participant::cli::cli_for_processed_args(
+                        pargs,
+                        &mut "".as_bytes(),
+                        &mut Vec::new(),
+                    )
+                    .await
+                }
+            });
+    let (p1, p2) = (participants.next().unwrap(), participants.next().unwrap());
+
+    let pargs = coordinator::args::ProcessedArgs {
+        signers: Vec::new(),
+        session_id: Some(session_id),
+        message_commitment_salt: Some(salt),
+        ..coordinator_pargs(
+            2755,
+            &cacert,
+            &public_key_package,
+            &coordinator_keypair,
+            &participant_pubkeys,
+            b"another message",
+        )
+    };
+    let coordinator = async {
+        let mut comms = coordinator::comms::http::HTTPComms::new(&pargs)?;
+        let mut input = "".as_bytes();
+        let mut logger = Vec::new();
+        let participants_config =
+            coordinator::step_1::step_1(&pargs, &mut comms, &mut input, &mut logger).await?;
+        let signing_package = coordinator::step_2::step_2(
+            &pargs,
+            &mut logger,
+            participants_config.commitments.clone(),
+        )?;
+        coordinator::step_3::step_3(
+            &pargs,
+            &mut comms,
+            &mut input,
+            &mut logger,
+            participants_config,
+            &signing_package,
+        )
+        .await
+    };
+
+    let (c, r1, r2) = tokio::join!(coordinator, p1, p2);
+    for r in [r1, r2] {
+        assert_eq!(
+            r.unwrap_err().to_string(),
+            "the messages to sign do not match the ones the coordinator committed to"
+        );
+    }
+    // The participants report the mismatch, which aborts the session.
+    let err = c
+        .unwrap_err()
+        .downcast::<ParticipantReportedError>()
+        .unwrap();
+    assert_eq!(
+        err.reason,
+        "the messages to sign do not match the ones the coordinator committed to"
+    );
+
+    Ok(())
+}
+
+/// Test if the keep-alive of the coordinator prevents the session from timing
+/// out while it waits for participants that take longer than the session
+/// timeout to join.
+#[tokio::test]
+async fn test_http_keep_alive() -> Result<(), Box<dyn std::error::Error>> {
+    let (cacert, _temp_dir) = spawn_tls_server_with(2751, |args| args.session_timeout = 5).await?;
+
+    let mut rng = thread_rng();
+    let (shares, public_key_package) = frost_ed25519::keys::generate_with_dealer(
+        2,
+        2,
+        frost_ed25519::keys::IdentifierList::Default,
+        &mut rng,
+    )?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let coordinator_keypair = builder.generate_keypair().unwrap();
+    let participant_keypairs = [
+        builder.generate_keypair().unwrap(),
+        builder.generate_keypair().unwrap(),
+    ];
+    let participant_pubkeys = participant_keypairs
+        .iter()
+        .map(|k| k.public.clone())
+        .collect::<Vec<_>>();
+
+    // Create the session beforehand, to be able to tell the participants
+    // which one to join.
+    let client = participant::comms::http::build_client(Some(&cacert), false, None)?;
+    let access_token = participant::comms::http::login(
+        &client,
+        "https://127.0.0.1:2751",
+        &coordinator_keypair.private,
+        &coordinator_keypair.public,
+    )
+    .await?;
+    let session_id = client
+        .post("https://127.0.0.1:2751/create_new_session")
+        .bearer_auth(access_token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: participant_pubkeys
+                .iter()
+                .cloned()
+                .map(frostd::PublicKey)
+                .collect(),
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .send()
+        .await?
+        .json::<frostd::CreateNewSessionOutput>()
+        .await?
+        .session_id;
+
+    let mut participants =
+        shares
+            .values()
+            .zip(participant_keypairs.iter())
+            .map(|(share, keypair)| {
+                let pargs = participant::args::ProcessedArgs {
+                    session_id: session_id.to_string(),
+                    ..participant_pargs(
+                        2751,
+                        &cacert,
+                        frost_ed25519::keys::KeyPackage::try_from(share.clone()).unwrap(),
+                        keypair,
+                        &coordinator_keypair.public,
+                    )
+                };
+                async move {
+                    // Join long after the session would have timed out if it
+                    // was not kept alive.
+                    tokio::time::sleep(Duration::from_secs(12)).await;
+                    participant::cli::cli_for_processed_args(
+                        pargs,
+                        &mut "".as_bytes(),
+                        &mut Vec::new(),
+                    )
+                    .await
+                }
+            });
+    let (p1, p2) = (participants.next().unwrap(), participants.next().unwrap());
+
+    let message = b"message to sign";
+    let pargs = coordinator::args::ProcessedArgs {
+        signers: Vec::new(),
+        session_id: Some(session_id),
+        keep_alive: Some(Duration::from_secs(1)),
+        ..coordinator_pargs(
+            2751,
+            &cacert,
+            &public_key_package,
+            &coordinator_keypair,
+            &participant_pubkeys,
+            message,
+        )
+    };
+    let coordinator = async {
+        let mut comms = coordinator::comms::http::HTTPComms::new(&pargs)?;
+        let mut input = "".as_bytes();
+        let mut logger = Vec::new();
+        let participants_config =
+            coordinator::step_1::step_1(&pargs, &mut comms, &mut input, &mut logger).await?;
+        let signing_package = coordinator::step_2::step_2(
+            &pargs,
+            &mut logger,
+            participants_config.commitments.clone(),
+        )?;
+        let signature = coordinator::step_3::step_3(
+            &pargs,
+            &mut comms,
+            &mut input,
+            &mut logger,
+            participants_config,
+            &signing_package,
+        )
+        .await?;
+        Ok::<_, Box<dyn Error>>(signature)
+    };
+
+    let (signature, r1, r2) = tokio::join!(coordinator, p1, p2);
+    r1?;
+    r2?;
+    public_key_package
+        .verifying_key()
+        .verify(message, &signature?)?;
+
+    Ok(())
+}
+
+/// Test if the participants sign with the randomizer derived from the
+/// auxiliary message sent by the coordinator. The shares would not aggregate
+/// otherwise, since the coordinator derives the same randomizer.
+#[tokio::test]
+async fn test_http_aux_msg() -> Result<(), Box<dyn std::error::Error>> {
+    use coordinator::step_3::AuditRecord;
+    use frost_rerandomized::RandomizedParams;
+    use reddsa::frost::redpallas::PallasBlake2b512;
+
+    let (cacert, temp_dir) = spawn_tls_server(2756).await?;
+    let audit_log_path = temp_dir.path().join("audit.log");
+
+    let mut rng = thread_rng();
+    let (shares, public_key_package) = frost::keys::generate_with_dealer::<PallasBlake2b512, _>(
+        2,
+        2,
+        frost::keys::IdentifierList::Default,
+        &mut rng,
+    )?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let coordinator_keypair = builder.generate_keypair().unwrap();
+    let participant_keypairs = [
+        builder.generate_keypair().unwrap(),
+        builder.generate_keypair().unwrap(),
+    ];
+    let participant_pubkeys = participant_keypairs
+        .iter()
+        .map(|k| k.public.clone())
+        .collect::<Vec<_>>();
+
+    let message = b"message to sign";
+    let aux_msg = b"transaction the message belongs to";
+    let pargs = coordinator::args::ProcessedArgs {
+        aux_msg: aux_msg.to_vec(),
+        aux_msg_seed: coordinator::args::generate_aux_msg_seed(aux_msg),
+        audit_log: Some(audit_log_path.to_str().unwrap().to_string()),
+        ..coordinator_pargs(
+            2756,
+            &cacert,
+            &public_key_package,
+            &coordinator_keypair,
+            &participant_pubkeys,
+            message,
+        )
+    };
+    let mut coordinator_input = "".as_bytes();
+    let mut coordinator_logger = Vec::new();
+    let coordinator = coordinator::cli::cli_for_processed_args(
+        pargs,
+        &mut coordinator_input,
+        &mut coordinator_logger,
+    );
+
+    let mut participants =
+        shares
+            .values()
+            .zip(participant_keypairs.iter())
+            .map(|(share, keypair)| {
+                let pargs = participant_pargs(
+                    2756,
+                    &cacert,
+                    frost::keys::KeyPackage::try_from(share.clone()).unwrap(),
+                    keypair,
+                    &coordinator_keypair.public,
+                );
+                async move {
+                    // Give the coordinator time to create the session.
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    let mut logger = Vec::new();
+                    participant::cli::cli_for_processed_args(
+                        pargs,
+                        &mut "".as_bytes(),
+                        &mut logger,
+                    )
+                    .await?;
+                    Ok::<_, Box<dyn Error>>(String::from_utf8(logger)?)
+                }
+            });
+    let (p1, p2) = (participants.next().unwrap(), participants.next().unwrap());
+
+    let (signature, r1, r2) = tokio::join!(coordinator, p1, p2);
+    let signature = signature?;
+    // The participants were shown the auxiliary message.
+    for log in [r1?, r2?] {
+        assert!(log.contains(&format!("Auxiliary message: {}", hex::encode(aux_msg))));
+    }
+
+    // The signature verifies under the key randomized with the randomizer
+    // derived from the auxiliary message, which is recorded in the audit log.
+    let audit_log = std::fs::read_to_string(&audit_log_path)?;
+    let record: AuditRecord<PallasBlake2b512> =
+        serde_json::from_str(audit_log.lines().next().unwrap())?;
+    let randomizer = record.randomizer.unwrap();
+    let randomized_params =
+        RandomizedParams::from_randomizer(public_key_package.verifying_key(), randomizer);
+    randomized_params
+        .randomized_verifying_key()
+        .verify(message, &signature)?;
+
+    Ok(())
+}
+
+/// Test if a message much larger than a single Noise message can be signed:
+/// the signing package must be split into chunks on the way to the
+/// participants.
+#[tokio::test]
+async fn test_http_large_message() -> Result<(), Box<dyn std::error::Error>> {
+    use frost_ed25519::Ed25519Sha512;
+
+    let (cacert, _temp_dir) = spawn_tls_server(2757).await?;
+
+    let mut rng = thread_rng();
+    let (shares, public_key_package) = frost::keys::generate_with_dealer::<Ed25519Sha512, _>(
+        2,
+        2,
+        frost::keys::IdentifierList::Default,
+        &mut rng,
+    )?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let coordinator_keypair = builder.generate_keypair().unwrap();
+    let participant_keypairs = [
+        builder.generate_keypair().unwrap(),
+        builder.generate_keypair().unwrap(),
+    ];
+    let participant_pubkeys = participant_keypairs
+        .iter()
+        .map(|k| k.public.clone())
+        .collect::<Vec<_>>();
+
+    // Its hex encoding in the signing package takes several chunks.
+    let message = (0..100 * 1024).map(|i| i as u8).collect::<Vec<_>>();
+    let pargs = coordinator_pargs(
+        2757,
+        &cacert,
+        &public_key_package,
+        &coordinator_keypair,
+        &participant_pubkeys,
+        &message,
+    );
+    let mut coordinator_input = "".as_bytes();
+    let mut coordinator_logger = Vec::new();
+    let coordinator = coordinator::cli::cli_for_processed_args(
+        pargs,
+        &mut coordinator_input,
+        &mut coordinator_logger,
+    );
+
+    let mut participants =
+        shares
+            .values()
+            .zip(participant_keypairs.iter())
+            .map(|(share, keypair)| {
+                let pargs = participant_pargs(
+                    2757,
+                    &cacert,
+                    frost::keys::KeyPackage::try_from(share.clone()).unwrap(),
+                    keypair,
+                    &coordinator_keypair.public,
+                );
+                async move {
+                    // Give the coordinator time to create the session.
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    participant::cli::cli_for_processed_args(
+                        pargs,
+                        &mut "".as_bytes(),
+                        &mut Vec::new(),
+                    )
+                    .await
+                }
+            });
+    let (p1, p2) = (participants.next().unwrap(), participants.next().unwrap());
+
+    let (signature, r1, r2) = tokio::join!(coordinator, p1, p2);
+    r1?;
+    r2?;
+    public_key_package
+        .verifying_key()
+        .verify(&message, &signature?)?;
+
+    Ok(())
+}
+
+/// Test if the coordinator names the participant which did not respond
+/// within the participant timeout, and if it proceeds without it when enough
+/// participants responded to reach the given threshold.
+#[tokio::test]
+async fn test_http_participant_timeout() -> Result<(), Box<dyn std::error::Error>> {
+    use coordinator::comms::http::ParticipantsTimedOut;
+
+    let (cacert, _temp_dir) = spawn_tls_server(2753).await?;
+
+    let mut rng = thread_rng();
+    let (shares, public_key_package) = frost_ed25519::keys::generate_with_dealer(
+        3,
+        2,
+        frost_ed25519::keys::IdentifierList::Default,
+        &mut rng,
+    )?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let coordinator_keypair = builder.generate_keypair().unwrap();
+
+    let message = b"message to sign";
+    for threshold in [None, Some(2)] {
+        // Use new participant keys for each session, so that participants
+        // find it without being told its ID.
+        let participant_keypairs = [
+            builder.generate_keypair().unwrap(),
+            builder.generate_keypair().unwrap(),
+            builder.generate_keypair().unwrap(),
+        ];
+        let participant_pubkeys = participant_keypairs
+            .iter()
+            .map(|k| k.public.clone())
+            .collect::<Vec<_>>();
+        // The last participant never responds.
+        let laggard_pubkey = participant_pubkeys[2].clone();
+
+        let mut participants = shares
+            .values()
+            .zip(participant_keypairs.iter())
+            .take(2)
+            .map(|(share, keypair)| {
+                let pargs = participant_pargs(
+                    2753,
+                    &cacert,
+                    frost_ed25519::keys::KeyPackage::try_from(share.clone()).unwrap(),
+                    keypair,
+                    &coordinator_keypair.public,
+                );
+                async move {
+                    // Give the coordinator time to create the session.
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    participant::cli::cli_for_processed_args(
+                        pargs,
+                        &mut "".as_bytes(),
+                        &mut Vec::new(),
+                    )
+                    .await
+                }
+            });
+        let (p1, p2) = (participants.next().unwrap(), participants.next().unwrap());
+
+        let pargs = coordinator::args::ProcessedArgs {
+            participant_timeout: Some(Duration::from_secs(8)),
+            threshold,
+            ..coordinator_pargs(
+                2753,
+                &cacert,
+                &public_key_package,
+                &coordinator_keypair,
+                &participant_pubkeys,
+                message,
+            )
+        };
+        let coordinator = async {
+            let mut comms = coordinator::comms::http::HTTPComms::new(&pargs)?;
+            let mut input = "".as_bytes();
+            let mut logger = Vec::new();
+            let participants_config =
+                coordinator::step_1::step_1(&pargs, &mut comms, &mut input, &mut logger).await?;
+            let signing_package = coordinator::step_2::step_2(
+                &pargs,
+                &mut logger,
+                participants_config.commitments.clone(),
+            )?;
+            let signature = coordinator::step_3::step_3(
+                &pargs,
+                &mut comms,
+                &mut input,
+                &mut logger,
+                participants_config,
+                &signing_package,
+            )
+            .await?;
+            Ok::<_, Box<dyn Error>>(signature)
+        };
+
+        match threshold {
+            None => {
+                // The participants which responded keep waiting for the
+                // signing package, so only wait for the coordinator.
+                let participants = async { tokio::join!(p1, p2) };
+                let r = tokio::select! {
+                    r = coordinator => r,
+                    _ = participants => panic!("participants should not finish"),
+                };
+                let err = r.unwrap_err().downcast::<ParticipantsTimedOut>().unwrap();
+                assert_eq!(err.pubkeys, vec![laggard_pubkey.clone()]);
+                assert!(err.to_string().contains(&hex::encode(&laggard_pubkey)));
+            }
+            Some(_) => {
+                let (signature, r1, r2) = tokio::join!(coordinator, p1, p2);
+                r1?;
+                r2?;
+                public_key_package
+                    .verifying_key()
+                    .verify(message, &signature?)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Test if a participant waiting for the signing package stops with a clear
+/// error when the coordinator closes the session, instead of failing to parse
+/// the server's response.
+#[tokio::test]
+async fn test_http_session_closed() -> Result<(), Box<dyn std::error::Error>> {
+    let (cacert, _temp_dir) = spawn_tls_server(2747).await?;
+
+    let mut rng = thread_rng();
+    let (shares, _) = frost_ed25519::keys::generate_with_dealer(
+        2,
+        2,
+        frost_ed25519::keys::IdentifierList::Default,
+        &mut rng,
+    )?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let coordinator_keypair = builder.generate_keypair().unwrap();
+    let participant_keypairs = [
+        builder.generate_keypair().unwrap(),
+        builder.generate_keypair().unwrap(),
+    ];
+
+    let client = participant::comms::http::build_client(Some(&cacert), false, None)?;
+    let access_token = participant::comms::http::login(
+        &client,
+        "https://127.0.0.1:2747",
+        &coordinator_keypair.private,
+        &coordinator_keypair.public,
+    )
+    .await?;
+    let session_id = client
+        .post("https://127.0.0.1:2747/create_new_session")
+        .bearer_auth(&access_token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: participant_keypairs
+                .iter()
+                .map(|k| frostd::PublicKey(k.public.clone()))
+                .collect(),
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .send()
+        .await?
+        .json::<frostd::CreateNewSessionOutput>()
+        .await?
+        .session_id;
+
+    let pargs = participant::args::ProcessedArgs {
+        session_id: session_id.to_string(),
+        ..participant_pargs(
+            2747,
+            &cacert,
+            frost_ed25519::keys::KeyPackage::try_from(shares.values().next().unwrap().clone())?,
+            &participant_keypairs[0],
+            &coordinator_keypair.public,
+        )
+    };
+    let participant =
+        participant::cli::cli_for_processed_args(pargs, &mut "".as_bytes(), &mut Vec::new());
+
+    // Close the session while the participant is waiting for the signing
+    // package.
+    let coordinator = async {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        client
+            .post("https://127.0.0.1:2747/close_session")
+            .bearer_auth(&access_token)
+            .json(&frostd::CloseSessionArgs { session_id })
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok::<_, Box<dyn Error>>(())
+    };
+
+    let (r, c) = tokio::join!(participant, coordinator);
+    c?;
+    let err = r.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the session was closed by the coordinator before sending the signing package"
+    );
+
+    // The session is reported as gone rather than as an error.
+    let r = participant::comms::http::receive(
+        &client,
+        "https://127.0.0.1:2747",
+        &access_token,
+        &frostd::ReceiveArgs {
+            session_id,
+            as_coordinator: true,
+            peek: false,
+        },
+    )
+    .await?;
+    assert!(r.is_none());
+
+    Ok(())
+}
+
+/// Test if a participant refuses to join a session created by a coordinator
+/// other than the expected one.
+#[tokio::test]
+async fn test_http_unexpected_coordinator() -> Result<(), Box<dyn std::error::Error>> {
+    let (cacert, _temp_dir) = spawn_tls_server(2748).await?;
+
+    let mut rng = thread_rng();
+    let (shares, _) = frost_ed25519::keys::generate_with_dealer(
+        2,
+        2,
+        frost_ed25519::keys::IdentifierList::Default,
+        &mut rng,
+    )?;
+
+    let builder = snow::Builder::new("Noise_K_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let coordinator_keypair = builder.generate_keypair().unwrap();
+    let expected_coordinator_keypair = builder.generate_keypair().unwrap();
+    let participant_keypairs = [
+        builder.generate_keypair().unwrap(),
+        builder.generate_keypair().unwrap(),
+    ];
+
+    let client = participant::comms::http::build_client(Some(&cacert), false, None)?;
+    let access_token = participant::comms::http::login(
+        &client,
+        "https://127.0.0.1:2748",
+        &coordinator_keypair.private,
+        &coordinator_keypair.public,
+    )
+    .await?;
+    let session_id = client
+        .post("https://127.0.0.1:2748/create_new_session")
+        .bearer_auth(&access_token)
+        .json(&frostd::CreateNewSessionArgs {
+            pubkeys: participant_keypairs
+                .iter()
+                .map(|k| frostd::PublicKey(k.public.clone()))
+                .collect(),
+            message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
+        })
+        .send()
+        .await?
+        .json::<frostd::CreateNewSessionOutput>()
+        .await?
+        .session_id;
+
+    // The participant trusts the actual coordinator, but expects the session
+    // to have been created by another one.
+    let pargs = participant::args::ProcessedArgs {
+        session_id: session_id.to_string(),
+        expected_coordinator_pubkey: Some(expected_coordinator_keypair.public.clone()),
+        ..participant_pargs(
+            2748,
+            &cacert,
+            frost_ed25519::keys::KeyPackage::try_from(shares.values().next().unwrap().clone())?,
+            &participant_keypairs[0],
+            &coordinator_keypair.public,
+        )
+    };
+    let err = participant::cli::cli_for_processed_args(pargs, &mut "".as_bytes(), &mut Vec::new())
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "The session coordinator {} is not the expected one {}",
+            hex::encode(&coordinator_keypair.public),
+            hex::encode(&expected_coordinator_keypair.public)
+        )
+    );
+
+    // The participant did not send anything to the coordinator.
+    let r = participant::comms::http::receive(
+        &client,
+        "https://127.0.0.1:2748",
+        &access_token,
+        &frostd::ReceiveArgs {
+            session_id,
+            as_coordinator: true,
+            peek: false,
+        },
+    )
+    .await?
+    .expect("session is still open");
+    assert!(r.msgs.is_empty());
+
+    Ok(())
+}
+
+/// Test if a server which only accepts TLS 1.3 rejects TLS 1.2 clients.
+#[tokio::test]
+async fn test_http_tls_min_version() -> Result<(), Box<dyn std::error::Error>> {
+    use rcgen::{generate_simple_self_signed, CertifiedKey};
+
+    let subject_alt_names = vec!["127.0.0.1".to_string(), "localhost".to_string()];
+    let CertifiedKey { cert, key_pair } = generate_simple_self_signed(subject_alt_names).unwrap();
+    let temp_dir = tempfile::tempdir()?;
+    let cert_path = temp_dir.path().join("cert.pem");
+    let key_path = temp_dir.path().join("cert.key.pem");
+    std::fs::write(&cert_path, cert.pem())?;
+    std::fs::write(&key_path, key_pair.serialize_pem())?;
+
+    let args = Args {
+        ip: vec!["127.0.0.1".to_string()],
+        port: 2749,
+        tls_cert: Some(cert_path.to_str().unwrap().to_string()),
+        tls_key: Some(key_path.to_str().unwrap().to_string()),
+        no_tls_very_insecure: false,
+        tls_min_version: TlsVersion::Tls13,
+        max_participants: DEFAULT_MAX_PARTICIPANTS,
+        max_queue_len: DEFAULT_MAX_QUEUE_LEN,
+        queue_full: QueueFullPolicy::Reject,
+        session_timeout: DEFAULT_SESSION_TIMEOUT,
+        max_session_extension: DEFAULT_MAX_SESSION_EXTENSION,
+        admin_token: None,
+    };
+    tokio::spawn(async move {
+        frostd::run(&args).await.unwrap();
+    });
+
+    // Wait for server to start listening
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let client = reqwest::Client::builder()
+        .add_root_certificate(Certificate::from_pem(cert.pem().as_bytes())?)
+        .max_tls_version(reqwest::tls::Version::TLS_1_2)
+        .build()?;
+    let r = client
+        .post("https://127.0.0.1:2749/challenge")
+        .json(&frostd::ChallengeArgs {})
+        .send()
+        .await;
+    assert!(r.is_err());
+
+    let client = reqwest::Client::builder()
+        .add_root_certificate(Certificate::from_pem(cert.pem().as_bytes())?)
+        .build()?;
+    let r = client
+        .post("https://127.0.0.1:2749/challenge")
+        .json(&frostd::ChallengeArgs {})
+        .send()
+        .await?;
+    assert_eq!(r.status(), reqwest::StatusCode::OK);
+
+    Ok(())
+}
+
+/// Test if the server can listen on both IPv4 and IPv6 loopback addresses,
+/// and if failing to bind to an address does not prevent it from listening
+/// on the others.
+#[tokio::test]
+async fn test_http_multiple_ips() -> Result<(), Box<dyn std::error::Error>> {
+    use rcgen::{generate_simple_self_signed, CertifiedKey};
+
+    let subject_alt_names = vec!["127.0.0.1".to_string(), "::1".to_string()];
+    let CertifiedKey { cert, key_pair } = generate_simple_self_signed(subject_alt_names).unwrap();
+    let temp_dir = tempfile::tempdir()?;
+    let cert_path = temp_dir.path().join("cert.pem");
+    let key_path = temp_dir.path().join("cert.key.pem");
+    std::fs::write(&cert_path, cert.pem())?;
+    std::fs::write(&key_path, key_pair.serialize_pem())?;
+
+    let args = Args {
+        // 192.0.2.1 is reserved for documentation and is not assigned to any
+        // local interface, so binding to it fails.
+        ip: vec![
+            "127.0.0.1".to_string(),
+            "192.0.2.1".to_string(),
+            "::1".to_string(),
+        ],
+        port: 2750,
+        tls_cert: Some(cert_path.to_str().unwrap().to_string()),
+        tls_key: Some(key_path.to_str().unwrap().to_string()),
+        no_tls_very_insecure: false,
+        tls_min_version: TlsVersion::Tls12,
+        max_participants: DEFAULT_MAX_PARTICIPANTS,
+        max_queue_len: DEFAULT_MAX_QUEUE_LEN,
+        queue_full: QueueFullPolicy::Reject,
+        session_timeout: DEFAULT_SESSION_TIMEOUT,
+        max_session_extension: DEFAULT_MAX_SESSION_EXTENSION,
+        admin_token: None,
+    };
+    tokio::spawn(async move {
+        frostd::run(&args).await.unwrap();
+    });
+
+    // Wait for server to start listening
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let client = reqwest::Client::builder()
+        .add_root_certificate(Certificate::from_pem(cert.pem().as_bytes())?)
+        .build()?;
+    for host in ["127.0.0.1", "[::1]"] {
+        let r = client
+            .post(format!("https://{}:2750/challenge", host))
+            .json(&frostd::ChallengeArgs {})
+            .send()
+            .await?;
+        assert_eq!(r.status(), reqwest::StatusCode::OK);
+    }
+
+    Ok(())
+}
+
+/// Smoke test the load testing tool with a few concurrent ceremonies.
+#[tokio::test]
+async fn test_bench() -> Result<(), Box<dyn std::error::Error>> {
+    use rcgen::{generate_simple_self_signed, CertifiedKey};
+
+    let subject_alt_names = vec!["127.0.0.1".to_string(), "localhost".to_string()];
+    let CertifiedKey { cert, key_pair } = generate_simple_self_signed(subject_alt_names).unwrap();
+    let temp_dir = tempfile::tempdir()?;
+    let cert_path = temp_dir.path().join("cert.pem");
+    let key_path = temp_dir.path().join("cert.key.pem");
+    std::fs::write(&cert_path, cert.pem())?;
+    std::fs::write(&key_path, key_pair.serialize_pem())?;
+    let cacert = Some(cert_path.to_str().unwrap().to_string());
+
+    let args = Args {
+        ip: vec!["127.0.0.1".to_string()],
+        port: 2752,
+        tls_cert: cacert.clone(),
+        tls_key: Some(key_path.to_str().unwrap().to_string()),
+        no_tls_very_insecure: false,
+        tls_min_version: TlsVersion::Tls12,
+        max_participants: DEFAULT_MAX_PARTICIPANTS,
+        max_queue_len: DEFAULT_MAX_QUEUE_LEN,
+        queue_full: QueueFullPolicy::Reject,
+        session_timeout: DEFAULT_SESSION_TIMEOUT,
+        max_session_extension: DEFAULT_MAX_SESSION_EXTENSION,
+        admin_token: None,
+    };
+    tokio::spawn(async move {
+        frostd::run(&args).await.unwrap();
+    });
+
+    // Wait for server to start listening
+    tokio::time::sleep(Duration::from_secs(2)).await;
+
+    let report = frostd::bench::run_bench(&frostd::bench::BenchArgs {
+        url: "https://127.0.0.1:2752".to_string(),
+        sessions: 4,
+        count: 2,
+        participants: 2,
+        cacert,
+        insecure_skip_verify: false,
+    })
+    .await?;
+
+    assert_eq!(report.ceremonies, 8);
+    assert_eq!(report.create_new_session.count(), 8);
+    // Each participant sends its commitments and signature share, and the
+    // coordinator sends the signing package to each of them.
+    assert_eq!(report.send.count(), 8 * 2 * 3);
+    // At least one call for each message received.
+    assert!(report.receive.count() >= 8 * 2 * 3);
+    assert!(report.send.percentile(50.0) <= report.send.percentile(100.0));
+
+    Ok(())
+}
+
 /// Actually spawn the HTTP server and connect to it using reqwest.
 /// A better example on how to write client code.
 #[tokio::test]
@@ -403,7 +3611,7 @@ async fn test_http() -> Result<(), Box<dyn std::error::Error>> {
     // Spawn server for testing
     tokio::spawn(async move {
         frostd::run(&Args {
-            ip: "127.0.0.1".to_string(),
+            ip: vec!["127.0.0.1".to_string()],
             port: 2744,
             tls_cert: Some(
                 temp_dir
@@ -422,6 +3630,13 @@ async fn test_http() -> Result<(), Box<dyn std::error::Error>> {
                     .to_string(),
             ),
             no_tls_very_insecure: false,
+            tls_min_version: TlsVersion::Tls12,
+            max_participants: DEFAULT_MAX_PARTICIPANTS,
+            max_queue_len: DEFAULT_MAX_QUEUE_LEN,
+            queue_full: QueueFullPolicy::Reject,
+            session_timeout: DEFAULT_SESSION_TIMEOUT,
+            max_session_extension: DEFAULT_MAX_SESSION_EXTENSION,
+            admin_token: None,
         })
         .await
         .unwrap();
@@ -484,6 +3699,10 @@ async fn test_http() -> Result<(), Box<dyn std::error::Error>> {
                 frostd::PublicKey(bob_keypair.public.clone()),
             ],
             message_count: 1,
+            coordinators: vec![],
+            close_quorum: None,
+            ciphersuite: None,
+            message_commitment: None,
         })
         .send()
         .await?;
@@ -1,18 +1,23 @@
 use std::{
+    collections::BTreeMap,
     env,
     error::Error,
     io::{BufRead, Write},
+    path::PathBuf,
     rc::Rc,
+    time::Duration,
 };
 
 use clap::Parser;
-use eyre::eyre;
+use eyre::{eyre, OptionExt};
 use frost_core::{
     keys::{KeyPackage, SecretShare},
+    serde::{self, Deserialize},
     Ciphersuite,
 };
+use frostd::Uuid;
 
-use crate::input::read_from_file_or_stdin;
+use crate::{input::read_from_file_or_stdin, offline::OfflinePhase};
 
 #[derive(Parser, Debug, Default)]
 #[command(author, version, about, long_about = None)]
@@ -32,6 +37,17 @@ pub struct Args {
     #[arg(short = 'k', long, default_value = "key-package-1.json")]
     pub key_package: String,
 
+    /// Load the key package from the frost-client config instead, from the
+    /// group with the given hex-encoded public key (as listed by
+    /// `frost-client groups`). If specified, `key_package` is ignored.
+    #[arg(long)]
+    pub group: Option<String>,
+
+    /// Path to the frost-client config file, used with `group`. If not
+    /// specified, the default frost-client config path is used.
+    #[arg(long)]
+    pub config: Option<String>,
+
     /// IP to connect to, if using online comms
     #[arg(short, long, default_value = "127.0.0.1")]
     pub ip: String,
@@ -43,6 +59,63 @@ pub struct Args {
     /// Optional Session ID
     #[arg(short, long, default_value = "")]
     pub session_id: String,
+
+    /// Path to the PEM-encoded certificate of the CA which issued the server
+    /// certificate, if it is not trusted by the system (e.g. a private CA).
+    /// For HTTP mode.
+    #[arg(long)]
+    pub cacert: Option<String>,
+
+    /// Do not verify the server certificate. This is very insecure and should
+    /// only be used for testing. For HTTP mode.
+    #[arg(long, default_value_t = false)]
+    pub insecure_skip_verify: bool,
+
+    /// The proxy to connect to the server through, e.g.
+    /// "http://proxy.example.com:3128". If not specified, the `HTTPS_PROXY`
+    /// and `ALL_PROXY` environment variables are used, if set. For HTTP mode.
+    #[arg(long)]
+    pub proxy: Option<String>,
+
+    /// Show the message to be signed and ask for confirmation before signing
+    /// it. If declined, the coordinator is told so and nothing is signed.
+    #[arg(long, default_value_t = false)]
+    pub confirm: bool,
+
+    /// Offline signing, step 1: save the signing package received from the
+    /// coordinator to the given file instead of signing it, to be signed
+    /// with `--sign-offline`. The file holds the secret nonces too.
+    #[arg(long)]
+    pub save_signing_package: Option<String>,
+
+    /// Offline signing, step 2: sign the signing package saved with
+    /// `--save-signing-package`, writing the signature share to
+    /// `--signature-share-out`. This does not contact the coordinator. The
+    /// signing package file is deleted afterwards, since its nonces must never
+    /// be used again.
+    #[arg(long)]
+    pub sign_offline: Option<String>,
+
+    /// The file to write the signature share to, with `--sign-offline`.
+    #[arg(long)]
+    pub signature_share_out: Option<String>,
+
+    /// Offline signing, step 3: send the signature share written with
+    /// `--sign-offline` to the coordinator.
+    #[arg(long)]
+    pub send_signature_share: Option<String>,
+
+    /// The file where the hashes of the commitments already used to sign are
+    /// recorded, to refuse signing twice with the same nonces, e.g. if a saved
+    /// signing package is signed again. If not specified, they are only
+    /// checked within the current run.
+    #[arg(long)]
+    pub used_commitments: Option<String>,
+
+    /// On failure, print the error to standard error as a JSON object with
+    /// `error` and `code` fields, for scripts, instead of as plain text.
+    #[arg(long, default_value_t = false)]
+    pub json_errors: bool,
 }
 
 #[derive(Clone)]
@@ -70,6 +143,16 @@ pub struct ProcessedArgs<C: Ciphersuite> {
     /// Optional Session ID
     pub session_id: String,
 
+    /// Path to the PEM-encoded certificate of the CA which issued the server
+    /// certificate, if any. For HTTP mode.
+    pub cacert: Option<String>,
+
+    /// Do not verify the server certificate. For HTTP mode.
+    pub insecure_skip_verify: bool,
+
+    /// The proxy to connect to the server through, if any. For HTTP mode.
+    pub proxy: Option<String>,
+
     /// The participant's communication private key for HTTP mode.
     pub comm_privkey: Option<Vec<u8>>,
 
@@ -83,6 +166,36 @@ pub struct ProcessedArgs<C: Ciphersuite> {
     // require a lot of code change for something simple.
     #[allow(clippy::type_complexity)]
     pub comm_coordinator_pubkey_getter: Option<Rc<dyn Fn(&Vec<u8>) -> Option<Vec<u8>>>>,
+
+    /// The communication public key that the coordinator of the session must
+    /// have, if any. Joining a session created by anyone else is refused.
+    /// For HTTP mode.
+    pub expected_coordinator_pubkey: Option<Vec<u8>>,
+
+    /// The file where the hashes of the commitments already used to sign are
+    /// recorded, to refuse signing twice with the same nonces. If `None`,
+    /// they are only checked within the current run.
+    pub used_commitments_path: Option<PathBuf>,
+
+    /// Ask the user to confirm the message before signing it.
+    pub confirm: bool,
+
+    /// A function called with the session ID and the communication public
+    /// key of its coordinator once the session is joined, e.g. to keep a
+    /// record of it. For HTTP mode.
+    #[allow(clippy::type_complexity)]
+    pub on_session: Option<Rc<dyn Fn(Uuid, &[u8])>>,
+
+    /// How often to refresh the session while waiting for the coordinator,
+    /// so that the server does not time it out. For HTTP mode.
+    pub keep_alive: Option<Duration>,
+
+    /// Compress messages sent to the coordinator before encrypting them.
+    /// Received messages say whether they are compressed. For HTTP mode.
+    pub compress: bool,
+
+    /// The step of offline signing to run, if signing offline.
+    pub offline: Option<OfflinePhase>,
 }
 
 impl<C: Ciphersuite + 'static> ProcessedArgs<C> {
@@ -94,13 +207,17 @@ impl<C: Ciphersuite + 'static> ProcessedArgs<C> {
         input: &mut dyn BufRead,
         output: &mut dyn Write,
     ) -> Result<Self, Box<dyn Error>> {
-        let bytes = read_from_file_or_stdin(input, output, "key package", &args.key_package)?;
-
-        let key_package = if let Ok(secret_share) = serde_json::from_str::<SecretShare<C>>(&bytes) {
-            KeyPackage::try_from(secret_share)?
+        let key_package = if let Some(group) = &args.group {
+            read_key_package_from_config(args.config.clone(), group)?
         } else {
-            // TODO: Improve error
-            serde_json::from_str::<KeyPackage<C>>(&bytes)?
+            let bytes = read_from_file_or_stdin(input, output, "key package", &args.key_package)?;
+
+            if let Ok(secret_share) = serde_json::from_str::<SecretShare<C>>(&bytes) {
+                KeyPackage::try_from(secret_share)?
+            } else {
+                // TODO: Improve error
+                serde_json::from_str::<KeyPackage<C>>(&bytes)?
+            }
         };
 
         Ok(ProcessedArgs {
@@ -110,13 +227,67 @@ impl<C: Ciphersuite + 'static> ProcessedArgs<C> {
             ip: args.ip.clone(),
             port: args.port,
             session_id: args.session_id.clone(),
+            cacert: args.cacert.clone(),
+            insecure_skip_verify: args.insecure_skip_verify,
+            proxy: args.proxy.clone(),
             comm_privkey: None,
             comm_pubkey: None,
             comm_coordinator_pubkey_getter: None,
+            expected_coordinator_pubkey: None,
+            used_commitments_path: args.used_commitments.as_ref().map(PathBuf::from),
+            confirm: args.confirm,
+            on_session: None,
+            keep_alive: None,
+            compress: false,
+            offline: OfflinePhase::from_args(
+                args.save_signing_package.as_deref(),
+                args.sign_offline.as_deref(),
+                args.signature_share_out.as_deref(),
+                args.send_signature_share.as_deref(),
+            )?,
         })
     }
 }
 
+/// The subset of the frost-client config needed to load a key package.
+#[derive(Deserialize)]
+#[serde(crate = "self::serde")]
+struct ClientConfig {
+    #[serde(default)]
+    group: BTreeMap<String, ClientGroup>,
+}
+
+/// The subset of a frost-client config group needed to load a key package.
+#[derive(Deserialize)]
+#[serde(crate = "self::serde")]
+struct ClientGroup {
+    ciphersuite: String,
+    #[serde(deserialize_with = "serdect::slice::deserialize_hex_or_bin_vec")]
+    key_package: Vec<u8>,
+}
+
+/// Read the key package for the given group from the frost-client config at
+/// `path`, or at the default frost-client config path if None.
+pub fn read_key_package_from_config<C: Ciphersuite>(
+    path: Option<String>,
+    group: &str,
+) -> Result<KeyPackage<C>, Box<dyn Error>> {
+    let path = if let Some(path) = path {
+        PathBuf::from(path)
+    } else {
+        dirs::config_local_dir()
+            .ok_or_eyre("could not find the config directory")?
+            .join("frost")
+            .join("credentials.toml")
+    };
+    let config: ClientConfig = toml::from_str(&std::fs::read_to_string(&path)?)?;
+    let group = config.group.get(group).ok_or_eyre("Group not found")?;
+    if group.ciphersuite != C::ID {
+        return Err(eyre!("group uses a different ciphersuite").into());
+    }
+    Ok(postcard::from_bytes(&group.key_package)?)
+}
+
 pub fn read_password(password_env_name: &str) -> Result<String, Box<dyn Error>> {
     if password_env_name.is_empty() {
         Ok(
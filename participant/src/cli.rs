@@ -1,19 +1,28 @@
 use crate::args::{Args, ProcessedArgs};
 
 use crate::comms::cli::CLIComms;
-use crate::comms::http::HTTPComms;
+use crate::comms::http::{self, HTTPComms};
 use crate::comms::socket::SocketComms;
 
 use crate::comms::Comms;
+use crate::nonce_guard::UsedCommitments;
+use crate::offline::{self, OfflinePhase, SavedSigningPackage};
 
 use crate::round1::{generate_nonces_and_commitments, print_values};
-use crate::round2::{generate_signature, print_values_round_2, round_2_request_inputs};
+use crate::round2::{
+    confirm_signing, generate_signature, print_values_round_2, round_2_request_inputs,
+    validate_own_commitments,
+};
+use eyre::{eyre, OptionExt};
 use frost_core::Ciphersuite;
 use frost_ed25519::Ed25519Sha512;
 use frost_rerandomized::RandomizedCiphersuite;
+use frostd::Uuid;
+use futures::future::join_all;
 use rand::thread_rng;
 use reddsa::frost::redpallas::PallasBlake2b512;
 use std::io::{BufRead, Write};
+use std::path::Path;
 
 pub async fn cli<C: RandomizedCiphersuite + 'static>(
     args: &Args,
@@ -29,6 +38,14 @@ pub async fn cli_for_processed_args<C: RandomizedCiphersuite + 'static>(
     input: &mut impl BufRead,
     logger: &mut impl Write,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(OfflinePhase::Sign {
+        signing_package,
+        signature_share,
+    }) = &pargs.offline
+    {
+        return sign_offline(&pargs, signing_package, signature_share, input, logger);
+    }
+
     let mut comms: Box<dyn Comms<C>> = if pargs.cli {
         Box::new(CLIComms::new())
     } else if pargs.http {
@@ -37,12 +54,32 @@ pub async fn cli_for_processed_args<C: RandomizedCiphersuite + 'static>(
         Box::new(SocketComms::new(&pargs))
     };
 
+    run_with_comms(pargs, &mut *comms, input, logger).await
+}
+
+/// Run the signing protocol as a participant, communicating with the
+/// coordinator through the given `comms`.
+pub async fn run_with_comms<C: RandomizedCiphersuite + 'static>(
+    pargs: ProcessedArgs<C>,
+    comms: &mut dyn Comms<C>,
+    input: &mut impl BufRead,
+    logger: &mut impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
     // Round 1
 
     let key_package = pargs.key_package;
 
     writeln!(logger, "Key Package succesfully created.")?;
 
+    if let Some(OfflinePhase::SendSignatureShare(path)) = &pargs.offline {
+        let signature = offline::load_signature_share::<C>(path)?;
+        comms
+            .send_signature_share(*key_package.identifier(), signature)
+            .await?;
+        writeln!(logger, "Signature share sent.")?;
+        return Ok(());
+    }
+
     let mut rng = thread_rng();
     let (nonces, commitments) = generate_nonces_and_commitments(&key_package, &mut rng);
 
@@ -67,7 +104,50 @@ pub async fn cli_for_processed_args<C: RandomizedCiphersuite + 'static>(
         rerandomized,
     )
     .await?;
-    let signature = generate_signature(round_2_config, &key_package, &nonces)?;
+    if let Err(e) = validate_own_commitments(
+        &round_2_config.signing_package,
+        *key_package.identifier(),
+        &commitments,
+    ) {
+        report_error(comms, &e.to_string()).await;
+        return Err(e);
+    }
+    if let Some(OfflinePhase::SaveSigningPackage(path)) = &pargs.offline {
+        offline::save_signing_package(
+            path,
+            &SavedSigningPackage {
+                identifier: *key_package.identifier(),
+                nonces,
+                commitments,
+                signing_package: round_2_config.signing_package,
+                randomizer: round_2_config.randomizer,
+                aux_msg: round_2_config.aux_msg,
+                seed: round_2_config.seed,
+            },
+        )?;
+        writeln!(
+            logger,
+            "Signing package saved to {}; sign it with --sign-offline.",
+            path.display()
+        )?;
+        return Ok(());
+    }
+    if pargs.confirm && !confirm_signing(&round_2_config, input, logger)? {
+        comms.send_decline("declined by the user").await?;
+        writeln!(logger, "Declined to sign.")?;
+        return Ok(());
+    }
+    // Never sign twice with the same nonces, which would reveal the signing
+    // share.
+    UsedCommitments::load(pargs.used_commitments_path)?.mark_used(&commitments)?;
+
+    let signature = match generate_signature(round_2_config, &key_package, &nonces) {
+        Ok(signature) => signature,
+        Err(e) => {
+            report_error(comms, &e.to_string()).await;
+            return Err(e.into());
+        }
+    };
 
     comms
         .send_signature_share(*key_package.identifier(), signature)
@@ -77,3 +157,140 @@ pub async fn cli_for_processed_args<C: RandomizedCiphersuite + 'static>(
 
     Ok(())
 }
+
+/// Run the signing protocol as a participant in several sessions of the
+/// server at once, logging in only once. Each session is signed as soon as
+/// its signing package arrives, regardless of the others. If `session_ids` is
+/// empty, all the active sessions of the user are joined.
+///
+/// Since the sessions run concurrently, their logs are written to `logger`
+/// one session after the other once all of them are done. Asking for
+/// confirmation and offline signing are not supported.
+pub async fn run_sessions<C: RandomizedCiphersuite + 'static>(
+    pargs: ProcessedArgs<C>,
+    session_ids: Vec<Uuid>,
+    logger: &mut impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if pargs.confirm {
+        return Err(eyre!("confirmation is not supported when joining several sessions").into());
+    }
+    if pargs.offline.is_some() {
+        return Err(eyre!("offline signing is not supported when joining several sessions").into());
+    }
+
+    let client = http::build_client(
+        pargs.cacert.as_deref(),
+        pargs.insecure_skip_verify,
+        pargs.proxy.as_deref(),
+    )?;
+    let host_port = format!("https://{}:{}", pargs.ip, pargs.port);
+    let access_token = http::login(
+        &client,
+        &host_port,
+        pargs
+            .comm_privkey
+            .as_deref()
+            .ok_or_eyre("comm_privkey must be specified")?,
+        pargs
+            .comm_pubkey
+            .as_deref()
+            .ok_or_eyre("comm_pubkey must be specified")?,
+    )
+    .await?;
+
+    let session_ids = if session_ids.is_empty() {
+        http::list_sessions(&client, &host_port, &access_token).await?
+    } else {
+        session_ids
+    };
+    if session_ids.is_empty() {
+        return Err(eyre!("user has no active sessions").into());
+    }
+
+    let runs = session_ids.iter().map(|&session_id| {
+        let mut comms =
+            HTTPComms::with_login(&pargs, client.clone(), access_token.clone(), session_id);
+        let pargs = pargs.clone();
+        async move {
+            let mut log = Vec::new();
+            let r = run_with_comms(pargs, &mut comms, &mut std::io::empty(), &mut log).await;
+            (session_id, r, log)
+        }
+    });
+    let results = join_all(runs).await;
+    http::logout(&client, &host_port, &access_token).await?;
+
+    let mut failed = 0;
+    for (session_id, r, log) in results {
+        writeln!(logger, "Session {}:", session_id)?;
+        logger.write_all(&log)?;
+        if let Err(e) = r {
+            writeln!(logger, "Session {} failed: {}", session_id, e)?;
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(eyre!("{} of {} sessions failed", failed, session_ids.len()).into());
+    }
+
+    Ok(())
+}
+
+/// Sign the signing package saved in `signing_package_path` by a previous
+/// run with [`OfflinePhase::SaveSigningPackage`], writing the signature share
+/// to `signature_share_path`. This does not communicate with the coordinator.
+pub fn sign_offline<C: RandomizedCiphersuite + 'static>(
+    pargs: &ProcessedArgs<C>,
+    signing_package_path: &Path,
+    signature_share_path: &Path,
+    input: &mut impl BufRead,
+    logger: &mut impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let key_package = &pargs.key_package;
+    let saved = offline::load_signing_package::<C>(signing_package_path)?;
+    if saved.identifier != *key_package.identifier() {
+        return Err(eyre!("the signing package was saved by another participant").into());
+    }
+    validate_own_commitments(
+        &saved.signing_package,
+        *key_package.identifier(),
+        &saved.commitments,
+    )?;
+
+    let round_2_config = saved.round_2_config();
+    if !round_2_config.aux_msg.is_empty() {
+        writeln!(
+            logger,
+            "Auxiliary message: {}",
+            hex::encode(&round_2_config.aux_msg)
+        )?;
+    }
+    if pargs.confirm && !confirm_signing(&round_2_config, input, logger)? {
+        writeln!(
+            logger,
+            "Declined to sign. The coordinator must be told out of band."
+        )?;
+        return Ok(());
+    }
+    UsedCommitments::load(pargs.used_commitments_path.clone())?.mark_used(&saved.commitments)?;
+
+    let signature = generate_signature(round_2_config, key_package, &saved.nonces)?;
+    offline::save_signature_share(signature_share_path, &signature)?;
+    // The nonces must never be used again, and nothing else needs them.
+    std::fs::remove_file(signing_package_path)?;
+
+    print_values_round_2(signature, logger)?;
+
+    Ok(())
+}
+
+/// Report an error to the coordinator so that it can abort the session.
+/// Failing to do so is only logged, since the original error matters more.
+async fn report_error<C: Ciphersuite>(comms: &mut dyn Comms<C>, reason: &str) {
+    if let Err(send_err) = comms.send_error(reason).await {
+        eprintln!(
+            "Could not report the error to the coordinator: {}",
+            send_err
+        );
+    }
+}
@@ -1,3 +1,4 @@
+pub mod channel;
 pub mod cli;
 pub mod http;
 pub mod socket;
@@ -18,6 +19,8 @@ use frost::{
     Identifier,
 };
 
+use crate::round2::Round2Config;
+
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "self::serde")]
 #[serde(bound = "C: Ciphersuite")]
@@ -32,6 +35,10 @@ pub enum Message<C: Ciphersuite> {
         randomizer: Option<frost_rerandomized::Randomizer<C>>,
     },
     SignatureShare(SignatureShare<C>),
+    /// Sent instead of a SignatureShare if the participant declined to sign.
+    Declined {
+        reason: String,
+    },
 }
 
 #[async_trait(?Send)]
@@ -43,17 +50,26 @@ pub trait Comms<C: Ciphersuite> {
         commitments: SigningCommitments<C>,
         identifier: Identifier<C>,
         rerandomized: bool,
-    ) -> Result<
-        (
-            frost::SigningPackage<C>,
-            Option<frost_rerandomized::Randomizer<C>>,
-        ),
-        Box<dyn Error>,
-    >;
+    ) -> Result<Round2Config<C>, Box<dyn Error>>;
 
     async fn send_signature_share(
         &mut self,
         identifier: Identifier<C>,
         signature_share: SignatureShare<C>,
     ) -> Result<(), Box<dyn Error>>;
+
+    /// Report to the coordinator that the participant could not proceed, so
+    /// that it can abort instead of waiting for the participant. Does nothing
+    /// by default, for comms where the user relays messages manually.
+    async fn send_error(&mut self, _reason: &str) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    /// Tell the coordinator that the participant declined to sign, so that
+    /// it can abort instead of waiting for the signature share, and leave the
+    /// session. Does nothing by default, for comms where the user relays
+    /// messages manually.
+    async fn send_decline(&mut self, _reason: &str) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
 }
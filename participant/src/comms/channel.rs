@@ -0,0 +1,103 @@
+//! In-memory implementation of the Comms trait, using channels. It does not
+//! require a server nor encryption, which makes it useful for testing the
+//! protocol logic.
+
+use async_trait::async_trait;
+
+use frost_core::{self as frost, Ciphersuite};
+
+use eyre::eyre;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+use frost::{round1::SigningCommitments, round2::SignatureShare, Identifier};
+
+use std::{
+    error::Error,
+    io::{BufRead, Write},
+};
+
+use super::{Comms, Message};
+use crate::round2::Round2Config;
+
+pub struct ChannelComms<C: Ciphersuite> {
+    /// The index of this participant, used by the coordinator to tell
+    /// which participant sent each message.
+    index: usize,
+    /// Sends messages to the coordinator, tagged with `index`.
+    tx: UnboundedSender<(usize, Message<C>)>,
+    /// Receives messages from the coordinator.
+    rx: UnboundedReceiver<Message<C>>,
+}
+
+impl<C: Ciphersuite> ChannelComms<C> {
+    /// Create a new ChannelComms for the participant with the given index.
+    /// `tx` and `rx` must be connected to the coordinator's own comms.
+    pub fn new(
+        index: usize,
+        tx: UnboundedSender<(usize, Message<C>)>,
+        rx: UnboundedReceiver<Message<C>>,
+    ) -> Self {
+        Self { index, tx, rx }
+    }
+
+    fn send(&self, message: Message<C>) -> Result<(), Box<dyn Error>> {
+        self.tx
+            .send((self.index, message))
+            .map_err(|_| eyre!("coordinator disconnected"))?;
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl<C> Comms<C> for ChannelComms<C>
+where
+    C: Ciphersuite + 'static,
+{
+    async fn get_signing_package(
+        &mut self,
+        _input: &mut dyn BufRead,
+        _output: &mut dyn Write,
+        commitments: SigningCommitments<C>,
+        identifier: Identifier<C>,
+        _rerandomized: bool,
+    ) -> Result<Round2Config<C>, Box<dyn Error>> {
+        self.send(Message::IdentifiedCommitments {
+            identifier,
+            commitments,
+        })?;
+
+        let message = self
+            .rx
+            .recv()
+            .await
+            .ok_or(eyre!("Did not receive signing package!"))?;
+        if let Message::SigningPackageAndRandomizer {
+            signing_package,
+            randomizer,
+        } = message
+        {
+            Ok(Round2Config {
+                signing_package,
+                randomizer,
+                aux_msg: Vec::new(),
+                seed: Vec::new(),
+            })
+        } else {
+            Err(eyre!("Expected SigningPackage message"))?
+        }
+    }
+
+    async fn send_signature_share(
+        &mut self,
+        _identifier: Identifier<C>,
+        signature_share: SignatureShare<C>,
+    ) -> Result<(), Box<dyn Error>> {
+        self.send(Message::SignatureShare(signature_share))
+    }
+
+    async fn send_decline(&mut self, reason: &str) -> Result<(), Box<dyn Error>> {
+        self.send(Message::Declined {
+            reason: reason.to_string(),
+        })
+    }
+}
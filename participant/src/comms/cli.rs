@@ -17,7 +17,7 @@ use std::{
     marker::PhantomData,
 };
 
-use crate::comms::Comms;
+use crate::{comms::Comms, input::read_line, round2::Round2Config};
 
 #[derive(Default)]
 pub struct CLIComms<C: Ciphersuite> {
@@ -47,18 +47,10 @@ where
         _commitments: SigningCommitments<C>,
         _identifier: Identifier<C>,
         rerandomized: bool,
-    ) -> Result<
-        (
-            frost::SigningPackage<C>,
-            Option<frost_rerandomized::Randomizer<C>>,
-        ),
-        Box<dyn Error>,
-    > {
+    ) -> Result<Round2Config<C>, Box<dyn Error>> {
         writeln!(output, "Enter the JSON-encoded SigningPackage:")?;
 
-        let mut signing_package_json = String::new();
-
-        input.read_line(&mut signing_package_json)?;
+        let signing_package_json = read_line(input)?;
 
         // TODO: change to return a generic Error and use a better error
         let signing_package: SigningPackage<C> = serde_json::from_str(signing_package_json.trim())?;
@@ -66,14 +58,23 @@ where
         if rerandomized {
             writeln!(output, "Enter the randomizer (hex string):")?;
 
-            let mut json = String::new();
-            input.read_line(&mut json).unwrap();
+            let json = read_line(input)?;
 
             let randomizer =
                 frost_rerandomized::Randomizer::<C>::deserialize(&hex::decode(json.trim())?)?;
-            Ok((signing_package, Some(randomizer)))
+            Ok(Round2Config {
+                signing_package,
+                randomizer: Some(randomizer),
+                aux_msg: Vec::new(),
+                seed: Vec::new(),
+            })
         } else {
-            Ok((signing_package, None))
+            Ok(Round2Config {
+                signing_package,
+                randomizer: None,
+                aux_msg: Vec::new(),
+                seed: Vec::new(),
+            })
         }
     }
 
@@ -89,8 +90,7 @@ where
 pub fn read_identifier<C: Ciphersuite + 'static>(
     input: &mut dyn BufRead,
 ) -> Result<Identifier<C>, Box<dyn Error>> {
-    let mut identifier_input = String::new();
-    input.read_line(&mut identifier_input)?;
+    let identifier_input = read_line(input)?;
     let bytes = hex::decode(identifier_input.trim())?;
     let identifier = Identifier::<C>::deserialize(&bytes)?;
     Ok(identifier)
@@ -1,23 +1,87 @@
 //! HTTP implementation of the Comms trait.
 
 use std::{
+    collections::VecDeque,
     error::Error,
+    future::Future,
     io::{BufRead, Write},
     marker::PhantomData,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use async_trait::async_trait;
 use eyre::{eyre, OptionExt};
-use frost_core::{
-    self as frost, round1::SigningCommitments, round2::SignatureShare, Ciphersuite, Identifier,
-};
+use frost_core::{round1::SigningCommitments, round2::SignatureShare, Ciphersuite, Identifier};
 use rand::thread_rng;
 use snow::{HandshakeState, TransportState};
+use thiserror::Error;
 use xeddsa::{xed25519, Sign as _};
 
 use super::Comms;
-use crate::args::ProcessedArgs;
+use crate::{
+    args::ProcessedArgs,
+    offline::OfflinePhase,
+    round2::{round_2_config_from_args, Round2Config},
+};
+
+/// The maximum size of a Noise message.
+const MAX_NOISE_MESSAGE_SIZE: usize = 65535;
+
+/// The version of the chunk format, sent as the first byte of each chunk so
+/// that peers using another format fail clearly instead of misreading it.
+/// Peers which predate chunking send the JSON plaintext as is, which starts
+/// with `{`.
+const CHUNK_FORMAT_VERSION: u8 = 3;
+
+/// The size of the header prepended to each chunk: the format version, the
+/// flags, then the chunk index and the total number of chunks, both encoded as
+/// big-endian u32.
+const CHUNK_HEADER_SIZE: usize = 10;
+
+/// The maximum size of the plaintext carried by a single chunk. This leaves
+/// room for the chunk header, the handshake data sent along with the first
+/// message, and the authentication tag.
+const MAX_CHUNK_SIZE: usize = MAX_NOISE_MESSAGE_SIZE - 1024;
+
+/// The chunk flag telling that the plaintext was compressed. The flags are
+/// the same in all chunks of a plaintext, and chunks with unknown flags set
+/// are rejected, so that each message says how to read it and peers never
+/// have to agree on it beforehand.
+const FLAG_COMPRESSED: u8 = 1;
+
+/// The chunk flag telling that the plaintext is the first one of a new Noise
+/// session, which replaces the previous one of the sender in the same FROST
+/// session; see [`Noise::with_restart()`].
+const FLAG_RESTART: u8 = 2;
+
+/// The Noise pattern used to encrypt messages between the coordinator and the
+/// participants.
+const NOISE_PARAMS: &str = "Noise_K_25519_ChaChaPoly_BLAKE2s";
+
+/// The maximum size of a decompressed plaintext, to prevent a peer from
+/// exhausting our memory with a small message that decompresses to a huge one.
+const MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+/// An error decrypting a message received through Noise.
+#[derive(Debug, Error)]
+pub enum DecryptError {
+    #[error(
+        "failed to decrypt message from {sender}: it is larger than the maximum Noise message size"
+    )]
+    TooLarge { sender: String },
+    #[error("failed to decrypt message from {sender}: possibly tampered or wrong key")]
+    Invalid {
+        sender: String,
+        #[source]
+        source: snow::Error,
+    },
+    #[error(
+        "failed to decrypt message from {sender}: unsupported message format version {version}; the peer is probably running an incompatible version"
+    )]
+    UnsupportedVersion { sender: String, version: u8 },
+    #[error("failed to decrypt message from {sender}: unsupported message flags {flags:#04x}")]
+    UnsupportedFlags { sender: String, flags: u8 },
+}
 
 /// A Noise state.
 ///
@@ -32,15 +96,180 @@ pub struct Noise {
     handshake_state: Option<HandshakeState>,
     /// The transport state; None before handshake is complete.
     transport_state: Option<TransportState>,
+    /// The plaintext of the chunks received so far by [`Self::decrypt()`].
+    pending: Vec<u8>,
+    /// The index of the next chunk expected by [`Self::decrypt()`].
+    next_chunk: u32,
+    /// The number of chunks of the plaintext being received.
+    chunk_count: u32,
+    /// The flags of the plaintext being received.
+    chunk_flags: u8,
+    /// Whether plaintexts are compressed before being encrypted. Each message
+    /// says whether it was compressed, so this only affects sending.
+    compress: bool,
+    /// Whether the plaintexts sent are marked as restarting the Noise session.
+    restart: bool,
+    /// The hex-encoded static public key of the peer, to tell where
+    /// undecryptable messages came from.
+    peer: String,
 }
 
 impl Noise {
     /// Create a new Noise state from a HandshakeState created with the `snow`
     /// crate.
     pub fn new(handshake_state: HandshakeState) -> Self {
+        let peer = handshake_state
+            .get_remote_static()
+            .map(hex::encode)
+            .unwrap_or_else(|| "unknown peer".to_string());
         Self {
             handshake_state: Some(handshake_state),
             transport_state: None,
+            pending: Vec::new(),
+            next_chunk: 0,
+            chunk_count: 0,
+            chunk_flags: 0,
+            compress: false,
+            restart: false,
+            peer,
+        }
+    }
+
+    /// Enable or disable compression of the plaintexts sent. When enabled, a
+    /// plaintext is only compressed if it actually makes it smaller, and its
+    /// chunks are flagged as such. Received plaintexts are decompressed as
+    /// flagged, whether or not this is enabled.
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Mark the plaintexts sent as the first ones of a new Noise session,
+    /// which replaces the previous one with the same peer in the same FROST
+    /// session, e.g. when the participant was restarted to sign offline. The
+    /// mark is encrypted, so the peer can check it with [`Self::restarted()`]
+    /// before accepting the new session.
+    pub fn with_restart(mut self, restart: bool) -> Self {
+        self.restart = restart;
+        self
+    }
+
+    /// Whether the plaintext being received, or the last one received, was
+    /// marked as restarting the Noise session with [`Self::with_restart()`].
+    pub fn restarted(&self) -> bool {
+        self.chunk_flags & FLAG_RESTART != 0
+    }
+
+    /// Encrypts a plaintext of any size, splitting it into as many Noise
+    /// messages as required. All of them must be passed, in order, to
+    /// [`Self::decrypt()`] on the receiving side.
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        let compressed;
+        let (plaintext, mut flags) = if self.compress {
+            compressed = miniz_oxide::deflate::compress_to_vec(plaintext, 6);
+            if compressed.len() < plaintext.len() {
+                (&compressed[..], FLAG_COMPRESSED)
+            } else {
+                (plaintext, 0)
+            }
+        } else {
+            (plaintext, 0)
+        };
+        if self.restart {
+            flags |= FLAG_RESTART;
+        }
+        let chunks: Vec<&[u8]> = if plaintext.is_empty() {
+            vec![plaintext]
+        } else {
+            plaintext.chunks(MAX_CHUNK_SIZE).collect()
+        };
+        let count = u32::try_from(chunks.len())?;
+        let mut messages = Vec::with_capacity(chunks.len());
+        for (index, chunk) in (0..count).zip(chunks) {
+            let mut payload = Vec::with_capacity(CHUNK_HEADER_SIZE + chunk.len());
+            payload.push(CHUNK_FORMAT_VERSION);
+            payload.push(flags);
+            payload.extend_from_slice(&index.to_be_bytes());
+            payload.extend_from_slice(&count.to_be_bytes());
+            payload.extend_from_slice(chunk);
+            let mut encrypted = vec![0; MAX_NOISE_MESSAGE_SIZE];
+            let len = self.write_message(&payload, &mut encrypted)?;
+            encrypted.truncate(len);
+            messages.push(encrypted);
+        }
+        Ok(messages)
+    }
+
+    /// Decrypts a message created with [`Self::encrypt()`]. Returns the
+    /// plaintext once its last chunk is received, or `None` if more chunks are
+    /// still expected.
+    pub fn decrypt(&mut self, message: &[u8]) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+        if message.len() > MAX_NOISE_MESSAGE_SIZE {
+            Err(DecryptError::TooLarge {
+                sender: self.peer.clone(),
+            })?;
+        }
+        let mut payload = vec![0; MAX_NOISE_MESSAGE_SIZE];
+        let len =
+            self.read_message(message, &mut payload)
+                .map_err(|source| DecryptError::Invalid {
+                    sender: self.peer.clone(),
+                    source,
+                })?;
+        payload.truncate(len);
+        if let Some(&version) = payload.first() {
+            if version != CHUNK_FORMAT_VERSION {
+                Err(DecryptError::UnsupportedVersion {
+                    sender: self.peer.clone(),
+                    version,
+                })?;
+            }
+        }
+        if payload.len() < CHUNK_HEADER_SIZE {
+            Err(eyre!("message chunk is too short"))?;
+        }
+        let (header, chunk) = payload.split_at(CHUNK_HEADER_SIZE);
+        let flags = header[1];
+        if flags & !(FLAG_COMPRESSED | FLAG_RESTART) != 0 {
+            self.pending.clear();
+            self.next_chunk = 0;
+            Err(DecryptError::UnsupportedFlags {
+                sender: self.peer.clone(),
+                flags,
+            })?;
+        }
+        let index = u32::from_be_bytes(header[2..6].try_into().expect("has 4 bytes"));
+        let count = u32::from_be_bytes(header[6..].try_into().expect("has 4 bytes"));
+        if index == 0 {
+            self.chunk_count = count;
+            self.chunk_flags = flags;
+        }
+        if index != self.next_chunk
+            || count != self.chunk_count
+            || flags != self.chunk_flags
+            || index >= count
+        {
+            self.pending.clear();
+            self.next_chunk = 0;
+            Err(eyre!("unexpected message chunk {} of {}", index, count))?;
+        }
+        self.pending.extend_from_slice(chunk);
+        if index + 1 == count {
+            self.next_chunk = 0;
+            let plaintext = std::mem::take(&mut self.pending);
+            if flags & FLAG_COMPRESSED == 0 {
+                return Ok(Some(plaintext));
+            }
+            Ok(Some(
+                miniz_oxide::inflate::decompress_to_vec_with_limit(
+                    &plaintext,
+                    MAX_DECOMPRESSED_SIZE,
+                )
+                .map_err(|e| eyre!("invalid compressed message: {:?}", e.status))?,
+            ))
+        } else {
+            self.next_chunk = index + 1;
+            Ok(None)
         }
     }
 
@@ -100,18 +329,332 @@ impl Noise {
     }
 }
 
+/// Create a builder for the Noise states used in the FROST session with the
+/// given ID. The session ID is the Noise prologue, so that the handshake fails
+/// if a message is replayed from another session.
+pub fn noise_builder(session_id: &Uuid) -> snow::Builder<'_> {
+    snow::Builder::new(NOISE_PARAMS.parse().expect("should be a valid cipher"))
+        .prologue(session_id.as_bytes())
+}
+
+/// Create a HTTP client to connect to the FROST server.
+///
+/// If `cacert` is specified, the PEM-encoded certificate in that path is added
+/// as a trusted root, which is required if the server certificate was issued
+/// by a private CA. If `insecure_skip_verify` is true, the server certificate
+/// is not verified at all, which should only be used for testing.
+///
+/// If `proxy` is specified, all requests are sent through it. Otherwise, the
+/// proxy set in the `HTTPS_PROXY` or `ALL_PROXY` environment variables (if
+/// any) is used.
+pub fn build_client(
+    cacert: Option<&str>,
+    insecure_skip_verify: bool,
+    proxy: Option<&str>,
+) -> Result<reqwest::Client, Box<dyn Error>> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+    }
+    if let Some(cacert) = cacert {
+        let pem = std::fs::read(cacert).map_err(|e| eyre!("error reading {}: {}", cacert, e))?;
+        builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&pem)?);
+    }
+    if insecure_skip_verify {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+    Ok(builder.build()?)
+}
+
+/// The maximum number of times a request is retried by
+/// [`RequestBuilderExt::send_with_retries()`].
+pub const MAX_RETRIES: usize = 3;
+
+/// The delay before the first retry; it doubles after each one.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Call `f` until it succeeds, retrying up to `retries` times if it fails with
+/// an error for which `is_transient` returns true, waiting between attempts
+/// with exponential backoff. Other errors are returned immediately.
+pub async fn retry<T, E, F, Fut>(
+    retries: usize,
+    is_transient: impl Fn(&E) -> bool,
+    mut f: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut delay = RETRY_DELAY;
+    let mut attempt = 0;
+    loop {
+        match f().await {
+            Err(e) if attempt < retries && is_transient(&e) => {
+                attempt += 1;
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            r => return r,
+        }
+    }
+}
+
+/// Extension trait to send requests to the FROST server with retries.
+pub trait RequestBuilderExt {
+    /// Send the request, retrying it up to [`MAX_RETRIES`] times if the
+    /// connection to the server could not be established. Since the request
+    /// never reached the server in that case, this is safe for any request,
+    /// including `send`. Errors returned by the server itself (e.g. session
+    /// not found) are not retried.
+    fn send_with_retries(self) -> impl Future<Output = Result<reqwest::Response, reqwest::Error>>;
+}
+
+impl RequestBuilderExt for reqwest::RequestBuilder {
+    async fn send_with_retries(self) -> Result<reqwest::Response, reqwest::Error> {
+        retry(MAX_RETRIES, reqwest::Error::is_connect, || {
+            self.try_clone()
+                .expect("requests without a streaming body can be cloned")
+                .send()
+        })
+        .await
+    }
+}
+
+/// Return the given response if it was successful, or the error sent by the
+/// server otherwise.
+pub async fn check_response(
+    response: reqwest::Response,
+) -> Result<reqwest::Response, Box<dyn Error>> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let error = response.json::<frostd::Error>().await?;
+    Err(eyre!("server error: {}", error).into())
+}
+
+/// Receive the messages sent to the user in a session. Returns `None` if the
+/// server no longer knows about the session, e.g. because the coordinator
+/// closed it, so that callers can tell if that was expected.
+pub async fn receive(
+    client: &reqwest::Client,
+    host_port: &str,
+    access_token: &str,
+    args: &frostd::ReceiveArgs,
+) -> Result<Option<frostd::ReceiveOutput>, Box<dyn Error>> {
+    let response = client
+        .post(format!("{}/receive", host_port))
+        .bearer_auth(access_token)
+        .json(args)
+        .send_with_retries()
+        .await?;
+    if response.status().is_success() {
+        return Ok(Some(response.json().await?));
+    }
+    let error = response.json::<frostd::Error>().await?;
+    if error.code == frostd::SESSION_NOT_FOUND {
+        Ok(None)
+    } else {
+        Err(eyre!("server error: {}", error).into())
+    }
+}
+
+/// Remove the messages received with `peek` from the queue, up to the given
+/// index.
+pub async fn ack(
+    client: &reqwest::Client,
+    host_port: &str,
+    access_token: &str,
+    args: &frostd::AckArgs,
+) -> Result<(), Box<dyn Error>> {
+    let response = client
+        .post(format!("{}/ack", host_port))
+        .bearer_auth(access_token)
+        .json(args)
+        .send_with_retries()
+        .await?;
+    check_response(response).await?;
+    Ok(())
+}
+
+/// Log in to the server with the given communication keypair, returning the
+/// access token.
+pub async fn login(
+    client: &reqwest::Client,
+    host_port: &str,
+    comm_privkey: &[u8],
+    comm_pubkey: &[u8],
+) -> Result<String, Box<dyn Error>> {
+    let challenge = client
+        .post(format!("{}/challenge", host_port))
+        .json(&frostd::ChallengeArgs {})
+        .send_with_retries()
+        .await?
+        .json::<frostd::ChallengeOutput>()
+        .await?
+        .challenge;
+
+    let privkey = xed25519::PrivateKey::from(
+        &TryInto::<[u8; 32]>::try_into(comm_privkey).map_err(|_| eyre!("invalid comm_privkey"))?,
+    );
+    let signature: [u8; 64] = privkey.sign(challenge.as_bytes(), &mut thread_rng());
+
+    Ok(client
+        .post(format!("{}/login", host_port))
+        .json(&frostd::KeyLoginArgs {
+            challenge,
+            pubkey: comm_pubkey.to_vec(),
+            signature: signature.to_vec(),
+        })
+        .send_with_retries()
+        .await?
+        .json::<frostd::LoginOutput>()
+        .await?
+        .access_token
+        .to_string())
+}
+
+/// Log out of the server, invalidating the access token.
+pub async fn logout(
+    client: &reqwest::Client,
+    host_port: &str,
+    access_token: &str,
+) -> Result<(), Box<dyn Error>> {
+    client
+        .post(format!("{}/logout", host_port))
+        .bearer_auth(access_token)
+        .send_with_retries()
+        .await?;
+    Ok(())
+}
+
+/// List the IDs of the active sessions the user is in.
+pub async fn list_sessions(
+    client: &reqwest::Client,
+    host_port: &str,
+    access_token: &str,
+) -> Result<Vec<Uuid>, Box<dyn Error>> {
+    Ok(client
+        .post(format!("{}/list_sessions", host_port))
+        .bearer_auth(access_token)
+        .send_with_retries()
+        .await?
+        .json::<frostd::ListSessionsOutput>()
+        .await?
+        .session_ids)
+}
+
+/// Check that the session described by `session_info` uses the ciphersuite
+/// `C`, if the coordinator specified it when creating the session. This
+/// allows failing early, instead of failing to deserialize its messages.
+pub fn check_session_ciphersuite<C: Ciphersuite>(
+    session_info: &frostd::GetSessionInfoOutput,
+) -> Result<(), Box<dyn Error>> {
+    match &session_info.ciphersuite {
+        Some(ciphersuite) if ciphersuite != C::ID => Err(eyre!(
+            "session uses ciphersuite {} but you are running {}",
+            ciphersuite,
+            C::ID
+        )
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+/// Check that the messages in the signing package received from the
+/// coordinator match the commitment it made when creating the session, if
+/// any.
+pub fn check_message_commitment<C: Ciphersuite>(
+    message_commitment: Option<&frostd::MessageCommitment>,
+    args: &SendSigningPackageArgs<C>,
+) -> Result<(), Box<dyn Error>> {
+    let Some(message_commitment) = message_commitment else {
+        return Ok(());
+    };
+    let messages: Vec<&[u8]> = args
+        .signing_package
+        .iter()
+        .map(|p| &p.message()[..])
+        .collect();
+    if frostd::commit_to_messages(&args.message_commitment_salt, &messages) != *message_commitment {
+        return Err(eyre!(
+            "the messages to sign do not match the ones the coordinator committed to"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Keeps a session open while waiting, by periodically calling
+/// `get_session_info`, which renews the session timeout in the server. This
+/// prevents a slow (e.g. human-driven) session from timing out.
+pub struct KeepAlive {
+    /// How often to refresh the session; it is not refreshed if `None`.
+    interval: Option<Duration>,
+    /// When the session was last refreshed.
+    last: Instant,
+}
+
+impl KeepAlive {
+    /// Create a KeepAlive that refreshes the session every `interval`, if
+    /// specified. It should be well under the session timeout of the server.
+    pub fn new(interval: Option<Duration>) -> Self {
+        Self {
+            interval,
+            last: Instant::now(),
+        }
+    }
+
+    /// Refresh the session if `interval` has elapsed since the last time.
+    /// Meant to be called from the loops that wait for messages.
+    pub async fn tick(
+        &mut self,
+        client: &reqwest::Client,
+        host_port: &str,
+        access_token: &str,
+        session_id: Uuid,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(interval) = self.interval else {
+            return Ok(());
+        };
+        if self.last.elapsed() < interval {
+            return Ok(());
+        }
+        // The response is ignored: if the session is gone, the next
+        // `receive` call will tell.
+        client
+            .post(format!("{}/get_session_info", host_port))
+            .bearer_auth(access_token)
+            .json(&frostd::GetSessionInfoArgs { session_id })
+            .send_with_retries()
+            .await?;
+        self.last = Instant::now();
+        Ok(())
+    }
+}
+
 pub struct HTTPComms<C: Ciphersuite> {
     client: reqwest::Client,
     host_port: String,
     session_id: Option<Uuid>,
     access_token: Option<String>,
+    /// Whether to log out when done; false if the login is shared with other
+    /// sessions, see [`Self::with_login()`].
+    owns_login: bool,
     args: ProcessedArgs<C>,
     send_noise: Option<Noise>,
     recv_noise: Option<Noise>,
+    /// Messages decrypted from the coordinator but not yet processed.
+    received: VecDeque<Vec<u8>>,
+    /// The commitment to the messages made by the coordinator when creating
+    /// the session, if any, checked when the signing package is received.
+    message_commitment: Option<frostd::MessageCommitment>,
     _phantom: PhantomData<C>,
 }
 
-use frostd::{SendCommitmentsArgs, SendSignatureSharesArgs, SendSigningPackageArgs, Uuid};
+use frostd::{
+    ParticipantDeclinedArgs, ParticipantErrorArgs, SendCommitmentsArgs, SendSignatureSharesArgs,
+    SendSigningPackageArgs, Uuid,
+};
 
 // TODO: Improve error handling for invalid session id
 impl<C> HTTPComms<C>
@@ -119,124 +662,121 @@ where
     C: Ciphersuite,
 {
     pub fn new(args: &ProcessedArgs<C>) -> Result<Self, Box<dyn Error>> {
-        let client = reqwest::Client::new();
+        let client = build_client(
+            args.cacert.as_deref(),
+            args.insecure_skip_verify,
+            args.proxy.as_deref(),
+        )?;
         Ok(Self {
             client,
             host_port: format!("https://{}:{}", args.ip, args.port),
             session_id: Uuid::parse_str(&args.session_id).ok(),
             access_token: None,
+            owns_login: true,
             args: args.clone(),
             send_noise: None,
             recv_noise: None,
+            received: VecDeque::new(),
+            message_commitment: None,
             _phantom: Default::default(),
         })
     }
 
-    // Encrypts a message for the coordinator.
-    fn encrypt(&mut self, msg: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+    /// Create a HTTPComms for the given session which uses an existing login
+    /// to the server, so that a participant can take part in several sessions
+    /// while logging in once. It does not log out when done; the caller must
+    /// do so once all the sessions are done.
+    pub fn with_login(
+        args: &ProcessedArgs<C>,
+        client: reqwest::Client,
+        access_token: String,
+        session_id: Uuid,
+    ) -> Self {
+        Self {
+            client,
+            host_port: format!("https://{}:{}", args.ip, args.port),
+            session_id: Some(session_id),
+            access_token: Some(access_token),
+            owns_login: false,
+            args: args.clone(),
+            send_noise: None,
+            recv_noise: None,
+            received: VecDeque::new(),
+            message_commitment: None,
+            _phantom: Default::default(),
+        }
+    }
+
+    // Logs out of the server, unless the login is shared.
+    async fn logout(&self) -> Result<(), Box<dyn Error>> {
+        if !self.owns_login {
+            return Ok(());
+        }
+        logout(
+            &self.client,
+            &self.host_port,
+            self.access_token.as_ref().ok_or_eyre("not logged in")?,
+        )
+        .await
+    }
+
+    // Encrypts a message for the coordinator, returning the chunks that must
+    // be sent in order.
+    fn encrypt(&mut self, msg: Vec<u8>) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
         let noise = self
             .send_noise
             .as_mut()
             .expect("send_noise must have been set previously");
-        let mut encrypted = vec![0; 65535];
-        let len = noise.write_message(&msg, &mut encrypted)?;
-        encrypted.truncate(len);
-        Ok(encrypted)
+        noise.encrypt(&msg)
     }
 
-    // Decrypts a message from the coordinator.
-    fn decrypt(&mut self, msg: Vec<u8>) -> Result<Vec<u8>, Box<dyn Error>> {
+    // Decrypts a message chunk from the coordinator. Returns None if more
+    // chunks are needed to complete the message.
+    fn decrypt(&mut self, msg: Vec<u8>) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
         let noise = self
             .recv_noise
             .as_mut()
             .expect("recv_noise must have been set previously");
-        let mut decrypted = vec![0; 65535];
-        decrypted.resize(65535, 0);
-        let len = noise.read_message(&msg, &mut decrypted)?;
-        decrypted.truncate(len);
-        Ok(decrypted)
+        noise.decrypt(&msg)
     }
-}
 
-#[async_trait(?Send)]
-impl<C> Comms<C> for HTTPComms<C>
-where
-    C: Ciphersuite + 'static,
-{
-    async fn get_signing_package(
-        &mut self,
-        _input: &mut dyn BufRead,
-        _output: &mut dyn Write,
-        commitments: SigningCommitments<C>,
-        identifier: Identifier<C>,
-        rerandomized: bool,
-    ) -> Result<
-        (
-            frost::SigningPackage<C>,
-            Option<frost_rerandomized::Randomizer<C>>,
-        ),
-        Box<dyn Error>,
-    > {
-        let mut rng = thread_rng();
-        let challenge = self
-            .client
-            .post(format!("{}/challenge", self.host_port))
-            .json(&frostd::ChallengeArgs {})
-            .send()
-            .await?
-            .json::<frostd::ChallengeOutput>()
-            .await?
-            .challenge;
-
-        let privkey = xed25519::PrivateKey::from(
-            &TryInto::<[u8; 32]>::try_into(
-                self.args
-                    .comm_privkey
-                    .clone()
-                    .ok_or_eyre("comm_privkey must be specified")?,
-            )
-            .map_err(|_| eyre!("invalid comm_privkey"))?,
-        );
-        let signature: [u8; 64] = privkey.sign(challenge.as_bytes(), &mut rng);
-
-        self.access_token = Some(
-            self.client
-                .post(format!("{}/login", self.host_port))
-                .json(&frostd::KeyLoginArgs {
-                    challenge,
-                    pubkey: self
-                        .args
+    // Logs in to the server, unless already logged in, finds the session and
+    // sets up encryption with its coordinator. Returns the session ID.
+    async fn connect(&mut self) -> Result<Uuid, Box<dyn Error>> {
+        if self.access_token.is_none() {
+            self.access_token = Some(
+                login(
+                    &self.client,
+                    &self.host_port,
+                    self.args
+                        .comm_privkey
+                        .as_deref()
+                        .ok_or_eyre("comm_privkey must be specified")?,
+                    self.args
                         .comm_pubkey
-                        .clone()
+                        .as_deref()
                         .ok_or_eyre("comm_pubkey must be specified")?,
-                    signature: signature.to_vec(),
-                })
-                .send()
-                .await?
-                .json::<frostd::LoginOutput>()
-                .await?
-                .access_token
-                .to_string(),
-        );
+                )
+                .await?,
+            );
+        }
 
         let session_id = match self.session_id {
             Some(s) => s,
             None => {
                 // Get session ID from server
-                let r = self
-                    .client
-                    .post(format!("{}/list_sessions", self.host_port))
-                    .bearer_auth(self.access_token.as_ref().expect("was just set"))
-                    .send()
-                    .await?
-                    .json::<frostd::ListSessionsOutput>()
-                    .await?;
-                if r.session_ids.len() > 1 {
-                    return Err(eyre!("user has more than one FROST session active; use `frost-client sessions` to list them and specify the session ID with `-S`").into());
-                } else if r.session_ids.is_empty() {
+                let session_ids = list_sessions(
+                    &self.client,
+                    &self.host_port,
+                    self.access_token.as_ref().expect("was just set"),
+                )
+                .await?;
+                if session_ids.len() > 1 {
+                    return Err(eyre!("user has more than one FROST session active; use `frost-client sessions` to list them and specify the session ID with `-S`, or pass `--list` to pick one").into());
+                } else if session_ids.is_empty() {
                     return Err(eyre!("User has no current sessions active. The Coordinator should either specify your username, or manually share the session ID which you can specify with --session_id").into());
                 }
-                r.session_ids[0]
+                session_ids[0]
             }
         };
         self.session_id = Some(session_id);
@@ -259,97 +799,155 @@ where
             .post(format!("{}/get_session_info", self.host_port))
             .json(&frostd::GetSessionInfoArgs { session_id })
             .bearer_auth(self.access_token.as_ref().expect("was just set"))
-            .send()
+            .send_with_retries()
             .await?
             .json::<frostd::GetSessionInfoOutput>()
             .await?;
 
+        if let Some(expected_coordinator_pubkey) = &self.args.expected_coordinator_pubkey {
+            if session_info.coordinator_pubkey != *expected_coordinator_pubkey {
+                return Err(eyre!(
+                    "The session coordinator {} is not the expected one {}",
+                    hex::encode(&session_info.coordinator_pubkey),
+                    hex::encode(expected_coordinator_pubkey)
+                )
+                .into());
+            }
+        }
+        check_session_ciphersuite::<C>(&session_info)?;
+        self.message_commitment = session_info.message_commitment;
+
         let comm_coordinator_pubkey = comm_coordinator_pubkey_getter(&session_info.coordinator_pubkey).ok_or_eyre("The coordinator for the specified FROST session is not registered in the user's address book")?;
-        let builder = snow::Builder::new(
-            "Noise_K_25519_ChaChaPoly_BLAKE2s"
-                .parse()
-                .expect("should be a valid cipher"),
-        );
+        // When sending a signature share signed offline, the Noise session
+        // used to send the commitments was lost with the run which sent them,
+        // so the coordinator must be told that this is a new one.
+        let restart = matches!(self.args.offline, Some(OfflinePhase::SendSignatureShare(_)));
         let send_noise = Noise::new(
-            builder
+            noise_builder(&session_id)
                 .local_private_key(comm_privkey)
                 .remote_public_key(&comm_coordinator_pubkey)
                 .build_initiator()?,
-        );
-        let builder = snow::Builder::new(
-            "Noise_K_25519_ChaChaPoly_BLAKE2s"
-                .parse()
-                .expect("should be a valid cipher"),
-        );
+        )
+        .with_compression(self.args.compress)
+        .with_restart(restart);
         let recv_noise = Noise::new(
-            builder
+            noise_builder(&session_id)
                 .local_private_key(comm_privkey)
                 .remote_public_key(&comm_coordinator_pubkey)
                 .build_responder()?,
-        );
+        )
+        .with_compression(self.args.compress);
         self.send_noise = Some(send_noise);
         self.recv_noise = Some(recv_noise);
 
+        if let Some(on_session) = &self.args.on_session {
+            on_session(session_id, &session_info.coordinator_pubkey);
+        }
+
+        Ok(session_id)
+    }
+}
+
+#[async_trait(?Send)]
+impl<C> Comms<C> for HTTPComms<C>
+where
+    C: Ciphersuite + 'static,
+{
+    async fn get_signing_package(
+        &mut self,
+        _input: &mut dyn BufRead,
+        _output: &mut dyn Write,
+        commitments: SigningCommitments<C>,
+        identifier: Identifier<C>,
+        rerandomized: bool,
+    ) -> Result<Round2Config<C>, Box<dyn Error>> {
+        let session_id = self.connect().await?;
+
         // Send Commitments to Server
         let send_commitments_args = SendCommitmentsArgs {
             identifier,
             commitments: vec![commitments],
         };
-        let msg = self.encrypt(serde_json::to_vec(&send_commitments_args)?)?;
-        self.client
-            .post(format!("{}/send", self.host_port))
-            .bearer_auth(self.access_token.as_ref().expect("was just set"))
-            .json(&frostd::SendArgs {
-                session_id,
-                // Empty recipients: Coordinator
-                recipients: vec![],
-                msg,
-            })
-            .send()
-            .await?;
+        for msg in self.encrypt(serde_json::to_vec(&send_commitments_args)?)? {
+            let response = self
+                .client
+                .post(format!("{}/send", self.host_port))
+                .bearer_auth(self.access_token.as_ref().expect("was just set"))
+                .json(&frostd::SendArgs {
+                    session_id,
+                    // Empty recipients: Coordinator
+                    recipients: vec![],
+                    msg,
+                })
+                .send_with_retries()
+                .await?;
+            check_response(response).await?;
+        }
 
         eprint!("Waiting for coordinator to send signing package...");
 
         // Receive SigningPackage from Coordinator
 
-        let r: SendSigningPackageArgs<C> = loop {
-            let r = self
-                .client
-                .post(format!("{}/receive", self.host_port))
-                .bearer_auth(self.access_token.as_ref().expect("was just set"))
-                .json(&frostd::ReceiveArgs {
+        let mut keep_alive = KeepAlive::new(self.args.keep_alive);
+        let msg = loop {
+            if let Some(msg) = self.received.pop_front() {
+                break msg;
+            }
+            let Some(r) = receive(
+                &self.client,
+                &self.host_port,
+                self.access_token.as_ref().expect("was just set"),
+                &frostd::ReceiveArgs {
                     session_id,
                     as_coordinator: false,
-                })
-                .send()
-                .await?
-                .json::<frostd::ReceiveOutput>()
-                .await?;
-            if r.msgs.is_empty() {
+                    peek: false,
+                },
+            )
+            .await?
+            else {
+                // The session is only expected to be closed after the
+                // signature share is sent, which we haven't done yet.
+                return Err(eyre!(
+                    "the session was closed by the coordinator before sending the signing package"
+                )
+                .into());
+            };
+            // Decrypt every message received, even past a complete one, since
+            // the Noise state must see all of them in order; complete ones
+            // are queued until needed.
+            for msg in r.msgs {
+                if let Some(msg) = self.decrypt(msg.msg)? {
+                    self.received.push_back(msg);
+                }
+            }
+            if self.received.is_empty() {
                 tokio::time::sleep(Duration::from_secs(2)).await;
                 eprint!(".");
-            } else {
-                eprintln!("\nSigning package received");
-                let msg = self.decrypt(r.msgs[0].msg.clone())?;
-                eprintln!("\n{}", String::from_utf8_lossy(&msg.clone()));
-                break serde_json::from_slice(&msg)?;
+                keep_alive
+                    .tick(
+                        &self.client,
+                        &self.host_port,
+                        self.access_token.as_ref().expect("was just set"),
+                        session_id,
+                    )
+                    .await?;
             }
         };
+        eprintln!("\nSigning package received");
+        eprintln!("\n{}", String::from_utf8_lossy(&msg));
+        let r: SendSigningPackageArgs<C> = serde_json::from_slice(&msg)?;
 
-        if rerandomized {
-            let signing_package = r
-                .signing_package
-                .first()
-                .ok_or(eyre!("missing signing package"))?;
-            let randomizer = r.randomizer.first().ok_or(eyre!("missing randomizer"))?;
-            Ok((signing_package.clone(), Some(*randomizer)))
-        } else {
-            let signing_package = r
-                .signing_package
-                .first()
-                .ok_or(eyre!("missing signing package"))?;
-            Ok((signing_package.clone(), None))
+        if let Err(e) = check_message_commitment(self.message_commitment.as_ref(), &r) {
+            if let Err(send_err) = self.send_error(&e.to_string()).await {
+                eprintln!(
+                    "Could not report the error to the coordinator: {}",
+                    send_err
+                );
+            }
+            return Err(e);
         }
+
+        round_2_config_from_args(r, rerandomized)
     }
 
     async fn send_signature_share(
@@ -359,6 +957,12 @@ where
     ) -> Result<(), Box<dyn Error>> {
         // Send signature share to Coordinator
 
+        // When signing offline, this is a new run which has not connected
+        // yet.
+        if self.send_noise.is_none() {
+            self.connect().await?;
+        }
+
         eprintln!("Sending signature share to coordinator...");
 
         let send_signature_shares_args = SendSignatureSharesArgs {
@@ -366,27 +970,77 @@ where
             signature_share: vec![signature_share],
         };
 
-        let msg = self.encrypt(serde_json::to_vec(&send_signature_shares_args)?)?;
+        for msg in self.encrypt(serde_json::to_vec(&send_signature_shares_args)?)? {
+            let response = self
+                .client
+                .post(format!("{}/send", self.host_port))
+                .bearer_auth(self.access_token.as_ref().expect("must be set before"))
+                .json(&frostd::SendArgs {
+                    session_id: self.session_id.unwrap(),
+                    // Empty recipients: Coordinator
+                    recipients: vec![],
+                    msg,
+                })
+                .send_with_retries()
+                .await?;
+            check_response(response).await?;
+        }
+
+        self.logout().await?;
 
-        let _r = self
-            .client
-            .post(format!("{}/send", self.host_port))
-            .bearer_auth(self.access_token.as_ref().expect("must be set before"))
-            .json(&frostd::SendArgs {
-                session_id: self.session_id.unwrap(),
-                // Empty recipients: Coordinator
-                recipients: vec![],
-                msg,
-            })
-            .send()
-            .await?;
+        Ok(())
+    }
 
-        let _r = self
-            .client
-            .post(format!("{}/logout", self.host_port))
-            .bearer_auth(self.access_token.as_ref().expect("must be set before"))
-            .send()
-            .await?;
+    async fn send_error(&mut self, reason: &str) -> Result<(), Box<dyn Error>> {
+        eprintln!("Reporting error to coordinator...");
+
+        let msgs = self.encrypt(serde_json::to_vec(&ParticipantErrorArgs {
+            participant_error: reason.to_string(),
+        })?)?;
+
+        for msg in msgs {
+            let response = self
+                .client
+                .post(format!("{}/send", self.host_port))
+                .bearer_auth(self.access_token.as_ref().ok_or_eyre("not logged in")?)
+                .json(&frostd::SendArgs {
+                    session_id: self.session_id.ok_or_eyre("session ID not set")?,
+                    // Empty recipients: Coordinator
+                    recipients: vec![],
+                    msg,
+                })
+                .send_with_retries()
+                .await?;
+            check_response(response).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn send_decline(&mut self, reason: &str) -> Result<(), Box<dyn Error>> {
+        eprintln!("Telling the coordinator that signing was declined...");
+
+        let msgs = self.encrypt(serde_json::to_vec(&ParticipantDeclinedArgs {
+            participant_declined: reason.to_string(),
+        })?)?;
+
+        for msg in msgs {
+            let response = self
+                .client
+                .post(format!("{}/send", self.host_port))
+                .bearer_auth(self.access_token.as_ref().ok_or_eyre("not logged in")?)
+                .json(&frostd::SendArgs {
+                    session_id: self.session_id.ok_or_eyre("session ID not set")?,
+                    // Empty recipients: Coordinator
+                    recipients: vec![],
+                    msg,
+                })
+                .send_with_retries()
+                .await?;
+            check_response(response).await?;
+        }
+
+        self.logout().await?;
 
         Ok(())
     }
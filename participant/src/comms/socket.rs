@@ -20,7 +20,7 @@ use std::{
 };
 
 use super::{Comms, Message};
-use crate::args::ProcessedArgs;
+use crate::{args::ProcessedArgs, round2::Round2Config};
 
 pub struct SocketComms<C: Ciphersuite> {
     input_rx: Receiver<(Endpoint, Vec<u8>)>,
@@ -89,13 +89,7 @@ where
         commitments: SigningCommitments<C>,
         identifier: Identifier<C>,
         _rerandomized: bool,
-    ) -> Result<
-        (
-            frost::SigningPackage<C>,
-            Option<frost_rerandomized::Randomizer<C>>,
-        ),
-        Box<dyn Error>,
-    > {
+    ) -> Result<Round2Config<C>, Box<dyn Error>> {
         // Send Commitments to Coordinator
         let data = serde_json::to_vec(&Message::<C>::IdentifiedCommitments {
             identifier,
@@ -116,7 +110,12 @@ where
             randomizer,
         } = message
         {
-            Ok((signing_package, randomizer))
+            Ok(Round2Config {
+                signing_package,
+                randomizer,
+                aux_msg: Vec::new(),
+                seed: Vec::new(),
+            })
         } else {
             Err(eyre!("Expected SigningPackage message"))?
         }
@@ -133,4 +132,13 @@ where
 
         Ok(())
     }
+
+    async fn send_decline(&mut self, reason: &str) -> Result<(), Box<dyn Error>> {
+        let data = serde_json::to_vec(&Message::<C>::Declined {
+            reason: reason.to_string(),
+        })?;
+        self.handler.network().send(self.endpoint, &data);
+
+        Ok(())
+    }
 }
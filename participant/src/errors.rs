@@ -0,0 +1,26 @@
+//! Reporting of fatal errors by the command line tools.
+
+use std::error::Error;
+
+use serde_json::json;
+
+/// The exit status of the command line tools when they fail.
+pub const ERROR_EXIT_CODE: i32 = 1;
+
+/// Format `error` to be printed before exiting with [`ERROR_EXIT_CODE`]: as a
+/// JSON object with its `error` message and exit `code` if `json` is set, so
+/// that scripts can parse it, or as plain text otherwise.
+pub fn format_error(error: &dyn Error, json: bool) -> String {
+    if json {
+        json!({ "error": error.to_string(), "code": ERROR_EXIT_CODE }).to_string()
+    } else {
+        error.to_string()
+    }
+}
+
+/// Print `error` to standard error (see [`format_error()`]) and exit the
+/// process with [`ERROR_EXIT_CODE`].
+pub fn exit_with_error(error: &dyn Error, json: bool) -> ! {
+    eprintln!("{}", format_error(error, json));
+    std::process::exit(ERROR_EXIT_CODE);
+}
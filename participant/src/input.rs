@@ -1,10 +1,23 @@
 use std::{
     error::Error,
     fs,
-    io::{BufRead, Write},
+    io::{self, BufRead, Write},
     path::Path,
 };
 
+/// Read a line from `input`, returning an `UnexpectedEof` I/O error if the
+/// input has ended, e.g. if it was piped and it was truncated.
+pub fn read_line(input: &mut dyn BufRead) -> Result<String, Box<dyn Error>> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err(Box::new(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "unexpected end of input",
+        )));
+    }
+    Ok(line)
+}
+
 /// Read the contents of a file or from a stdin.
 /// If `object_name` is "-" or a file that does not exist, then it reads from
 /// stdin.
@@ -38,9 +51,7 @@ pub fn read_from_file_or_stdin(
         Some(file_path) => Ok(fs::read_to_string(file_path)?),
         None => {
             writeln!(output, "Paste the {}: ", object_name)?;
-            let mut key_package = String::new();
-            input.read_line(&mut key_package)?;
-            Ok(key_package)
+            read_line(input)
         }
     }
 
@@ -1,7 +1,10 @@
 pub mod args;
 pub mod cli;
 pub mod comms;
+pub mod errors;
 
 pub mod input;
+pub mod nonce_guard;
+pub mod offline;
 pub mod round1;
 pub mod round2;
@@ -6,7 +6,6 @@ use participant::cli::cli;
 
 use std::io;
 
-// TODO: Update to use exit codes
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
@@ -25,9 +24,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // now.
     match r {
         Ok(_) => std::process::exit(0),
-        Err(e) => {
-            eprintln!("{}", e);
-            std::process::exit(1);
-        }
+        Err(e) => participant::errors::exit_with_error(&*e, args.json_errors),
     }
 }
@@ -0,0 +1,70 @@
+//! Protection against nonce reuse.
+//!
+//! Signing twice with the same nonces, e.g. two different signing packages
+//! sent by a malicious coordinator, reveals the signing share of the
+//! participant. Nonces are generated for each run and never stored, so the
+//! used ones are recorded before signing to make sure this can't happen even
+//! if that changes.
+
+use std::{
+    collections::HashSet,
+    error::Error,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+use eyre::eyre;
+use frost_core::{round1::SigningCommitments, Ciphersuite};
+use sha2::{Digest, Sha256};
+
+/// The commitments whose nonces were already used to produce a signature
+/// share, identified by their hash.
+pub struct UsedCommitments {
+    /// The file where the hashes are persisted, one hex-encoded hash per
+    /// line. If `None`, they are only kept in memory.
+    path: Option<PathBuf>,
+    hashes: HashSet<String>,
+}
+
+impl UsedCommitments {
+    /// Load the hashes of the used commitments from the given file, if any.
+    /// A missing file is the same as an empty one.
+    pub fn load(path: Option<PathBuf>) -> Result<Self, Box<dyn Error>> {
+        let hashes = match &path {
+            Some(path) if path.exists() => fs::read_to_string(path)?
+                .lines()
+                .map(|l| l.trim().to_string())
+                .filter(|l| !l.is_empty())
+                .collect(),
+            _ => HashSet::new(),
+        };
+        Ok(Self { path, hashes })
+    }
+
+    /// Record that the nonces of the given commitments are about to be used
+    /// to sign. Returns an error if they were already used, in which case no
+    /// signature share must be produced.
+    pub fn mark_used<C: Ciphersuite>(
+        &mut self,
+        commitments: &SigningCommitments<C>,
+    ) -> Result<(), Box<dyn Error>> {
+        let hash = hex::encode(Sha256::digest(commitments.serialize()?));
+        if self.hashes.contains(&hash) {
+            return Err(eyre!(
+                "REFUSING TO SIGN: these nonces were already used to sign, and signing \
+                 again would reveal your signing share. The coordinator may be malicious."
+            )
+            .into());
+        }
+        // Persist before signing, so that the nonces can't be used again
+        // even if the process is interrupted right after signing.
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}", hash)?;
+            file.sync_all()?;
+        }
+        self.hashes.insert(hash);
+        Ok(())
+    }
+}
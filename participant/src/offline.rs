@@ -0,0 +1,146 @@
+//! Offline signing, for participants whose key package is kept on a machine
+//! that can't connect to the coordinator (e.g. an air-gapped one).
+//!
+//! Signing is then split into three runs, connected by files:
+//!
+//! 1. Online, the commitments are sent and the signing package received from
+//!    the coordinator is saved, along with the nonces, instead of signed.
+//! 2. Offline, the saved signing package is signed, and the signature share
+//!    is saved. This does not communicate with the coordinator at all.
+//! 3. Online, the saved signature share is sent to the coordinator.
+
+use std::{
+    error::Error,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use eyre::eyre;
+use frost_core::{
+    round1::{SigningCommitments, SigningNonces},
+    round2::SignatureShare,
+    serde::{self, Deserialize, Serialize},
+    Ciphersuite, Identifier, SigningPackage,
+};
+
+use crate::round2::Round2Config;
+
+/// The step of offline signing to run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OfflinePhase {
+    /// Save the signing package received from the coordinator to the given
+    /// file, instead of signing it.
+    SaveSigningPackage(PathBuf),
+    /// Sign the signing package saved in `signing_package`, writing the
+    /// signature share to `signature_share`.
+    Sign {
+        signing_package: PathBuf,
+        signature_share: PathBuf,
+    },
+    /// Send the signature share saved in the given file to the coordinator.
+    SendSignatureShare(PathBuf),
+}
+
+impl OfflinePhase {
+    /// Get the offline signing step selected by the command line arguments,
+    /// if any. At most one of them can be selected, and signing requires
+    /// `signature_share_out`.
+    pub fn from_args(
+        save_signing_package: Option<&str>,
+        sign_offline: Option<&str>,
+        signature_share_out: Option<&str>,
+        send_signature_share: Option<&str>,
+    ) -> Result<Option<Self>, Box<dyn Error>> {
+        match (save_signing_package, sign_offline, send_signature_share) {
+            (None, None, None) => Ok(None),
+            (Some(path), None, None) => Ok(Some(Self::SaveSigningPackage(path.into()))),
+            (None, Some(path), None) => Ok(Some(Self::Sign {
+                signing_package: path.into(),
+                signature_share: signature_share_out
+                    .ok_or(eyre!("the signature share output file must be specified"))?
+                    .into(),
+            })),
+            (None, None, Some(path)) => Ok(Some(Self::SendSignatureShare(path.into()))),
+            _ => Err(eyre!("only one offline signing step can be run at a time").into()),
+        }
+    }
+}
+
+/// A signing package saved to be signed offline, along with everything
+/// needed to sign it.
+///
+/// It includes the secret nonces, which reveal the signing share if used to
+/// sign twice, so it must be handled with the same care as the key package.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "self::serde")]
+#[serde(bound = "C: Ciphersuite")]
+pub struct SavedSigningPackage<C: Ciphersuite> {
+    pub identifier: Identifier<C>,
+    pub nonces: SigningNonces<C>,
+    pub commitments: SigningCommitments<C>,
+    pub signing_package: SigningPackage<C>,
+    pub randomizer: Option<frost_rerandomized::Randomizer<C>>,
+    pub aux_msg: Vec<u8>,
+    pub seed: Vec<u8>,
+}
+
+impl<C: Ciphersuite> SavedSigningPackage<C> {
+    /// Get the round 2 config to sign the saved signing package with.
+    pub fn round_2_config(&self) -> Round2Config<C> {
+        Round2Config {
+            signing_package: self.signing_package.clone(),
+            randomizer: self.randomizer,
+            aux_msg: self.aux_msg.clone(),
+            seed: self.seed.clone(),
+        }
+    }
+}
+
+/// Save the signing package to the given file, which must not exist, so that
+/// saved nonces are never overwritten. It is readable by the owner only.
+pub fn save_signing_package<C: Ciphersuite>(
+    path: &Path,
+    saved: &SavedSigningPackage<C>,
+) -> Result<(), Box<dyn Error>> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .map_err(|e| eyre!("error creating {}: {}", path.display(), e))?;
+    file.write_all(&serde_json::to_vec(saved)?)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Load a signing package saved with [`save_signing_package()`].
+pub fn load_signing_package<C: Ciphersuite>(
+    path: &Path,
+) -> Result<SavedSigningPackage<C>, Box<dyn Error>> {
+    let bytes = fs::read(path).map_err(|e| eyre!("error reading {}: {}", path.display(), e))?;
+    let saved: SavedSigningPackage<C> = serde_json::from_slice(&bytes)?;
+    if SigningCommitments::from(&saved.nonces) != saved.commitments {
+        return Err(eyre!("the saved nonces do not match the saved commitments").into());
+    }
+    Ok(saved)
+}
+
+/// Write the JSON-encoded signature share to the given file.
+pub fn save_signature_share<C: Ciphersuite>(
+    path: &Path,
+    signature_share: &SignatureShare<C>,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(path, serde_json::to_vec(signature_share)?)
+        .map_err(|e| eyre!("error writing {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Read a JSON-encoded signature share from the given file.
+pub fn load_signature_share<C: Ciphersuite>(
+    path: &Path,
+) -> Result<SignatureShare<C>, Box<dyn Error>> {
+    let bytes = fs::read(path).map_err(|e| eyre!("error reading {}: {}", path.display(), e))?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
@@ -3,13 +3,27 @@ use frost_core::{self as frost, Ciphersuite};
 use crate::args::Args;
 use crate::input::read_from_file_or_stdin;
 use frost::{
-    keys::{KeyPackage, SecretShare},
+    keys::{KeyPackage, SecretShare, SigningShare, VerifyingShare},
     round1::SigningCommitments,
     round1::SigningNonces,
     Error,
 };
-use rand::rngs::ThreadRng;
+use rand::{rngs::ThreadRng, CryptoRng, RngCore};
 use std::io::{BufRead, Write};
+use thiserror::Error;
+
+/// An error when reading the participant inputs. Adds variants to tell which
+/// field of the key package was malformed, which frost's `Error` does not
+/// have.
+#[derive(Debug, Error, PartialEq)]
+pub enum ParticipantError<C: Ciphersuite> {
+    #[error("malformed signing share")]
+    MalformedSigningShare,
+    #[error("malformed verifying share")]
+    MalformedVerifyingShare,
+    #[error(transparent)]
+    Frost(#[from] Error<C>),
+}
 
 // TODO: Rethink the types here. They're inconsistent with each other
 #[derive(Debug, PartialEq)]
@@ -29,16 +43,34 @@ pub async fn request_inputs<C: Ciphersuite + 'static>(
 
     let key_package =
         if let Ok(secret_share) = serde_json::from_str::<SecretShare<C>>(&secret_share) {
-            KeyPackage::try_from(secret_share)?
+            KeyPackage::try_from(secret_share).map_err(ParticipantError::from)?
         } else {
-            // TODO: Improve error
             serde_json::from_str::<KeyPackage<C>>(&secret_share)
-                .map_err(|_| Error::<C>::InvalidSecretShare)?
+                .map_err(|_| malformed_field_error::<C>(&secret_share))?
         };
 
     Ok(Round1Config { key_package })
 }
 
+/// Find which field made the given secret share or key package invalid, so
+/// that the user can be told which value is wrong.
+fn malformed_field_error<C: Ciphersuite>(secret_share: &str) -> ParticipantError<C> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(secret_share) else {
+        return Error::<C>::InvalidSecretShare.into();
+    };
+    if let Some(signing_share) = value.get("signing_share") {
+        if serde_json::from_value::<SigningShare<C>>(signing_share.clone()).is_err() {
+            return ParticipantError::MalformedSigningShare;
+        }
+    }
+    if let Some(verifying_share) = value.get("verifying_share") {
+        if serde_json::from_value::<VerifyingShare<C>>(verifying_share.clone()).is_err() {
+            return ParticipantError::MalformedVerifyingShare;
+        }
+    }
+    Error::<C>::InvalidSecretShare.into()
+}
+
 pub fn print_values<C: Ciphersuite>(
     commitments: SigningCommitments<C>,
     logger: &mut dyn Write,
@@ -62,6 +94,16 @@ pub fn print_values<C: Ciphersuite>(
 pub fn generate_nonces_and_commitments<C: Ciphersuite>(
     key_package: &KeyPackage<C>,
     rng: &mut ThreadRng,
+) -> (SigningNonces<C>, SigningCommitments<C>) {
+    generate_nonces_and_commitments_with_rng(key_package, rng)
+}
+
+/// Same as [`generate_nonces_and_commitments`], but using the given RNG. A
+/// seeded RNG makes the nonces, and thus the signature share computed from
+/// them, reproducible, which is useful for tests.
+pub fn generate_nonces_and_commitments_with_rng<C: Ciphersuite, R: RngCore + CryptoRng>(
+    key_package: &KeyPackage<C>,
+    rng: &mut R,
 ) -> (SigningNonces<C>, SigningCommitments<C>) {
     let (nonces, commitments) = frost::round1::commit(key_package.signing_share(), rng);
 
@@ -1,18 +1,28 @@
 use frost_core::{self as frost, Ciphersuite};
 
-use crate::comms::Comms;
+use crate::{comms::Comms, input::read_line};
+use eyre::eyre;
 use frost::{
     keys::KeyPackage,
     round1::{SigningCommitments, SigningNonces},
     round2::{self, SignatureShare},
-    Error, Identifier, SigningPackage,
+    Field, Group, Identifier, SigningPackage,
 };
+use frost_rerandomized::{RandomizedCiphersuite, Randomizer};
+use frostd::SendSigningPackageArgs;
 use std::io::{BufRead, Write};
 
 #[derive(Clone)]
 pub struct Round2Config<C: Ciphersuite> {
     pub signing_package: SigningPackage<C>,
     pub randomizer: Option<frost_rerandomized::Randomizer<C>>,
+    /// Auxiliary message sent by the coordinator, e.g. the transaction being
+    /// signed; empty if none. If set, `randomizer` is `None` and the
+    /// randomizer is derived from it and `seed`; see [`aux_msg_randomizer()`].
+    pub aux_msg: Vec<u8>,
+    /// The seed sent by the coordinator to derive the randomizer from, if
+    /// `aux_msg` is set.
+    pub seed: Vec<u8>,
 }
 
 // TODO: refactor to generate config
@@ -27,24 +37,157 @@ pub async fn round_2_request_inputs<C: Ciphersuite>(
 ) -> Result<Round2Config<C>, Box<dyn std::error::Error>> {
     writeln!(logger, "=== Round 2 ===")?;
 
-    let r = comms
+    let config = comms
         .get_signing_package(input, logger, commitments, identifier, rerandomized)
         .await?;
 
+    // Show the auxiliary message so that the user can check what they are
+    // about to sign.
+    if !config.aux_msg.is_empty() {
+        writeln!(
+            logger,
+            "Auxiliary message: {}",
+            hex::encode(&config.aux_msg)
+        )?;
+    }
+
+    Ok(config)
+}
+
+/// Build the round 2 config from the signing packages sent by the
+/// coordinator, after validating them.
+// TODO: support more than 1
+pub fn round_2_config_from_args<C: Ciphersuite>(
+    args: SendSigningPackageArgs<C>,
+    rerandomized: bool,
+) -> Result<Round2Config<C>, Box<dyn std::error::Error>> {
+    // With an auxiliary message, the randomizers are derived from the seed
+    // instead of being sent.
+    let with_aux_msg = !args.aux_msg.is_empty();
+    if with_aux_msg && !rerandomized {
+        return Err(eyre!("the auxiliary message requires rerandomized signing").into());
+    }
+    if with_aux_msg && args.seed.is_empty() {
+        return Err(eyre!("missing seed to derive the randomizer from").into());
+    }
+    validate_signing_packages(
+        &args.signing_package,
+        &args.randomizer,
+        rerandomized && !with_aux_msg,
+    )?;
+
+    let signing_package = args
+        .signing_package
+        .first()
+        .ok_or(eyre!("missing signing package"))?
+        .clone();
+    let randomizer = if rerandomized && !with_aux_msg {
+        Some(*args.randomizer.first().ok_or(eyre!("missing randomizer"))?)
+    } else {
+        None
+    };
     Ok(Round2Config {
-        signing_package: r.0,
-        randomizer: r.1,
+        signing_package,
+        randomizer,
+        aux_msg: args.aux_msg,
+        seed: args.seed,
     })
 }
 
-pub fn generate_signature<C: frost_rerandomized::RandomizedCiphersuite>(
+/// Check if the signing packages received from the coordinator are
+/// consistent: there must be at least one and, if `rerandomized`, exactly one
+/// randomizer for each of them. Otherwise the participant would end up
+/// signing fewer messages than requested.
+pub fn validate_signing_packages<C: Ciphersuite>(
+    signing_packages: &[SigningPackage<C>],
+    randomizers: &[frost_rerandomized::Randomizer<C>],
+    rerandomized: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if signing_packages.is_empty() {
+        return Err(eyre!("missing signing package").into());
+    }
+    if rerandomized && randomizers.len() != signing_packages.len() {
+        return Err(eyre!(
+            "number of randomizers ({}) does not match the number of signing packages ({})",
+            randomizers.len(),
+            signing_packages.len()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Check if the signing package includes the commitments this participant
+/// sent, under its own identifier. Otherwise the coordinator either made a
+/// mistake or tampered with them, and the participant must not sign.
+pub fn validate_own_commitments<C: Ciphersuite>(
+    signing_package: &SigningPackage<C>,
+    identifier: Identifier<C>,
+    commitments: &SigningCommitments<C>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match signing_package.signing_commitment(&identifier) {
+        None => Err(eyre!("the signing package does not include our commitments").into()),
+        Some(c) if c != *commitments => Err(eyre!(
+            "the commitments in the signing package do not match the ones we sent"
+        )
+        .into()),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Show the message to be signed and ask the user whether to sign it.
+/// Returns true only if the user answers "y" or "yes".
+pub fn confirm_signing<C: Ciphersuite>(
+    config: &Round2Config<C>,
+    input: &mut dyn BufRead,
+    logger: &mut dyn Write,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    writeln!(
+        logger,
+        "Message to sign: {}",
+        hex::encode(config.signing_package.message())
+    )?;
+    writeln!(logger, "Sign it? [y/N]")?;
+    let answer = read_line(input)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Derive the randomizer to sign `signing_package` with from the `seed` sent by
+/// the coordinator and the auxiliary message, as documented in
+/// [`SendSigningPackageArgs::seed`]. It binds the signature to the auxiliary
+/// message, e.g. to the transaction the signed messages belong to.
+pub fn aux_msg_randomizer<C: RandomizedCiphersuite>(
+    seed: &[u8],
+    signing_package: &SigningPackage<C>,
+    aux_msg: &[u8],
+) -> Result<Randomizer<C>, Box<dyn std::error::Error>> {
+    let scalar = C::hash_randomizer(&[seed, &signing_package.serialize()?[..], aux_msg].concat())
+        .ok_or(eyre!("could not derive the randomizer"))?;
+    Ok(Randomizer::deserialize(
+        <<C::Group as Group>::Field as Field>::serialize(&scalar).as_ref(),
+    )?)
+}
+
+pub fn generate_signature<C: RandomizedCiphersuite>(
     config: Round2Config<C>,
     key_package: &KeyPackage<C>,
     signing_nonces: &SigningNonces<C>,
-) -> Result<SignatureShare<C>, Error<C>> {
+) -> Result<SignatureShare<C>, Box<dyn std::error::Error>> {
     let signing_package = config.signing_package;
 
-    let signature = if let Some(randomizer) = config.randomizer {
+    let randomizer = if config.aux_msg.is_empty() {
+        config.randomizer
+    } else if config.seed.is_empty() {
+        return Err(eyre!("missing seed to derive the randomizer from").into());
+    } else {
+        Some(aux_msg_randomizer(
+            &config.seed,
+            &signing_package,
+            &config.aux_msg,
+        )?)
+    };
+
+    let signature = if let Some(randomizer) = randomizer {
         frost_rerandomized::sign::<C>(&signing_package, signing_nonces, key_package, randomizer)?
     } else {
         round2::sign(&signing_package, signing_nonces, key_package)?
@@ -1,3 +1,6 @@
+mod args;
 mod cli;
+mod comms;
+mod nonce_guard;
 mod round1;
 mod round2;
@@ -0,0 +1,76 @@
+#![cfg(test)]
+
+use std::io::BufWriter;
+
+use frost_ed25519 as frost;
+
+use frost::keys::{KeyPackage, SecretShare};
+use participant::args::{Args, ProcessedArgs};
+
+const SECRET_SHARE_JSON: &str = r#"{"header":{"version":0,"ciphersuite":"FROST-ED25519-SHA512-v1"},"identifier":"0100000000000000000000000000000000000000000000000000000000000000","signing_share":"ceed7dd148a1a1ec2e65b50ecab6a7c453ccbd38c397c3506a540b7cf0dd9104","commitment":["087e22f970daf6ac5b07b55bd7fc0af6dea199ab847dc34fc92a6f8641a1bb8e","926d5910e146dccb9148ca39dc7607f4f7123ff1c0ffaf109add1d165c568bf2", "291bb78d7e4ef124f5aa6a36cbcf8c276e70fbb4e208212e916d762fc42c1bbc"]}"#;
+const GROUP_PUBLIC_KEY: &str = "087e22f970daf6ac5b07b55bd7fc0af6dea199ab847dc34fc92a6f8641a1bb8e";
+
+#[test]
+fn check_key_package_from_config() {
+    let secret_share: SecretShare = serde_json::from_str(SECRET_SHARE_JSON).unwrap();
+    let key_package = KeyPackage::try_from(secret_share).unwrap();
+
+    // Write a config in the same format as frost-client does.
+    let temp_dir = tempfile::tempdir().unwrap();
+    let config_path = temp_dir.path().join("credentials.toml");
+    let config = format!(
+        r#"version = 0
+
+[group.{GROUP_PUBLIC_KEY}]
+description = "test"
+ciphersuite = "FROST-ED25519-SHA512-v1"
+public_key_package = ""
+key_package = "{}"
+
+[group.{GROUP_PUBLIC_KEY}.participant]
+"#,
+        hex::encode(postcard::to_allocvec(&key_package).unwrap())
+    );
+    std::fs::write(&config_path, config).unwrap();
+
+    let args = Args {
+        group: Some(GROUP_PUBLIC_KEY.to_string()),
+        config: Some(config_path.to_str().unwrap().to_string()),
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let pargs_from_config =
+        ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut "".as_bytes(), &mut buf).unwrap();
+
+    let args = Args {
+        key_package: "-".to_string(),
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let pargs_interactive = ProcessedArgs::<frost::Ed25519Sha512>::new(
+        &args,
+        &mut format!("{}\n", SECRET_SHARE_JSON).as_bytes(),
+        &mut buf,
+    )
+    .unwrap();
+
+    assert_eq!(pargs_from_config.key_package, key_package);
+    assert_eq!(pargs_from_config.key_package, pargs_interactive.key_package);
+}
+
+#[test]
+fn check_key_package_from_config_missing_group() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let config_path = temp_dir.path().join("credentials.toml");
+    std::fs::write(&config_path, "version = 0\n").unwrap();
+
+    let args = Args {
+        group: Some(GROUP_PUBLIC_KEY.to_string()),
+        config: Some(config_path.to_str().unwrap().to_string()),
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let r = ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut "".as_bytes(), &mut buf);
+
+    assert!(r.is_err());
+}
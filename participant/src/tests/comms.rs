@@ -0,0 +1,310 @@
+#![cfg(test)]
+
+use std::cell::Cell;
+
+use frostd::Uuid;
+use participant::comms::http::{noise_builder, retry, DecryptError, Noise};
+
+const NOISE_PARAMS: &str = "Noise_K_25519_ChaChaPoly_BLAKE2s";
+
+/// Create a pair of connected Noise states: one to send and one to receive.
+fn noise_pair() -> (Noise, Noise) {
+    noise_pair_with_compression(false, false)
+}
+
+/// Create a pair of connected Noise states, with compression enabled or not
+/// on each.
+fn noise_pair_with_compression(sender_compress: bool, recipient_compress: bool) -> (Noise, Noise) {
+    let builder = snow::Builder::new(NOISE_PARAMS.parse().unwrap());
+    let sender_keys = builder.generate_keypair().unwrap();
+    let recipient_keys = builder.generate_keypair().unwrap();
+    let sender = snow::Builder::new(NOISE_PARAMS.parse().unwrap())
+        .local_private_key(&sender_keys.private)
+        .remote_public_key(&recipient_keys.public)
+        .build_initiator()
+        .unwrap();
+    let recipient = snow::Builder::new(NOISE_PARAMS.parse().unwrap())
+        .local_private_key(&recipient_keys.private)
+        .remote_public_key(&sender_keys.public)
+        .build_responder()
+        .unwrap();
+    (
+        Noise::new(sender).with_compression(sender_compress),
+        Noise::new(recipient).with_compression(recipient_compress),
+    )
+}
+
+/// Encrypt the plaintext with `sender` and decrypt it with `recipient`,
+/// returning the result and the total size of the encrypted messages.
+fn roundtrip(sender: &mut Noise, recipient: &mut Noise, plaintext: &[u8]) -> (Vec<u8>, usize) {
+    let msgs = sender.encrypt(plaintext).unwrap();
+    let size = msgs.iter().map(|m| m.len()).sum();
+    let (last, rest) = msgs.split_last().unwrap();
+    for msg in rest {
+        assert_eq!(recipient.decrypt(msg).unwrap(), None);
+    }
+    (recipient.decrypt(last).unwrap().unwrap(), size)
+}
+
+#[test]
+fn check_noise_compression_roundtrip() {
+    // A large, repetitive payload, like a signing package for a large group.
+    let large: Vec<u8> =
+        b"{\"hiding\":\"beb81feb53ed75a2\",\"binding\":\"d2102c5f8b8abb7a\"},".repeat(10_000);
+    // A payload that can't be compressed.
+    let random: Vec<u8> = (0..1000).map(|_| rand::random()).collect();
+
+    let (mut sender, mut recipient) = noise_pair_with_compression(true, true);
+    let (decrypted, compressed_size) = roundtrip(&mut sender, &mut recipient, &large);
+    assert_eq!(decrypted, large);
+    let (decrypted, _) = roundtrip(&mut sender, &mut recipient, &random);
+    assert_eq!(decrypted, random);
+    let (decrypted, _) = roundtrip(&mut sender, &mut recipient, b"");
+    assert_eq!(decrypted, b"");
+
+    let (mut sender, mut recipient) = noise_pair_with_compression(false, false);
+    let (decrypted, uncompressed_size) = roundtrip(&mut sender, &mut recipient, &large);
+    assert_eq!(decrypted, large);
+    let (decrypted, _) = roundtrip(&mut sender, &mut recipient, &random);
+    assert_eq!(decrypted, random);
+
+    // The large payload shrinks when compressed.
+    assert!(compressed_size * 10 < uncompressed_size);
+
+    // Each message says whether it is compressed, so the peers don't need to
+    // agree on it.
+    let (mut sender, mut recipient) = noise_pair_with_compression(true, false);
+    let (decrypted, _) = roundtrip(&mut sender, &mut recipient, &large);
+    assert_eq!(decrypted, large);
+    let (mut sender, mut recipient) = noise_pair_with_compression(false, true);
+    let (decrypted, _) = roundtrip(&mut sender, &mut recipient, &large);
+    assert_eq!(decrypted, large);
+}
+
+/// Encrypt a raw chunk with the given flags, index and count.
+fn raw_chunk(sender: &mut Noise, flags: u8, index: u32, count: u32, data: &[u8]) -> Vec<u8> {
+    let payload = [
+        &[3, flags][..],
+        &index.to_be_bytes(),
+        &count.to_be_bytes(),
+        data,
+    ]
+    .concat();
+    let mut msg = vec![0; 65535];
+    let len = sender.write_message(&payload, &mut msg).unwrap();
+    msg.truncate(len);
+    msg
+}
+
+#[test]
+fn check_noise_unsupported_flags() {
+    let (mut sender, mut recipient) = noise_pair();
+
+    let msg = raw_chunk(&mut sender, 0x80, 0, 1, b"hello");
+    let err = recipient.decrypt(&msg).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<DecryptError>().unwrap(),
+        DecryptError::UnsupportedFlags { flags: 0x80, .. }
+    ));
+
+    // The flags can't change between the chunks of a plaintext.
+    let msg = raw_chunk(&mut sender, 0, 0, 2, b"hel");
+    assert_eq!(recipient.decrypt(&msg).unwrap(), None);
+    let msg = raw_chunk(&mut sender, 1, 1, 2, b"lo");
+    assert!(recipient.decrypt(&msg).is_err());
+}
+
+#[test]
+fn check_noise_chunking_roundtrip() {
+    let (mut sender, mut recipient) = noise_pair();
+
+    let plaintext: Vec<u8> = (0..300_000).map(|i| i as u8).collect();
+    let msgs = sender.encrypt(&plaintext).unwrap();
+    assert!(msgs.len() > 4);
+    assert!(msgs.iter().all(|m| m.len() <= 65535));
+
+    let (last, rest) = msgs.split_last().unwrap();
+    for msg in rest {
+        assert_eq!(recipient.decrypt(msg).unwrap(), None);
+    }
+    assert_eq!(recipient.decrypt(last).unwrap(), Some(plaintext));
+
+    // Small messages still fit in a single chunk.
+    let msgs = sender.encrypt(b"hello").unwrap();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(
+        recipient.decrypt(&msgs[0]).unwrap(),
+        Some(b"hello".to_vec())
+    );
+}
+
+#[test]
+fn check_noise_chunking_missing_chunk() {
+    let (mut sender, mut recipient) = noise_pair();
+
+    let plaintext = vec![0u8; 200_000];
+    let msgs = sender.encrypt(&plaintext).unwrap();
+
+    // Noise itself rejects a skipped message since the nonces no longer
+    // match, so this can't be used to silently drop chunks.
+    assert_eq!(recipient.decrypt(&msgs[0]).unwrap(), None);
+    assert!(recipient.decrypt(&msgs[2]).is_err());
+}
+
+#[test]
+fn check_noise_unsupported_version() {
+    let (mut sender, mut recipient) = noise_pair();
+
+    // A peer which predates chunking sends the JSON plaintext as is.
+    let mut msg = vec![0; 65535];
+    let len = sender
+        .write_message(b"{\"identifier\":\"01\"}", &mut msg)
+        .unwrap();
+    msg.truncate(len);
+    let err = recipient.decrypt(&msg).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<DecryptError>().unwrap(),
+        DecryptError::UnsupportedVersion { version: b'{', .. }
+    ));
+}
+
+#[test]
+fn check_noise_corrupted_message() {
+    let (mut sender, mut recipient) = noise_pair();
+
+    let mut msgs = sender.encrypt(b"hello").unwrap();
+    let last = msgs[0].len() - 1;
+    msgs[0][last] ^= 1;
+    let err = recipient.decrypt(&msgs[0]).unwrap_err();
+    let err = err.downcast_ref::<DecryptError>().unwrap();
+    assert!(matches!(err, DecryptError::Invalid { .. }));
+    assert!(err
+        .to_string()
+        .starts_with("failed to decrypt message from "));
+    assert!(err
+        .to_string()
+        .ends_with(": possibly tampered or wrong key"));
+
+    let err = recipient.decrypt(&[0; 70_000]).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<DecryptError>().unwrap(),
+        DecryptError::TooLarge { .. }
+    ));
+}
+
+#[test]
+fn check_noise_restart() {
+    let (sender, mut recipient) = noise_pair();
+    let mut sender = sender.with_restart(true);
+    let msgs = sender.encrypt(b"hello").unwrap();
+    assert_eq!(
+        recipient.decrypt(&msgs[0]).unwrap(),
+        Some(b"hello".to_vec())
+    );
+    assert!(recipient.restarted());
+
+    let (mut sender, mut recipient) = noise_pair();
+    let msgs = sender.encrypt(b"hello").unwrap();
+    assert_eq!(
+        recipient.decrypt(&msgs[0]).unwrap(),
+        Some(b"hello".to_vec())
+    );
+    assert!(!recipient.restarted());
+}
+
+#[test]
+fn check_noise_bound_to_session() {
+    let sender_keys = noise_builder(&Uuid::nil()).generate_keypair().unwrap();
+    let recipient_keys = noise_builder(&Uuid::nil()).generate_keypair().unwrap();
+    let session_id = Uuid::new_v4();
+    let sender = |session_id: &Uuid| {
+        Noise::new(
+            noise_builder(session_id)
+                .local_private_key(&sender_keys.private)
+                .remote_public_key(&recipient_keys.public)
+                .build_initiator()
+                .unwrap(),
+        )
+    };
+    let recipient = |session_id: &Uuid| {
+        Noise::new(
+            noise_builder(session_id)
+                .local_private_key(&recipient_keys.private)
+                .remote_public_key(&sender_keys.public)
+                .build_responder()
+                .unwrap(),
+        )
+    };
+
+    let msgs = sender(&session_id).encrypt(b"hello").unwrap();
+    assert_eq!(
+        recipient(&session_id).decrypt(&msgs[0]).unwrap(),
+        Some(b"hello".to_vec())
+    );
+
+    // A handshake from another session is rejected.
+    let err = recipient(&Uuid::new_v4()).decrypt(&msgs[0]).unwrap_err();
+    assert!(matches!(
+        err.downcast_ref::<DecryptError>().unwrap(),
+        DecryptError::Invalid { .. }
+    ));
+}
+
+/// An error returned by a stub transport.
+#[derive(Debug, PartialEq)]
+enum StubError {
+    ConnectionReset,
+    SessionNotFound,
+}
+
+#[tokio::test]
+async fn check_retry_transient_error() {
+    // A flaky transport which fails once, then succeeds.
+    let calls = &Cell::new(0);
+    let r = retry(
+        3,
+        |e: &StubError| *e == StubError::ConnectionReset,
+        || async move {
+            calls.set(calls.get() + 1);
+            if calls.get() == 1 {
+                Err(StubError::ConnectionReset)
+            } else {
+                Ok("response")
+            }
+        },
+    )
+    .await;
+    assert_eq!(r, Ok("response"));
+    assert_eq!(calls.get(), 2);
+}
+
+#[tokio::test]
+async fn check_retry_gives_up() {
+    // Logical errors are not retried.
+    let calls = &Cell::new(0);
+    let r: Result<(), _> = retry(
+        3,
+        |e: &StubError| *e == StubError::ConnectionReset,
+        || async move {
+            calls.set(calls.get() + 1);
+            Err(StubError::SessionNotFound)
+        },
+    )
+    .await;
+    assert_eq!(r, Err(StubError::SessionNotFound));
+    assert_eq!(calls.get(), 1);
+
+    // Transient errors are retried only up to the given number of times.
+    let calls = &Cell::new(0);
+    let r: Result<(), _> = retry(
+        2,
+        |e: &StubError| *e == StubError::ConnectionReset,
+        || async move {
+            calls.set(calls.get() + 1);
+            Err(StubError::ConnectionReset)
+        },
+    )
+    .await;
+    assert_eq!(r, Err(StubError::ConnectionReset));
+    assert_eq!(calls.get(), 3);
+}
@@ -0,0 +1,124 @@
+#![cfg(test)]
+
+use frost_ed25519 as frost;
+
+use std::collections::BTreeMap;
+
+use frost::{
+    keys::{IdentifierList, KeyPackage},
+    round1::SigningCommitments,
+    SigningPackage,
+};
+use participant::{
+    args::{Args, ProcessedArgs},
+    cli::sign_offline,
+    nonce_guard::UsedCommitments,
+    offline::{save_signing_package, SavedSigningPackage},
+    round1::generate_nonces_and_commitments,
+};
+use rand::thread_rng;
+
+fn commitments(key_package: &KeyPackage) -> SigningCommitments {
+    generate_nonces_and_commitments(key_package, &mut thread_rng()).1
+}
+
+#[test]
+fn check_reused_commitments_are_rejected() {
+    let mut rng = thread_rng();
+    let (shares, _) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+    let key_package = KeyPackage::try_from(shares.into_values().next().unwrap()).unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let path = temp_dir.path().join("used-commitments");
+
+    let first = commitments(&key_package);
+    let mut used = UsedCommitments::load(Some(path.clone())).unwrap();
+    used.mark_used(&first).unwrap();
+
+    // A coordinator replaying the signing package for the same commitments
+    // is rejected, in the same run...
+    assert!(used.mark_used(&first).is_err());
+
+    // ... and in a later one.
+    let mut used = UsedCommitments::load(Some(path.clone())).unwrap();
+    assert!(used.mark_used(&first).is_err());
+
+    // Fresh commitments can still be used.
+    used.mark_used(&commitments(&key_package)).unwrap();
+}
+
+#[test]
+fn check_saved_signing_package_is_signed_once() {
+    let mut rng = thread_rng();
+    let (shares, _) =
+        frost::keys::generate_with_dealer(3, 2, IdentifierList::Default, &mut rng).unwrap();
+    let mut key_packages = shares
+        .into_values()
+        .map(|share| KeyPackage::try_from(share).unwrap());
+    let key_package = key_packages.next().unwrap();
+    let other_key_package = key_packages.next().unwrap();
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let used_commitments_path = temp_dir.path().join("used-commitments");
+    let args = Args {
+        key_package: "-".to_string(),
+        used_commitments: Some(used_commitments_path.to_str().unwrap().to_string()),
+        ..Default::default()
+    };
+    let input = format!("{}\n", serde_json::to_string(&key_package).unwrap());
+    let pargs =
+        ProcessedArgs::<frost::Ed25519Sha512>::new(&args, &mut input.as_bytes(), &mut Vec::new())
+            .unwrap();
+    assert_eq!(pargs.used_commitments_path, Some(used_commitments_path));
+
+    let (nonces, own_commitments) = generate_nonces_and_commitments(&key_package, &mut rng);
+    let signing_package = SigningPackage::new(
+        BTreeMap::from([
+            (*key_package.identifier(), own_commitments),
+            (
+                *other_key_package.identifier(),
+                commitments(&other_key_package),
+            ),
+        ]),
+        b"message",
+    );
+    let signing_package_path = temp_dir.path().join("signing-package.json");
+    save_signing_package(
+        &signing_package_path,
+        &SavedSigningPackage {
+            identifier: *key_package.identifier(),
+            nonces,
+            commitments: own_commitments,
+            signing_package,
+            randomizer: None,
+            aux_msg: vec![],
+            seed: vec![],
+        },
+    )
+    .unwrap();
+    // Keep a copy, since signing deletes the saved signing package.
+    let copy_path = temp_dir.path().join("signing-package-copy.json");
+    std::fs::copy(&signing_package_path, &copy_path).unwrap();
+
+    let signature_share_path = temp_dir.path().join("signature-share.json");
+    sign_offline(
+        &pargs,
+        &signing_package_path,
+        &signature_share_path,
+        &mut "".as_bytes(),
+        &mut Vec::new(),
+    )
+    .unwrap();
+
+    // Signing the copy again in a later run would reuse the nonces.
+    let err = sign_offline(
+        &pargs,
+        &copy_path,
+        &signature_share_path,
+        &mut "".as_bytes(),
+        &mut Vec::new(),
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("already"), "{}", err);
+}
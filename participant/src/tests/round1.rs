@@ -11,7 +11,7 @@ use frost::{
 };
 use participant::{
     args::Args,
-    round1::{print_values, request_inputs, Round1Config},
+    round1::{print_values, request_inputs, ParticipantError, Round1Config},
 };
 
 use rand::thread_rng;
@@ -42,9 +42,15 @@ async fn check_valid_round_1_inputs() {
         ciphersuite: "ed25519".to_string(),
         cli: true,
         key_package: "-".to_string(),
+        group: None,
+        config: None,
         ip: "0.0.0.0".to_string(),
         port: 80,
         session_id: "session-id".to_string(),
+        cacert: None,
+        insecure_skip_verify: false,
+        proxy: None,
+        ..Default::default()
     };
     let input = SECRET_SHARE_JSON;
     let mut valid_input = input.as_bytes();
@@ -70,8 +76,10 @@ async fn check_0_input_for_identifier() {
             .unwrap_err();
 
     assert_eq!(
-        *expected.downcast::<Error>().unwrap(),
-        Error::InvalidSecretShare
+        *expected
+            .downcast::<ParticipantError<frost::Ed25519Sha512>>()
+            .unwrap(),
+        ParticipantError::Frost(Error::InvalidSecretShare)
     );
 }
 
@@ -80,7 +88,7 @@ async fn check_invalid_length_signing_share() {
     let mut buf = BufWriter::new(Vec::new());
     let args = Args::default();
 
-    let input = r#"{"identifier":"0100000000000000000000000000000000000000000000000000000000000000","value":"ed7dd148a1a1ec2e65b50ecab6a7c453ccbd38c397c3506a540b7cf0dd9104","commitment":["087e22f970daf6ac5b07b55bd7fc0af6dea199ab847dc34fc92a6f8641a1bb8e","291bb78d7e4ef124f5aa6a36cbcf8c276e70fbb4e208212e916d762fc42c1bbc"],"ciphersuite":"FROST(Ed25519, SHA-512)"}"#;
+    let input = r#"{"header":{"version":0,"ciphersuite":"FROST-ED25519-SHA512-v1"},"identifier":"0100000000000000000000000000000000000000000000000000000000000000","signing_share":"ed7dd148a1a1ec2e65b50ecab6a7c453ccbd38c397c3506a540b7cf0dd9104","commitment":["087e22f970daf6ac5b07b55bd7fc0af6dea199ab847dc34fc92a6f8641a1bb8e","926d5910e146dccb9148ca39dc7607f4f7123ff1c0ffaf109add1d165c568bf2", "291bb78d7e4ef124f5aa6a36cbcf8c276e70fbb4e208212e916d762fc42c1bbc"]}"#;
 
     let mut invalid_input = input.as_bytes();
 
@@ -90,8 +98,34 @@ async fn check_invalid_length_signing_share() {
             .unwrap_err();
 
     assert_eq!(
-        *expected.downcast::<Error>().unwrap(),
-        Error::InvalidSecretShare
+        *expected
+            .downcast::<ParticipantError<frost::Ed25519Sha512>>()
+            .unwrap(),
+        ParticipantError::MalformedSigningShare
+    );
+}
+
+#[tokio::test]
+async fn check_invalid_length_verifying_share() {
+    let mut buf = BufWriter::new(Vec::new());
+    let args = Args::default();
+
+    let mut key_package = serde_json::to_value(build_key_package().await).unwrap();
+    key_package["verifying_share"] = (&PUBLIC_KEY[2..]).into();
+    let input = key_package.to_string();
+
+    let mut invalid_input = input.as_bytes();
+
+    let expected =
+        request_inputs::<frost_ed25519::Ed25519Sha512>(&args, &mut invalid_input, &mut buf)
+            .await
+            .unwrap_err();
+
+    assert_eq!(
+        *expected
+            .downcast::<ParticipantError<frost::Ed25519Sha512>>()
+            .unwrap(),
+        ParticipantError::MalformedVerifyingShare
     );
 }
 
@@ -109,8 +143,10 @@ async fn check_invalid_round_1_inputs() {
             .await
             .unwrap_err();
     assert_eq!(
-        *expected.downcast::<Error>().unwrap(),
-        Error::InvalidSecretShare
+        *expected
+            .downcast::<ParticipantError<frost::Ed25519Sha512>>()
+            .unwrap(),
+        ParticipantError::Frost(Error::InvalidSecretShare)
     );
 }
 
@@ -7,15 +7,20 @@ use frost_ed25519 as frost;
 use frost::Identifier;
 use frost::{
     keys::{KeyPackage, SigningShare, VerifyingShare},
-    round1::{self, NonceCommitment, SigningCommitments},
+    round1::{NonceCommitment, SigningCommitments},
     round2::SignatureShare,
     SigningPackage, VerifyingKey,
 };
 use hex::FromHex;
 use participant::comms::cli::CLIComms;
+use participant::round1::generate_nonces_and_commitments_with_rng;
 use participant::round2::print_values_round_2;
-use participant::round2::{generate_signature, round_2_request_inputs, Round2Config};
-use rand::thread_rng;
+use participant::round2::{
+    aux_msg_randomizer, generate_signature, round_2_config_from_args, round_2_request_inputs,
+    validate_own_commitments, validate_signing_packages, Round2Config,
+};
+use rand::{thread_rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 
 const PUBLIC_KEY: &str = "adf6ab1f882d04988eadfaa52fb175bf37b6247785d7380fde3fb9d68032470d";
 const GROUP_PUBLIC_KEY: &str = "087e22f970daf6ac5b07b55bd7fc0af6dea199ab847dc34fc92a6f8641a1bb8e";
@@ -29,6 +34,13 @@ const HIDING_COMMITMENT_2: &str =
     "cc9e9503921cdd3f4d64f2c9e7b22c9ab6d7c940111ce36f84e4a114331c6edd";
 const BINDING_COMMITMENT_2: &str =
     "b0e13794eaf00be2e430b16ec7f72ab0b6579e52ca604d17406a4fd1597afd66";
+// The outputs of `check_sign`, which uses an RNG seeded with `[42; 32]`.
+const SEEDED_HIDING_COMMITMENT: &str =
+    "56376ea6d50c656b5e14c9148a82ed7c51b5b9ac6fe36b04fdaa50f9821b84e1";
+const SEEDED_BINDING_COMMITMENT: &str =
+    "53069d2273f681a7d0322a4b468a473080e40b494ad4a2204421ae2103b27d2d";
+const SEEDED_SIGNATURE_SHARE: &str =
+    "204d07bacd7977e41aa440e7bdd8e5dd53032e9c40978a339e80cf8601955f02";
 
 pub fn nonce_commitment(input: &str) -> NonceCommitment {
     NonceCommitment::deserialize(&hex::decode(input).unwrap()).unwrap()
@@ -62,6 +74,8 @@ async fn check_valid_round_2_inputs() {
     let expected = Round2Config {
         signing_package: SigningPackage::new(signer_commitments, &message),
         randomizer: None,
+        aux_msg: Vec::new(),
+        seed: Vec::new(),
     };
 
     let mut buf = BufWriter::new(Vec::new());
@@ -88,6 +102,36 @@ async fn check_valid_round_2_inputs() {
 
 // TODO: test for invalid inputs
 
+#[tokio::test]
+async fn check_truncated_round_2_inputs() {
+    let mut comms = CLIComms::new();
+    let my_signer_commitments = SigningCommitments::new(
+        nonce_commitment(MY_HIDING_COMMITMENT),
+        nonce_commitment(MY_BINDING_COMMITMENT),
+    );
+
+    let signing_package = r#"{"header":{"version":0,"ciphersuite":"FROST-ED25519-SHA512-v1"},"signing_commitments":{"0100000000000000000000000000000000000000000000000000000000000000":{"header":{"version":0,"ciphersuite":"FROST-ED25519-SHA512-v1"},"hiding":"beb81feb53ed75a2695b07f377b464a88c4c2824e7d7b63911b745df01dc2d87","binding":"d2102c5f8b8abb7ad2f1706f47a4aab3be6ede28e408f3e74baeff1f6fbcd5c0"},"0200000000000000000000000000000000000000000000000000000000000000":{"header":{"version":0,"ciphersuite":"FROST-ED25519-SHA512-v1"},"hiding":"cc9e9503921cdd3f4d64f2c9e7b22c9ab6d7c940111ce36f84e4a114331c6edd","binding":"b0e13794eaf00be2e430b16ec7f72ab0b6579e52ca604d17406a4fd1597afd66"}},"message":"15d21ccd7ee42959562fc8aa63224c8851fb3ec85a3faf66040d380fb9738673"}"#;
+
+    // The input ends before the randomizer is read.
+    let mut buf = BufWriter::new(Vec::new());
+    let input = format!("{}\n", signing_package);
+    let mut truncated_input = input.as_bytes();
+
+    let r = round_2_request_inputs(
+        &mut comms,
+        &mut truncated_input,
+        &mut buf,
+        my_signer_commitments,
+        Identifier::try_from(1).unwrap(),
+        true,
+    )
+    .await;
+
+    let err = r.err().unwrap();
+    let err = err.downcast_ref::<std::io::Error>().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
 #[tokio::test]
 async fn check_sign() {
     let key_package = KeyPackage::new(
@@ -98,12 +142,17 @@ async fn check_sign() {
         2,
     );
 
-    let mut rng = thread_rng();
+    // Use a seeded RNG so that the nonces, and thus the signature share, are
+    // always the same.
+    let mut rng = ChaCha20Rng::from_seed([42; 32]);
 
-    // TODO: Nonce doesn't seem to be exported. Look into this to improve these tests
-    let (nonces, my_commitments) = round1::commit(
-        &SigningShare::deserialize(&hex::decode(SIGNING_SHARE).unwrap()).unwrap(),
-        &mut rng,
+    let (nonces, my_commitments) = generate_nonces_and_commitments_with_rng(&key_package, &mut rng);
+    assert_eq!(
+        my_commitments,
+        SigningCommitments::new(
+            nonce_commitment(SEEDED_HIDING_COMMITMENT),
+            nonce_commitment(SEEDED_BINDING_COMMITMENT),
+        )
     );
 
     let signer_commitments_2 = SigningCommitments::new(
@@ -123,11 +172,323 @@ async fn check_sign() {
     let config = Round2Config {
         signing_package,
         randomizer: None,
+        aux_msg: Vec::new(),
+        seed: Vec::new(),
     };
 
-    let signature = generate_signature(config, &key_package, &nonces);
+    let signature = generate_signature(config, &key_package, &nonces).unwrap();
+
+    assert_eq!(
+        signature,
+        SignatureShare::deserialize(&hex::decode(SEEDED_SIGNATURE_SHARE).unwrap()).unwrap()
+    );
+}
+
+#[test]
+fn check_validate_signing_packages() {
+    let mut rng = thread_rng();
+
+    let signer_commitments_2 = SigningCommitments::new(
+        nonce_commitment(HIDING_COMMITMENT_2),
+        nonce_commitment(BINDING_COMMITMENT_2),
+    );
+    let mut signer_commitments = BTreeMap::new();
+    signer_commitments.insert(
+        Identifier::try_from(1).unwrap(),
+        SigningCommitments::new(
+            nonce_commitment(MY_HIDING_COMMITMENT),
+            nonce_commitment(MY_BINDING_COMMITMENT),
+        ),
+    );
+    signer_commitments.insert(Identifier::try_from(2).unwrap(), signer_commitments_2);
+
+    let signing_packages = vec![
+        SigningPackage::new(signer_commitments.clone(), b"message 1"),
+        SigningPackage::new(signer_commitments, b"message 2"),
+    ];
+    let randomizers = signing_packages
+        .iter()
+        .map(|p| frost_rerandomized::Randomizer::new(&mut rng, p).unwrap())
+        .collect::<Vec<_>>();
+
+    // Two signing packages but a single randomizer
+    assert!(validate_signing_packages(&signing_packages, &randomizers[..1], true).is_err());
+    assert!(validate_signing_packages(&signing_packages, &randomizers, true).is_ok());
+    // Randomizers are not required if not rerandomized
+    assert!(validate_signing_packages(&signing_packages, &[], false).is_ok());
+    assert!(validate_signing_packages::<frost::Ed25519Sha512>(&[], &[], false).is_err());
+}
+
+#[test]
+fn check_validate_own_commitments() {
+    let my_commitments = SigningCommitments::new(
+        nonce_commitment(MY_HIDING_COMMITMENT),
+        nonce_commitment(MY_BINDING_COMMITMENT),
+    );
+    let signer_commitments_2 = SigningCommitments::new(
+        nonce_commitment(HIDING_COMMITMENT_2),
+        nonce_commitment(BINDING_COMMITMENT_2),
+    );
+    let my_identifier = Identifier::try_from(1).unwrap();
+    let identifier_2 = Identifier::try_from(2).unwrap();
+
+    let signing_package = SigningPackage::new(
+        BTreeMap::from([
+            (my_identifier, my_commitments),
+            (identifier_2, signer_commitments_2),
+        ]),
+        b"message",
+    );
+    assert!(validate_own_commitments(&signing_package, my_identifier, &my_commitments).is_ok());
+
+    // Our commitments were replaced
+    let signing_package = SigningPackage::new(
+        BTreeMap::from([
+            (my_identifier, signer_commitments_2),
+            (identifier_2, signer_commitments_2),
+        ]),
+        b"message",
+    );
+    assert!(validate_own_commitments(&signing_package, my_identifier, &my_commitments).is_err());
 
-    assert!(signature.is_ok()) // TODO: Should be able to test this more specifically when I remove randomness from the test
+    // Our commitments were omitted
+    let signing_package = SigningPackage::new(
+        BTreeMap::from([(identifier_2, signer_commitments_2)]),
+        b"message",
+    );
+    assert!(validate_own_commitments(&signing_package, my_identifier, &my_commitments).is_err());
+}
+
+/// Comms which send a signing package without the participant's commitments,
+/// recording what the participant replies.
+#[derive(Default)]
+struct OmittingComms {
+    error: Option<String>,
+    signed: bool,
+}
+
+#[async_trait::async_trait(?Send)]
+impl participant::comms::Comms<frost::Ed25519Sha512> for OmittingComms {
+    async fn get_signing_package(
+        &mut self,
+        _input: &mut dyn std::io::BufRead,
+        _output: &mut dyn std::io::Write,
+        _commitments: SigningCommitments<frost::Ed25519Sha512>,
+        _identifier: Identifier,
+        _rerandomized: bool,
+    ) -> Result<Round2Config<frost::Ed25519Sha512>, Box<dyn std::error::Error>> {
+        let signer_commitments_2 = SigningCommitments::new(
+            nonce_commitment(HIDING_COMMITMENT_2),
+            nonce_commitment(BINDING_COMMITMENT_2),
+        );
+        Ok(Round2Config {
+            signing_package: SigningPackage::new(
+                BTreeMap::from([(Identifier::try_from(2).unwrap(), signer_commitments_2)]),
+                b"message",
+            ),
+            randomizer: None,
+            aux_msg: Vec::new(),
+            seed: Vec::new(),
+        })
+    }
+
+    async fn send_signature_share(
+        &mut self,
+        _identifier: Identifier,
+        _signature_share: SignatureShare,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.signed = true;
+        Ok(())
+    }
+
+    async fn send_error(&mut self, reason: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.error = Some(reason.to_string());
+        Ok(())
+    }
+}
+
+/// Test if the participant refuses to sign a signing package which does not
+/// include its commitments, and tells the coordinator why.
+#[tokio::test]
+async fn check_refuse_signing_package_without_own_commitments() {
+    let key_package = KeyPackage::new(
+        Identifier::try_from(1).unwrap(),
+        SigningShare::deserialize(&hex::decode(SIGNING_SHARE).unwrap()).unwrap(),
+        VerifyingShare::deserialize(&hex::decode(PUBLIC_KEY).unwrap()).unwrap(),
+        VerifyingKey::deserialize(&hex::decode(GROUP_PUBLIC_KEY).unwrap()).unwrap(),
+        2,
+    );
+    let args = participant::args::Args {
+        key_package: "-".to_string(),
+        ..Default::default()
+    };
+    let input = format!("{}\n", serde_json::to_string(&key_package).unwrap());
+    let mut buf = Vec::new();
+    let pargs = participant::args::ProcessedArgs::<frost::Ed25519Sha512>::new(
+        &args,
+        &mut input.as_bytes(),
+        &mut buf,
+    )
+    .unwrap();
+
+    let mut comms = OmittingComms::default();
+    let r = participant::cli::run_with_comms(pargs, &mut comms, &mut "".as_bytes(), &mut buf).await;
+
+    let expected = "the signing package does not include our commitments";
+    assert_eq!(r.unwrap_err().to_string(), expected);
+    assert_eq!(comms.error.as_deref(), Some(expected));
+    assert!(!comms.signed);
+}
+
+/// Comms which return a fixed round 2 config.
+struct FixedComms(Round2Config<frost::Ed25519Sha512>);
+
+#[async_trait::async_trait(?Send)]
+impl participant::comms::Comms<frost::Ed25519Sha512> for FixedComms {
+    async fn get_signing_package(
+        &mut self,
+        _input: &mut dyn std::io::BufRead,
+        _output: &mut dyn std::io::Write,
+        _commitments: SigningCommitments<frost::Ed25519Sha512>,
+        _identifier: Identifier,
+        _rerandomized: bool,
+    ) -> Result<Round2Config<frost::Ed25519Sha512>, Box<dyn std::error::Error>> {
+        Ok(self.0.clone())
+    }
+
+    async fn send_signature_share(
+        &mut self,
+        _identifier: Identifier,
+        _signature_share: SignatureShare,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn check_aux_msg() {
+    let my_signer_commitments = SigningCommitments::new(
+        nonce_commitment(MY_HIDING_COMMITMENT),
+        nonce_commitment(MY_BINDING_COMMITMENT),
+    );
+    let mut signer_commitments = BTreeMap::new();
+    signer_commitments.insert(Identifier::try_from(1).unwrap(), my_signer_commitments);
+    signer_commitments.insert(
+        Identifier::try_from(2).unwrap(),
+        SigningCommitments::new(
+            nonce_commitment(HIDING_COMMITMENT_2),
+            nonce_commitment(BINDING_COMMITMENT_2),
+        ),
+    );
+    let signing_package = SigningPackage::new(signer_commitments, b"message");
+    let aux_msg = b"aux msg".to_vec();
+    let seed = b"seed".to_vec();
+
+    // Simulate the coordinator sending the signing package.
+    let args = frostd::SendSigningPackageArgs {
+        signing_package: vec![signing_package.clone()],
+        aux_msg: aux_msg.clone(),
+        randomizer: Vec::new(),
+        seed: seed.clone(),
+        message_commitment_salt: Vec::new(),
+    };
+    let bytes = serde_json::to_vec(&args).unwrap();
+
+    let config = round_2_config_from_args(serde_json::from_slice(&bytes).unwrap(), true).unwrap();
+    assert!(config.signing_package == signing_package);
+    assert!(config.randomizer.is_none());
+    assert_eq!(config.aux_msg, aux_msg);
+    assert_eq!(config.seed, seed);
+
+    // The randomizer can only be derived from the auxiliary message with a
+    // seed, and when signing rerandomized.
+    assert!(round_2_config_from_args(serde_json::from_slice(&bytes).unwrap(), false).is_err());
+    let args = frostd::SendSigningPackageArgs {
+        seed: Vec::new(),
+        ..args
+    };
+    assert!(round_2_config_from_args(args, true).is_err());
+
+    // The participant must be shown the auxiliary message before signing.
+    let mut buf = BufWriter::new(Vec::new());
+    let config = round_2_request_inputs(
+        &mut FixedComms(config),
+        &mut "".as_bytes(),
+        &mut buf,
+        my_signer_commitments,
+        Identifier::try_from(1).unwrap(),
+        true,
+    )
+    .await
+    .unwrap();
+    assert_eq!(config.aux_msg, aux_msg);
+
+    let out = String::from_utf8(buf.into_inner().unwrap()).unwrap();
+    assert!(out.contains(&format!("Auxiliary message: {}", hex::encode(&aux_msg))));
+}
+
+#[test]
+fn check_aux_msg_randomizer() {
+    let mut rng = thread_rng();
+    let (shares, pubkeys) =
+        frost::keys::generate_with_dealer(2, 2, frost::keys::IdentifierList::Default, &mut rng)
+            .unwrap();
+    let key_packages = shares
+        .into_iter()
+        .map(|(identifier, share)| (identifier, KeyPackage::try_from(share).unwrap()))
+        .collect::<BTreeMap<_, _>>();
+    let mut nonces = BTreeMap::new();
+    let mut commitments = BTreeMap::new();
+    for (identifier, key_package) in &key_packages {
+        let (n, c) = frost::round1::commit(key_package.signing_share(), &mut rng);
+        nonces.insert(*identifier, n);
+        commitments.insert(*identifier, c);
+    }
+    let signing_package = SigningPackage::new(commitments, b"message");
+    let seed = b"seed".to_vec();
+    let aux_msg = b"aux msg".to_vec();
+
+    let signature_shares = key_packages
+        .iter()
+        .map(|(identifier, key_package)| {
+            let config = Round2Config {
+                signing_package: signing_package.clone(),
+                randomizer: None,
+                aux_msg: aux_msg.clone(),
+                seed: seed.clone(),
+            };
+            let share = generate_signature(config, key_package, &nonces[identifier]).unwrap();
+            (*identifier, share)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    // The shares only aggregate with the randomizer derived from the same
+    // auxiliary message and seed, not with another message or seed.
+    let aggregate = |randomizer| {
+        let randomized_params = frost_rerandomized::RandomizedParams::from_randomizer(
+            pubkeys.verifying_key(),
+            randomizer,
+        );
+        frost_rerandomized::aggregate(
+            &signing_package,
+            &signature_shares,
+            &pubkeys,
+            &randomized_params,
+        )
+    };
+    assert!(aggregate(aux_msg_randomizer(&seed, &signing_package, &aux_msg).unwrap()).is_ok());
+    assert!(aggregate(aux_msg_randomizer(b"other", &signing_package, &aux_msg).unwrap()).is_err());
+    assert!(aggregate(aux_msg_randomizer(&seed, &signing_package, b"other").unwrap()).is_err());
+
+    // Without a seed the randomizer can't be derived.
+    let (identifier, key_package) = key_packages.iter().next().unwrap();
+    let config = Round2Config {
+        signing_package: signing_package.clone(),
+        randomizer: None,
+        aux_msg,
+        seed: Vec::new(),
+    };
+    assert!(generate_signature(config, key_package, &nonces[identifier]).is_err());
 }
 
 #[tokio::test]
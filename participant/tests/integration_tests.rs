@@ -1,4 +1,5 @@
 use std::collections::{BTreeMap, HashMap};
+use std::io::Write as _;
 
 use frost_ed25519 as frost;
 
@@ -43,6 +44,8 @@ fn check_participant() {
         let config = Round2Config {
             signing_package: SigningPackage::new(commitments.clone(), &message),
             randomizer: None,
+            aux_msg: Vec::new(),
+            seed: Vec::new(),
         };
         let signature = generate_signature(
             config,
@@ -62,3 +65,29 @@ fn check_participant() {
 
     assert!(verify_signature.is_ok());
 }
+
+#[test]
+fn check_json_errors() {
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_participant"))
+        .args(["--cli", "--json-errors", "-k", "-"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"not a key package\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(
+        output.status.code(),
+        Some(participant::errors::ERROR_EXIT_CODE)
+    );
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["code"], participant::errors::ERROR_EXIT_CODE);
+    assert!(!error["error"].as_str().unwrap().is_empty());
+}
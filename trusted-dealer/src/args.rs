@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
 #[derive(Parser, Debug, Default)]
 #[command(author, version, about, long_about = None)]
@@ -36,4 +36,54 @@ pub struct Args {
     /// specified, a random one will be generated.
     #[arg(long)]
     pub key: Option<String>,
+
+    /// The comma-separated hex-encoded communication public keys of the
+    /// participants, in the same order as their identifiers. If specified,
+    /// each key package will be encrypted to the matching public key, so that
+    /// only its recipient can open it.
+    #[arg(short = 'e', long, value_delimiter = ',')]
+    pub encrypt_to: Vec<String>,
+
+    /// Path to a key package previously encrypted with `encrypt_to`, hex-encoded
+    /// as written or printed by the dealer. If specified, it will be decrypted
+    /// (prompting for the hex-encoded communication private key) and written
+    /// to standard output, instead of generating new shares.
+    #[arg(long)]
+    pub decrypt_share: Option<String>,
+
+    /// The format of the public key package and secret shares printed in CLI
+    /// mode. The default, "json", can be read by the participant and
+    /// coordinator; "hex" and "base64" encode the binary serialization of each
+    /// value. Encrypted shares are printed as hex unless "base64" is specified.
+    #[arg(long, alias = "format", value_enum, default_value_t = OutputFormat::Json)]
+    pub output_format: OutputFormat,
+
+    /// In CLI mode, print each secret share (encrypted, if `encrypt_to` is
+    /// specified) as QR codes to be scanned onto an offline device, instead of
+    /// as text. Large shares are split across multiple QR codes.
+    #[arg(long, default_value_t = false)]
+    pub qr: bool,
+
+    /// Indent the JSON-encoded public key package and secret shares, to make
+    /// them easier to read and diff. Encrypted shares and QR codes always use
+    /// the compact encoding.
+    #[arg(long, default_value_t = false)]
+    pub pretty: bool,
+
+    /// Skip checking that the generated shares can be used to sign for the
+    /// group verifying key before writing them.
+    #[arg(long, default_value_t = false)]
+    pub no_verify: bool,
+}
+
+/// The format of the values printed in CLI mode.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    /// JSON, which can be read by the participant CLI.
+    #[default]
+    Json,
+    /// Hex-encoded binary serialization.
+    Hex,
+    /// Base64-encoded binary serialization.
+    Base64,
 }
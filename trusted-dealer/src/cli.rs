@@ -1,10 +1,14 @@
+use eyre::eyre;
 use rand::thread_rng;
+use std::fs;
 use std::io::{BufRead, Write};
 
 use frost_core::Ciphersuite;
 
 use crate::args::Args;
+use crate::encryption::decrypt_share;
 use crate::inputs::{print_values, request_inputs};
+use crate::trusted_dealer_keygen::verify_shares;
 use crate::{trusted_dealer, MaybeIntoEvenY};
 
 pub fn cli<C: Ciphersuite + 'static + MaybeIntoEvenY>(
@@ -12,13 +16,42 @@ pub fn cli<C: Ciphersuite + 'static + MaybeIntoEvenY>(
     input: &mut impl BufRead,
     logger: &mut impl Write,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(path) = &args.decrypt_share {
+        return decrypt_share_file(path, input, logger);
+    }
+
     let config = request_inputs::<C>(args, input, logger)?;
 
     let mut rng = thread_rng();
 
     let (shares, pubkeys) = trusted_dealer(&config, &mut rng)?;
 
+    if !args.no_verify {
+        verify_shares(&shares, &pubkeys, &mut rng)?;
+    }
+
     print_values::<C>(args, &shares, &pubkeys, logger)?;
 
     Ok(())
 }
+
+/// Decrypt a hex-encoded key package written by the dealer with `encrypt_to`
+/// and write it to the logger.
+fn decrypt_share_file(
+    path: &str,
+    input: &mut impl BufRead,
+    logger: &mut impl Write,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let sealed = hex::decode(fs::read_to_string(path)?.trim())
+        .map_err(|_| eyre!("the encrypted share must be hex-encoded"))?;
+
+    writeln!(logger, "Your communication private key (hex string):")?;
+    let mut privkey = String::new();
+    input.read_line(&mut privkey)?;
+    let privkey = hex::decode(privkey.trim())?;
+
+    let share = decrypt_share(&privkey, &sealed)?;
+    writeln!(logger, "Secret share:\n{}", String::from_utf8(share)?)?;
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+//! Encryption of secret shares to the communication public key of their
+//! recipients.
+//!
+//! This uses the one-way Noise `N` pattern, which does not require the dealer
+//! to have a communication key pair: only the holder of the private key
+//! matching the recipient public key is able to open the sealed share.
+
+use std::error::Error;
+
+use eyre::eyre;
+
+/// The Noise pattern used to seal shares.
+const NOISE_PATTERN: &str = "Noise_N_25519_ChaChaPoly_BLAKE2s";
+/// The maximum size of a Noise message.
+const MAX_NOISE_MESSAGE_SIZE: usize = 65535;
+
+/// Encrypt the given (serialized) share to the given communication public key.
+pub fn encrypt_share(recipient_pubkey: &[u8], share: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let builder = snow::Builder::new(NOISE_PATTERN.parse().expect("should be a valid cipher"));
+    let mut noise = builder
+        .remote_public_key(recipient_pubkey)
+        .build_initiator()?;
+    let mut encrypted = vec![0; MAX_NOISE_MESSAGE_SIZE];
+    let len = noise.write_message(share, &mut encrypted)?;
+    encrypted.truncate(len);
+    Ok(encrypted)
+}
+
+/// Decrypt a share sealed with [`encrypt_share()`], using the communication
+/// private key of the recipient.
+pub fn decrypt_share(recipient_privkey: &[u8], sealed: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let builder = snow::Builder::new(NOISE_PATTERN.parse().expect("should be a valid cipher"));
+    let mut noise = builder
+        .local_private_key(recipient_privkey)
+        .build_responder()?;
+    let mut decrypted = vec![0; MAX_NOISE_MESSAGE_SIZE];
+    let len = noise
+        .read_message(sealed, &mut decrypted)
+        .map_err(|_| eyre!("could not decrypt share; is it sealed to your key?"))?;
+    decrypted.truncate(len);
+    Ok(decrypted)
+}
+
+#[cfg(test)]
+mod tests {
+    use frost_ed25519::keys::{IdentifierList, SecretShare};
+    use rand::thread_rng;
+
+    use crate::{
+        encryption::{decrypt_share, encrypt_share},
+        inputs::Config,
+        trusted_dealer_keygen::trusted_dealer_keygen,
+    };
+
+    #[test]
+    fn check_share_encryption_round_trip() {
+        let mut rng = thread_rng();
+        let config = Config {
+            min_signers: 2,
+            max_signers: 3,
+            secret: Vec::new(),
+        };
+        let (shares, _pubkeys) =
+            trusted_dealer_keygen(&config, IdentifierList::Default, &mut rng).unwrap();
+        let share = shares.values().next().unwrap();
+
+        let builder = snow::Builder::new("Noise_N_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+        let keypair = builder.generate_keypair().unwrap();
+        let other_keypair = builder.generate_keypair().unwrap();
+
+        let sealed = encrypt_share(&keypair.public, &serde_json::to_vec(share).unwrap()).unwrap();
+
+        // Only the recipient can open the share
+        assert!(decrypt_share(&other_keypair.private, &sealed).is_err());
+
+        let opened = decrypt_share(&keypair.private, &sealed).unwrap();
+        let opened: SecretShare = serde_json::from_slice(&opened).unwrap();
+        assert_eq!(&opened, share);
+    }
+}
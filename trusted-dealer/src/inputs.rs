@@ -1,6 +1,9 @@
 use frost_core::{self as frost, Ciphersuite};
 
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use eyre::eyre;
 use frost::keys::{PublicKeyPackage, SecretShare};
+use frost::serde::Serialize;
 use frost::Error;
 use frost::Identifier;
 use itertools::Itertools;
@@ -8,7 +11,9 @@ use std::collections::BTreeMap;
 use std::fs;
 use std::io::{BufRead, Write};
 
-use crate::args::Args;
+use crate::args::{Args, OutputFormat};
+use crate::encryption::encrypt_share;
+use crate::qr::{encode_frames, render_frame};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Config {
@@ -17,20 +22,28 @@ pub struct Config {
     pub secret: Vec<u8>,
 }
 
-fn validate_inputs<C: Ciphersuite>(config: &Config) -> Result<(), Error<C>> {
-    if config.min_signers < 2 {
-        return Err(Error::<C>::InvalidMinSigners);
-    }
-
-    if config.max_signers < 2 {
-        return Err(Error::<C>::InvalidMaxSigners);
-    }
-
-    if config.min_signers > config.max_signers {
-        return Err(Error::<C>::InvalidMinSigners);
+impl Config {
+    /// Create a new Config, checking that the threshold parameters are valid.
+    pub fn new(
+        min_signers: u16,
+        max_signers: u16,
+        secret: Vec<u8>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if max_signers < 2 {
+            return Err(eyre!("maximum signers must be 2 or more").into());
+        }
+        if min_signers < 2 || min_signers > max_signers {
+            return Err(eyre!(
+                "minimum signers must be at least 2 and no greater than maximum signers"
+            )
+            .into());
+        }
+        Ok(Self {
+            min_signers,
+            max_signers,
+            secret,
+        })
     }
-
-    Ok(())
 }
 
 pub fn request_inputs<C: Ciphersuite + 'static>(
@@ -47,7 +60,7 @@ pub fn request_inputs<C: Ciphersuite + 'static>(
         let min_signers = min
             .trim()
             .parse::<u16>()
-            .map_err(|_| Error::<C>::InvalidMinSigners)?;
+            .map_err(|_| eyre!("minimum signers must be a number (2 or more)"))?;
 
         writeln!(logger, "The maximum number of signers: ")?;
 
@@ -56,7 +69,7 @@ pub fn request_inputs<C: Ciphersuite + 'static>(
         let max_signers = max
             .trim()
             .parse::<u16>()
-            .map_err(|_| Error::<C>::InvalidMaxSigners)?;
+            .map_err(|_| eyre!("maximum signers must be a number (2 or more)"))?;
 
         writeln!(
             logger,
@@ -68,11 +81,7 @@ pub fn request_inputs<C: Ciphersuite + 'static>(
         let secret =
             hex::decode(secret_input.trim()).map_err(|_| Error::<C>::MalformedSigningKey)?;
 
-        Config {
-            min_signers,
-            max_signers,
-            secret,
-        }
+        Config::new(min_signers, max_signers, secret)?
     } else {
         let secret = hex::decode(args.key.clone().unwrap_or("".to_string()))
             .map_err(|_| Error::<C>::MalformedSigningKey)?;
@@ -80,46 +89,88 @@ pub fn request_inputs<C: Ciphersuite + 'static>(
             "Generating {} shares with threshold {}...",
             args.num_signers, args.threshold
         );
-        Config {
-            min_signers: args.threshold,
-            max_signers: args.num_signers,
-            secret,
-        }
+        Config::new(args.threshold, args.num_signers, secret)?
     };
 
-    validate_inputs::<C>(&config)?;
-
     Ok(config)
 }
 
-pub fn print_values<C: Ciphersuite>(
+pub fn print_values<C: Ciphersuite + 'static>(
     args: &Args,
     keys: &BTreeMap<Identifier<C>, SecretShare<C>>,
     pubkeys: &PublicKeyPackage<C>,
     logger: &mut dyn Write,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    if args.cli {
-        writeln!(
-            logger,
-            "Public key package:\n{}",
-            serde_json::to_string(pubkeys)?
-        )?;
+    let recipients = args
+        .encrypt_to
+        .iter()
+        .map(hex::decode)
+        .collect::<Result<Vec<_>, _>>()?;
+    if !recipients.is_empty() && recipients.len() != keys.len() {
+        return Err(eyre!(
+            "The `encrypt_to` option must specify one public key for each of the {} participants",
+            keys.len()
+        )
+        .into());
+    }
+    // Serialize each share, sealing it to its recipient if requested.
+    let encoded_shares = keys
+        .iter()
+        .sorted_by_key(|x| x.0)
+        .enumerate()
+        .map(|(i, (k, v))| {
+            let bytes = match recipients.get(i) {
+                Some(recipient) => encrypt_share(recipient, &serde_json::to_vec(v)?)?,
+                None if args.qr => serde_json::to_vec(v)?,
+                None => to_json(v, args.pretty)?,
+            };
+            Ok((k, bytes))
+        })
+        .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
 
-        for (k, v) in keys.iter().sorted_by_key(|x| x.0) {
+    if args.cli {
+        let encode = |bytes: &[u8]| match args.output_format {
+            OutputFormat::Json | OutputFormat::Hex => hex::encode(bytes),
+            OutputFormat::Base64 => BASE64_STANDARD.encode(bytes),
+        };
+        let pubkeys = match args.output_format {
+            OutputFormat::Json => String::from_utf8(to_json(pubkeys, args.pretty)?)?,
+            _ => encode(&pubkeys.serialize()?),
+        };
+        writeln!(logger, "Public key package:\n{}", pubkeys)?;
+
+        for (k, v) in encoded_shares {
             writeln!(logger, "Participant: {}", hex::encode(k.serialize()))?;
-            writeln!(
-                logger,
-                "Secret share:\n{}",
-                serde_json::to_string(v).unwrap()
-            )?;
+            if args.qr {
+                let frames = encode_frames(&v);
+                for (i, frame) in frames.iter().enumerate() {
+                    writeln!(
+                        logger,
+                        "Secret share (QR code {} of {}):\n{}",
+                        i + 1,
+                        frames.len(),
+                        render_frame(frame)?
+                    )?;
+                }
+            } else if !recipients.is_empty() {
+                writeln!(logger, "Encrypted secret share:\n{}", encode(&v))?;
+            } else if args.output_format == OutputFormat::Json {
+                writeln!(logger, "Secret share:\n{}", String::from_utf8(v)?)?;
+            } else {
+                writeln!(logger, "Secret share:\n{}", encode(&keys[k].serialize()?))?;
+            }
         }
     } else {
-        fs::write(&args.public_key_package, serde_json::to_vec(pubkeys)?)?;
+        fs::write(&args.public_key_package, to_json(pubkeys, args.pretty)?)?;
         eprintln!("Public key package written to {}", &args.public_key_package);
 
-        for (i, (k, v)) in keys.iter().sorted_by_key(|x| x.0).enumerate() {
+        for (i, (k, v)) in encoded_shares.into_iter().enumerate() {
             let path = str::replace(&args.key_package, "{}", format!("{}", i + 1).as_str());
-            fs::write(&path, serde_json::to_vec(v)?)?;
+            if recipients.is_empty() {
+                fs::write(&path, v)?;
+            } else {
+                fs::write(&path, hex::encode(v))?;
+            }
             eprintln!(
                 "Key package for participant {} written to {}",
                 hex::encode(k.serialize()),
@@ -130,3 +181,12 @@ pub fn print_values<C: Ciphersuite>(
 
     Ok(())
 }
+
+/// JSON-encode `value`, indented if `pretty` is set.
+fn to_json<T: Serialize>(value: &T, pretty: bool) -> serde_json::Result<Vec<u8>> {
+    if pretty {
+        serde_json::to_vec_pretty(value)
+    } else {
+        serde_json::to_vec(value)
+    }
+}
@@ -1,6 +1,8 @@
 pub mod args;
 pub mod cli;
+pub mod encryption;
 pub mod inputs;
+pub mod qr;
 pub mod trusted_dealer_keygen;
 
 pub use inputs::Config;
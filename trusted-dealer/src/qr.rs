@@ -0,0 +1,166 @@
+//! Rendering of secret shares as QR codes, for distributing them to air-gapped
+//! devices.
+//!
+//! Shares (in particular encrypted ones) may be too large to be comfortably
+//! scanned from a single QR code, so they are split into frames. Each frame
+//! holds a chunk of the share, encoded in base64 and prefixed with its
+//! position, e.g. `1/3:eyJoZWFkZXIi...`. The frames can be scanned in any
+//! order and reassembled with [`decode_frames()`].
+
+use std::error::Error;
+
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use eyre::eyre;
+use qrcode::{render::unicode::Dense1x2, QrCode};
+
+/// The maximum number of share bytes held by a single frame. This keeps each
+/// QR code small enough to be scanned from a terminal.
+pub const MAX_FRAME_DATA_LEN: usize = 512;
+
+/// Split the given (serialized, and possibly encrypted) share into frames, each
+/// to be encoded as a QR code.
+pub fn encode_frames(share: &[u8]) -> Vec<String> {
+    let chunks = share.chunks(MAX_FRAME_DATA_LEN).collect::<Vec<_>>();
+    let total = chunks.len();
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| format!("{}/{}:{}", i + 1, total, BASE64_STANDARD.encode(chunk)))
+        .collect()
+}
+
+/// Reassemble a share from frames created with [`encode_frames()`], which can
+/// be passed in any order.
+pub fn decode_frames(frames: &[String]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut chunks: Vec<Option<Vec<u8>>> = Vec::new();
+    for frame in frames {
+        let (position, data) = frame
+            .trim()
+            .split_once(':')
+            .ok_or(eyre!("invalid QR code frame"))?;
+        let (index, total) = position
+            .split_once('/')
+            .ok_or(eyre!("invalid QR code frame"))?;
+        let (index, total): (usize, usize) = (index.parse()?, total.parse()?);
+        if chunks.is_empty() {
+            chunks.resize(total, None);
+        }
+        if total != chunks.len() || index == 0 || index > total {
+            return Err(eyre!(
+                "QR code frame {}/{} does not belong to this share",
+                index,
+                total
+            )
+            .into());
+        }
+        chunks[index - 1] = Some(BASE64_STANDARD.decode(data)?);
+    }
+    if chunks.is_empty() {
+        return Err(eyre!("no QR code frames").into());
+    }
+    let mut share = Vec::new();
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        share.extend(chunk.ok_or(eyre!("missing QR code frame {}", i + 1))?);
+    }
+    Ok(share)
+}
+
+/// Render the given frame as a QR code that can be printed to a terminal.
+pub fn render_frame(frame: &str) -> Result<String, Box<dyn Error>> {
+    let code = QrCode::new(frame.as_bytes())?;
+    Ok(code.render::<Dense1x2>().quiet_zone(true).build())
+}
+
+#[cfg(test)]
+mod tests {
+    use frost_ed25519::keys::{IdentifierList, SecretShare};
+    use qrcode::{Color, QrCode};
+    use rand::thread_rng;
+
+    use crate::{
+        encryption::encrypt_share,
+        inputs::Config,
+        qr::{decode_frames, encode_frames},
+        trusted_dealer_keygen::trusted_dealer_keygen,
+    };
+
+    /// Scan the given QR code, returning its contents.
+    fn scan(code: &QrCode) -> String {
+        // Scale each module to a few pixels and add a quiet zone, as a camera
+        // would see it.
+        const SCALE: usize = 4;
+        const QUIET_ZONE: usize = 4;
+        let width = code.width();
+        let colors = code.to_colors();
+        let size = (width + 2 * QUIET_ZONE) * SCALE;
+        let mut image = rqrr::PreparedImage::prepare_from_greyscale(size, size, |x, y| {
+            let (x, y) = (x / SCALE, y / SCALE);
+            if x < QUIET_ZONE
+                || y < QUIET_ZONE
+                || x >= width + QUIET_ZONE
+                || y >= width + QUIET_ZONE
+            {
+                return 255;
+            }
+            match colors[(y - QUIET_ZONE) * width + (x - QUIET_ZONE)] {
+                Color::Dark => 0,
+                Color::Light => 255,
+            }
+        });
+        let grids = image.detect_grids();
+        assert_eq!(grids.len(), 1);
+        grids[0].decode().unwrap().1
+    }
+
+    #[test]
+    fn check_qr_round_trip() {
+        let mut rng = thread_rng();
+        let config = Config {
+            min_signers: 2,
+            max_signers: 3,
+            secret: Vec::new(),
+        };
+        let (shares, _pubkeys) =
+            trusted_dealer_keygen(&config, IdentifierList::Default, &mut rng).unwrap();
+        let share = shares.values().next().unwrap();
+        let bytes = serde_json::to_vec(share).unwrap();
+
+        let keypair = snow::Builder::new("Noise_N_25519_ChaChaPoly_BLAKE2s".parse().unwrap())
+            .generate_keypair()
+            .unwrap();
+        let sealed = encrypt_share(&keypair.public, &bytes).unwrap();
+
+        for bytes in [bytes, sealed] {
+            let frames = encode_frames(&bytes);
+            let mut scanned = frames
+                .iter()
+                .map(|frame| scan(&QrCode::new(frame.as_bytes()).unwrap()))
+                .collect::<Vec<_>>();
+            assert_eq!(scanned, frames);
+            // Frames can be scanned in any order.
+            scanned.reverse();
+            assert_eq!(decode_frames(&scanned).unwrap(), bytes);
+        }
+
+        let opened: SecretShare = serde_json::from_slice(
+            &decode_frames(&encode_frames(&serde_json::to_vec(share).unwrap())).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(&opened, share);
+    }
+
+    #[test]
+    fn check_qr_multiple_frames() {
+        let bytes = (0..2000).map(|i| i as u8).collect::<Vec<_>>();
+        let mut frames = encode_frames(&bytes);
+        assert_eq!(frames.len(), 4);
+        assert!(frames[0].starts_with("1/4:"));
+        assert_eq!(decode_frames(&frames).unwrap(), bytes);
+
+        frames.remove(2);
+        assert_eq!(
+            decode_frames(&frames).unwrap_err().to_string(),
+            "missing QR code frame 3"
+        );
+    }
+}
@@ -1,7 +1,8 @@
 use frost_core::{self as frost, Ciphersuite};
 
-use frost::keys::{IdentifierList, PublicKeyPackage, SecretShare};
-use frost::{Error, Identifier, SigningKey};
+use eyre::eyre;
+use frost::keys::{IdentifierList, KeyPackage, PublicKeyPackage, SecretShare};
+use frost::{Error, Identifier, SigningKey, SigningPackage};
 use rand::{CryptoRng, RngCore};
 use std::collections::BTreeMap;
 
@@ -49,13 +50,67 @@ pub fn split_secret<C: Ciphersuite, R: RngCore + CryptoRng>(
     Ok((shares, pubkeys))
 }
 
+/// The message signed by [`verify_shares`].
+const SELF_CHECK_MESSAGE: &[u8] = b"FROST trusted dealer self-check";
+
+/// Check that the generated shares work: sign a test message with a
+/// threshold of them and verify the signature against the group verifying
+/// key in `pubkeys`. This catches inconsistent output before the shares are
+/// handed out.
+pub fn verify_shares<C: Ciphersuite + 'static, R: RngCore + CryptoRng>(
+    shares: &BTreeMap<Identifier<C>, SecretShare<C>>,
+    pubkeys: &PublicKeyPackage<C>,
+    rng: &mut R,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let key_packages = shares
+        .values()
+        .map(|share| KeyPackage::try_from(share.clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let min_signers = *key_packages
+        .first()
+        .ok_or(eyre!("no shares to verify"))?
+        .min_signers() as usize;
+    let signers = &key_packages[..min_signers.min(key_packages.len())];
+
+    let mut nonces = BTreeMap::new();
+    let mut commitments = BTreeMap::new();
+    for key_package in signers {
+        let (n, c) = frost::round1::commit(key_package.signing_share(), rng);
+        nonces.insert(*key_package.identifier(), n);
+        commitments.insert(*key_package.identifier(), c);
+    }
+
+    let signing_package = SigningPackage::new(commitments, SELF_CHECK_MESSAGE);
+    let mut signature_shares = BTreeMap::new();
+    for key_package in signers {
+        let signature_share = frost::round2::sign(
+            &signing_package,
+            &nonces[key_package.identifier()],
+            key_package,
+        )?;
+        signature_shares.insert(*key_package.identifier(), signature_share);
+    }
+
+    let signature = frost::aggregate(&signing_package, &signature_shares, pubkeys)
+        .map_err(|e| eyre!("generated shares failed the self-check: {}", e))?;
+    pubkeys
+        .verifying_key()
+        .verify(SELF_CHECK_MESSAGE, &signature)
+        .map_err(|e| eyre!("generated shares failed the self-check: {}", e))?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
 
-    use frost_ed25519::keys::IdentifierList;
+    use frost_ed25519::keys::{IdentifierList, SecretShare};
     use rand::thread_rng;
 
-    use crate::{inputs::Config, trusted_dealer_keygen::split_secret};
+    use crate::{
+        inputs::Config,
+        trusted_dealer_keygen::{split_secret, trusted_dealer_keygen, verify_shares},
+    };
 
     #[test]
     fn return_malformed_signing_key_error_if_secret_is_invalid() {
@@ -88,4 +143,46 @@ mod tests {
 
         assert!(out.is_err());
     }
+
+    #[test]
+    fn verify_shares_passes_for_valid_output() {
+        let mut rng = thread_rng();
+        let config = Config {
+            min_signers: 2,
+            max_signers: 3,
+            secret: Vec::new(),
+        };
+
+        let (shares, pubkeys) =
+            trusted_dealer_keygen(&config, IdentifierList::Default, &mut rng).unwrap();
+
+        assert!(verify_shares(&shares, &pubkeys, &mut rng).is_ok());
+    }
+
+    #[test]
+    fn verify_shares_fails_for_corrupted_share() {
+        let mut rng = thread_rng();
+        let config = Config {
+            min_signers: 2,
+            max_signers: 3,
+            secret: Vec::new(),
+        };
+
+        let (mut shares, pubkeys) =
+            trusted_dealer_keygen(&config, IdentifierList::Default, &mut rng).unwrap();
+        let (other_shares, _) =
+            trusted_dealer_keygen(&config, IdentifierList::Default, &mut rng).unwrap();
+
+        // Replace the signing share of the first participant with one from
+        // an unrelated key generation.
+        let (identifier, share) = shares.pop_first().unwrap();
+        let corrupted = SecretShare::new(
+            identifier,
+            *other_shares[&identifier].signing_share(),
+            share.commitment().clone(),
+        );
+        shares.insert(identifier, corrupted);
+
+        assert!(verify_shares(&shares, &pubkeys, &mut rng).is_err());
+    }
 }
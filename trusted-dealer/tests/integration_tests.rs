@@ -2,11 +2,17 @@ mod helpers;
 
 use frost_ed25519 as frost;
 
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use clap::Parser as _;
 use frost::aggregate;
 use frost::keys::IdentifierList;
+use frost::keys::{PublicKeyPackage, SecretShare};
 use helpers::signature_gen::{key_package, round_1, round_2};
 use rand::thread_rng;
-use trusted_dealer::inputs::Config;
+use std::io::BufWriter;
+use trusted_dealer::args::{Args, OutputFormat};
+use trusted_dealer::cli::cli;
+use trusted_dealer::inputs::{print_values, request_inputs, Config};
 use trusted_dealer::trusted_dealer_keygen::split_secret;
 
 use trusted_dealer::trusted_dealer_keygen::trusted_dealer_keygen;
@@ -101,3 +107,252 @@ fn check_keygen_with_dealer_with_secret_with_large_num_of_signers() {
 
     assert!(verify_signature.is_ok());
 }
+
+/// Print the values of a fresh keygen with the given format and check if they
+/// parse back into the original structures.
+fn check_output_format(output_format: OutputFormat) {
+    let mut rng = thread_rng();
+    let config = Config {
+        min_signers: 2,
+        max_signers: 3,
+        secret: Vec::new(),
+    };
+    let (shares, pubkeys) =
+        trusted_dealer_keygen(&config, IdentifierList::Default, &mut rng).unwrap();
+
+    let args = Args {
+        cli: true,
+        output_format,
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    print_values(&args, &shares, &pubkeys, &mut buf).unwrap();
+    let out = String::from_utf8(buf.into_inner().unwrap()).unwrap();
+
+    let decode = |s: &str| match output_format {
+        OutputFormat::Hex => hex::decode(s).unwrap(),
+        OutputFormat::Base64 => BASE64_STANDARD.decode(s).unwrap(),
+        OutputFormat::Json => unreachable!(),
+    };
+
+    let lines = out.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "Public key package:");
+    let parsed_pubkeys: PublicKeyPackage = match output_format {
+        OutputFormat::Json => serde_json::from_str(lines[1]).unwrap(),
+        _ => PublicKeyPackage::deserialize(&decode(lines[1])).unwrap(),
+    };
+    assert_eq!(parsed_pubkeys, pubkeys);
+
+    let parsed_shares = lines
+        .windows(2)
+        .filter(|w| w[0] == "Secret share:")
+        .map(|w| match output_format {
+            OutputFormat::Json => serde_json::from_str::<SecretShare>(w[1]).unwrap(),
+            _ => SecretShare::deserialize(&decode(w[1])).unwrap(),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(parsed_shares, shares.into_values().collect::<Vec<_>>());
+}
+
+#[test]
+fn check_output_format_json() {
+    check_output_format(OutputFormat::Json);
+}
+
+#[test]
+fn check_output_format_hex() {
+    check_output_format(OutputFormat::Hex);
+}
+
+#[test]
+fn check_output_format_base64() {
+    check_output_format(OutputFormat::Base64);
+}
+
+#[test]
+fn check_output_format_default_is_json() {
+    let args = Args::parse_from(["trusted-dealer", "--cli"]);
+    assert_eq!(args.output_format, OutputFormat::Json);
+    assert_eq!(Args::default().output_format, OutputFormat::Json);
+
+    // Hex is opt-in.
+    let args = Args::parse_from(["trusted-dealer", "--cli", "--format", "hex"]);
+    assert_eq!(args.output_format, OutputFormat::Hex);
+}
+
+/// Write the values of a fresh keygen to files, compact and indented, and
+/// check if both parse back into the same structures.
+#[test]
+fn check_output_pretty() {
+    let mut rng = thread_rng();
+    let config = Config {
+        min_signers: 2,
+        max_signers: 3,
+        secret: Vec::new(),
+    };
+    let (shares, pubkeys) =
+        trusted_dealer_keygen(&config, IdentifierList::Default, &mut rng).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let write = |pretty: bool| {
+        let prefix = if pretty { "pretty" } else { "compact" };
+        let public_key_package = dir
+            .path()
+            .join(format!("{}-public-key-package.json", prefix));
+        let key_package = dir.path().join(format!("{}-key-package-{{}}.json", prefix));
+        let args = Args {
+            public_key_package: public_key_package.to_str().unwrap().to_owned(),
+            key_package: key_package.to_str().unwrap().to_owned(),
+            pretty,
+            ..Default::default()
+        };
+        print_values(&args, &shares, &pubkeys, &mut std::io::sink()).unwrap();
+        let share = str::replace(key_package.to_str().unwrap(), "{}", "1");
+        (
+            std::fs::read_to_string(public_key_package).unwrap(),
+            std::fs::read_to_string(share).unwrap(),
+        )
+    };
+    let (compact_pubkeys, compact_share) = write(false);
+    let (pretty_pubkeys, pretty_share) = write(true);
+
+    assert!(!compact_pubkeys.contains('\n'));
+    assert!(pretty_pubkeys.contains('\n'));
+    assert!(pretty_share.contains('\n'));
+    assert_eq!(
+        serde_json::from_str::<PublicKeyPackage>(&pretty_pubkeys).unwrap(),
+        serde_json::from_str::<PublicKeyPackage>(&compact_pubkeys).unwrap()
+    );
+    assert_eq!(
+        serde_json::from_str::<SecretShare>(&pretty_share).unwrap(),
+        serde_json::from_str::<SecretShare>(&compact_share).unwrap()
+    );
+}
+
+/// Encrypt the shares of a fresh keygen, printed and written to files, and
+/// check if `--decrypt-share` reads them back.
+#[test]
+fn check_decrypt_share_output() {
+    let mut rng = thread_rng();
+    let config = Config {
+        min_signers: 2,
+        max_signers: 3,
+        secret: Vec::new(),
+    };
+    let (shares, pubkeys) =
+        trusted_dealer_keygen(&config, IdentifierList::Default, &mut rng).unwrap();
+
+    let builder = snow::Builder::new("Noise_N_25519_ChaChaPoly_BLAKE2s".parse().unwrap());
+    let keypairs = (0..shares.len())
+        .map(|_| builder.generate_keypair().unwrap())
+        .collect::<Vec<_>>();
+    let encrypt_to = keypairs
+        .iter()
+        .map(|k| hex::encode(&k.public))
+        .collect::<Vec<_>>();
+
+    let dir = tempfile::tempdir().unwrap();
+    let key_package = dir.path().join("key-package-{}.json");
+    let written_path = str::replace(key_package.to_str().unwrap(), "{}", "1");
+    let args = Args {
+        public_key_package: dir
+            .path()
+            .join("public-key-package.json")
+            .to_str()
+            .unwrap()
+            .to_owned(),
+        key_package: key_package.to_str().unwrap().to_owned(),
+        encrypt_to: encrypt_to.clone(),
+        ..Default::default()
+    };
+    print_values(&args, &shares, &pubkeys, &mut std::io::sink()).unwrap();
+
+    let args = Args {
+        cli: true,
+        encrypt_to,
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    print_values(&args, &shares, &pubkeys, &mut buf).unwrap();
+    let out = String::from_utf8(buf.into_inner().unwrap()).unwrap();
+    let printed = out
+        .lines()
+        .skip_while(|l| *l != "Encrypted secret share:")
+        .nth(1)
+        .unwrap();
+    let printed_path = dir.path().join("printed-share.txt");
+    std::fs::write(&printed_path, format!("{}\n", printed)).unwrap();
+
+    for path in [written_path, printed_path.to_str().unwrap().to_owned()] {
+        let args = Args {
+            cli: true,
+            decrypt_share: Some(path),
+            ..Default::default()
+        };
+        let input = format!("{}\n", hex::encode(&keypairs[0].private));
+        let mut buf = BufWriter::new(Vec::new());
+        cli::<frost::Ed25519Sha512>(&args, &mut input.as_bytes(), &mut buf).unwrap();
+        let out = String::from_utf8(buf.into_inner().unwrap()).unwrap();
+        let share = out
+            .lines()
+            .skip_while(|l| *l != "Secret share:")
+            .nth(1)
+            .unwrap();
+        assert_eq!(
+            &serde_json::from_str::<SecretShare>(share).unwrap(),
+            shares.values().next().unwrap()
+        );
+    }
+}
+
+fn check_invalid_signers(min_signers: u16, max_signers: u16, message: &str) {
+    let err = Config::new(min_signers, max_signers, Vec::new()).unwrap_err();
+    assert_eq!(err.to_string(), message);
+}
+
+#[test]
+fn check_config_min_signers_greater_than_max_signers() {
+    check_invalid_signers(
+        4,
+        3,
+        "minimum signers must be at least 2 and no greater than maximum signers",
+    );
+}
+
+#[test]
+fn check_config_min_signers_less_than_2() {
+    check_invalid_signers(
+        1,
+        3,
+        "minimum signers must be at least 2 and no greater than maximum signers",
+    );
+}
+
+#[test]
+fn check_config_max_signers_less_than_2() {
+    check_invalid_signers(2, 0, "maximum signers must be 2 or more");
+    check_invalid_signers(2, 1, "maximum signers must be 2 or more");
+}
+
+#[test]
+fn check_request_inputs_invalid_signers() {
+    let args = Args {
+        cli: true,
+        ..Default::default()
+    };
+    let mut buf = BufWriter::new(Vec::new());
+    let err =
+        request_inputs::<frost::Ed25519Sha512>(&args, &mut "hello\n3\n\n".as_bytes(), &mut buf)
+            .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "minimum signers must be a number (2 or more)"
+    );
+
+    let err = request_inputs::<frost::Ed25519Sha512>(&args, &mut "3\n2\n\n".as_bytes(), &mut buf)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "minimum signers must be at least 2 and no greater than maximum signers"
+    );
+}
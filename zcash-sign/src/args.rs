@@ -1,4 +1,5 @@
 use clap::{Parser, Subcommand};
+use frost_zcash_sign::OvkPolicy;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -20,6 +21,17 @@ pub(crate) enum Command {
         /// funds will become unspendable!
         #[arg(long, default_value_t = false)]
         danger_dummy_sapling: bool,
+        /// The Sapling diversifiable full viewing key to include in the
+        /// UnifiedFullViewingKey, in hex format. Can't be used along with
+        /// `--danger-dummy-sapling`.
+        #[arg(long, conflicts_with = "danger_dummy_sapling")]
+        sapling_fvk: Option<String>,
+        /// The file where to write the Orchard-only unified address and the
+        /// UnifiedFullViewingKey, as a JSON object with the `network`,
+        /// `orchard_address` and `ufvk` fields. If not specified, they are
+        /// printed.
+        #[arg(short = 'o', long)]
+        out: Option<String>,
     },
     Sign {
         /// The file containing the JSON Ywallet transaction plan
@@ -30,8 +42,64 @@ pub(crate) enum Command {
         #[arg(short = 'o', long)]
         tx: String,
 
-        /// The UnifiedFullViewingKey generated previously, in hex format
+        /// The UnifiedFullViewingKey generated previously, in hex format.
+        /// Specify it multiple times if the plan spends notes controlled by
+        /// different keys; the key used to create the plan must be included.
+        #[arg(short, long = "ufvk", required = true)]
+        ufvks: Vec<String>,
+
+        /// The expiry height to use instead of the one in the transaction
+        /// plan. Must be greater than the anchor height, or 0 for no expiry.
+        #[arg(long)]
+        expiry_height: Option<u32>,
+
+        /// The file where to write the SIGHASH and randomizers as JSON, so
+        /// that the signatures can be generated programmatically (e.g. by a
+        /// FROST coordinator). They are still printed as well.
+        #[arg(long)]
+        emit_signing_requests: Option<String>,
+
+        /// The outgoing viewing key to encrypt the Orchard outputs to.
+        /// "none" prevents anyone, including the sender, from recovering them
+        /// with the full viewing key.
+        #[arg(long, value_enum, default_value_t = OvkPolicy::External)]
+        ovk: OvkPolicy,
+
+        /// Pad the Orchard bundle with dummy outputs up to the given number
+        /// of actions, so that it does not reveal how many notes are spent
+        /// and created. Bundles always have at least 2 actions.
+        #[arg(long, default_value_t = 0)]
+        orchard_padding: usize,
+    },
+    /// Print a summary of a signed transaction
+    Inspect {
+        /// The file containing the base64 transaction written by `sign`
+        #[arg(short = 'i', long)]
+        tx: String,
+    },
+    /// Sign a 32-byte digest (e.g. a SIGHASH) with a randomized SpendAuth
+    /// signature, outside of a transaction. The signature must be generated
+    /// externally (e.g. by a FROST coordinator); it is checked against the
+    /// randomized verifying key.
+    SignDigest {
+        /// The digest to sign, in hex format
+        #[arg(short, long)]
+        sighash: String,
+        /// The randomizer (alpha) to use, in hex format. If not specified, a
+        /// random one is generated.
+        #[arg(short, long)]
+        randomizer: Option<String>,
+        /// The SpendValidatingKey (VerifyingKey in FROST) that must sign, in
+        /// hex format
         #[arg(short, long)]
-        ufvk: String,
+        ak: String,
+    },
+    /// Write an example Orchard-only transaction plan, showing the format
+    /// expected by `sign`. Its note, witness and anchor are placeholders.
+    ExamplePlan {
+        /// The file where to write the JSON transaction plan. If not
+        /// specified, it is printed.
+        #[arg(short = 'o', long)]
+        out: Option<String>,
     },
 }
@@ -0,0 +1,87 @@
+use std::error::Error;
+
+use eyre::eyre;
+use halo2_proofs::arithmetic::CurveExt as _;
+use halo2_proofs::pasta::{
+    group::{
+        ff::{Field, PrimeField},
+        GroupEncoding,
+    },
+    pallas,
+};
+use orchard::primitives::redpallas::{self, SpendAuth};
+use rand_core::{CryptoRng, RngCore};
+
+/// The SpendAuth basepoint, GroupHash^P("z.cash:Orchard", "G").
+fn spend_auth_basepoint() -> pallas::Point {
+    pallas::Point::hash_to_curve("z.cash:Orchard")(b"G")
+}
+
+/// Generate a random randomizer (alpha) to sign a digest with.
+pub fn random_randomizer(rng: &mut (impl RngCore + CryptoRng)) -> [u8; 32] {
+    pallas::Scalar::random(rng).to_repr()
+}
+
+/// Return the randomized verifying key (rk) for the given SpendValidatingKey
+/// (VerifyingKey in FROST) and randomizer, i.e. `ak + [alpha] G`.
+pub fn randomized_verifying_key(
+    ak: &[u8; 32],
+    alpha: &[u8; 32],
+) -> Result<[u8; 32], Box<dyn Error>> {
+    let ak =
+        Option::<pallas::Point>::from(pallas::Point::from_bytes(ak)).ok_or(eyre!("Invalid ak"))?;
+    let alpha = Option::<pallas::Scalar>::from(pallas::Scalar::from_repr(*alpha))
+        .ok_or(eyre!("Invalid randomizer"))?;
+    Ok((ak + spend_auth_basepoint() * alpha).to_bytes())
+}
+
+/// Verify a SpendAuth signature of `digest`, generated outside of a
+/// transaction (e.g. by a FROST coordinator) with the given randomizer.
+pub fn verify_digest_signature(
+    ak: &[u8; 32],
+    alpha: &[u8; 32],
+    digest: &[u8; 32],
+    signature: &[u8; 64],
+) -> Result<(), Box<dyn Error>> {
+    let rk =
+        redpallas::VerificationKey::<SpendAuth>::try_from(randomized_verifying_key(ak, alpha)?)
+            .map_err(|_| eyre!("Invalid randomized verifying key"))?;
+    rk.verify(digest, &redpallas::Signature::<SpendAuth>::from(*signature))
+        .map_err(|_| eyre!("Invalid signature"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use orchard::keys::{FullViewingKey, SpendAuthorizingKey};
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::sign::tests::example_spending_key;
+
+    #[test]
+    fn check_verify_digest_signature() {
+        let sk = example_spending_key();
+        let ak: [u8; 32] = FullViewingKey::from(&sk).to_bytes()[..32]
+            .try_into()
+            .unwrap();
+        let digest = [3; 32];
+        let alpha = random_randomizer(&mut thread_rng());
+
+        let rsk =
+            SpendAuthorizingKey::from(&sk).randomize(&pallas::Scalar::from_repr(alpha).unwrap());
+        let signature: [u8; 64] = (&rsk.sign(thread_rng(), &digest)).into();
+
+        // The randomized verifying key is the one of the randomized signing
+        // key.
+        let rk: [u8; 32] = (&redpallas::VerificationKey::from(&rsk)).into();
+        assert_eq!(randomized_verifying_key(&ak, &alpha).unwrap(), rk);
+
+        verify_digest_signature(&ak, &alpha, &digest, &signature).unwrap();
+
+        // The signature is only valid for the same randomizer and digest.
+        let other_alpha = random_randomizer(&mut thread_rng());
+        assert!(verify_digest_signature(&ak, &other_alpha, &digest, &signature).is_err());
+        assert!(verify_digest_signature(&ak, &alpha, &[4; 32], &signature).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use orchard::{
+    keys::{FullViewingKey, Scope, SpendingKey},
+    note::{ExtractedNoteCommitment, RandomSeed, Rho},
+    value::NoteValue,
+    Note,
+};
+use zcash_protocol::memo::MemoBytes;
+
+use crate::transaction_plan::{
+    CTree, Destination, Fill, Hasher, OrchardHasher, Source, TransactionPlan, UTXO,
+};
+
+/// The value of the note spent by the example plan, in zatoshis.
+const EXAMPLE_SPEND_AMOUNT: u64 = 100_000;
+/// The fee paid by the example plan, in zatoshis (the ZIP-317 fee for the two
+/// Orchard actions it requires).
+const EXAMPLE_FEE: u64 = 10_000;
+/// The anchor height of the example plan.
+const EXAMPLE_ANCHOR_HEIGHT: u32 = 2_500_000;
+/// The spending key which controls the note spent by the example plan. It is
+/// public, so it must never control real funds.
+pub(crate) const EXAMPLE_SPENDING_KEY: [u8; 32] = [1; 32];
+
+/// Create a minimal Orchard-only transaction plan, which spends a single note
+/// and sends its value (minus the fee) back to the same key. It is meant to
+/// show the format expected by `sign`; its fields are:
+///
+/// - `taddr` and `fvk`: the transparent address and Sapling key of the wallet,
+///   unused for Orchard-only plans.
+/// - `orchard_fvk`: the hex-encoded Orchard full viewing key which created the
+///   plan; it must be part of one of the UFVKs passed to `sign`.
+/// - `anchor_height` and `expiry_height`: the height of the block the anchor
+///   was taken from, and the height after which the transaction expires.
+/// - `orchard_anchor`: the root of the Orchard note commitment tree at
+///   `anchor_height`.
+/// - `spends`: the notes to spend, each with the data required to recompute
+///   it and its Merkle path (`witness`).
+/// - `outputs`: the recipients, amounts and hex-encoded memos.
+/// - `fee` and `net_chg`: the fee, and the net change of the Sapling and
+///   Orchard pools.
+///
+/// The note, witness and anchor are placeholders computed from a fixed key:
+/// they are consistent with each other, but do not exist on chain.
+pub fn example_plan() -> TransactionPlan {
+    let sk = SpendingKey::from_bytes(EXAMPLE_SPENDING_KEY).expect("fixed key is valid");
+    let fvk = FullViewingKey::from(&sk);
+    let address = fvk.address_at(0u64, Scope::External);
+
+    let rho = Rho::from_bytes(&[1; 32]).expect("fixed rho is valid");
+    let rseed = RandomSeed::from_bytes([2; 32], &rho).expect("fixed rseed is valid");
+    let note = Note::from_parts(
+        address,
+        NoteValue::from_raw(EXAMPLE_SPEND_AMOUNT),
+        rho,
+        rseed,
+    )
+    .expect("fixed note is valid");
+    let cmx = ExtractedNoteCommitment::from(note.commitment()).to_bytes();
+
+    // A commitment tree holding only the spent note, which is the leftmost
+    // leaf; see `Witness::read()` for the encoding.
+    let mut witness = Vec::new();
+    witness.push(1);
+    witness.extend_from_slice(&cmx);
+    witness.extend_from_slice(&[0, 0, 0, 0]);
+    witness.extend_from_slice(&cmx);
+    let tree = CTree {
+        left: Some(cmx),
+        right: None,
+        parents: vec![],
+    };
+    let hasher = OrchardHasher::new();
+    let orchard_anchor = tree.root(32, &hasher.empty_roots(32), &hasher);
+
+    TransactionPlan {
+        taddr: String::new(),
+        fvk: String::new(),
+        orchard_fvk: hex::encode(fvk.to_bytes()),
+        anchor_height: EXAMPLE_ANCHOR_HEIGHT,
+        expiry_height: EXAMPLE_ANCHOR_HEIGHT + 40,
+        orchard_anchor,
+        spends: vec![UTXO {
+            id: 0,
+            source: Source::Orchard {
+                id_note: 0,
+                diversifier: *address.diversifier().as_array(),
+                rseed: *rseed.as_bytes(),
+                rho: rho.to_bytes(),
+                witness,
+            },
+            amount: EXAMPLE_SPEND_AMOUNT,
+        }],
+        outputs: vec![Fill {
+            id_order: None,
+            destination: Destination::Orchard(address.to_raw_address_bytes()),
+            amount: EXAMPLE_SPEND_AMOUNT - EXAMPLE_FEE,
+            memo: MemoBytes::empty(),
+        }],
+        fee: EXAMPLE_FEE,
+        net_chg: [0, 0],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        sign::{check_witness_anchor, validate_expiry_height, ORCHARD_ROOTS},
+        transaction_plan::Witness,
+    };
+
+    #[test]
+    fn check_example_plan() {
+        // The plan printed by `example-plan` is read back by `sign` as is.
+        let json = serde_json::to_string_pretty(&example_plan()).unwrap();
+        let tx_plan: TransactionPlan = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string_pretty(&tx_plan).unwrap(), json);
+
+        assert_eq!(
+            validate_expiry_height(&tx_plan, None).unwrap(),
+            EXAMPLE_ANCHOR_HEIGHT + 40
+        );
+        let spent: u64 = tx_plan.spends.iter().map(|s| s.amount).sum();
+        let sent: u64 = tx_plan.outputs.iter().map(|o| o.amount).sum();
+        assert_eq!(spent, sent + tx_plan.fee);
+
+        let fvk = FullViewingKey::from_bytes(
+            &hex::decode(&tx_plan.orchard_fvk)
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap();
+        for spend in &tx_plan.spends {
+            let Source::Orchard {
+                id_note,
+                diversifier,
+                rseed,
+                rho,
+                witness,
+            } = &spend.source
+            else {
+                panic!("the example plan only spends Orchard notes");
+            };
+            // The note is controlled by the plan's key...
+            let rho = Rho::from_bytes(rho).unwrap();
+            let note = Note::from_parts(
+                fvk.address(
+                    orchard::keys::Diversifier::from_bytes(*diversifier),
+                    Scope::External,
+                ),
+                NoteValue::from_raw(spend.amount),
+                rho,
+                RandomSeed::from_bytes(*rseed, &rho).unwrap(),
+            )
+            .unwrap();
+            // ... and its witness leads from its commitment to the anchor.
+            let witness = Witness::from_bytes(*id_note, witness).unwrap();
+            assert_eq!(
+                ExtractedNoteCommitment::from(note.commitment()).to_bytes(),
+                witness.cmx
+            );
+            let auth_path = witness.auth_path(32, &ORCHARD_ROOTS, &OrchardHasher::new());
+            check_witness_anchor(&witness, &auth_path, &tx_plan.orchard_anchor).unwrap();
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use std::{error::Error, fmt};
+
+use base64::{prelude::BASE64_STANDARD, Engine as _};
+use zcash_primitives::transaction::{Transaction, TxVersion};
+use zcash_protocol::consensus::BranchId;
+
+/// A summary of the contents of a transaction.
+pub struct TransactionSummary {
+    pub version: TxVersion,
+    pub consensus_branch_id: BranchId,
+    pub orchard_actions: usize,
+    pub sapling_spends: usize,
+    pub sapling_outputs: usize,
+    pub transparent_outputs: usize,
+    /// The fee in zatoshis, if it can be computed from the transaction alone
+    /// (i.e. if it has no transparent inputs).
+    pub fee: Option<i64>,
+    /// The shielded signature hash, if it can be computed from the transaction
+    /// alone (i.e. if it is a v5 transaction without transparent inputs).
+    pub sighash: Option<[u8; 32]>,
+}
+
+impl fmt::Display for TransactionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Version: {:?}", self.version)?;
+        writeln!(
+            f,
+            "Consensus branch ID: {:?} ({:#010x})",
+            self.consensus_branch_id,
+            u32::from(self.consensus_branch_id)
+        )?;
+        writeln!(f, "Orchard actions: {}", self.orchard_actions)?;
+        writeln!(f, "Sapling spends: {}", self.sapling_spends)?;
+        writeln!(f, "Sapling outputs: {}", self.sapling_outputs)?;
+        writeln!(f, "Transparent outputs: {}", self.transparent_outputs)?;
+        match self.fee {
+            Some(fee) => writeln!(f, "Fee: {} zatoshis", fee)?,
+            None => writeln!(f, "Fee: unknown (transaction has transparent inputs)")?,
+        }
+        match self.sighash {
+            Some(sighash) => write!(f, "SIGHASH: {}", hex::encode(sighash)),
+            None => write!(f, "SIGHASH: unknown"),
+        }
+    }
+}
+
+/// Read a transaction encoded in base64, as written by the `sign` command.
+pub fn read_transaction(tx: &str) -> Result<Transaction, Box<dyn Error>> {
+    let tx_bytes = BASE64_STANDARD.decode(tx.trim())?;
+    // The branch ID is only used for transactions before v5, which do not
+    // encode it; `sign` only creates v5 transactions.
+    Ok(Transaction::read(&tx_bytes[..], BranchId::Nu5)?)
+}
+
+/// Summarize the contents of the given transaction.
+pub fn inspect(tx: &Transaction) -> TransactionSummary {
+    let orchard_bundle = tx.orchard_bundle();
+    let sapling_bundle = tx.sapling_bundle();
+    let transparent_bundle = tx.transparent_bundle();
+
+    let has_transparent_inputs = transparent_bundle.is_some_and(|b| !b.vin.is_empty());
+
+    let fee = if has_transparent_inputs {
+        None
+    } else {
+        let transparent_out: i64 = transparent_bundle
+            .map(|b| b.vout.iter().map(|o| u64::from(o.value) as i64).sum())
+            .unwrap_or_default();
+        let orchard_balance = orchard_bundle
+            .map(|b| i64::from(*b.value_balance()))
+            .unwrap_or_default();
+        let sapling_balance = sapling_bundle
+            .map(|b| i64::from(*b.value_balance()))
+            .unwrap_or_default();
+        Some(orchard_balance + sapling_balance - transparent_out)
+    };
+
+    // Per ZIP 244, the signature hash for shielded inputs of a v5 transaction
+    // without transparent inputs is identical to its txid digest.
+    let sighash = if matches!(tx.version(), TxVersion::Zip225) && !has_transparent_inputs {
+        Some(*tx.txid().as_ref())
+    } else {
+        None
+    };
+
+    TransactionSummary {
+        version: tx.version(),
+        consensus_branch_id: tx.consensus_branch_id(),
+        orchard_actions: orchard_bundle
+            .map(|b| b.actions().len())
+            .unwrap_or_default(),
+        sapling_spends: sapling_bundle
+            .map(|b| b.shielded_spends().len())
+            .unwrap_or_default(),
+        sapling_outputs: sapling_bundle
+            .map(|b| b.shielded_outputs().len())
+            .unwrap_or_default(),
+        transparent_outputs: transparent_bundle.map(|b| b.vout.len()).unwrap_or_default(),
+        fee,
+        sighash,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        example_plan,
+        sign::tests::{example_spending_key, sign_with_keys},
+        OvkPolicy,
+    };
+
+    #[test]
+    fn check_inspect() {
+        let tx_plan = example_plan();
+        let (tx, requests) = sign_with_keys(
+            &tx_plan,
+            &[example_spending_key()],
+            None,
+            OvkPolicy::External,
+            0,
+        )
+        .unwrap();
+
+        // Read it back as written by the `sign` command.
+        let mut tx_bytes = vec![];
+        tx.write(&mut tx_bytes).unwrap();
+        let tx = read_transaction(&BASE64_STANDARD.encode(&tx_bytes)).unwrap();
+        let summary = inspect(&tx);
+
+        // The builder creates one action for each spend or output, whichever
+        // are more numerous, but at least two.
+        assert_eq!(
+            summary.orchard_actions,
+            tx_plan.spends.len().max(tx_plan.outputs.len()).max(2)
+        );
+        assert_eq!(summary.sapling_spends, 0);
+        assert_eq!(summary.sapling_outputs, 0);
+        assert_eq!(summary.transparent_outputs, 0);
+        assert_eq!(summary.fee, Some(tx_plan.fee as i64));
+        assert_eq!(summary.sighash, Some(requests.sighash));
+    }
+}
@@ -1,6 +1,12 @@
+mod digest;
+mod example;
 mod generate;
+mod inspect;
 mod sign;
 pub mod transaction_plan;
 
+pub use digest::{random_randomizer, randomized_verifying_key, verify_digest_signature};
+pub use example::example_plan;
 pub use generate::generate;
-pub use sign::sign;
+pub use inspect::{inspect, read_transaction, TransactionSummary};
+pub use sign::{sign, OvkPolicy, RandomizerRequest, SigningRequests};
@@ -8,12 +8,12 @@ use eyre::eyre;
 use rand::{thread_rng, RngCore};
 
 use orchard::keys::{Scope, SpendValidatingKey};
-use sapling_crypto::zip32::ExtendedSpendingKey;
+use sapling_crypto::zip32::{DiversifiableFullViewingKey, ExtendedSpendingKey};
 use zcash_client_backend::address::UnifiedAddress;
 use zcash_keys::keys::UnifiedFullViewingKey;
 use zcash_protocol::consensus::MainNetwork;
 
-use frost_zcash_sign::transaction_plan::TransactionPlan;
+use frost_zcash_sign::{transaction_plan::TransactionPlan, SigningRequests};
 
 use args::{Args, Command};
 
@@ -21,6 +21,8 @@ fn generate(args: &Command) -> Result<(), Box<dyn Error>> {
     let Command::Generate {
         ak,
         danger_dummy_sapling,
+        sapling_fvk,
+        out,
     } = args
     else {
         panic!("invalid Command");
@@ -39,9 +41,15 @@ fn generate(args: &Command) -> Result<(), Box<dyn Error>> {
     // TODO: make params selectable
     let unified_address_str = unified_address.encode(&MainNetwork);
 
-    println!("Orchard-only unified address: {:?}", unified_address_str);
-
-    let sapling_fvk = if *danger_dummy_sapling {
+    let sapling_fvk = if let Some(sapling_fvk) = sapling_fvk {
+        let sapling_fvk: [u8; 128] = hex::decode(sapling_fvk.trim())?
+            .try_into()
+            .map_err(|_| eyre!("Invalid Sapling FVK length"))?;
+        Some(
+            DiversifiableFullViewingKey::from_bytes(&sapling_fvk)
+                .ok_or(eyre!("Invalid Sapling FVK"))?,
+        )
+    } else if *danger_dummy_sapling {
         let mut seed = [0u8; 64];
         rng.fill_bytes(&mut seed[..]);
         let spending_key = ExtendedSpendingKey::master(&seed);
@@ -53,7 +61,18 @@ fn generate(args: &Command) -> Result<(), Box<dyn Error>> {
     let ufvk = UnifiedFullViewingKey::new(sapling_fvk, Some(fvk.clone())).unwrap();
     let ufvk_str = ufvk.encode(&MainNetwork);
 
-    println!("Unified Full Viewing Key: {:?}", ufvk_str);
+    if let Some(path) = out {
+        let keys = serde_json::json!({
+            "network": "main",
+            "orchard_address": unified_address_str,
+            "ufvk": ufvk_str,
+        });
+        fs::write(path, serde_json::to_string_pretty(&keys)?)?;
+        println!("Address and UFVK written to {}", path);
+    } else {
+        println!("Orchard-only unified address: {:?}", unified_address_str);
+        println!("Unified Full Viewing Key: {:?}", ufvk_str);
+    }
 
     Ok(())
 }
@@ -61,8 +80,12 @@ fn generate(args: &Command) -> Result<(), Box<dyn Error>> {
 fn sign(args: &Command) -> Result<(), Box<dyn Error>> {
     let Command::Sign {
         tx_plan,
-        ufvk,
+        ufvks,
         tx: tx_path,
+        expiry_height,
+        emit_signing_requests,
+        ovk,
+        orchard_padding,
     } = args
     else {
         panic!("invalid Command")
@@ -73,11 +96,28 @@ fn sign(args: &Command) -> Result<(), Box<dyn Error>> {
     let tx_plan = fs::read_to_string(tx_plan)?;
     let tx_plan: TransactionPlan = serde_json::from_str(&tx_plan)?;
 
-    let ufvk = UnifiedFullViewingKey::decode(&network, ufvk.trim()).unwrap();
+    let ufvks = ufvks
+        .iter()
+        .map(|ufvk| UnifiedFullViewingKey::decode(&network, ufvk.trim()).unwrap())
+        .collect::<Vec<_>>();
 
     let mut rng = thread_rng();
 
-    let tx = frost_zcash_sign::sign(&mut rng, &tx_plan, &ufvk)?;
+    let tx = frost_zcash_sign::sign(
+        &mut rng,
+        &tx_plan,
+        &ufvks,
+        *expiry_height,
+        *ovk,
+        *orchard_padding,
+        |requests| {
+            if let Some(path) = emit_signing_requests {
+                fs::write(path, serde_json::to_string_pretty(requests)?)?;
+                println!("Signing requests written to {}", path);
+            }
+            read_signatures(requests)
+        },
+    )?;
 
     let mut tx_bytes = vec![];
     tx.write(&mut tx_bytes).unwrap();
@@ -88,13 +128,203 @@ fn sign(args: &Command) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Print the SIGHASH and randomizers, and read the corresponding signatures
+/// from stdin.
+fn read_signatures(requests: &SigningRequests) -> Result<Vec<[u8; 64]>, Box<dyn Error>> {
+    println!("SIGHASH: {}", hex::encode(requests.sighash));
+
+    // Each key signs in its own FROST session, so print which key each
+    // group of signatures is for when there are multiple keys.
+    let multiple_keys = requests
+        .randomizers
+        .iter()
+        .any(|r| r.ak != requests.randomizers[0].ak);
+    let mut signatures = Vec::new();
+    for request in &requests.randomizers {
+        if multiple_keys && request.index == 0 {
+            println!("Signatures for key {}:", hex::encode(request.ak));
+        }
+        println!(
+            "Randomizer #{}: {}",
+            request.index,
+            hex::encode(request.alpha)
+        );
+        let mut buffer = String::new();
+        println!("Input hex-encoded signature #{}: ", request.index);
+        std::io::stdin().read_line(&mut buffer)?;
+        let signature = hex::decode(buffer.trim())?
+            .try_into()
+            .map_err(|_| eyre!("Invalid signature length"))?;
+        signatures.push(signature);
+    }
+    Ok(signatures)
+}
+
+fn sign_digest(args: &Command) -> Result<(), Box<dyn Error>> {
+    let Command::SignDigest {
+        sighash,
+        randomizer,
+        ak,
+    } = args
+    else {
+        panic!("invalid Command")
+    };
+
+    let sighash: [u8; 32] = hex::decode(sighash.trim())?
+        .try_into()
+        .map_err(|_| eyre!("Invalid SIGHASH length"))?;
+    let ak: [u8; 32] = hex::decode(ak.trim())?
+        .try_into()
+        .map_err(|_| eyre!("Invalid ak length"))?;
+    let alpha: [u8; 32] = if let Some(randomizer) = randomizer {
+        hex::decode(randomizer.trim())?
+            .try_into()
+            .map_err(|_| eyre!("Invalid randomizer length"))?
+    } else {
+        frost_zcash_sign::random_randomizer(&mut thread_rng())
+    };
+    let rk = frost_zcash_sign::randomized_verifying_key(&ak, &alpha)?;
+
+    println!("SIGHASH: {}", hex::encode(sighash));
+    println!("Randomizer: {}", hex::encode(alpha));
+    println!("Randomized verifying key: {}", hex::encode(rk));
+
+    let mut buffer = String::new();
+    println!("Input hex-encoded signature: ");
+    std::io::stdin().read_line(&mut buffer)?;
+    let signature: [u8; 64] = hex::decode(buffer.trim())?
+        .try_into()
+        .map_err(|_| eyre!("Invalid signature length"))?;
+    frost_zcash_sign::verify_digest_signature(&ak, &alpha, &sighash, &signature)?;
+
+    println!("Signature: {}", hex::encode(signature));
+
+    Ok(())
+}
+
+fn inspect(args: &Command) -> Result<(), Box<dyn Error>> {
+    let Command::Inspect { tx: tx_path } = args else {
+        panic!("invalid Command")
+    };
+
+    let tx = frost_zcash_sign::read_transaction(&fs::read_to_string(tx_path)?)?;
+
+    println!("{}", frost_zcash_sign::inspect(&tx));
+
+    Ok(())
+}
+
+fn example_plan(args: &Command) -> Result<(), Box<dyn Error>> {
+    let Command::ExamplePlan { out } = args else {
+        panic!("invalid Command")
+    };
+
+    let tx_plan = serde_json::to_string_pretty(&frost_zcash_sign::example_plan())?;
+
+    if let Some(path) = out {
+        fs::write(path, tx_plan)?;
+        println!("Transaction plan written to {}", path);
+    } else {
+        println!("{}", tx_plan);
+    }
+
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
 
     match args.command {
         Command::Generate { .. } => generate(&args.command),
         Command::Sign { .. } => sign(&args.command),
+        Command::SignDigest { .. } => sign_digest(&args.command),
+        Command::Inspect { .. } => inspect(&args.command),
+        Command::ExamplePlan { .. } => example_plan(&args.command),
     }?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use orchard::keys::{FullViewingKey, SpendingKey};
+
+    use super::*;
+
+    /// Return the hex-encoded ak of a fixed Orchard key.
+    fn test_ak() -> String {
+        let fvk = FullViewingKey::from(&SpendingKey::from_bytes([1; 32]).unwrap());
+        hex::encode(&fvk.to_bytes()[..32])
+    }
+
+    /// Run the `generate` command for `test_ak()`, returning the JSON document
+    /// it writes to the `--out` file.
+    fn generate_to_json(
+        danger_dummy_sapling: bool,
+        sapling_fvk: Option<String>,
+    ) -> serde_json::Value {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let out = temp_dir.path().join("keys.json");
+        generate(&Command::Generate {
+            ak: test_ak(),
+            danger_dummy_sapling,
+            sapling_fvk,
+            out: Some(out.to_str().unwrap().to_string()),
+        })
+        .unwrap();
+        serde_json::from_str(&fs::read_to_string(&out).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn check_generate_sapling_fvk() {
+        let sapling_fvk = ExtendedSpendingKey::master(&[1; 64]).to_diversifiable_full_viewing_key();
+        let sapling_fvk_hex = hex::encode(sapling_fvk.to_bytes());
+
+        let keys = generate_to_json(false, Some(sapling_fvk_hex.clone()));
+        let ufvk =
+            UnifiedFullViewingKey::decode(&MainNetwork, keys["ufvk"].as_str().unwrap()).unwrap();
+        assert_eq!(ufvk.sapling().unwrap().to_bytes(), sapling_fvk.to_bytes());
+        assert!(ufvk.orchard().is_some());
+
+        // Without a Sapling key, the UFVK is Orchard-only.
+        let keys = generate_to_json(false, None);
+        let ufvk =
+            UnifiedFullViewingKey::decode(&MainNetwork, keys["ufvk"].as_str().unwrap()).unwrap();
+        assert!(ufvk.sapling().is_none());
+
+        // A real and a dummy Sapling key can't be both requested.
+        assert!(Args::try_parse_from([
+            "frost-zcash-sign",
+            "generate",
+            "--ak",
+            &test_ak(),
+            "--danger-dummy-sapling",
+            "--sapling-fvk",
+            &sapling_fvk_hex,
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn check_generate_out() {
+        let keys = generate_to_json(false, None);
+        assert_eq!(keys["network"], "main");
+
+        // The UFVK is the one of the given ak...
+        let ufvk =
+            UnifiedFullViewingKey::decode(&MainNetwork, keys["ufvk"].as_str().unwrap()).unwrap();
+        let fvk = ufvk.orchard().unwrap();
+        assert_eq!(hex::encode(&fvk.to_bytes()[..32]), test_ak());
+
+        // ... and the address is an Orchard-only address of that UFVK.
+        let address =
+            UnifiedAddress::decode(&MainNetwork, keys["orchard_address"].as_str().unwrap())
+                .unwrap();
+        assert!(address.sapling().is_none());
+        assert_eq!(
+            address.orchard().unwrap(),
+            &fvk.address_at(0u64, Scope::External)
+        );
+    }
+}
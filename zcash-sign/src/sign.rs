@@ -3,14 +3,16 @@ use std::error::Error;
 use eyre::eyre;
 use lazy_static::lazy_static;
 use rand_core::{CryptoRng, RngCore};
+use serde::Serialize;
+use serde_hex::{SerHex, Strict};
 
 use halo2_proofs::pasta::group::ff::PrimeField;
 use orchard::{
     builder::MaybeSigned,
     bundle::Flags,
     circuit::ProvingKey,
-    keys::{Scope, SpendValidatingKey},
-    note::Rho,
+    keys::{FullViewingKey, OutgoingViewingKey, Scope, SpendValidatingKey, SpendingKey},
+    note::{ExtractedNoteCommitment, Rho},
     primitives::redpallas::{self, SpendAuth},
     value::NoteValue,
     Address, Anchor,
@@ -44,27 +46,153 @@ lazy_static! {
     };
 }
 
+/// The signatures required to authorize a transaction, which must be generated
+/// externally (e.g. by a FROST coordinator).
+#[derive(Clone, Debug, Serialize)]
+pub struct SigningRequests {
+    /// The shielded signature hash, i.e. the message to sign.
+    #[serde(with = "SerHex::<Strict>")]
+    pub sighash: [u8; 32],
+    /// The signatures to generate, grouped by key, in the order in which the
+    /// keys were given to [`sign`].
+    pub randomizers: Vec<RandomizerRequest>,
+}
+
+/// A request for a signature of the SIGHASH with a re-randomized key.
+#[derive(Clone, Debug, Serialize)]
+pub struct RandomizerRequest {
+    /// The index of the signature among the ones generated with the same key.
+    pub index: usize,
+    /// The randomizer to use.
+    #[serde(with = "SerHex::<Strict>")]
+    pub alpha: [u8; 32],
+    /// The SpendValidatingKey (VerifyingKey in FROST) that must sign.
+    #[serde(with = "SerHex::<Strict>")]
+    pub ak: [u8; 32],
+}
+
+/// Return the expiry height to use: `expiry_height` if specified, otherwise
+/// the one in the transaction plan. It must be greater than the anchor height,
+/// unless it is zero (meaning no expiry).
+pub(crate) fn validate_expiry_height(
+    tx_plan: &TransactionPlan,
+    expiry_height: Option<u32>,
+) -> Result<u32, Box<dyn Error>> {
+    let expiry_height = expiry_height.unwrap_or(tx_plan.expiry_height);
+    if expiry_height != 0 && expiry_height <= tx_plan.anchor_height {
+        return Err(eyre!(
+            "Expiry height {} must be greater than the anchor height {} (or 0 for no expiry)",
+            expiry_height,
+            tx_plan.anchor_height
+        )
+        .into());
+    }
+    Ok(expiry_height)
+}
+
+/// Check that the Merkle path of `witness` leads from its note commitment to
+/// `anchor`, so that an inconsistent plan is rejected before building the
+/// (expensive) proof.
+pub(crate) fn check_witness_anchor(
+    witness: &Witness,
+    auth_path: &[[u8; 32]],
+    anchor: &[u8; 32],
+) -> Result<(), Box<dyn Error>> {
+    let hasher = OrchardHasher::new();
+    let root = auth_path
+        .iter()
+        .enumerate()
+        .fold(witness.cmx, |cur, (depth, sibling)| {
+            if (witness.position >> depth) & 1 == 0 {
+                hasher.node_combine(depth as u8, &cur, sibling)
+            } else {
+                hasher.node_combine(depth as u8, sibling, &cur)
+            }
+        });
+    if &root != anchor {
+        return Err(eyre!("witness for note {} does not match anchor", witness.id_note).into());
+    }
+    Ok(())
+}
+
+/// The outgoing viewing key to encrypt the Orchard outputs to, which allows
+/// the holder of the full viewing key to recover them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum OvkPolicy {
+    /// The OVK of the external scope, as used by most wallets.
+    #[default]
+    External,
+    /// The OVK of the internal scope, as used for change.
+    Internal,
+    /// No OVK, so that outputs can't be recovered (e.g. to prevent linking
+    /// change to the recipients).
+    None,
+}
+
+impl OvkPolicy {
+    /// Return the OVK of `fvk` selected by the policy, if any.
+    pub fn ovk(&self, fvk: &FullViewingKey) -> Option<OutgoingViewingKey> {
+        match self {
+            OvkPolicy::External => Some(fvk.to_ovk(Scope::External)),
+            OvkPolicy::Internal => Some(fvk.to_ovk(Scope::Internal)),
+            OvkPolicy::None => None,
+        }
+    }
+}
+
+/// Return an address of a random Orchard key, which nobody can spend from.
+fn dummy_orchard_address(rng: &mut impl RngCore) -> Address {
+    let sk = loop {
+        let mut random_bytes = [0u8; 32];
+        rng.fill_bytes(&mut random_bytes);
+        let sk = SpendingKey::from_bytes(random_bytes);
+        if sk.is_some().into() {
+            break sk.unwrap();
+        }
+    };
+    FullViewingKey::from(&sk).address_at(0u64, Scope::External)
+}
+
 /// Sign a transaction plan with externally-generated signatures.
-/// TODO: make this non-interactive by possibly using a callback
+/// If `expiry_height` is specified, it overrides the one in the plan. The
+/// Orchard outputs are encrypted to the OVK selected by `ovk_policy`, and
+/// the Orchard bundle is padded to at least `orchard_padding` actions.
+///
+/// The plan can spend notes controlled by different keys (e.g. different
+/// FROST groups), in which case `ufvks` must contain all of them, including
+/// the one used to create the plan. The signatures are then requested
+/// separately for each key.
+///
+/// `get_signatures` is called with the SIGHASH and randomizers, and must
+/// return the corresponding hex-encoded signatures, in any order.
 pub fn sign(
     mut rng: &mut (impl RngCore + CryptoRng),
     tx_plan: &TransactionPlan,
-    ufvk: &UnifiedFullViewingKey,
+    ufvks: &[UnifiedFullViewingKey],
+    expiry_height: Option<u32>,
+    ovk_policy: OvkPolicy,
+    orchard_padding: usize,
+    get_signatures: impl FnOnce(&SigningRequests) -> Result<Vec<[u8; 64]>, Box<dyn Error>>,
 ) -> Result<Transaction, Box<dyn Error>> {
     // TODO: make params selectable
     let network = MainNetwork;
 
-    let orchard_fvk = ufvk
-        .orchard()
-        .ok_or(eyre!("UFVK must have an Orchard component"))?;
-    let orchard_fvk_hex = hex::encode(orchard_fvk.to_bytes());
-    let orchard_ovk = orchard_fvk.clone().to_ovk(Scope::External);
-
-    if tx_plan.orchard_fvk != orchard_fvk_hex {
-        return Err(
-            eyre!("Key does not match the key used to create the given transaction plan").into(),
-        );
-    }
+    let orchard_fvks = ufvks
+        .iter()
+        .map(|ufvk| {
+            ufvk.orchard()
+                .cloned()
+                .ok_or(eyre!("UFVK must have an Orchard component"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let orchard_fvk = orchard_fvks
+        .iter()
+        .find(|fvk| hex::encode(fvk.to_bytes()) == tx_plan.orchard_fvk)
+        .ok_or(eyre!(
+            "Key does not match the key used to create the given transaction plan"
+        ))?;
+    let orchard_ovk = ovk_policy.ovk(orchard_fvk);
+    let expiry_height = BlockHeight::from_u32(validate_expiry_height(tx_plan, expiry_height)?);
 
     let mut transparent_builder = TransparentBuilder::empty();
     let mut sapling_builder = sapling_crypto::builder::Builder::new(
@@ -99,14 +227,32 @@ pub fn sign(
                 witness,
             } => {
                 let diversifier = orchard::keys::Diversifier::from_bytes(*diversifier);
-                let sender_address = orchard_fvk.address(diversifier, Scope::External);
                 let value = NoteValue::from_raw(spend.amount);
                 let rho = Rho::from_bytes(rho).unwrap();
                 let rseed = orchard::note::RandomSeed::from_bytes(*rseed, &rho).unwrap();
-                let note = orchard::Note::from_parts(sender_address, value, rho, rseed).unwrap();
                 let witness = Witness::from_bytes(*id_note, witness)?;
-                let auth_path: Vec<_> = witness
-                    .auth_path(32, &ORCHARD_ROOTS, &OrchardHasher::new())
+                // Find which key controls the note by recomputing its
+                // commitment with each of them. The plan does not say it.
+                let (spend_fvk, note) = orchard_fvks
+                    .iter()
+                    .map(|fvk| {
+                        let sender_address = fvk.address(diversifier, Scope::External);
+                        let note =
+                            orchard::Note::from_parts(sender_address, value, rho, rseed).unwrap();
+                        (fvk, note)
+                    })
+                    .find(|(_, note)| {
+                        orchard_fvks.len() == 1
+                            || ExtractedNoteCommitment::from(note.commitment()).to_bytes()
+                                == witness.cmx
+                    })
+                    .ok_or(eyre!(
+                        "Note {} is not controlled by any of the given keys",
+                        id_note
+                    ))?;
+                let auth_path = witness.auth_path(32, &ORCHARD_ROOTS, &OrchardHasher::new());
+                check_witness_anchor(&witness, &auth_path, &tx_plan.orchard_anchor)?;
+                let auth_path: Vec<_> = auth_path
                     .iter()
                     .map(|n| orchard::tree::MerkleHashOrchard::from_bytes(n).unwrap())
                     .collect();
@@ -115,7 +261,7 @@ pub fn sign(
                     auth_path.try_into().unwrap(),
                 );
                 orchard_builder
-                    .add_spend(orchard_fvk.clone(), note, merkle_path)
+                    .add_spend(spend_fvk.clone(), note, merkle_path)
                     .map_err(|e| eyre!(e.to_string()))?;
             }
         }
@@ -146,7 +292,7 @@ pub fn sign(
                 let orchard_address = Address::from_raw_address_bytes(addr).unwrap();
                 orchard_builder
                     .add_output(
-                        Some(orchard_ovk.clone()),
+                        orchard_ovk.clone(),
                         orchard_address,
                         NoteValue::from_raw(output.amount),
                         Some(*output.memo.as_array()),
@@ -156,6 +302,25 @@ pub fn sign(
         }
     }
 
+    // The builder creates one action for each spend or output, whichever
+    // are more numerous. Pad the outputs with zero-valued notes to a
+    // throwaway address, so that the bundle does not reveal how many notes
+    // were actually spent and created.
+    let orchard_outputs = tx_plan
+        .outputs
+        .iter()
+        .filter(|output| matches!(output.destination, Destination::Orchard(_)))
+        .count();
+    let dummy_outputs = orchard_padding.saturating_sub(tx_plan.spends.len().max(orchard_outputs));
+    if dummy_outputs > 0 {
+        let dummy_address = dummy_orchard_address(&mut rng);
+        for _ in 0..dummy_outputs {
+            orchard_builder
+                .add_output(None, dummy_address, NoteValue::from_raw(0), None)
+                .map_err(|e| eyre!(e.to_string()))?;
+        }
+    }
+
     let transparent_bundle = transparent_builder.build();
     let sapling_bundle = sapling_builder
         .build::<LocalTxProver, LocalTxProver, _, Amount>(&mut rng)
@@ -180,7 +345,7 @@ pub fn sign(
             version,
             consensus_branch_id,
             0,
-            BlockHeight::from_u32(tx_plan.expiry_height),
+            expiry_height,
             transparent_bundle,
             None,
             sapling_bundle,
@@ -191,8 +356,6 @@ pub fn sign(
     let sig_hash = signature_hash(&unauthed_tx, &SignableInput::Shielded, &txid_parts);
     let sig_hash: [u8; 32] = *sig_hash.as_ref();
 
-    println!("SIGHASH: {}", hex::encode(sig_hash));
-
     // There are no transaprent inputs to sign, but we need to move the Bundle
     // to the Authorized state, which we do by calling `apply_signatures()`
     // (which does not take arguments since the transparent-inputs feature is
@@ -212,19 +375,23 @@ pub fn sign(
 
     let proving_key = ProvingKey::build();
 
+    let mut randomizers = Vec::new();
     let orchard_bundle = unauthed_tx.orchard_bundle().map(|ob| {
         let proven = ob.clone().create_proof(&proving_key, &mut rng).unwrap();
         let proven = proven.prepare(&mut rng, sig_hash);
 
-        let expected_ak: SpendValidatingKey = orchard_fvk.clone().into();
+        let expected_aks: Vec<SpendValidatingKey> =
+            orchard_fvks.iter().map(|fvk| fvk.clone().into()).collect();
 
-        let mut alphas = Vec::new();
+        // The randomizers of the spends controlled by each key, in the same
+        // order as `ufvks`.
+        let mut alphas = vec![Vec::new(); expected_aks.len()];
         let proven = proven.map_authorization(
             &mut rng,
             |_rng, _partial, maybe| {
                 if let MaybeSigned::SigningMetadata(parts) = &maybe {
-                    if *parts.ak() == expected_ak {
-                        alphas.push(parts.alpha());
+                    if let Some(k) = expected_aks.iter().position(|ak| ak == parts.ak()) {
+                        alphas[k].push(parts.alpha());
                     }
                 }
                 maybe
@@ -232,37 +399,49 @@ pub fn sign(
             |_rng, auth| auth,
         );
 
-        let mut signatures = Vec::new();
-
-        for (i, alpha) in alphas.iter().enumerate() {
-            println!(
-                "Randomizer #{}: {}",
-                i,
-                hex::encode(alpha.to_repr().as_ref())
-            );
-            let mut buffer = String::new();
-            let stdin = std::io::stdin();
-            println!("Input hex-encoded signature #{}: ", i);
-            stdin.read_line(&mut buffer).unwrap();
-            let signature = hex::decode(buffer.trim()).unwrap();
-            let signature: [u8; 64] = signature.try_into().unwrap();
-            let signature = redpallas::Signature::<SpendAuth>::from(signature);
-            signatures.push(signature);
+        for (k, alphas) in alphas.iter().enumerate() {
+            // The encoding of a full viewing key starts with its ak.
+            let ak: [u8; 32] = orchard_fvks[k].to_bytes()[..32].try_into().unwrap();
+            // Each key signs in its own FROST session, so number the
+            // signatures per key.
+            for (i, alpha) in alphas.iter().enumerate() {
+                randomizers.push(RandomizerRequest {
+                    index: i,
+                    alpha: alpha.to_repr(),
+                    ak,
+                });
+            }
         }
 
         proven
-            .append_signatures(&signatures)
-            .unwrap()
-            .finalize()
-            .unwrap()
     });
 
+    // Signatures are matched to the actions they sign when appended, so
+    // they can be returned in any order.
+    let signatures = get_signatures(&SigningRequests {
+        sighash: sig_hash,
+        randomizers,
+    })?
+    .into_iter()
+    .map(redpallas::Signature::<SpendAuth>::from)
+    .collect::<Vec<_>>();
+
+    let orchard_bundle = orchard_bundle
+        .map(|proven| {
+            proven
+                .append_signatures(&signatures)
+                .map_err(|_| eyre!("Invalid signatures"))?
+                .finalize()
+                .map_err(|_| eyre!("Missing signatures"))
+        })
+        .transpose()?;
+
     let tx_data: TransactionData<zcash_primitives::transaction::Authorized> =
         TransactionData::from_parts(
             version,
             consensus_branch_id,
             0,
-            BlockHeight::from_u32(tx_plan.expiry_height),
+            expiry_height,
             transparent_bundle,
             None,
             sapling_bundle,
@@ -271,3 +450,333 @@ pub fn sign(
     let tx = tx_data.freeze().unwrap();
     Ok(tx)
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use halo2_proofs::pasta::pallas;
+    use orchard::{keys::SpendAuthorizingKey, note::RandomSeed, Note};
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::{
+        example::EXAMPLE_SPENDING_KEY,
+        example_plan,
+        transaction_plan::{CTree, UTXO},
+    };
+
+    /// Return the spending key which controls the note of the example plan.
+    pub(crate) fn example_spending_key() -> SpendingKey {
+        SpendingKey::from_bytes(EXAMPLE_SPENDING_KEY).unwrap()
+    }
+
+    /// Build a plan like the example one, but spending one note of each of
+    /// the given keys (at most two), which are the only leaves of the
+    /// commitment tree.
+    pub(crate) fn plan_spending(sks: &[SpendingKey]) -> TransactionPlan {
+        assert!(sks.len() <= 2, "the tree is built with at most two leaves");
+        let amount = 100_000;
+        let notes = sks
+            .iter()
+            .enumerate()
+            .map(|(i, sk)| {
+                let address = FullViewingKey::from(sk).address_at(0u64, Scope::External);
+                let rho = Rho::from_bytes(&[i as u8 + 1; 32]).unwrap();
+                let rseed = RandomSeed::from_bytes([2; 32], &rho).unwrap();
+                let note =
+                    Note::from_parts(address, NoteValue::from_raw(amount), rho, rseed).unwrap();
+                let cmx = ExtractedNoteCommitment::from(note.commitment()).to_bytes();
+                (address, rho, rseed, cmx)
+            })
+            .collect::<Vec<_>>();
+        let cmxs = notes.iter().map(|(_, _, _, cmx)| *cmx).collect::<Vec<_>>();
+
+        // See `Witness::read()` for the encoding. The first note is the left
+        // leaf, whose sibling is filled in after it was added; the second one
+        // is the right leaf.
+        let witness = |position: usize| {
+            let mut witness = vec![1];
+            witness.extend_from_slice(&cmxs[0]);
+            if position == 0 {
+                // No right leaf nor parents yet.
+                witness.extend_from_slice(&[0, 0]);
+                match cmxs.get(1) {
+                    Some(sibling) => {
+                        witness.push(1);
+                        witness.extend_from_slice(sibling);
+                    }
+                    None => witness.push(0),
+                }
+            } else {
+                witness.push(1);
+                witness.extend_from_slice(&cmxs[1]);
+                // No parents nor filled nodes.
+                witness.extend_from_slice(&[0, 0]);
+            }
+            // No cursor.
+            witness.push(0);
+            witness.extend_from_slice(&cmxs[position]);
+            witness
+        };
+        let tree = CTree {
+            left: Some(cmxs[0]),
+            right: cmxs.get(1).copied(),
+            parents: vec![],
+        };
+
+        let mut tx_plan = example_plan();
+        tx_plan.orchard_fvk = hex::encode(FullViewingKey::from(&sks[0]).to_bytes());
+        tx_plan.orchard_anchor = tree.root(32, &ORCHARD_ROOTS, &OrchardHasher::new());
+        tx_plan.spends = notes
+            .iter()
+            .enumerate()
+            .map(|(i, (address, rho, rseed, _))| UTXO {
+                id: i as u32,
+                source: Source::Orchard {
+                    id_note: i as u32,
+                    diversifier: *address.diversifier().as_array(),
+                    rseed: *rseed.as_bytes(),
+                    rho: rho.to_bytes(),
+                    witness: witness(i),
+                },
+                amount,
+            })
+            .collect();
+        tx_plan.outputs[0].destination = Destination::Orchard(notes[0].0.to_raw_address_bytes());
+        tx_plan.outputs[0].amount = amount * sks.len() as u64 - tx_plan.fee;
+        tx_plan
+    }
+
+    /// Sign `tx_plan` with the given spending keys, standing in for the FROST
+    /// groups which would control them. Returns the transaction along with
+    /// the signing requests that were made.
+    pub(crate) fn sign_with_keys(
+        tx_plan: &TransactionPlan,
+        sks: &[SpendingKey],
+        expiry_height: Option<u32>,
+        ovk_policy: OvkPolicy,
+        orchard_padding: usize,
+    ) -> Result<(Transaction, SigningRequests), Box<dyn Error>> {
+        let ufvks = sks
+            .iter()
+            .map(|sk| UnifiedFullViewingKey::new(None, Some(FullViewingKey::from(sk))).unwrap())
+            .collect::<Vec<_>>();
+        let mut signing_requests = None;
+        let tx = sign(
+            &mut thread_rng(),
+            tx_plan,
+            &ufvks,
+            expiry_height,
+            ovk_policy,
+            orchard_padding,
+            |requests| {
+                signing_requests = Some(requests.clone());
+                requests
+                    .randomizers
+                    .iter()
+                    .map(|request| -> Result<[u8; 64], Box<dyn Error>> {
+                        let sk = sks
+                            .iter()
+                            .find(|sk| FullViewingKey::from(*sk).to_bytes()[..32] == request.ak)
+                            .ok_or(eyre!("Unknown ak"))?;
+                        let alpha = Option::<pallas::Scalar>::from(pallas::Scalar::from_repr(
+                            request.alpha,
+                        ))
+                        .ok_or(eyre!("Invalid randomizer"))?;
+                        let signature = SpendAuthorizingKey::from(sk)
+                            .randomize(&alpha)
+                            .sign(thread_rng(), &requests.sighash);
+                        Ok((&signature).into())
+                    })
+                    .collect()
+            },
+        )?;
+        Ok((tx, signing_requests.expect("requested when signing")))
+    }
+
+    #[test]
+    fn check_validate_expiry_height() {
+        let tx_plan = example_plan();
+
+        assert_eq!(
+            validate_expiry_height(&tx_plan, None).unwrap(),
+            tx_plan.expiry_height
+        );
+        assert_eq!(
+            validate_expiry_height(&tx_plan, Some(tx_plan.anchor_height + 100)).unwrap(),
+            tx_plan.anchor_height + 100
+        );
+        // Zero means no expiry.
+        assert_eq!(validate_expiry_height(&tx_plan, Some(0)).unwrap(), 0);
+
+        for expiry_height in [tx_plan.anchor_height - 1, tx_plan.anchor_height] {
+            let err = validate_expiry_height(&tx_plan, Some(expiry_height)).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "Expiry height {} must be greater than the anchor height {} (or 0 for no expiry)",
+                    expiry_height, tx_plan.anchor_height
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn check_sign_expiry_height() {
+        let tx_plan = example_plan();
+        let sks = [example_spending_key()];
+
+        let expiry_height = tx_plan.anchor_height + 100;
+        let (tx, _) =
+            sign_with_keys(&tx_plan, &sks, Some(expiry_height), OvkPolicy::External, 0).unwrap();
+        assert_eq!(tx.expiry_height(), BlockHeight::from_u32(expiry_height));
+
+        // An invalid expiry height is rejected before signing.
+        assert!(sign_with_keys(
+            &tx_plan,
+            &sks,
+            Some(tx_plan.anchor_height - 1),
+            OvkPolicy::External,
+            0
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn check_sign_multiple_keys() {
+        let sk_a = example_spending_key();
+        let sk_b = SpendingKey::from_bytes([2; 32]).unwrap();
+        let ak = |sk: &SpendingKey| -> [u8; 32] {
+            FullViewingKey::from(sk).to_bytes()[..32]
+                .try_into()
+                .unwrap()
+        };
+        let requested = |requests: &SigningRequests| {
+            requests
+                .randomizers
+                .iter()
+                .map(|r| (r.ak, r.index))
+                .collect::<Vec<_>>()
+        };
+
+        // One note of each key: each key signs its own group of randomizers,
+        // in the order in which the keys were given.
+        let tx_plan = plan_spending(&[sk_a, sk_b]);
+        let (tx, requests) =
+            sign_with_keys(&tx_plan, &[sk_a, sk_b], None, OvkPolicy::External, 0).unwrap();
+        assert_eq!(requested(&requests), vec![(ak(&sk_a), 0), (ak(&sk_b), 0)]);
+        assert_eq!(tx.orchard_bundle().unwrap().actions().len(), 2);
+
+        // Two notes of the same key: both randomizers are collected for it.
+        let tx_plan = plan_spending(&[sk_a, sk_a]);
+        let (_, requests) =
+            sign_with_keys(&tx_plan, &[sk_a], None, OvkPolicy::External, 0).unwrap();
+        assert_eq!(requested(&requests), vec![(ak(&sk_a), 0), (ak(&sk_a), 1)]);
+
+        // A note of a key which was not given can't be spent.
+        let tx_plan = plan_spending(&[sk_a, sk_b]);
+        let sk_c = SpendingKey::from_bytes([3; 32]).unwrap();
+        assert!(sign_with_keys(&tx_plan, &[sk_a, sk_c], None, OvkPolicy::External, 0).is_err());
+    }
+
+    #[test]
+    fn check_witness_anchor_mismatch() {
+        let tx_plan = plan_spending(&[example_spending_key(), example_spending_key()]);
+        for spend in &tx_plan.spends {
+            let Source::Orchard {
+                id_note, witness, ..
+            } = &spend.source
+            else {
+                unreachable!("only Orchard notes are spent");
+            };
+            let witness = Witness::from_bytes(*id_note, witness).unwrap();
+            let auth_path = witness.auth_path(32, &ORCHARD_ROOTS, &OrchardHasher::new());
+            check_witness_anchor(&witness, &auth_path, &tx_plan.orchard_anchor).unwrap();
+            let err = check_witness_anchor(&witness, &auth_path, &[0; 32]).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("witness for note {} does not match anchor", id_note)
+            );
+        }
+
+        // A plan whose anchor does not match its witnesses is rejected before
+        // building the proof.
+        let mut tx_plan = example_plan();
+        tx_plan.orchard_anchor = [0; 32];
+        let err = sign_with_keys(
+            &tx_plan,
+            &[example_spending_key()],
+            None,
+            OvkPolicy::External,
+            0,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "witness for note 0 does not match anchor");
+    }
+
+    #[test]
+    fn check_ovk_policy() {
+        let sk = example_spending_key();
+        let fvk = FullViewingKey::from(&sk);
+        let external = fvk.to_ovk(Scope::External);
+        let internal = fvk.to_ovk(Scope::Internal);
+        assert_eq!(
+            OvkPolicy::External.ovk(&fvk).unwrap().as_ref(),
+            external.as_ref()
+        );
+        assert_eq!(
+            OvkPolicy::Internal.ovk(&fvk).unwrap().as_ref(),
+            internal.as_ref()
+        );
+        assert!(OvkPolicy::None.ovk(&fvk).is_none());
+
+        // The outputs can only be recovered with the OVK selected by the
+        // policy, if any. Returns how many are recovered with the external
+        // and the internal OVK.
+        let recovered = |ovk_policy| {
+            let (tx, _) = sign_with_keys(&example_plan(), &[sk], None, ovk_policy, 0).unwrap();
+            let bundle = tx.orchard_bundle().unwrap();
+            (
+                bundle.recover_outputs_with_ovks(&[external.clone()]).len(),
+                bundle.recover_outputs_with_ovks(&[internal.clone()]).len(),
+            )
+        };
+        assert_eq!(recovered(OvkPolicy::External), (1, 0));
+        assert_eq!(recovered(OvkPolicy::Internal), (0, 1));
+        assert_eq!(recovered(OvkPolicy::None), (0, 0));
+    }
+
+    #[test]
+    fn check_orchard_padding() {
+        let sk = example_spending_key();
+        let fvk = FullViewingKey::from(&sk);
+
+        let (tx, _) = sign_with_keys(&example_plan(), &[sk], None, OvkPolicy::External, 4).unwrap();
+        let bundle = tx.orchard_bundle().unwrap();
+        assert_eq!(bundle.actions().len(), 4);
+        // The dummy outputs go to a throwaway address, and can't be recovered
+        // nor decrypted by the sender.
+        assert_eq!(
+            bundle
+                .recover_outputs_with_ovks(&[fvk.to_ovk(Scope::External)])
+                .len(),
+            1
+        );
+        assert_eq!(
+            bundle
+                .decrypt_outputs_with_keys(&[fvk.to_ivk(Scope::External)])
+                .len(),
+            1
+        );
+
+        // Padding below the two actions of the plan has no effect.
+        let (tx, _) = sign_with_keys(&example_plan(), &[sk], None, OvkPolicy::External, 1).unwrap();
+        assert_eq!(tx.orchard_bundle().unwrap().actions().len(), 2);
+
+        // The throwaway addresses are random, so they can't be linked.
+        let mut rng = thread_rng();
+        assert_ne!(
+            dummy_orchard_address(&mut rng),
+            dummy_orchard_address(&mut rng)
+        );
+    }
+}